@@ -0,0 +1,33 @@
+CREATE TABLE statement_files (
+  id TEXT PRIMARY KEY,
+  statement_id TEXT NOT NULL,
+
+  file_hash TEXT NOT NULL UNIQUE,
+  file_size INTEGER NOT NULL,
+
+  original_filename TEXT,
+  kind TEXT NOT NULL,
+
+  attached_at TEXT NOT NULL DEFAULT (datetime('now')),
+
+  FOREIGN KEY(statement_id) REFERENCES statements(id)
+);
+
+CREATE INDEX statement_files_statement_id_idx ON statement_files(statement_id);
+
+-- statements.file_hash/file_size stay as the primary file for backward compatibility;
+-- mirror them here so every managed file has a row.
+INSERT INTO statement_files (id, statement_id, file_hash, file_size, kind, attached_at)
+SELECT
+  lower(hex(randomblob(4))) || '-' ||
+  lower(hex(randomblob(2))) || '-4' ||
+  substr(lower(hex(randomblob(2))), 2) || '-' ||
+  substr('89ab', 1 + (abs(random()) % 4), 1) ||
+  substr(lower(hex(randomblob(2))), 2) || '-' ||
+  lower(hex(randomblob(6))),
+  id,
+  file_hash,
+  file_size,
+  'primary',
+  imported_at
+FROM statements;
@@ -0,0 +1,23 @@
+-- External-content FTS5 index over transaction descriptions, kept in sync by triggers.
+CREATE VIRTUAL TABLE transactions_fts USING fts5(
+  description,
+  content='transactions',
+  content_rowid='rowid'
+);
+
+CREATE TRIGGER transactions_fts_after_insert AFTER INSERT ON transactions BEGIN
+  INSERT INTO transactions_fts(rowid, description) VALUES (new.rowid, new.description);
+END;
+
+CREATE TRIGGER transactions_fts_after_delete AFTER DELETE ON transactions BEGIN
+  INSERT INTO transactions_fts(transactions_fts, rowid, description)
+  VALUES ('delete', old.rowid, old.description);
+END;
+
+CREATE TRIGGER transactions_fts_after_update AFTER UPDATE OF description ON transactions BEGIN
+  INSERT INTO transactions_fts(transactions_fts, rowid, description)
+  VALUES ('delete', old.rowid, old.description);
+  INSERT INTO transactions_fts(rowid, description) VALUES (new.rowid, new.description);
+END;
+
+INSERT INTO transactions_fts(transactions_fts) VALUES ('rebuild');
@@ -0,0 +1,6 @@
+-- Advisory locks held by long write operations so a second writer fails fast.
+CREATE TABLE locks (
+  name TEXT PRIMARY KEY,
+  holder TEXT NOT NULL,
+  acquired_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
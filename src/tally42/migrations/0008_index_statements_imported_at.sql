@@ -0,0 +1 @@
+CREATE INDEX statements_imported_at_idx ON statements(imported_at);
@@ -0,0 +1,5 @@
+-- Per-data-dir settings, such as the statement layout chosen by reshard-statements.
+CREATE TABLE settings (
+  key TEXT PRIMARY KEY,
+  value TEXT NOT NULL
+);
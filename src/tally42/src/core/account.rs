@@ -1,4 +1,4 @@
-use super::db::Db;
+use super::db::{is_foreign_key_violation, Db};
 use std::fmt::{Display, Formatter};
 use uuid::Uuid;
 
@@ -54,12 +54,12 @@ pub enum AccountListError {
 impl Display for AccountListError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Sql(err) => write!(f, "sqlite error while listing accounts: {err}"),
-            Self::InvalidId { value, source } => {
-                write!(f, "invalid account id UUID '{value}': {source}")
+            Self::Sql(_) => write!(f, "sqlite error while listing accounts"),
+            Self::InvalidId { value, .. } => {
+                write!(f, "invalid account id UUID '{value}'")
             }
-            Self::InvalidParentId { value, source } => {
-                write!(f, "invalid parent account id UUID '{value}': {source}")
+            Self::InvalidParentId { value, .. } => {
+                write!(f, "invalid parent account id UUID '{value}'")
             }
         }
     }
@@ -86,14 +86,19 @@ pub enum AccountWriteError {
     Sql(rusqlite::Error),
     ReadBack(AccountListError),
     NotFound(Uuid),
+    HasStatements { id: Uuid, count: i64 },
 }
 
 impl Display for AccountWriteError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Sql(err) => write!(f, "sqlite error while writing account: {err}"),
-            Self::ReadBack(err) => write!(f, "failed to read back account after write: {err}"),
+            Self::Sql(_) => write!(f, "sqlite error while writing account"),
+            Self::ReadBack(_) => write!(f, "failed to read back account after write"),
             Self::NotFound(id) => write!(f, "account not found: {id}"),
+            Self::HasStatements { id, count } => write!(
+                f,
+                "account {id} still has {count} statement(s); remove its {count} statement(s) first"
+            ),
         }
     }
 }
@@ -104,6 +109,7 @@ impl std::error::Error for AccountWriteError {
             Self::Sql(err) => Some(err),
             Self::ReadBack(err) => Some(err),
             Self::NotFound(_) => None,
+            Self::HasStatements { .. } => None,
         }
     }
 }
@@ -175,7 +181,32 @@ impl Db {
         self.get_account_by_id(id)?.ok_or(AccountWriteError::NotFound(id))
     }
 
-    fn get_account_by_id(&self, id: Uuid) -> Result<Option<Account>, AccountWriteError> {
+    pub fn delete_account(&self, id: Uuid) -> Result<(), AccountWriteError> {
+        let deleted = match self.conn().execute(
+            "DELETE FROM accounts WHERE id = ?1",
+            rusqlite::params![id.to_string()],
+        ) {
+            Ok(deleted) => deleted,
+            Err(err) if is_foreign_key_violation(&err) => {
+                let count: i64 = self.conn().query_row(
+                    "SELECT COUNT(*) FROM statements WHERE account_id = ?1",
+                    rusqlite::params![id.to_string()],
+                    |row| row.get(0),
+                )?;
+                if count > 0 {
+                    return Err(AccountWriteError::HasStatements { id, count });
+                }
+                return Err(AccountWriteError::Sql(err));
+            }
+            Err(err) => return Err(AccountWriteError::Sql(err)),
+        };
+        if deleted == 0 {
+            return Err(AccountWriteError::NotFound(id));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get_account_by_id(&self, id: Uuid) -> Result<Option<Account>, AccountWriteError> {
         let mut stmt = self.conn().prepare(
             "
             SELECT id, parent_id, name, currency, is_closed, created_at, note
@@ -194,9 +225,11 @@ impl Db {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::statement::AddStatementInput;
     use crate::core::db::Db;
     use rusqlite::params;
 
+    #[allow(clippy::too_many_arguments)]
     fn insert_account(
         conn: &rusqlite::Connection,
         id: &str,
@@ -224,7 +257,7 @@ mod tests {
 
         let id = Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap();
         insert_account(
-            &conn,
+            conn,
             &id.to_string(),
             None,
             "checking",
@@ -257,7 +290,7 @@ mod tests {
 
         let id = Uuid::parse_str("22222222-2222-2222-2222-222222222222").unwrap();
         insert_account(
-            &conn,
+            conn,
             &id.to_string(),
             None,
             "root",
@@ -283,7 +316,7 @@ mod tests {
         let child_a2 = Uuid::parse_str("aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaa3").unwrap();
 
         insert_account(
-            &conn,
+            conn,
             &root_b.to_string(),
             None,
             "b-root",
@@ -293,7 +326,7 @@ mod tests {
             None,
         );
         insert_account(
-            &conn,
+            conn,
             &root_a.to_string(),
             None,
             "a-root",
@@ -303,7 +336,7 @@ mod tests {
             None,
         );
         insert_account(
-            &conn,
+            conn,
             &child_a1.to_string(),
             Some(&root_a.to_string()),
             "a-child",
@@ -313,7 +346,7 @@ mod tests {
             None,
         );
         insert_account(
-            &conn,
+            conn,
             &child_a2.to_string(),
             Some(&root_a.to_string()),
             "z-child",
@@ -336,7 +369,7 @@ mod tests {
         let open_id = Uuid::parse_str("33333333-3333-3333-3333-333333333333").unwrap();
         let closed_id = Uuid::parse_str("44444444-4444-4444-4444-444444444444").unwrap();
         insert_account(
-            &conn,
+            conn,
             &open_id.to_string(),
             None,
             "open",
@@ -346,7 +379,7 @@ mod tests {
             None,
         );
         insert_account(
-            &conn,
+            conn,
             &closed_id.to_string(),
             None,
             "closed",
@@ -369,7 +402,7 @@ mod tests {
         let conn = db.conn();
 
         insert_account(
-            &conn,
+            conn,
             "not-a-uuid",
             None,
             "broken",
@@ -391,7 +424,7 @@ mod tests {
             .expect("disable foreign keys for malformed parent_id fixture");
 
         insert_account(
-            &conn,
+            conn,
             "55555555-5555-5555-5555-555555555555",
             Some("not-a-uuid"),
             "broken-child",
@@ -470,4 +503,63 @@ mod tests {
 
         assert!(matches!(err, AccountWriteError::NotFound(id) if id == missing));
     }
+
+    #[test]
+    fn delete_account_removes_unused_account() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let id = Uuid::parse_str("abababab-0000-0000-0000-000000000001").unwrap();
+        db.create_account(id, None, "Scratch", "USD", None)
+            .expect("create account");
+
+        db.delete_account(id).expect("delete account");
+
+        assert!(db.list_accounts().expect("list accounts").is_empty());
+    }
+
+    #[test]
+    fn delete_account_returns_not_found_for_missing_id() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let missing = Uuid::parse_str("abababab-0000-0000-0000-000000000002").unwrap();
+
+        let err = db.delete_account(missing).expect_err("delete should fail");
+
+        assert!(matches!(err, AccountWriteError::NotFound(id) if id == missing));
+    }
+
+    #[test]
+    fn delete_account_reports_statement_count_when_restricted() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let id = Uuid::parse_str("abababab-0000-0000-0000-000000000003").unwrap();
+        db.create_account(id, None, "Checking", "USD", None)
+            .expect("create account");
+        for (statement_id, hash) in [
+            ("abababab-0000-0000-0000-000000000004", "sha256:aa"),
+            ("abababab-0000-0000-0000-000000000005", "sha256:bb"),
+        ] {
+            db.create_statement(
+                Uuid::parse_str(statement_id).unwrap(),
+                &AddStatementInput {
+                    institution: "Chase".to_string(),
+                    account_id: id,
+                    period_start: "2026-01-01".to_string(),
+                    period_end: "2026-01-31".to_string(),
+                    currency: "USD".to_string(),
+                    replaced_by: None,
+                    allow_currency_mismatch: false,
+                },
+                hash,
+                1,
+            )
+            .expect("create statement");
+        }
+
+        let err = db.delete_account(id).expect_err("delete should be restricted");
+
+        assert!(matches!(
+            err,
+            AccountWriteError::HasStatements { id: err_id, count: 2 } if err_id == id
+        ));
+        assert!(err.to_string().contains("remove its 2 statement(s) first"));
+        assert_eq!(db.list_accounts().expect("list accounts").len(), 1);
+    }
 }
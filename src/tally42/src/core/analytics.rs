@@ -0,0 +1,560 @@
+use super::account::AccountListError;
+use super::currency::parse_minor_units;
+use super::db::Db;
+use super::statement::StatementListError;
+use super::transaction::TransactionListError;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct YearMonth {
+    pub year: i32,
+    pub month: u32,
+}
+
+impl YearMonth {
+    /// Parse the month of a `YYYY-MM-DD` (or `YYYY-MM`) date.
+    pub fn parse(value: &str) -> Option<Self> {
+        let year = value.get(0..4)?.parse::<i32>().ok()?;
+        if value.get(4..5)? != "-" {
+            return None;
+        }
+        let month = value.get(5..7)?.parse::<u32>().ok()?;
+        if !(1..=12).contains(&month) {
+            return None;
+        }
+        match value.get(7..) {
+            Some("") => {}
+            Some(rest) if rest.len() == 3 && rest.starts_with('-') => {
+                rest[1..].parse::<u32>().ok().filter(|day| (1..=31).contains(day))?;
+            }
+            _ => return None,
+        }
+        Some(Self { year, month })
+    }
+
+    pub fn next(self) -> Self {
+        if self.month == 12 {
+            Self {
+                year: self.year + 1,
+                month: 1,
+            }
+        } else {
+            Self {
+                year: self.year,
+                month: self.month + 1,
+            }
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        if self.month == 1 {
+            Self {
+                year: self.year - 1,
+                month: 12,
+            }
+        } else {
+            Self {
+                year: self.year,
+                month: self.month - 1,
+            }
+        }
+    }
+}
+
+impl Display for YearMonth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
+}
+
+/// Monthly statement coverage for one account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountCoverage {
+    pub account_id: Uuid,
+    pub account_name: String,
+    pub first: YearMonth,
+    pub last: YearMonth,
+    pub missing: Vec<YearMonth>,
+}
+
+#[derive(Debug)]
+pub enum CoverageError {
+    Sql(rusqlite::Error),
+    ListAccounts(AccountListError),
+    ListStatements(StatementListError),
+    InvalidPeriod { statement_id: Uuid, value: String },
+    InvalidCurrentDate(String),
+}
+
+impl Display for CoverageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sql(_) => write!(f, "sqlite error while computing coverage"),
+            Self::ListAccounts(_) => write!(f, "failed to list accounts"),
+            Self::ListStatements(_) => write!(f, "failed to list statements"),
+            Self::InvalidPeriod {
+                statement_id,
+                value,
+            } => write!(f, "invalid period date '{value}' on statement {statement_id}"),
+            Self::InvalidCurrentDate(value) => {
+                write!(f, "sqlite returned an invalid current date: '{value}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CoverageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Sql(err) => Some(err),
+            Self::ListAccounts(err) => Some(err),
+            Self::ListStatements(err) => Some(err),
+            Self::InvalidPeriod { .. } => None,
+            Self::InvalidCurrentDate(_) => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for CoverageError {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sql(value)
+    }
+}
+
+/// Months in `first..=through` that no period overlaps, where `first` is the earliest
+/// period start. Periods are inclusive month ranges and may overlap each other.
+/// Returns `None` when there are no periods.
+pub fn monthly_gaps(
+    periods: &[(YearMonth, YearMonth)],
+    through: YearMonth,
+) -> Option<(YearMonth, Vec<YearMonth>)> {
+    let mut sorted: Vec<(YearMonth, YearMonth)> = periods
+        .iter()
+        .map(|&(start, end)| (start.min(end), start.max(end)))
+        .collect();
+    sorted.sort();
+    let first = sorted.first()?.0;
+
+    let mut missing = Vec::new();
+    let mut next_uncovered = first;
+    for (start, end) in sorted {
+        while next_uncovered < start && next_uncovered <= through {
+            missing.push(next_uncovered);
+            next_uncovered = next_uncovered.next();
+        }
+        if end >= next_uncovered {
+            next_uncovered = end.next();
+        }
+    }
+    while next_uncovered <= through {
+        missing.push(next_uncovered);
+        next_uncovered = next_uncovered.next();
+    }
+    Some((first, missing))
+}
+
+/// Histogram bucket edges used when none are given, in whole currency units.
+pub const DEFAULT_HISTOGRAM_EDGES: &str = "0,10,25,50,100,250,1000";
+
+/// Transactions whose amount falls in one histogram bucket. Amounts are in minor units
+/// (cents), like posting amounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountBucket {
+    pub lower: i64,
+    /// `None` for the overflow bucket above the last edge.
+    pub upper: Option<i64>,
+    pub count: usize,
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BucketEdgesError {
+    InvalidEdge(String),
+    NotIncreasing { previous: String, next: String },
+    TooFew,
+}
+
+impl Display for BucketEdgesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidEdge(value) => {
+                write!(f, "invalid bucket edge '{value}': use an amount like 25 or 12.50")
+            }
+            Self::NotIncreasing { previous, next } => {
+                write!(f, "bucket edges must be strictly increasing: {next} follows {previous}")
+            }
+            Self::TooFew => write!(f, "at least two bucket edges are required"),
+        }
+    }
+}
+
+impl std::error::Error for BucketEdgesError {}
+
+/// Parse comma-separated decimal bucket edges such as `0,10,25.50` into minor units of a
+/// currency with `places` decimal places.
+pub fn parse_bucket_edges(value: &str, places: u32) -> Result<Vec<i64>, BucketEdgesError> {
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+    let mut edges: Vec<i64> = Vec::with_capacity(parts.len());
+    for (idx, part) in parts.iter().enumerate() {
+        let edge = parse_minor_units(part, places)
+            .ok_or_else(|| BucketEdgesError::InvalidEdge(part.to_string()))?;
+        if edges.last().is_some_and(|&previous| previous >= edge) {
+            return Err(BucketEdgesError::NotIncreasing {
+                previous: parts[idx - 1].to_string(),
+                next: part.to_string(),
+            });
+        }
+        edges.push(edge);
+    }
+    if edges.len() < 2 {
+        return Err(BucketEdgesError::TooFew);
+    }
+    Ok(edges)
+}
+
+/// Count and total `amounts` per bucket. Bucket `i` holds amounts in
+/// `(edges[i], edges[i + 1]]`, so a value on an edge lands in the lower bucket; the first
+/// bucket also takes everything at or below the first edge, and a final overflow bucket
+/// takes everything above the last edge. `edges` must be strictly increasing.
+pub fn bucket_amounts(amounts: &[i64], edges: &[i64]) -> Vec<AmountBucket> {
+    let mut buckets: Vec<AmountBucket> = edges
+        .iter()
+        .enumerate()
+        .map(|(idx, &lower)| AmountBucket {
+            lower,
+            upper: edges.get(idx + 1).copied(),
+            count: 0,
+            total: 0,
+        })
+        .collect();
+    let Some(overflow) = edges.len().checked_sub(1) else {
+        return buckets;
+    };
+    for &amount in amounts {
+        let idx = edges[1..]
+            .iter()
+            .position(|&upper| amount <= upper)
+            .unwrap_or(overflow);
+        buckets[idx].count += 1;
+        buckets[idx].total = buckets[idx].total.saturating_add(amount);
+    }
+    buckets
+}
+
+impl Db {
+    /// The amount of each transaction posted in `from..=to` (either bound optional), taken as
+    /// the sum of its debit postings, keyed by upper-cased currency. A transaction with
+    /// postings in several currencies has an amount in each. With `currency`, only postings
+    /// in that currency (case-insensitive) count.
+    pub fn transaction_amounts(
+        &self,
+        from: Option<&str>,
+        to: Option<&str>,
+        currency: Option<&str>,
+    ) -> Result<BTreeMap<String, Vec<i64>>, TransactionListError> {
+        let mut stmt = self.conn().prepare(
+            "
+            SELECT UPPER(p.currency), SUM(p.amount)
+            FROM transactions t
+            JOIN postings p ON p.transaction_id = t.id
+            WHERE p.direction = 'debit'
+              AND (?1 IS NULL OR t.posted_at >= ?1)
+              AND (?2 IS NULL OR t.posted_at <= ?2)
+              AND (?3 IS NULL OR UPPER(p.currency) = UPPER(?3))
+            GROUP BY t.id, UPPER(p.currency)
+            ORDER BY t.posted_at, t.id
+            ",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![from, to, currency], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        let mut amounts: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+        for row in rows {
+            let (currency, amount) = row?;
+            amounts.entry(currency).or_default().push(amount);
+        }
+        Ok(amounts)
+    }
+
+    pub fn current_month(&self) -> Result<YearMonth, CoverageError> {
+        let today: String =
+            self.conn()
+                .query_row("SELECT strftime('%Y-%m', 'now')", [], |row| row.get(0))?;
+        YearMonth::parse(&today).ok_or(CoverageError::InvalidCurrentDate(today))
+    }
+
+    /// Per-account monthly coverage from the statements table. Open accounts are
+    /// expected to have statements through `through`; closed accounts only through
+    /// their last statement. Accounts without statements are omitted.
+    pub fn monthly_coverage(
+        &self,
+        through: YearMonth,
+    ) -> Result<Vec<AccountCoverage>, CoverageError> {
+        let accounts = self.list_accounts().map_err(CoverageError::ListAccounts)?;
+        let statements = self
+            .list_statements()
+            .map_err(CoverageError::ListStatements)?;
+
+        let mut coverage = Vec::new();
+        for account in accounts {
+            let mut periods = Vec::new();
+            for statement in statements.iter().filter(|s| s.account_id == account.id) {
+                let parse = |value: &str| {
+                    YearMonth::parse(value).ok_or_else(|| CoverageError::InvalidPeriod {
+                        statement_id: statement.id,
+                        value: value.to_string(),
+                    })
+                };
+                periods.push((parse(&statement.period_start)?, parse(&statement.period_end)?));
+            }
+            let Some(last_statement) = periods.iter().map(|&(_, end)| end).max() else {
+                continue;
+            };
+            let last = if account.is_closed {
+                last_statement
+            } else {
+                through.max(last_statement)
+            };
+            let Some((first, missing)) = monthly_gaps(&periods, last) else {
+                continue;
+            };
+            coverage.push(AccountCoverage {
+                account_id: account.id,
+                account_name: account.name,
+                first,
+                last,
+                missing,
+            });
+        }
+        Ok(coverage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::statement::AddStatementInput;
+
+    fn ym(value: &str) -> YearMonth {
+        YearMonth::parse(value).expect("valid year-month")
+    }
+
+    #[test]
+    fn year_month_parses_dates_and_steps() {
+        assert_eq!(ym("2026-01-31"), YearMonth { year: 2026, month: 1 });
+        assert_eq!(ym("2026-12"), YearMonth { year: 2026, month: 12 });
+        assert_eq!(YearMonth::parse("2026-13-01"), None);
+        assert_eq!(YearMonth::parse("2026-1-01"), None);
+        assert_eq!(YearMonth::parse("2026-01-01T00"), None);
+        assert_eq!(ym("2026-12").next(), ym("2027-01"));
+        assert_eq!(ym("2026-01").previous(), ym("2025-12"));
+        assert_eq!(ym("2026-03").to_string(), "2026-03");
+    }
+
+    fn bucket(lower: i64, upper: Option<i64>, count: usize, total: i64) -> AmountBucket {
+        AmountBucket {
+            lower,
+            upper,
+            count,
+            total,
+        }
+    }
+
+    #[test]
+    fn parse_bucket_edges_reads_decimal_amounts() {
+        assert_eq!(parse_bucket_edges("0,10,25.5", 2), Ok(vec![0, 1000, 2550]));
+        assert_eq!(parse_bucket_edges(" -5 , 0.05 ", 2), Ok(vec![-500, 5]));
+        assert_eq!(
+            parse_bucket_edges("0,ten", 2),
+            Err(BucketEdgesError::InvalidEdge("ten".to_string()))
+        );
+        assert_eq!(
+            parse_bucket_edges("0,1.005", 2),
+            Err(BucketEdgesError::InvalidEdge("1.005".to_string()))
+        );
+        assert_eq!(
+            parse_bucket_edges("0,10,10.00", 2),
+            Err(BucketEdgesError::NotIncreasing {
+                previous: "10".to_string(),
+                next: "10.00".to_string(),
+            })
+        );
+        assert_eq!(parse_bucket_edges("10", 2), Err(BucketEdgesError::TooFew));
+        assert_eq!(parse_bucket_edges(DEFAULT_HISTOGRAM_EDGES, 2).map(|e| e.len()), Ok(7));
+        assert_eq!(parse_bucket_edges("0,1000,5000", 0), Ok(vec![0, 1000, 5000]));
+        assert_eq!(
+            parse_bucket_edges("0,10.5", 0),
+            Err(BucketEdgesError::InvalidEdge("10.5".to_string()))
+        );
+    }
+
+    #[test]
+    fn bucket_amounts_puts_boundary_values_in_the_lower_bucket() {
+        let edges = [0, 1000, 2500];
+        let amounts = [0, 1, 1000, 1001, 2500, 2501, 99_999, -300];
+
+        assert_eq!(
+            bucket_amounts(&amounts, &edges),
+            vec![
+                bucket(0, Some(1000), 4, 701),
+                bucket(1000, Some(2500), 2, 3501),
+                bucket(2500, None, 2, 102_500),
+            ]
+        );
+        assert_eq!(
+            bucket_amounts(&[], &edges),
+            vec![
+                bucket(0, Some(1000), 0, 0),
+                bucket(1000, Some(2500), 0, 0),
+                bucket(2500, None, 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_gaps_reports_deliberate_gap() {
+        let periods = [
+            (ym("2026-01-01"), ym("2026-01-31")),
+            (ym("2026-02-01"), ym("2026-02-28")),
+            (ym("2026-04-01"), ym("2026-04-30")),
+        ];
+
+        let (first, missing) = monthly_gaps(&periods, ym("2026-04")).expect("some periods");
+
+        assert_eq!(first, ym("2026-01"));
+        assert_eq!(missing, vec![ym("2026-03")]);
+    }
+
+    #[test]
+    fn monthly_gaps_handles_overlapping_and_unsorted_periods() {
+        let periods = [
+            (ym("2026-03-15"), ym("2026-04-14")),
+            (ym("2026-01-15"), ym("2026-03-14")),
+            (ym("2026-02-01"), ym("2026-02-28")),
+        ];
+
+        let (first, missing) = monthly_gaps(&periods, ym("2026-04")).expect("some periods");
+
+        assert_eq!(first, ym("2026-01"));
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn monthly_gaps_extends_to_through_month() {
+        let periods = [(ym("2026-01-01"), ym("2026-01-31"))];
+
+        let (_, missing) = monthly_gaps(&periods, ym("2026-03")).expect("some periods");
+
+        assert_eq!(missing, vec![ym("2026-02"), ym("2026-03")]);
+        assert_eq!(monthly_gaps(&[], ym("2026-03")), None);
+    }
+
+    #[test]
+    fn monthly_coverage_stops_closed_accounts_at_last_statement() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let open_id = Uuid::parse_str("c0c0c0c0-0000-0000-0000-000000000001").unwrap();
+        let closed_id = Uuid::parse_str("c0c0c0c0-0000-0000-0000-000000000002").unwrap();
+        db.create_account(open_id, None, "checking", "USD", None)
+            .expect("create open account");
+        db.create_account(closed_id, None, "old-card", "USD", None)
+            .expect("create closed account");
+        db.close_account(closed_id).expect("close account");
+        for (id, account_id, start, end, hash) in [
+            ("c0c0c0c0-0000-0000-0000-000000000003", open_id, "2026-01-01", "2026-01-31", "a"),
+            ("c0c0c0c0-0000-0000-0000-000000000004", open_id, "2026-03-01", "2026-03-31", "b"),
+            ("c0c0c0c0-0000-0000-0000-000000000005", closed_id, "2026-01-01", "2026-01-31", "c"),
+        ] {
+            db.create_statement(
+                Uuid::parse_str(id).unwrap(),
+                &AddStatementInput {
+                    institution: "Bank".to_string(),
+                    account_id,
+                    period_start: start.to_string(),
+                    period_end: end.to_string(),
+                    currency: "USD".to_string(),
+                    replaced_by: None,
+                    allow_currency_mismatch: false,
+                },
+                &format!("sha256:{hash}"),
+                1,
+            )
+            .expect("create statement");
+        }
+
+        let coverage = db.monthly_coverage(ym("2026-04")).expect("coverage");
+
+        assert_eq!(coverage.len(), 2);
+        let open = coverage.iter().find(|c| c.account_id == open_id).expect("open");
+        assert_eq!(open.last, ym("2026-04"));
+        assert_eq!(open.missing, vec![ym("2026-02"), ym("2026-04")]);
+        let closed = coverage.iter().find(|c| c.account_id == closed_id).expect("closed");
+        assert_eq!(closed.last, ym("2026-01"));
+        assert!(closed.missing.is_empty());
+    }
+
+    #[test]
+    fn transaction_amounts_sums_debits_per_currency_within_dates() {
+        use crate::core::core_api::Core;
+        use crate::core::transaction::{AddPostingInput, AddTransactionInput, PostingDirection};
+
+        let mut core = Core::open_for_tests().expect("open core");
+        let cash_id = Uuid::parse_str("c0c0c0c0-0000-0000-0000-000000000011").unwrap();
+        let food_id = Uuid::parse_str("c0c0c0c0-0000-0000-0000-000000000012").unwrap();
+        for (id, name) in [(cash_id, "assets:cash"), (food_id, "expenses:food")] {
+            core.db_mut()
+                .create_account(id, None, name, "USD", None)
+                .expect("create account");
+        }
+        let amounts = [
+            ("2026-01-05", 1500, "USD"),
+            ("2026-02-10", 250, "usd"),
+            ("2026-02-20", 700, "JPY"),
+            ("2026-03-01", 9000, "USD"),
+        ];
+        for (posted_at, amount, currency) in amounts {
+            let posting = |account_id, direction| AddPostingInput {
+                account_id,
+                amount,
+                currency: currency.to_string(),
+                direction,
+            };
+            core.add_transaction(AddTransactionInput {
+                statement_id: None,
+                description: None,
+                posted_at: posted_at.to_string(),
+                postings: vec![
+                    posting(food_id, PostingDirection::Debit),
+                    posting(cash_id, PostingDirection::Credit),
+                ],
+            })
+            .expect("add transaction");
+        }
+
+        let db = core.db_mut();
+        let per_currency = |entries: &[(&str, &[i64])]| {
+            entries
+                .iter()
+                .map(|(currency, amounts)| (currency.to_string(), amounts.to_vec()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        assert_eq!(
+            db.transaction_amounts(None, None, None).expect("amounts"),
+            per_currency(&[("JPY", &[700]), ("USD", &[1500, 250, 9000])])
+        );
+        assert_eq!(
+            db.transaction_amounts(Some("2026-02-01"), Some("2026-02-15"), None)
+                .expect("amounts"),
+            per_currency(&[("USD", &[250])])
+        );
+        assert_eq!(
+            db.transaction_amounts(None, None, Some("Usd")).expect("amounts"),
+            per_currency(&[("USD", &[1500, 250, 9000])])
+        );
+        assert!(db
+            .transaction_amounts(None, None, Some("EUR"))
+            .expect("amounts")
+            .is_empty());
+    }
+}
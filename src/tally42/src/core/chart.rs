@@ -0,0 +1,456 @@
+use super::account::{Account, AccountListError, AccountWriteError};
+use super::db::Db;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// An account in a chart-of-accounts file, with the accounts nested under it.
+///
+/// The file is TOML: each `[[account]]` table is a top-level account, and each
+/// `[[account.children]]` (`[[account.children.children]]`, ...) table is a child
+/// of the nearest table one level up.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChartAccount {
+    pub name: String,
+    pub currency: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ChartAccount>,
+}
+
+/// A parse error with the line and column it was found at.
+pub type ChartParseError = toml::de::Error;
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChartFile {
+    #[serde(default)]
+    account: Vec<ChartAccount>,
+}
+
+#[derive(Debug)]
+pub enum AccountChartError {
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: ChartParseError,
+    },
+    AccountList(AccountListError),
+    AccountWrite(AccountWriteError),
+}
+
+impl Display for AccountChartError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read { path, .. } => write!(f, "failed to read {}", path.display()),
+            Self::Write { path, .. } => write!(f, "failed to write {}", path.display()),
+            Self::Parse { path, .. } => write!(f, "invalid account chart {}", path.display()),
+            Self::AccountList(_) => write!(f, "failed to list accounts"),
+            Self::AccountWrite(_) => write!(f, "failed to create accounts; none were created"),
+        }
+    }
+}
+
+impl std::error::Error for AccountChartError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Read { source, .. } => Some(source),
+            Self::Write { source, .. } => Some(source),
+            Self::Parse { source, .. } => Some(source),
+            Self::AccountList(err) => Some(err),
+            Self::AccountWrite(err) => Some(err),
+        }
+    }
+}
+
+impl From<AccountListError> for AccountChartError {
+    fn from(value: AccountListError) -> Self {
+        Self::AccountList(value)
+    }
+}
+
+impl From<AccountWriteError> for AccountChartError {
+    fn from(value: AccountWriteError) -> Self {
+        Self::AccountWrite(value)
+    }
+}
+
+/// What importing a chart did, as `Parent:Child` account paths in file order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChartImportSummary {
+    pub created: Vec<String>,
+    /// Accounts that already existed under the same parent and name.
+    pub skipped: Vec<String>,
+    /// Existing accounts whose note was replaced; only with `update_notes`.
+    pub notes_updated: Vec<String>,
+}
+
+/// Parse a chart-of-accounts file; see `ChartAccount`.
+pub fn parse_chart(contents: &str) -> Result<Vec<ChartAccount>, ChartParseError> {
+    Ok(toml::from_str::<ChartFile>(contents)?.account)
+}
+
+/// Render `accounts` in the format read by `parse_chart`.
+pub fn format_chart(accounts: &[ChartAccount]) -> String {
+    let file = ChartFile {
+        account: accounts.to_vec(),
+    };
+    toml::to_string(&file).expect("a chart is always valid TOML")
+}
+
+/// Nest `accounts` by `parent_id`, ordering siblings by name. Accounts whose parent
+/// is missing are treated as top-level.
+pub fn chart_from_accounts(accounts: &[Account]) -> Vec<ChartAccount> {
+    fn children_of(accounts: &[Account], parent_id: Option<Uuid>) -> Vec<ChartAccount> {
+        let mut children = accounts
+            .iter()
+            .filter(|account| {
+                account.parent_id == parent_id
+                    || (parent_id.is_none()
+                        && account
+                            .parent_id
+                            .is_some_and(|id| !accounts.iter().any(|other| other.id == id)))
+            })
+            .collect::<Vec<_>>();
+        children.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+        children
+            .into_iter()
+            .map(|account| ChartAccount {
+                name: account.name.clone(),
+                currency: account.currency.clone(),
+                note: account.note.clone(),
+                children: children_of(accounts, Some(account.id)),
+            })
+            .collect()
+    }
+
+    children_of(accounts, None)
+}
+
+impl Db {
+    /// The account tree as a chart; see `chart_from_accounts`.
+    pub fn account_chart(&self) -> Result<Vec<ChartAccount>, AccountListError> {
+        Ok(chart_from_accounts(&self.list_accounts()?))
+    }
+
+    /// Create the accounts in `chart` depth-first in one transaction, so a failure
+    /// creates none of them. An account with the same parent and name as an existing
+    /// one is skipped, but its children are still imported under it; with
+    /// `update_notes` the existing account's note is replaced by the chart's.
+    pub fn import_chart(
+        &self,
+        chart: &[ChartAccount],
+        update_notes: bool,
+    ) -> Result<ChartImportSummary, AccountWriteError> {
+        let tx = self.conn().unchecked_transaction()?;
+        let mut summary = ChartImportSummary::default();
+        for account in chart {
+            import_chart_account(&tx, account, None, "", update_notes, &mut summary)?;
+        }
+        tx.commit()?;
+        Ok(summary)
+    }
+}
+
+fn import_chart_account(
+    tx: &rusqlite::Transaction<'_>,
+    account: &ChartAccount,
+    parent_id: Option<Uuid>,
+    parent_path: &str,
+    update_notes: bool,
+    summary: &mut ChartImportSummary,
+) -> Result<(), AccountWriteError> {
+    let path = if parent_path.is_empty() {
+        account.name.clone()
+    } else {
+        format!("{parent_path}:{}", account.name)
+    };
+    let parent_id_str = parent_id.map(|id| id.to_string());
+    let existing = tx
+        .query_row(
+            "SELECT id, note FROM accounts WHERE parent_id IS ?1 AND name = ?2 ORDER BY id",
+            rusqlite::params![parent_id_str, account.name],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
+        )
+        .map(Some)
+        .or_else(|err| match err {
+            rusqlite::Error::QueryReturnedNoRows => Ok(None),
+            err => Err(err),
+        })?;
+
+    let id = match existing {
+        Some((id_str, note)) => {
+            let id = Uuid::parse_str(&id_str).map_err(|source| {
+                AccountWriteError::ReadBack(AccountListError::InvalidId {
+                    value: id_str.clone(),
+                    source,
+                })
+            })?;
+            if update_notes && note != account.note {
+                tx.execute(
+                    "UPDATE accounts SET note = ?2 WHERE id = ?1",
+                    rusqlite::params![id_str, account.note],
+                )?;
+                summary.notes_updated.push(path.clone());
+            } else {
+                summary.skipped.push(path.clone());
+            }
+            id
+        }
+        None => {
+            let id = Uuid::new_v4();
+            tx.execute(
+                "
+                INSERT INTO accounts (id, parent_id, name, currency, is_closed, note)
+                VALUES (?1, ?2, ?3, ?4, 0, ?5)
+                ",
+                rusqlite::params![
+                    id.to_string(),
+                    parent_id_str,
+                    account.name,
+                    account.currency,
+                    account.note
+                ],
+            )?;
+            summary.created.push(path.clone());
+            id
+        }
+    };
+
+    for child in &account.children {
+        import_chart_account(tx, child, Some(id), &path, update_notes, summary)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart_account(name: &str, note: Option<&str>, children: Vec<ChartAccount>) -> ChartAccount {
+        ChartAccount {
+            name: name.to_string(),
+            currency: "USD".to_string(),
+            note: note.map(str::to_string),
+            children,
+        }
+    }
+
+    fn sample_chart() -> Vec<ChartAccount> {
+        vec![
+            chart_account(
+                "Assets",
+                Some("everything \"owned\""),
+                vec![
+                    chart_account("Checking", None, Vec::new()),
+                    chart_account(
+                        "Savings",
+                        Some("rainy\tday"),
+                        vec![chart_account("Emergency", None, Vec::new())],
+                    ),
+                ],
+            ),
+            chart_account(
+                "Expenses",
+                None,
+                vec![chart_account("Food", None, Vec::new())],
+            ),
+        ]
+    }
+
+    /// (path, currency, note) per account, ignoring ids and timestamps.
+    fn account_rows(db: &Db) -> Vec<(String, String, Option<String>)> {
+        let accounts = db.list_accounts().expect("list accounts");
+        let path = |account: &Account| {
+            let mut names = vec![account.name.clone()];
+            let mut parent_id = account.parent_id;
+            while let Some(id) = parent_id {
+                let parent = accounts.iter().find(|other| other.id == id).unwrap();
+                names.insert(0, parent.name.clone());
+                parent_id = parent.parent_id;
+            }
+            names.join(":")
+        };
+        let mut rows = accounts
+            .iter()
+            .map(|account| {
+                (
+                    path(account),
+                    account.currency.clone(),
+                    account.note.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        rows.sort();
+        rows
+    }
+
+    #[test]
+    fn parse_chart_nests_children_tables() {
+        let contents = "\
+# chart of accounts
+[[account]]
+name = \"Assets\"
+currency = \"USD\"  # home currency
+
+[[account.children]]
+name = \"Checking\"
+currency = \"USD\"
+
+[[account.children]]
+name = \"Savings\"
+currency = \"USD\"
+note = \"rainy\\tday\"
+
+[[account.children.children]]
+name = \"Emergency\"
+currency = \"USD\"
+
+[[account]]
+name = \"Expenses\"
+currency = \"USD\"
+
+[[account.children]]
+name = \"Food\"
+currency = \"USD\"
+";
+        let mut expected = sample_chart();
+        expected[0].note = None;
+        assert_eq!(parse_chart(contents), Ok(expected));
+        assert_eq!(parse_chart(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn format_chart_round_trips_through_parse_chart() {
+        let chart = sample_chart();
+        let text = format_chart(&chart);
+
+        assert!(text.starts_with(
+            "[[account]]\nname = \"Assets\"\ncurrency = \"USD\"\n\
+             note = 'everything \"owned\"'\n\n[[account.children]]\n"
+        ));
+        assert!(text.contains("\n[[account.children.children]]\nname = \"Emergency\"\n"));
+        assert_eq!(parse_chart(&text), Ok(chart));
+    }
+
+    #[test]
+    fn parse_chart_reports_line_numbers() {
+        let cases = [
+            ("name = \"orphan\"\n", 1, "unknown field `name`"),
+            ("[[account.children]]\n", 1, "expected a sequence"),
+            ("[[accounts]]\n", 1, "unknown field `accounts`"),
+            (
+                "[[account]]\nname = \"a\"\nkind = \"asset\"\n",
+                3,
+                "unknown field `kind`",
+            ),
+            (
+                "[[account]]\nname = \"a\"\nname = \"b\"\n",
+                3,
+                "duplicate key `name`",
+            ),
+            (
+                "[[account]]\nname = \"a\"\ncurrency = \"USD\"\n\n\
+                 [[account]]\nname = \"b\"\n",
+                5,
+                "missing field `currency`",
+            ),
+            ("[[account]]\nname = unquoted\n", 2, "invalid string"),
+        ];
+        for (contents, line_number, message) in cases {
+            let err = parse_chart(contents).expect_err(contents).to_string();
+            assert!(
+                err.contains(&format!("line {line_number}")),
+                "{contents}: {err}"
+            );
+            assert!(err.contains(message), "{contents}: {err}");
+        }
+    }
+
+    #[test]
+    fn export_then_import_into_empty_db_round_trips_accounts() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let summary = db
+            .import_chart(&sample_chart(), false)
+            .expect("import chart");
+        assert_eq!(
+            summary.created,
+            vec![
+                "Assets",
+                "Assets:Checking",
+                "Assets:Savings",
+                "Assets:Savings:Emergency",
+                "Expenses",
+                "Expenses:Food",
+            ]
+        );
+
+        let exported = format_chart(&db.account_chart().expect("export chart"));
+        let fresh = Db::open_for_tests().expect("open in-memory db");
+        fresh
+            .import_chart(&parse_chart(&exported).expect("parse export"), false)
+            .expect("import export");
+
+        assert_eq!(account_rows(&fresh), account_rows(&db));
+        assert_eq!(fresh.account_chart().expect("export chart"), sample_chart());
+    }
+
+    #[test]
+    fn import_chart_skips_existing_accounts_unless_updating_notes() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        db.import_chart(&sample_chart(), false)
+            .expect("import chart");
+
+        let mut edited = sample_chart();
+        edited[0].note = Some("updated".to_string());
+        edited[1]
+            .children
+            .push(chart_account("Rent", None, Vec::new()));
+        let summary = db.import_chart(&edited, false).expect("reimport chart");
+        assert_eq!(summary.created, vec!["Expenses:Rent"]);
+        assert_eq!(summary.skipped.len(), 6);
+        assert!(summary.notes_updated.is_empty());
+        assert_eq!(
+            db.account_chart().unwrap()[0].note.as_deref(),
+            Some("everything \"owned\"")
+        );
+
+        let summary = db.import_chart(&edited, true).expect("update notes");
+        assert!(summary.created.is_empty());
+        assert_eq!(summary.notes_updated, vec!["Assets"]);
+        assert_eq!(
+            db.account_chart().unwrap()[0].note.as_deref(),
+            Some("updated")
+        );
+    }
+
+    #[test]
+    fn import_chart_creates_nothing_when_an_insert_fails() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        db.conn()
+            .execute_batch(
+                "
+                CREATE TRIGGER reject_food BEFORE INSERT ON accounts
+                WHEN NEW.name = 'Food'
+                BEGIN SELECT RAISE(ABORT, 'no food'); END;
+                ",
+            )
+            .expect("create trigger");
+
+        assert!(matches!(
+            db.import_chart(&sample_chart(), false),
+            Err(AccountWriteError::Sql(_))
+        ));
+        assert!(db.list_accounts().expect("list accounts").is_empty());
+    }
+}
@@ -1,11 +1,36 @@
 use super::account::AccountWriteError;
-use super::db::{Db, SchemaVersionError};
+use super::analytics::{AccountCoverage, CoverageError};
+use super::chart::{
+    chart_from_accounts, format_chart, parse_chart, AccountChartError, ChartImportSummary,
+};
+use super::db::{Db, DbError, DbSizeInfo, MigrationStatusError, SchemaVersionError, VacuumError};
+use super::file_hash::HashAlgorithm;
+use super::lock::{DbLock, LockError};
+use super::migration::MigrationStatus;
+use super::profile::{Profile, ProfileError, ProfileRegistry};
+use super::transaction::{Transaction, TransactionListError};
 use super::{Account, AccountListError};
-use super::user_data::{UserDataError, UserDataManager};
+use super::user_data::{
+    ManifestCheck, ManifestEntry, ManifestError, MigrateDataDirError, MigrationSummary,
+    PruneStatementsError, PruneSummary, ReshardError, ReshardSummary, StatementLayout,
+    UndoStatementError, UndoneStatement, UserDataError, UserDataManager,
+    STALE_TEMP_STATEMENT_AGE,
+};
+use super::statement::{
+    AddStatementError, AddStatementInput, ImportedSince, InvalidStatementPeriod, Statement,
+    StatementCurrencyMismatch, StatementListError, StatementWriteError,
+};
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use uuid::Uuid;
 
+/// Lock shared by operations that rewrite statements and their files.
+const STATEMENTS_LOCK: &str = "statements";
+/// How long a statements lock is honored before another writer may take it over.
+const STATEMENTS_LOCK_TTL: Duration = Duration::from_secs(60 * 60);
+
 pub struct Core {
     _user_data: UserDataManager,
     _db: Db,
@@ -18,21 +43,55 @@ pub struct VersionInfo {
     pub data_dir: PathBuf,
 }
 
+/// The library's error type. Every module's error converts into it, and `source()` walks
+/// down to the underlying sqlite or io error.
+pub type Error = CoreError;
+
 #[derive(Debug)]
 pub enum CoreError {
     UserData(UserDataError),
     AccountList(AccountListError),
     AccountWrite(AccountWriteError),
     SchemaVersion(SchemaVersionError),
+    MigrateDataDir(MigrateDataDirError),
+    Reshard(ReshardError),
+    StatementList(StatementListError),
+    TransactionList(TransactionListError),
+    Vacuum(VacuumError),
+    MigrationStatus(MigrationStatusError),
+    Manifest(ManifestError),
+    Profile(ProfileError),
+    UndoStatement(UndoStatementError),
+    PruneStatements(PruneStatementsError),
+    Coverage(CoverageError),
+    Lock(LockError),
+    AccountChart(AccountChartError),
+    FixPeriods(StatementWriteError),
+    AddStatement(AddStatementError),
 }
 
 impl Display for CoreError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::UserData(err) => write!(f, "failed to initialize core: {err}"),
-            Self::AccountList(err) => write!(f, "failed to list accounts: {err}"),
-            Self::AccountWrite(err) => write!(f, "failed to create account: {err}"),
-            Self::SchemaVersion(err) => write!(f, "failed to read schema version: {err}"),
+            Self::UserData(_) => write!(f, "failed to initialize core"),
+            Self::AccountList(_) => write!(f, "failed to list accounts"),
+            Self::AccountWrite(_) => write!(f, "failed to create account"),
+            Self::SchemaVersion(_) => write!(f, "failed to read schema version"),
+            Self::MigrateDataDir(_) => write!(f, "failed to move data directory"),
+            Self::Reshard(_) => write!(f, "failed to reshard statement files"),
+            Self::StatementList(_) => write!(f, "failed to list statements"),
+            Self::TransactionList(_) => write!(f, "failed to search transactions"),
+            Self::Vacuum(_) => write!(f, "failed to vacuum database"),
+            Self::MigrationStatus(_) => write!(f, "failed to read migration status"),
+            Self::Manifest(_) => write!(f, "statement manifest failed"),
+            Self::Profile(_) => write!(f, "profile operation failed"),
+            Self::UndoStatement(_) => write!(f, "failed to undo statement import"),
+            Self::PruneStatements(_) => write!(f, "failed to prune statements"),
+            Self::Coverage(_) => write!(f, "failed to compute statement coverage"),
+            Self::Lock(err) => write!(f, "{err}"),
+            Self::AccountChart(_) => write!(f, "account chart operation failed"),
+            Self::FixPeriods(_) => write!(f, "failed to fix statement periods"),
+            Self::AddStatement(_) => write!(f, "failed to import statement"),
         }
     }
 }
@@ -44,6 +103,22 @@ impl std::error::Error for CoreError {
             Self::AccountList(err) => Some(err),
             Self::AccountWrite(err) => Some(err),
             Self::SchemaVersion(err) => Some(err),
+            Self::MigrateDataDir(err) => Some(err),
+            Self::Reshard(err) => Some(err),
+            Self::StatementList(err) => Some(err),
+            Self::TransactionList(err) => Some(err),
+            Self::Vacuum(err) => Some(err),
+            Self::MigrationStatus(err) => Some(err),
+            Self::Manifest(err) => Some(err),
+            Self::Profile(err) => Some(err),
+            Self::UndoStatement(err) => Some(err),
+            Self::PruneStatements(err) => Some(err),
+            Self::Coverage(err) => Some(err),
+            // Displayed as is, so its source is this error's source.
+            Self::Lock(err) => err.source(),
+            Self::AccountChart(err) => Some(err),
+            Self::FixPeriods(err) => Some(err),
+            Self::AddStatement(err) => Some(err),
         }
     }
 }
@@ -54,6 +129,12 @@ impl From<UserDataError> for CoreError {
     }
 }
 
+impl From<DbError> for CoreError {
+    fn from(value: DbError) -> Self {
+        Self::UserData(UserDataError::OpenDb(value))
+    }
+}
+
 impl From<AccountListError> for CoreError {
     fn from(value: AccountListError) -> Self {
         Self::AccountList(value)
@@ -72,6 +153,90 @@ impl From<SchemaVersionError> for CoreError {
     }
 }
 
+impl From<MigrateDataDirError> for CoreError {
+    fn from(value: MigrateDataDirError) -> Self {
+        Self::MigrateDataDir(value)
+    }
+}
+
+impl From<ReshardError> for CoreError {
+    fn from(value: ReshardError) -> Self {
+        Self::Reshard(value)
+    }
+}
+
+impl From<StatementListError> for CoreError {
+    fn from(value: StatementListError) -> Self {
+        Self::StatementList(value)
+    }
+}
+
+impl From<TransactionListError> for CoreError {
+    fn from(value: TransactionListError) -> Self {
+        Self::TransactionList(value)
+    }
+}
+
+impl From<VacuumError> for CoreError {
+    fn from(value: VacuumError) -> Self {
+        Self::Vacuum(value)
+    }
+}
+
+impl From<MigrationStatusError> for CoreError {
+    fn from(value: MigrationStatusError) -> Self {
+        Self::MigrationStatus(value)
+    }
+}
+
+impl From<ManifestError> for CoreError {
+    fn from(value: ManifestError) -> Self {
+        Self::Manifest(value)
+    }
+}
+
+impl From<ProfileError> for CoreError {
+    fn from(value: ProfileError) -> Self {
+        Self::Profile(value)
+    }
+}
+
+impl From<UndoStatementError> for CoreError {
+    fn from(value: UndoStatementError) -> Self {
+        Self::UndoStatement(value)
+    }
+}
+
+impl From<PruneStatementsError> for CoreError {
+    fn from(value: PruneStatementsError) -> Self {
+        Self::PruneStatements(value)
+    }
+}
+
+impl From<CoverageError> for CoreError {
+    fn from(value: CoverageError) -> Self {
+        Self::Coverage(value)
+    }
+}
+
+impl From<LockError> for CoreError {
+    fn from(value: LockError) -> Self {
+        Self::Lock(value)
+    }
+}
+
+impl From<AccountChartError> for CoreError {
+    fn from(value: AccountChartError) -> Self {
+        Self::AccountChart(value)
+    }
+}
+
+impl From<AddStatementError> for CoreError {
+    fn from(value: AddStatementError) -> Self {
+        Self::AddStatement(value)
+    }
+}
+
 impl Core {
     pub fn from_environment() -> Result<Self, CoreError> {
         let user_data = UserDataManager::from_environment()?;
@@ -83,6 +248,29 @@ impl Core {
         Self::from_user_data(user_data)
     }
 
+    /// Like `from_data_dir`, but the database must already exist and is opened read-only
+    /// without running migrations; see `Db::open_read_only`.
+    pub fn open_read_only(data_dir: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let user_data = UserDataManager::from_data_dir(data_dir);
+        let db = user_data.open_db_read_only()?;
+        Ok(Self {
+            _user_data: user_data,
+            _db: db,
+        })
+    }
+
+    pub fn open_read_only_from_environment() -> Result<Self, CoreError> {
+        let user_data = UserDataManager::from_environment()?;
+        Self::open_read_only(user_data.data_dir())
+    }
+
+    /// Hash statement files imported from now on with `hash_algorithm`; see
+    /// `UserDataManager::with_hash_algorithm`.
+    pub fn with_hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Self {
+        self._user_data = self._user_data.with_hash_algorithm(hash_algorithm);
+        self
+    }
+
     pub fn init(&self) -> Result<(), CoreError> {
         Ok(())
     }
@@ -91,10 +279,33 @@ impl Core {
         self._user_data.db_path()
     }
 
+    pub fn vacuum_db(&self) -> Result<(DbSizeInfo, DbSizeInfo), CoreError> {
+        self._db.vacuum().map_err(CoreError::from)
+    }
+
     pub fn list_accounts(&self) -> Result<Vec<Account>, CoreError> {
         self._db.list_accounts().map_err(CoreError::from)
     }
 
+    pub fn list_statements(&self) -> Result<Vec<Statement>, CoreError> {
+        self._db.list_statements().map_err(CoreError::from)
+    }
+
+    pub fn list_statements_imported_since(
+        &self,
+        since: &ImportedSince,
+    ) -> Result<Vec<Statement>, CoreError> {
+        let cutoff = self
+            ._db
+            .imported_since_cutoff(since)
+            .map_err(|err| CoreError::StatementList(StatementListError::Sql(err)))?;
+        Ok(self._db.list_statements_imported_since(&cutoff)?)
+    }
+
+    pub fn search_transactions(&self, query: &str) -> Result<Vec<Transaction>, CoreError> {
+        self._db.search_transactions(query).map_err(CoreError::from)
+    }
+
     pub fn create_account(
         &self,
         name: &str,
@@ -106,6 +317,128 @@ impl Core {
             .map_err(CoreError::from)
     }
 
+    /// Create the accounts in the chart-of-accounts file at `path`; see
+    /// `Db::import_chart`.
+    pub fn import_account_chart(
+        &self,
+        path: &Path,
+        update_notes: bool,
+    ) -> Result<ChartImportSummary, CoreError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|source| AccountChartError::Read {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        let chart = parse_chart(&contents).map_err(|source| AccountChartError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let summary = self
+            ._db
+            .import_chart(&chart, update_notes)
+            .map_err(AccountChartError::from)?;
+        Ok(summary)
+    }
+
+    /// Write every account to `path` as a chart-of-accounts file, returning how many
+    /// accounts it holds.
+    pub fn export_account_chart(&self, path: &Path) -> Result<usize, CoreError> {
+        let accounts = self._db.list_accounts().map_err(AccountChartError::from)?;
+        let chart = chart_from_accounts(&accounts);
+        std::fs::write(path, format_chart(&chart)).map_err(|source| {
+            AccountChartError::Write {
+                path: path.to_path_buf(),
+                source,
+            }
+        })?;
+        Ok(accounts.len())
+    }
+
+    /// Copy the file at `source_path` into the data dir and record it as a statement; see
+    /// `UserDataManager::add_statement`. Also returns the stale statements lock this took
+    /// over, if any.
+    pub fn add_statement(
+        &self,
+        source_path: &Path,
+        input: AddStatementInput,
+    ) -> Result<(Statement, Option<DbLock>), CoreError> {
+        self.with_statements_lock("import-statement", || {
+            Ok(self._user_data.add_statement(source_path, input)?)
+        })
+    }
+
+    /// Remove temp files that imports which crashed over a day ago left in the statements
+    /// dir, returning their paths.
+    pub fn cleanup_stale_temp_files(&self) -> Result<Vec<PathBuf>, CoreError> {
+        Ok(self
+            ._user_data
+            .cleanup_stale_temp_files(STALE_TEMP_STATEMENT_AGE)?)
+    }
+
+    pub fn last_imported_statement(&self) -> Result<Option<Statement>, CoreError> {
+        self._user_data
+            .last_imported_statement()
+            .map_err(CoreError::from)
+    }
+
+    pub fn undo_last_statement(&self) -> Result<Option<UndoneStatement>, CoreError> {
+        self._user_data.undo_last_statement().map_err(CoreError::from)
+    }
+
+    /// See `UserDataManager::prune_superseded_statements`. Also returns the stale
+    /// statements lock this took over, if any.
+    pub fn prune_superseded_statements(
+        &self,
+        keep_latest: usize,
+        delete_files: bool,
+    ) -> Result<(PruneSummary, Option<DbLock>), CoreError> {
+        self.with_statements_lock("prune-statements", || {
+            self._user_data
+                .prune_superseded_statements(keep_latest, delete_files)
+                .map_err(CoreError::from)
+        })
+    }
+
+    /// Monthly statement coverage, expecting open accounts to be covered through the
+    /// last complete month.
+    pub fn monthly_coverage(&self) -> Result<Vec<AccountCoverage>, CoreError> {
+        let through = self._db.current_month()?.previous();
+        Ok(self._db.monthly_coverage(through)?)
+    }
+
+    /// The amount of every transaction posted in `from..=to` per currency, for bucketing
+    /// with `bucket_amounts`; only postings in `currency` when given.
+    pub fn transaction_amounts(
+        &self,
+        from: Option<&str>,
+        to: Option<&str>,
+        currency: Option<&str>,
+    ) -> Result<BTreeMap<String, Vec<i64>>, CoreError> {
+        Ok(self._db.transaction_amounts(from, to, currency)?)
+    }
+
+    pub fn statement_currency_mismatches(
+        &self,
+    ) -> Result<Vec<StatementCurrencyMismatch>, CoreError> {
+        Ok(self._db.statement_currency_mismatches()?)
+    }
+
+    pub fn invalid_statement_periods(
+        &self,
+    ) -> Result<Vec<InvalidStatementPeriod>, CoreError> {
+        Ok(self._db.invalid_statement_periods()?)
+    }
+
+    /// Rewrite the invalid statement periods that can be read as dates; see
+    /// `Db::fix_statement_periods`.
+    pub fn fix_statement_periods(&self) -> Result<Vec<InvalidStatementPeriod>, CoreError> {
+        self._db.fix_statement_periods().map_err(CoreError::FixPeriods)
+    }
+
+    pub fn migration_status(&self) -> Result<Vec<MigrationStatus>, CoreError> {
+        self._db.migration_status().map_err(CoreError::from)
+    }
+
     pub fn version_info(&self) -> Result<VersionInfo, CoreError> {
         Ok(VersionInfo {
             app_version: env!("CARGO_PKG_VERSION").to_string(),
@@ -121,6 +454,117 @@ impl Core {
         Ok((db_path, deleted))
     }
 
+    pub fn list_profiles_from_environment() -> Result<Vec<Profile>, CoreError> {
+        Ok(ProfileRegistry::from_environment()?.list()?)
+    }
+
+    pub fn create_profile_from_environment(name: &str) -> Result<Profile, CoreError> {
+        Ok(ProfileRegistry::from_environment()?.create(name)?)
+    }
+
+    pub fn remove_profile_from_environment(name: &str) -> Result<Profile, CoreError> {
+        Ok(ProfileRegistry::from_environment()?.remove(name)?)
+    }
+
+    /// Works without opening the database so it stays usable when the db is damaged.
+    pub fn write_manifest_from_environment() -> Result<(PathBuf, Vec<ManifestEntry>), CoreError> {
+        let user_data = UserDataManager::from_environment()?;
+        let entries = user_data.write_manifest()?;
+        Ok((user_data.manifest_path(), entries))
+    }
+
+    /// Temp files that imports which crashed over a day ago left in the statements dir.
+    /// Needs no database, like the manifest check.
+    pub fn stale_temp_files_from_environment() -> Result<Vec<PathBuf>, CoreError> {
+        let user_data = UserDataManager::from_environment()?;
+        Ok(user_data.stale_temp_files(STALE_TEMP_STATEMENT_AGE)?)
+    }
+
+    /// Remove the files `stale_temp_files_from_environment` lists, returning their paths.
+    pub fn cleanup_stale_temp_files_from_environment() -> Result<Vec<PathBuf>, CoreError> {
+        let user_data = UserDataManager::from_environment()?;
+        Ok(user_data.cleanup_stale_temp_files(STALE_TEMP_STATEMENT_AGE)?)
+    }
+
+    pub fn check_manifest_from_environment() -> Result<ManifestCheck, CoreError> {
+        let user_data = UserDataManager::from_environment()?;
+        user_data.check_manifest().map_err(CoreError::from)
+    }
+
+    /// See `migrate_data_dir`. The source database must already exist; it is not
+    /// created just to be moved.
+    pub fn migrate_data_dir_from_environment(
+        target: impl AsRef<Path>,
+        remove_source: bool,
+    ) -> Result<(MigrationSummary, Option<DbLock>), CoreError> {
+        let user_data = UserDataManager::from_environment()?;
+        if !user_data.db_path().is_file() {
+            let path = user_data.db_path().to_path_buf();
+            return Err(MigrateDataDirError::SourceDbMissing(path).into());
+        }
+        Self::from_user_data(user_data)?.migrate_data_dir(target.as_ref(), remove_source)
+    }
+
+    /// See `UserDataManager::migrate_data_dir`. Runs while holding the statements lock,
+    /// so no import or prune writes into the source while it is copied. Also returns the
+    /// stale statements lock this took over, if any.
+    pub fn migrate_data_dir(
+        &self,
+        target: &Path,
+        remove_source: bool,
+    ) -> Result<(MigrationSummary, Option<DbLock>), CoreError> {
+        let holder = format!("move-data (pid {})", std::process::id());
+        let stale = self._db.try_lock(STATEMENTS_LOCK, &holder, STATEMENTS_LOCK_TTL)?;
+        let result = self
+            ._user_data
+            .migrate_data_dir(target, remove_source)
+            .map_err(CoreError::from)
+            .and_then(|summary| {
+                // The backup copied the lock row; the moved data dir starts unlocked.
+                let target_db = Db::open(UserDataManager::from_data_dir(target).db_path())?;
+                target_db
+                    .unlock(STATEMENTS_LOCK, &holder)
+                    .map_err(|err| CoreError::Lock(LockError::Sql(err)))?;
+                Ok(summary)
+            });
+        // A removed source took its lock row with it.
+        if !matches!(&result, Ok(summary) if summary.source_removed) {
+            let unlocked = self._db.unlock(STATEMENTS_LOCK, &holder);
+            if result.is_ok() {
+                unlocked.map_err(|err| CoreError::Lock(LockError::Sql(err)))?;
+            }
+        }
+        Ok((result?, stale))
+    }
+
+    /// See `UserDataManager::reshard_statements`. Also returns the stale statements lock
+    /// this took over, if any.
+    pub fn reshard_statements(
+        &self,
+        layout: StatementLayout,
+    ) -> Result<(ReshardSummary, Option<DbLock>), CoreError> {
+        self.with_statements_lock("reshard-statements", || {
+            Ok(self._user_data.reshard_statements(layout)?)
+        })
+    }
+
+    /// Run `operation` while holding the statements lock, so a second writer gets a clear
+    /// error instead of interleaving with it. The lock is released even if `operation` fails.
+    /// Returns the stale lock taken over to run it, if any, for the caller to warn about.
+    fn with_statements_lock<T>(
+        &self,
+        operation: &str,
+        run: impl FnOnce() -> Result<T, CoreError>,
+    ) -> Result<(T, Option<DbLock>), CoreError> {
+        let holder = format!("{operation} (pid {})", std::process::id());
+        let stale = self._db.try_lock(STATEMENTS_LOCK, &holder, STATEMENTS_LOCK_TTL)?;
+        let result = run();
+        let unlocked = self._db.unlock(STATEMENTS_LOCK, &holder);
+        let value = result?;
+        unlocked.map_err(|err| CoreError::Lock(LockError::Sql(err)))?;
+        Ok((value, stale))
+    }
+
     pub(super) fn db_mut(&mut self) -> &mut Db {
         &mut self._db
     }
@@ -137,6 +581,7 @@ impl Core {
 
     fn from_user_data(user_data: UserDataManager) -> Result<Self, CoreError> {
         let db = user_data.open_db()?;
+        let user_data = user_data.with_stored_statement_layout(&db)?;
         Ok(Self {
             _user_data: user_data,
             _db: db,
@@ -198,7 +643,143 @@ mod tests {
         let info = core.version_info().expect("version info");
 
         assert_eq!(info.app_version, env!("CARGO_PKG_VERSION"));
-        assert_eq!(info.schema_version, 4);
+        assert_eq!(info.schema_version, 9);
         assert_eq!(info.data_dir, data_dir);
     }
+
+    #[test]
+    fn prune_refuses_while_another_writer_holds_the_statements_lock() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let core = Core::from_data_dir(temp_dir.path().join("state")).expect("open core");
+        core._db
+            .try_lock(STATEMENTS_LOCK, "reshard-statements (pid 1)", STATEMENTS_LOCK_TTL)
+            .expect("take lock");
+
+        let err = core
+            .prune_superseded_statements(1, false)
+            .expect_err("lock is held");
+        assert!(matches!(err, CoreError::Lock(LockError::Held(_))));
+
+        core._db
+            .unlock(STATEMENTS_LOCK, "reshard-statements (pid 1)")
+            .expect("unlock");
+        let (_, stale) = core.prune_superseded_statements(1, false).expect("prune");
+        assert_eq!(stale, None);
+        assert_eq!(core._db.lock(STATEMENTS_LOCK).expect("read lock"), None);
+    }
+
+    #[test]
+    fn move_data_refuses_while_another_writer_holds_the_statements_lock() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let core = Core::from_data_dir(temp_dir.path().join("state")).expect("open core");
+        let target = temp_dir.path().join("moved");
+        core._db
+            .try_lock(STATEMENTS_LOCK, "import-statement (pid 1)", STATEMENTS_LOCK_TTL)
+            .expect("take lock");
+
+        let err = core
+            .migrate_data_dir(&target, false)
+            .expect_err("lock is held");
+        assert!(matches!(err, CoreError::Lock(LockError::Held(_))));
+        assert!(!target.exists());
+
+        core._db
+            .unlock(STATEMENTS_LOCK, "import-statement (pid 1)")
+            .expect("unlock");
+        let (summary, stale) = core.migrate_data_dir(&target, false).expect("move data");
+        assert_eq!(stale, None);
+        assert!(!summary.source_removed);
+        assert_eq!(core._db.lock(STATEMENTS_LOCK).expect("read lock"), None);
+        let moved = Db::open(target.join("tally42.db")).expect("open moved db");
+        assert_eq!(moved.lock(STATEMENTS_LOCK).expect("read moved lock"), None);
+    }
+
+    #[test]
+    fn statements_lock_returns_the_stale_lock_it_took_over() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let core = Core::from_data_dir(temp_dir.path().join("state")).expect("open core");
+        core._db
+            .try_lock(STATEMENTS_LOCK, "reshard-statements (pid 1)", STATEMENTS_LOCK_TTL)
+            .expect("take lock");
+        core._db
+            .conn()
+            .execute(
+                "UPDATE locks SET acquired_at = datetime('now', '-2 days') WHERE name = ?1",
+                [STATEMENTS_LOCK],
+            )
+            .expect("backdate lock");
+
+        let (_, stale) = core.prune_superseded_statements(1, false).expect("prune");
+
+        assert_eq!(stale.expect("stale lock").holder, "reshard-statements (pid 1)");
+        assert_eq!(core._db.lock(STATEMENTS_LOCK).expect("read lock"), None);
+    }
+
+    #[test]
+    fn with_hash_algorithm_applies_to_imported_statements() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let core = Core::from_data_dir(temp_dir.path().join("state"))
+            .expect("open core")
+            .with_hash_algorithm(HashAlgorithm::Blake3);
+        let account_id = Uuid::parse_str("31313131-3131-3131-3131-313131313131").unwrap();
+        core._db
+            .create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let source_path = temp_dir.path().join("statement.pdf");
+        std::fs::write(&source_path, b"%PDF-1.7 sample").expect("write statement");
+
+        let (statement, _) = core
+            .add_statement(
+                &source_path,
+                AddStatementInput {
+                    institution: "Bank".to_string(),
+                    account_id,
+                    period_start: "2026-01-01".to_string(),
+                    period_end: "2026-01-31".to_string(),
+                    currency: "USD".to_string(),
+                    replaced_by: None,
+                    allow_currency_mismatch: false,
+                },
+            )
+            .expect("add statement");
+
+        assert!(statement.file_hash.starts_with("blake3:"), "{}", statement.file_hash);
+    }
+
+    #[test]
+    fn imports_after_resharding_use_the_stored_layout() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let data_dir = temp_dir.path().join("state");
+        let core = Core::from_data_dir(&data_dir).expect("open core");
+        let account_id = Uuid::parse_str("32323232-3232-3232-3232-323232323232").unwrap();
+        core._db
+            .create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        core.reshard_statements(StatementLayout::ByAccount)
+            .expect("reshard to by-account");
+        drop(core);
+
+        // Every command opens its own core, as the CLI does.
+        let core = Core::from_data_dir(&data_dir).expect("reopen core");
+        let source_path = temp_dir.path().join("statement.pdf");
+        std::fs::write(&source_path, b"%PDF-1.7 after reshard").expect("write statement");
+        let (statement, _) = core
+            .add_statement(
+                &source_path,
+                AddStatementInput {
+                    institution: "Bank".to_string(),
+                    account_id,
+                    period_start: "2026-02-01".to_string(),
+                    period_end: "2026-02-28".to_string(),
+                    currency: "USD".to_string(),
+                    replaced_by: None,
+                    allow_currency_mismatch: false,
+                },
+            )
+            .expect("add statement");
+
+        let path = core._user_data.statement_file_path(&statement.file_hash);
+        assert_eq!(path.parent(), Some(data_dir.join("statements").join("checking").as_path()));
+        assert!(path.is_file());
+    }
 }
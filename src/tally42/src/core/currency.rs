@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// Decimal places used when a currency has neither an override nor an ISO default.
+pub const DEFAULT_DECIMAL_PLACES: u32 = 2;
+
+/// The most decimal places a currency can have: one more and a single whole unit would
+/// not fit in an `i64` of minor units.
+pub const MAX_DECIMAL_PLACES: u32 = 18;
+
+/// ISO 4217 currencies whose minor unit is not a hundredth.
+const ISO_DECIMAL_PLACES: &[(&str, u32)] = &[
+    ("BHD", 3),
+    ("BIF", 0),
+    ("CLP", 0),
+    ("DJF", 0),
+    ("GNF", 0),
+    ("IQD", 3),
+    ("ISK", 0),
+    ("JOD", 3),
+    ("JPY", 0),
+    ("KMF", 0),
+    ("KRW", 0),
+    ("KWD", 3),
+    ("LYD", 3),
+    ("OMR", 3),
+    ("PYG", 0),
+    ("RWF", 0),
+    ("TND", 3),
+    ("UGX", 0),
+    ("VND", 0),
+    ("VUV", 0),
+    ("XAF", 0),
+    ("XOF", 0),
+    ("XPF", 0),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CurrencyPrecisionError {
+    TooManyPlaces { currency: String, places: u32 },
+}
+
+impl Display for CurrencyPrecisionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooManyPlaces { currency, places } => write!(
+                f,
+                "{currency} cannot have {places} decimal places, at most {MAX_DECIMAL_PLACES}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CurrencyPrecisionError {}
+
+/// How many decimal places each currency's minor unit has. Amounts are stored as
+/// integer minor units, so this only matters when reading or displaying decimals:
+/// `1250` is `12.50` USD but `1250` JPY.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CurrencyPrecision {
+    overrides: HashMap<String, u32>,
+}
+
+impl CurrencyPrecision {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `places` for `currency`, e.g. for a token tracked to more decimals than any
+    /// ISO currency. At most `MAX_DECIMAL_PLACES`.
+    pub fn set(&mut self, currency: &str, places: u32) -> Result<(), CurrencyPrecisionError> {
+        if places > MAX_DECIMAL_PLACES {
+            return Err(CurrencyPrecisionError::TooManyPlaces {
+                currency: currency.to_string(),
+                places,
+            });
+        }
+        self.overrides.insert(currency.to_ascii_uppercase(), places);
+        Ok(())
+    }
+
+    /// Decimal places for `currency` (case-insensitive): an override, else the ISO
+    /// default, else `DEFAULT_DECIMAL_PLACES`.
+    pub fn places(&self, currency: &str) -> u32 {
+        let currency = currency.to_ascii_uppercase();
+        if let Some(places) = self.overrides.get(&currency) {
+            return *places;
+        }
+        ISO_DECIMAL_PLACES
+            .iter()
+            .find(|(code, _)| *code == currency)
+            .map_or(DEFAULT_DECIMAL_PLACES, |(_, places)| *places)
+    }
+}
+
+/// Minor units as a decimal amount with `places` fractional digits, e.g. `-1250` as
+/// `-12.50` for 2 places or `-1250` for 0.
+pub fn format_minor_units(amount: i64, places: u32) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    let abs = amount.unsigned_abs();
+    if places == 0 {
+        return format!("{sign}{abs}");
+    }
+    // From 20 places the scale overflows, but every `i64` is then a fraction of a unit.
+    let (whole, fraction) = match 10u64.checked_pow(places) {
+        Some(scale) => (abs / scale, abs % scale),
+        None => (0, abs),
+    };
+    let width = places as usize;
+    format!("{sign}{whole}.{fraction:0width$}")
+}
+
+/// Parse a decimal amount with at most `places` fractional digits into minor units.
+pub fn parse_minor_units(value: &str, places: u32) -> Option<i64> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let is_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+    let width = places as usize;
+    if whole.is_empty() || fraction.len() > width || !is_digits(whole) || !is_digits(fraction) {
+        return None;
+    }
+    let minor = if width == 0 {
+        0
+    } else {
+        format!("{fraction:0<width$}").parse::<i64>().ok()?
+    };
+    let amount = whole
+        .parse::<i64>()
+        .ok()?
+        .checked_mul(10i64.checked_pow(places)?)?
+        .checked_add(minor)?;
+    Some(if negative { -amount } else { amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_use_iso_defaults_and_overrides() {
+        let mut precision = CurrencyPrecision::new();
+        assert_eq!(precision.places("USD"), 2);
+        assert_eq!(precision.places("jpy"), 0);
+        assert_eq!(precision.places("KWD"), 3);
+        assert_eq!(precision.places("XYZ"), DEFAULT_DECIMAL_PLACES);
+
+        precision.set("xyz", 4).expect("4 places");
+        precision.set("JPY", 2).expect("2 places");
+        assert_eq!(precision.places("XYZ"), 4);
+        assert_eq!(precision.places("JPY"), 2);
+    }
+
+    #[test]
+    fn jpy_amounts_have_no_decimals() {
+        let places = CurrencyPrecision::new().places("JPY");
+        assert_eq!(format_minor_units(1250, places), "1250");
+        assert_eq!(format_minor_units(-7, places), "-7");
+        assert_eq!(parse_minor_units("1250", places), Some(1250));
+        assert_eq!(parse_minor_units("12.5", places), None);
+    }
+
+    #[test]
+    fn custom_four_place_currency() {
+        let mut precision = CurrencyPrecision::new();
+        precision.set("XYZ", 4).expect("4 places");
+        let places = precision.places("XYZ");
+        assert_eq!(format_minor_units(12_345, places), "1.2345");
+        assert_eq!(format_minor_units(-5, places), "-0.0005");
+        assert_eq!(parse_minor_units("1.2345", places), Some(12_345));
+        assert_eq!(parse_minor_units("-0.5", places), Some(-5000));
+        assert_eq!(parse_minor_units("1.23456", places), None);
+    }
+
+    #[test]
+    fn precision_is_capped_and_large_places_do_not_overflow() {
+        let mut precision = CurrencyPrecision::new();
+        assert_eq!(precision.set("XYZ", MAX_DECIMAL_PLACES), Ok(()));
+        assert_eq!(
+            precision.set("abc", 20),
+            Err(CurrencyPrecisionError::TooManyPlaces {
+                currency: "abc".to_string(),
+                places: 20
+            })
+        );
+        assert_eq!(precision.places("ABC"), DEFAULT_DECIMAL_PLACES);
+
+        assert_eq!(format_minor_units(-5, 20), "-0.00000000000000000005");
+        assert_eq!(format_minor_units(i64::MAX, 19), "0.9223372036854775807");
+        assert_eq!(parse_minor_units("1", 19), None);
+        assert_eq!(parse_minor_units("0.5", 25), None);
+    }
+
+    #[test]
+    fn two_place_amounts() {
+        assert_eq!(format_minor_units(0, 2), "0.00");
+        assert_eq!(format_minor_units(5, 2), "0.05");
+        assert_eq!(format_minor_units(123_456, 2), "1234.56");
+        assert_eq!(format_minor_units(-1250, 2), "-12.50");
+        assert_eq!(parse_minor_units("25.5", 2), Some(2550));
+        assert_eq!(parse_minor_units(".5", 2), None);
+    }
+}
@@ -1,8 +1,9 @@
 use super::migration::{
-    Migration, MigrationDiscoveryError, MigrationRunner, MigrationRunnerError, MigrationsDir,
+    Migration, MigrationDiscoveryError, MigrationRunner, MigrationRunnerError, MigrationStatus,
+    MigrationsDir,
 };
 use std::fmt::{Display, Formatter};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub struct Db {
     conn: rusqlite::Connection,
@@ -17,7 +18,7 @@ pub enum SchemaVersionError {
 impl Display for SchemaVersionError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Sql(err) => write!(f, "sqlite error while reading schema version: {err}"),
+            Self::Sql(_) => write!(f, "sqlite error while reading schema version"),
             Self::InvalidVersion(version) => {
                 write!(f, "invalid schema version in database: {version}")
             }
@@ -40,9 +41,85 @@ impl From<rusqlite::Error> for SchemaVersionError {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbSizeInfo {
+    pub page_size: u64,
+    pub page_count: u64,
+    pub freelist_count: u64,
+}
+
+impl DbSizeInfo {
+    /// Size of the main database file.
+    pub fn file_bytes(&self) -> u64 {
+        self.page_size * self.page_count
+    }
+
+    /// Bytes held by free pages that `VACUUM` would return to the filesystem.
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.page_size * self.freelist_count
+    }
+}
+
+#[derive(Debug)]
+pub enum VacuumError {
+    Sql(rusqlite::Error),
+    InTransaction,
+}
+
+impl Display for VacuumError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sql(_) => write!(f, "sqlite error while vacuuming database"),
+            Self::InTransaction => {
+                write!(f, "cannot vacuum database while a transaction is open")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VacuumError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Sql(err) => Some(err),
+            Self::InTransaction => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for VacuumError {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sql(value)
+    }
+}
+
+#[derive(Debug)]
+pub enum MigrationStatusError {
+    Discover(MigrationDiscoveryError),
+    Read(MigrationRunnerError),
+}
+
+impl Display for MigrationStatusError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Discover(_) => write!(f, "failed to discover embedded migrations"),
+            Self::Read(_) => write!(f, "failed to read applied migrations"),
+        }
+    }
+}
+
+impl std::error::Error for MigrationStatusError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Discover(err) => Some(err),
+            Self::Read(err) => Some(err),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum DbError {
     Open(rusqlite::Error),
+    Missing(PathBuf),
     DiscoverMigrations(MigrationDiscoveryError),
     RunMigrations(MigrationRunnerError),
 }
@@ -50,11 +127,12 @@ pub enum DbError {
 impl Display for DbError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Open(err) => write!(f, "failed to open sqlite database: {err}"),
-            Self::DiscoverMigrations(err) => {
-                write!(f, "failed to discover embedded migrations: {err}")
+            Self::Open(_) => write!(f, "failed to open sqlite database"),
+            Self::Missing(path) => write!(f, "database does not exist: {}", path.display()),
+            Self::DiscoverMigrations(_) => {
+                write!(f, "failed to discover embedded migrations")
             }
-            Self::RunMigrations(err) => write!(f, "failed to run embedded migrations: {err}"),
+            Self::RunMigrations(_) => write!(f, "failed to run embedded migrations"),
         }
     }
 }
@@ -63,6 +141,7 @@ impl std::error::Error for DbError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::Open(err) => Some(err),
+            Self::Missing(_) => None,
             Self::DiscoverMigrations(err) => Some(err),
             Self::RunMigrations(err) => Some(err),
         }
@@ -75,6 +154,19 @@ impl Db {
         Self::from_connection(conn)
     }
 
+    /// Open an existing database without creating it or running migrations, so probes
+    /// see the schema as it is on disk.
+    pub fn open_read_only(path: impl AsRef<Path>) -> Result<Self, DbError> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(DbError::Missing(path.to_path_buf()));
+        }
+        let conn =
+            rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(DbError::Open)?;
+        Ok(Self { conn })
+    }
+
     pub fn open_for_tests() -> Result<Self, DbError> {
         let conn = rusqlite::Connection::open_in_memory().map_err(DbError::Open)?;
         Self::from_connection(conn)
@@ -90,6 +182,15 @@ impl Db {
         Ok(Self { conn })
     }
 
+    pub fn migration_status(&self) -> Result<Vec<MigrationStatus>, MigrationStatusError> {
+        let source = MigrationsDir::embedded();
+        let migrations =
+            Migration::from_source(&source).map_err(MigrationStatusError::Discover)?;
+        MigrationRunner::new(&self.conn)
+            .status(&source, &migrations)
+            .map_err(MigrationStatusError::Read)
+    }
+
     pub(crate) fn conn(&self) -> &rusqlite::Connection {
         &self.conn
     }
@@ -108,6 +209,40 @@ impl Db {
             .map_err(SchemaVersionError::from)?;
         u32::try_from(version).map_err(|_| SchemaVersionError::InvalidVersion(version))
     }
+
+    pub fn size_info(&self) -> Result<DbSizeInfo, rusqlite::Error> {
+        let pragma = |name: &str| -> Result<u64, rusqlite::Error> {
+            let value: i64 = self
+                .conn
+                .query_row(&format!("PRAGMA {name}"), [], |row| row.get(0))?;
+            Ok(u64::try_from(value).unwrap_or(0))
+        };
+        Ok(DbSizeInfo {
+            page_size: pragma("page_size")?,
+            page_count: pragma("page_count")?,
+            freelist_count: pragma("freelist_count")?,
+        })
+    }
+
+    /// Rebuild the database file to drop free pages, returning sizes before and after.
+    pub fn vacuum(&self) -> Result<(DbSizeInfo, DbSizeInfo), VacuumError> {
+        if !self.conn.is_autocommit() {
+            return Err(VacuumError::InTransaction);
+        }
+        let before = self.size_info()?;
+        self.conn.execute_batch("VACUUM")?;
+        let after = self.size_info()?;
+        Ok((before, after))
+    }
+}
+
+/// Whether `err` is sqlite's FOREIGN KEY constraint failure.
+pub(crate) fn is_foreign_key_violation(err: &rusqlite::Error) -> bool {
+    matches!(
+        err,
+        rusqlite::Error::SqliteFailure(failure, _)
+            if failure.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_FOREIGNKEY
+    )
 }
 
 #[cfg(test)]
@@ -123,7 +258,7 @@ mod tests {
             .conn
             .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| row.get(0))
             .expect("count applied migrations");
-        assert_eq!(applied_count, 4);
+        assert_eq!(applied_count, 9);
 
         let note_column_exists: i64 = db
             .conn
@@ -171,13 +306,52 @@ mod tests {
             .conn
             .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| row.get(0))
             .expect("count applied migrations");
-        assert_eq!(applied_count, 4);
+        assert_eq!(applied_count, 9);
     }
 
     #[test]
     fn schema_version_returns_highest_applied_migration() {
         let db = Db::open_for_tests().expect("open in-memory db");
 
-        assert_eq!(db.schema_version().expect("schema version"), 4);
+        assert_eq!(db.schema_version().expect("schema version"), 9);
+    }
+
+    #[test]
+    fn vacuum_shrinks_file_after_deletes() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let db_path = temp_dir.path().join("tally42.db");
+        let db = Db::open(&db_path).expect("open file db");
+        db.conn
+            .execute_batch(
+                "
+                WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 3000)
+                INSERT INTO transactions (id, description, posted_at)
+                SELECT printf('tx-%05d', i), printf('padding %0200d', i), '2026-01-01' FROM n;
+                DELETE FROM transactions;
+                ",
+            )
+            .expect("insert and delete rows");
+        let size_before = std::fs::metadata(&db_path).expect("stat db").len();
+        let info = db.size_info().expect("size info");
+        assert!(info.reclaimable_bytes() > 0);
+        assert_eq!(info.file_bytes(), size_before);
+
+        let (before, after) = db.vacuum().expect("vacuum");
+
+        let size_after = std::fs::metadata(&db_path).expect("stat db").len();
+        assert!(size_after < size_before);
+        assert_eq!(before.file_bytes(), size_before);
+        assert_eq!(after.file_bytes(), size_after);
+        assert_eq!(after.freelist_count, 0);
+    }
+
+    #[test]
+    fn vacuum_refuses_inside_open_transaction() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        db.conn.execute_batch("BEGIN").expect("begin transaction");
+
+        let err = db.vacuum().expect_err("vacuum inside transaction should fail");
+
+        assert!(matches!(err, VacuumError::InTransaction));
     }
 }
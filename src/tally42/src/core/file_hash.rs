@@ -0,0 +1,165 @@
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::path::Path;
+
+/// Algorithm used to content-address managed statement files.
+///
+/// Stored hashes are written as `<algorithm>:<hex>`; legacy un-prefixed hashes are SHA-256.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    pub fn from_prefix(value: &str) -> Option<Self> {
+        match value {
+            "sha256" => Some(Self::Sha256),
+            "blake3" => Some(Self::Blake3),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileHashError {
+    UnknownAlgorithm(String),
+    EmptyDigest(String),
+}
+
+impl Display for FileHashError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownAlgorithm(value) => write!(f, "unknown file hash algorithm in '{value}'"),
+            Self::EmptyDigest(value) => write!(f, "file hash has no digest: '{value}'"),
+        }
+    }
+}
+
+impl std::error::Error for FileHashError {}
+
+/// Split a stored `file_hash` into its algorithm and hex digest.
+pub fn parse_file_hash(value: &str) -> Result<(HashAlgorithm, &str), FileHashError> {
+    let (algorithm, digest) = match value.split_once(':') {
+        Some((prefix, digest)) => (
+            HashAlgorithm::from_prefix(prefix)
+                .ok_or_else(|| FileHashError::UnknownAlgorithm(value.to_string()))?,
+            digest,
+        ),
+        None => (HashAlgorithm::Sha256, value),
+    };
+    if digest.is_empty() {
+        return Err(FileHashError::EmptyDigest(value.to_string()));
+    }
+    Ok((algorithm, digest))
+}
+
+pub fn format_file_hash(algorithm: HashAlgorithm, digest: &str) -> String {
+    format!("{}:{digest}", algorithm.as_str())
+}
+
+/// Incremental hasher for whichever algorithm is configured.
+pub enum FileHasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl FileHasher {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            HashAlgorithm::Blake3 => Self::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(bytes),
+            Self::Blake3(hasher) => {
+                hasher.update(bytes);
+            }
+        }
+    }
+
+    /// Prefixed hash suitable for `statements.file_hash`.
+    pub fn finalize(self) -> String {
+        match self {
+            Self::Sha256(hasher) => {
+                format_file_hash(HashAlgorithm::Sha256, &format!("{:x}", hasher.finalize()))
+            }
+            Self::Blake3(hasher) => {
+                format_file_hash(HashAlgorithm::Blake3, hasher.finalize().to_hex().as_str())
+            }
+        }
+    }
+}
+
+/// Hash the file at `path` with `algorithm`, returning a prefixed hash.
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = FileHasher::new(algorithm);
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_file_hash_reads_prefixes_and_legacy_values() {
+        assert_eq!(
+            parse_file_hash("sha256:abc").expect("sha256"),
+            (HashAlgorithm::Sha256, "abc")
+        );
+        assert_eq!(
+            parse_file_hash("blake3:def").expect("blake3"),
+            (HashAlgorithm::Blake3, "def")
+        );
+        assert_eq!(
+            parse_file_hash("abc").expect("legacy"),
+            (HashAlgorithm::Sha256, "abc")
+        );
+        assert_eq!(
+            parse_file_hash("md5:abc"),
+            Err(FileHashError::UnknownAlgorithm("md5:abc".to_string()))
+        );
+        assert_eq!(
+            parse_file_hash("blake3:"),
+            Err(FileHashError::EmptyDigest("blake3:".to_string()))
+        );
+    }
+
+    #[test]
+    fn file_hasher_produces_prefixed_digests() {
+        let mut sha = FileHasher::new(HashAlgorithm::Sha256);
+        sha.update(b"abc");
+        assert_eq!(
+            sha.finalize(),
+            "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let mut blake = FileHasher::new(HashAlgorithm::Blake3);
+        blake.update(b"abc");
+        assert_eq!(
+            blake.finalize(),
+            format!("blake3:{}", blake3::hash(b"abc").to_hex())
+        );
+    }
+}
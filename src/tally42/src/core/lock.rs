@@ -0,0 +1,174 @@
+use super::db::Db;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// A row of the `locks` table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbLock {
+    pub name: String,
+    /// Who took the lock, e.g. `prune-statements (pid 4242)`.
+    pub holder: String,
+    pub acquired_at: String,
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    Sql(rusqlite::Error),
+    Held(DbLock),
+}
+
+impl Display for LockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sql(_) => write!(f, "sqlite error while taking lock"),
+            Self::Held(lock) => write!(
+                f,
+                "operation {} already in progress since {} (lock '{}')",
+                lock.holder, lock.acquired_at, lock.name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LockError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Sql(err) => Some(err),
+            Self::Held(_) => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for LockError {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sql(value)
+    }
+}
+
+impl Db {
+    /// Take the advisory lock `name` for `holder`. A lock older than `ttl` is
+    /// treated as abandoned and taken over; the stale lock is returned so the
+    /// caller can warn about it.
+    pub fn try_lock(
+        &self,
+        name: &str,
+        holder: &str,
+        ttl: Duration,
+    ) -> Result<Option<DbLock>, LockError> {
+        let existing = self.lock(name)?;
+        // Only insert, or replace a lock past its ttl, in one statement so two
+        // writers racing for the same lock cannot both win.
+        let changed = self.conn().execute(
+            "
+            INSERT INTO locks (name, holder, acquired_at)
+            VALUES (?1, ?2, datetime('now'))
+            ON CONFLICT(name) DO UPDATE SET
+              holder = excluded.holder,
+              acquired_at = excluded.acquired_at
+            WHERE locks.acquired_at <= datetime('now', ?3)
+            ",
+            rusqlite::params![name, holder, format!("-{} seconds", ttl.as_secs())],
+        )?;
+        if changed == 0 {
+            let held = self.lock(name)?.or(existing).unwrap_or_else(|| DbLock {
+                name: name.to_string(),
+                holder: "unknown".to_string(),
+                acquired_at: "unknown".to_string(),
+            });
+            return Err(LockError::Held(held));
+        }
+        Ok(existing)
+    }
+
+    /// Release the lock `name` if `holder` still holds it, returning whether it did. A
+    /// lock another writer took over after it went stale is left alone.
+    pub fn unlock(&self, name: &str, holder: &str) -> Result<bool, rusqlite::Error> {
+        let deleted = self.conn().execute(
+            "DELETE FROM locks WHERE name = ?1 AND holder = ?2",
+            [name, holder],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    pub fn lock(&self, name: &str) -> Result<Option<DbLock>, rusqlite::Error> {
+        let mut stmt = self
+            .conn()
+            .prepare("SELECT name, holder, acquired_at FROM locks WHERE name = ?1")?;
+        let mut rows = stmt.query([name])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(DbLock {
+                name: row.get("name")?,
+                holder: row.get("holder")?,
+                acquired_at: row.get("acquired_at")?,
+            })),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(3600);
+
+    fn backdate(db: &Db, name: &str, modifier: &str) {
+        db.conn()
+            .execute(
+                "UPDATE locks SET acquired_at = datetime('now', ?2) WHERE name = ?1",
+                [name, modifier],
+            )
+            .expect("backdate lock");
+    }
+
+    #[test]
+    fn try_lock_acquires_and_unlock_releases() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+
+        assert_eq!(db.try_lock("statements", "prune (pid 1)", TTL).expect("lock"), None);
+        let held = db.lock("statements").expect("read lock").expect("lock row");
+        assert_eq!(held.holder, "prune (pid 1)");
+
+        assert!(db.unlock("statements", "prune (pid 1)").expect("unlock"));
+        assert!(!db.unlock("statements", "prune (pid 1)").expect("second unlock"));
+        assert_eq!(db.try_lock("statements", "reshard (pid 2)", TTL).expect("relock"), None);
+    }
+
+    #[test]
+    fn try_lock_refuses_a_held_lock() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        db.try_lock("statements", "prune (pid 1)", TTL).expect("lock");
+        backdate(&db, "statements", "-10 minutes");
+
+        let err = db
+            .try_lock("statements", "reshard (pid 2)", TTL)
+            .expect_err("lock is held");
+
+        let LockError::Held(held) = &err else {
+            panic!("expected held lock, got {err:?}");
+        };
+        assert_eq!(held.holder, "prune (pid 1)");
+        assert!(err.to_string().starts_with("operation prune (pid 1) already in progress since"));
+        assert_eq!(db.try_lock("other", "reshard (pid 2)", TTL).expect("other lock"), None);
+    }
+
+    #[test]
+    fn try_lock_takes_over_a_stale_lock() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        db.try_lock("statements", "prune (pid 1)", TTL).expect("lock");
+        backdate(&db, "statements", "-2 hours");
+
+        let stale = db
+            .try_lock("statements", "reshard (pid 2)", TTL)
+            .expect("take over stale lock")
+            .expect("stale lock returned");
+
+        assert_eq!(stale.holder, "prune (pid 1)");
+        let held = db.lock("statements").expect("read lock").expect("lock row");
+        assert_eq!(held.holder, "reshard (pid 2)");
+
+        // The original holder finishing late must not release the new holder's lock.
+        assert!(!db.unlock("statements", "prune (pid 1)").expect("late unlock"));
+        assert!(db.lock("statements").expect("read lock").is_some());
+    }
+}
@@ -1,6 +1,9 @@
+use super::file_hash::{FileHasher, HashAlgorithm};
 use include_dir::{include_dir, Dir};
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 pub static EMBEDDED_MIGRATIONS_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/migrations");
 
@@ -101,12 +104,19 @@ impl Display for MigrationParseError {
             Self::InvalidFilename => {
                 write!(f, "migration filename must be <VERSION>_<NAME>.sql")
             }
-            Self::InvalidVersion(err) => write!(f, "invalid migration version: {err}"),
+            Self::InvalidVersion(_) => write!(f, "invalid migration version"),
         }
     }
 }
 
-impl std::error::Error for MigrationParseError {}
+impl std::error::Error for MigrationParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidVersion(err) => Some(err),
+            Self::InvalidExtension | Self::InvalidFilename => None,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum MigrationDiscoveryError {
@@ -119,8 +129,8 @@ pub enum MigrationDiscoveryError {
 impl Display for MigrationDiscoveryError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Io(err) => write!(f, "failed to discover migrations from source: {err}"),
-            Self::Parse(err) => write!(f, "failed to parse migration filename: {err}"),
+            Self::Io(_) => write!(f, "failed to discover migrations from source"),
+            Self::Parse(_) => write!(f, "failed to parse migration filename"),
             Self::DuplicateVersion(version) => {
                 write!(f, "duplicate migration version found: {version}")
             }
@@ -131,7 +141,15 @@ impl Display for MigrationDiscoveryError {
     }
 }
 
-impl std::error::Error for MigrationDiscoveryError {}
+impl std::error::Error for MigrationDiscoveryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::DuplicateVersion(_) | Self::InvalidUtf8FileName => None,
+        }
+    }
+}
 
 impl From<MigrationParseError> for MigrationDiscoveryError {
     fn from(value: MigrationParseError) -> Self {
@@ -149,7 +167,7 @@ pub enum MigrationContentError {
 impl Display for MigrationContentError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Io(err) => write!(f, "failed to read migration sql content: {err}"),
+            Self::Io(_) => write!(f, "failed to read migration sql content"),
             Self::MissingEmbeddedFile(file_name) => {
                 write!(f, "embedded migration file not found: {file_name}")
             }
@@ -160,7 +178,14 @@ impl Display for MigrationContentError {
     }
 }
 
-impl std::error::Error for MigrationContentError {}
+impl std::error::Error for MigrationContentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::MissingEmbeddedFile(_) | Self::NonUtf8EmbeddedFile(_) => None,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum MigrationRunnerError {
@@ -171,13 +196,20 @@ pub enum MigrationRunnerError {
 impl Display for MigrationRunnerError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Content(err) => write!(f, "failed to load migration content: {err}"),
-            Self::Sql(err) => write!(f, "sqlite error while running migrations: {err}"),
+            Self::Content(_) => write!(f, "failed to load migration content"),
+            Self::Sql(_) => write!(f, "sqlite error while running migrations"),
         }
     }
 }
 
-impl std::error::Error for MigrationRunnerError {}
+impl std::error::Error for MigrationRunnerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Content(err) => Some(err),
+            Self::Sql(err) => Some(err),
+        }
+    }
+}
 
 impl From<MigrationContentError> for MigrationRunnerError {
     fn from(value: MigrationContentError) -> Self {
@@ -191,6 +223,36 @@ impl From<rusqlite::Error> for MigrationRunnerError {
     }
 }
 
+/// Columns added to `schema_migrations` after its original `(version, name)` shape.
+/// Databases created by older binaries gain them via `ALTER TABLE` on the next run.
+const BOOKKEEPING_COLUMNS: &[(&str, &str)] = &[
+    ("applied_at", "TEXT"),
+    ("checksum", "TEXT"),
+    ("duration_ms", "INTEGER"),
+    ("app_version", "TEXT"),
+];
+
+/// One row of `migrate status`: a known migration, an applied one, or both.
+/// Bookkeeping fields are `None` when an older binary applied the migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: u32,
+    pub name: String,
+    pub applied: bool,
+    pub applied_at: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub app_version: Option<String>,
+    pub checksum: Option<String>,
+    /// `None` unless both a recorded checksum and the migration file are available.
+    pub checksum_matches: Option<bool>,
+}
+
+pub fn migration_checksum(sql: &str) -> String {
+    let mut hasher = FileHasher::new(HashAlgorithm::Sha256);
+    hasher.update(sql.as_bytes());
+    hasher.finalize()
+}
+
 pub struct MigrationRunner<'conn> {
     conn: &'conn rusqlite::Connection,
 }
@@ -270,10 +332,14 @@ impl<'conn> MigrationRunner<'conn> {
             CREATE TABLE IF NOT EXISTS schema_migrations (
                 version INTEGER PRIMARY KEY,
                 name TEXT NOT NULL,
-                applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+                applied_at TEXT NOT NULL DEFAULT (datetime('now')),
+                checksum TEXT,
+                duration_ms INTEGER,
+                app_version TEXT
             );
             ",
         )?;
+        self.upgrade_bookkeeping_columns()?;
 
         for migration in migrations {
             let already_applied = self.conn.query_row(
@@ -286,15 +352,116 @@ impl<'conn> MigrationRunner<'conn> {
             }
 
             let sql = migration.sql(source)?;
+            let started = Instant::now();
             self.conn.execute_batch(&sql)?;
+            let duration_ms = i64::try_from(started.elapsed().as_millis()).unwrap_or(i64::MAX);
             self.conn.execute(
-                "INSERT INTO schema_migrations(version, name) VALUES (?1, ?2)",
-                rusqlite::params![migration.version, migration.name],
+                "
+                INSERT INTO schema_migrations(
+                    version, name, applied_at, checksum, duration_ms, app_version
+                )
+                VALUES (?1, ?2, datetime('now'), ?3, ?4, ?5)
+                ",
+                rusqlite::params![
+                    migration.version,
+                    migration.name,
+                    migration_checksum(&sql),
+                    duration_ms,
+                    env!("CARGO_PKG_VERSION")
+                ],
             )?;
         }
 
         Ok(())
     }
+
+    /// Compare `schema_migrations` against the migrations in `source`.
+    pub fn status(
+        &self,
+        source: &MigrationsDir,
+        migrations: &[Migration],
+    ) -> Result<Vec<MigrationStatus>, MigrationRunnerError> {
+        // Without running migrations (e.g. on a read-only connection) the bookkeeping
+        // table or its newer columns may be missing; read those as never applied or unknown.
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM pragma_table_info('schema_migrations')")?;
+        let existing = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut applied = BTreeMap::new();
+        if !existing.is_empty() {
+            let columns = BOOKKEEPING_COLUMNS
+                .iter()
+                .map(|(column, _)| {
+                    if existing.iter().any(|name| name == column) {
+                        column.to_string()
+                    } else {
+                        format!("NULL AS {column}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut stmt = self
+                .conn
+                .prepare(&format!("SELECT version, name, {columns} FROM schema_migrations"))?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let version: u32 = row.get("version")?;
+                applied.insert(
+                    version,
+                    MigrationStatus {
+                        version,
+                        name: row.get("name")?,
+                        applied: true,
+                        applied_at: row.get("applied_at")?,
+                        duration_ms: row.get("duration_ms")?,
+                        app_version: row.get("app_version")?,
+                        checksum: row.get("checksum")?,
+                        checksum_matches: None,
+                    },
+                );
+            }
+        }
+
+        for migration in migrations {
+            let current = migration_checksum(&migration.sql(source)?);
+            let status = applied
+                .entry(migration.version)
+                .or_insert_with(|| MigrationStatus {
+                    version: migration.version,
+                    name: migration.name.clone(),
+                    applied: false,
+                    applied_at: None,
+                    duration_ms: None,
+                    app_version: None,
+                    checksum: None,
+                    checksum_matches: None,
+                });
+            status.checksum_matches = status.checksum.as_ref().map(|recorded| *recorded == current);
+        }
+
+        Ok(applied.into_values().collect())
+    }
+
+    fn upgrade_bookkeeping_columns(&self) -> Result<(), MigrationRunnerError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM pragma_table_info('schema_migrations')")?;
+        let existing = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (column, column_type) in BOOKKEEPING_COLUMNS {
+            if existing.iter().any(|name| name == column) {
+                continue;
+            }
+            self.conn.execute_batch(&format!(
+                "ALTER TABLE schema_migrations ADD COLUMN {column} {column_type};"
+            ))?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -497,7 +664,7 @@ mod tests {
         let applied_count: i64 = conn
             .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| row.get(0))
             .expect("count applied migrations");
-        assert_eq!(applied_count, 4);
+        assert_eq!(applied_count, 9);
 
         let accounts_exists: i64 = conn
             .query_row(
@@ -508,4 +675,121 @@ mod tests {
             .expect("check accounts table");
         assert_eq!(accounts_exists, 1);
     }
+
+    fn write_two_migrations(dir: &Path) {
+        std::fs::write(
+            dir.join("0001_create_accounts.sql"),
+            "CREATE TABLE accounts(id INTEGER PRIMARY KEY);",
+        )
+        .expect("write migration");
+        std::fs::write(
+            dir.join("0002_create_budgets.sql"),
+            "CREATE TABLE budgets(id INTEGER PRIMARY KEY);",
+        )
+        .expect("write migration");
+    }
+
+    #[test]
+    fn run_records_checksum_duration_and_app_version() {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        let runner = MigrationRunner::new(&conn);
+        let temp_dir = tempdir().expect("create temp dir");
+        write_two_migrations(temp_dir.path());
+        let source = MigrationsDir::fs(temp_dir.path());
+        let migrations = Migration::from_source(&source).expect("discover migrations");
+
+        runner.run(&source, &migrations).expect("run migrations");
+        let status = runner.status(&source, &migrations).expect("migration status");
+
+        assert_eq!(status.len(), 2);
+        for entry in &status {
+            assert!(entry.applied);
+            assert!(entry.applied_at.is_some());
+            assert!(entry.duration_ms.is_some());
+            assert_eq!(entry.app_version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+            assert_eq!(entry.checksum_matches, Some(true));
+        }
+        assert_eq!(
+            status[0].checksum.as_deref(),
+            Some(migration_checksum("CREATE TABLE accounts(id INTEGER PRIMARY KEY);").as_str())
+        );
+    }
+
+    #[test]
+    fn run_upgrades_legacy_schema_migrations_table() {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        conn.execute_batch(
+            "
+            CREATE TABLE schema_migrations (version INTEGER PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE accounts(id INTEGER PRIMARY KEY);
+            INSERT INTO schema_migrations(version, name) VALUES (1, 'create_accounts');
+            ",
+        )
+        .expect("create legacy table");
+        let runner = MigrationRunner::new(&conn);
+        let temp_dir = tempdir().expect("create temp dir");
+        write_two_migrations(temp_dir.path());
+        let source = MigrationsDir::fs(temp_dir.path());
+        let migrations = Migration::from_source(&source).expect("discover migrations");
+
+        runner.run(&source, &migrations).expect("upgrade and run");
+
+        let columns: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_table_info('schema_migrations')")
+            .expect("prepare table info")
+            .query_map([], |row| row.get(0))
+            .expect("query table info")
+            .collect::<Result<_, _>>()
+            .expect("read columns");
+        for (column, _) in BOOKKEEPING_COLUMNS {
+            assert!(columns.iter().any(|name| name == column), "missing {column}");
+        }
+
+        let status = runner.status(&source, &migrations).expect("migration status");
+        let legacy = &status[0];
+        assert_eq!(legacy.applied_at, None);
+        assert_eq!(legacy.duration_ms, None);
+        assert_eq!(legacy.app_version, None);
+        assert_eq!(legacy.checksum, None);
+        assert_eq!(legacy.checksum_matches, None);
+        assert!(status[1].applied_at.is_some());
+        assert!(status[1].duration_ms.is_some());
+
+        runner.run(&source, &migrations).expect("subsequent run");
+        let applied_count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| row.get(0))
+            .expect("count applied migrations");
+        assert_eq!(applied_count, 2);
+    }
+
+    #[test]
+    fn status_reports_pending_and_changed_migrations() {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite database");
+        let runner = MigrationRunner::new(&conn);
+        let temp_dir = tempdir().expect("create temp dir");
+        let dir = temp_dir.path();
+        std::fs::write(
+            dir.join("0001_create_accounts.sql"),
+            "CREATE TABLE accounts(id INTEGER PRIMARY KEY);",
+        )
+        .expect("write migration");
+        let source = MigrationsDir::fs(dir);
+        let migrations = Migration::from_source(&source).expect("discover migrations");
+        runner.run(&source, &migrations).expect("run migrations");
+
+        std::fs::write(
+            dir.join("0001_create_accounts.sql"),
+            "CREATE TABLE accounts(id INTEGER PRIMARY KEY, name TEXT);",
+        )
+        .expect("edit migration");
+        std::fs::write(dir.join("0002_pending.sql"), "SELECT 1;").expect("write migration");
+        let migrations = Migration::from_source(&source).expect("rediscover migrations");
+
+        let status = runner.status(&source, &migrations).expect("migration status");
+
+        assert_eq!(status[0].checksum_matches, Some(false));
+        assert!(!status[1].applied);
+        assert_eq!(status[1].name, "pending");
+        assert_eq!(status[1].checksum_matches, None);
+    }
 }
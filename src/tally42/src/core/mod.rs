@@ -1,10 +1,54 @@
-mod account;
-mod core_api;
-mod db;
-mod migration;
-mod statement;
-mod transaction;
-mod user_data;
+/// Accounts and the account tree.
+pub mod account;
+/// Reports computed from the database, such as statement coverage.
+pub mod analytics;
+/// Chart-of-accounts files for creating and exporting the account tree.
+pub mod chart;
+/// The `Core` facade used by the CLI.
+pub mod core_api;
+/// Per-currency decimal places for reading and displaying minor-unit amounts.
+pub mod currency;
+/// The sqlite connection wrapper and database maintenance.
+pub mod db;
+/// Content hashes for managed statement files.
+pub mod file_hash;
+/// Advisory locks that keep long write operations from interleaving.
+pub mod lock;
+/// Embedded schema migrations and their runner.
+pub mod migration;
+/// Named profiles with separate data dirs.
+pub mod profile;
+/// Per-data-dir settings stored in the database.
+pub mod settings;
+/// Statement rows, attached files, and statement ingest errors.
+pub mod statement;
+/// Transactions and postings.
+pub mod transaction;
+/// The on-disk data dir: database, managed statement files, and their maintenance.
+pub mod user_data;
 
 pub use account::{Account, AccountListError};
-pub use core_api::{Core, VersionInfo};
+pub use analytics::{
+    bucket_amounts, parse_bucket_edges, AccountCoverage, AmountBucket, BucketEdgesError,
+    YearMonth, DEFAULT_HISTOGRAM_EDGES,
+};
+pub use chart::{AccountChartError, ChartAccount, ChartImportSummary, ChartParseError};
+pub use core_api::{Core, CoreError, Error, VersionInfo};
+pub use currency::{
+    format_minor_units, parse_minor_units, CurrencyPrecision, CurrencyPrecisionError,
+    MAX_DECIMAL_PLACES,
+};
+pub use db::{Db, DbError, DbSizeInfo};
+pub use file_hash::HashAlgorithm;
+pub use lock::DbLock;
+pub use migration::MigrationStatus;
+pub use profile::{Profile, PROFILE_ENV_VAR};
+pub use statement::{
+    infer_period_from_filename, AddStatementError, AddStatementInput, ImportedSince, InferredPeriod,
+    InvalidStatementPeriod, Statement, StatementChainError, StatementCurrencyMismatch,
+};
+pub use transaction::Transaction;
+pub use user_data::{
+    ManifestCheck, ManifestEntry, MigrationSummary, PrunedChain, PruneSummary, ReshardSummary,
+    StatementLayout, UndoneStatement, UserDataError, UserDataManager,
+};
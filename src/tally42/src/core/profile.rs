@@ -0,0 +1,289 @@
+use super::user_data::{resolve_default_data_dir, UserDataError, UserDataManager};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_PROFILE_NAME: &str = "default";
+pub const PROFILE_ENV_VAR: &str = "TALLY42_PROFILE";
+const PROFILES_DIR_NAME: &str = "profiles";
+
+/// A named ledger with its own data dir. The default profile keeps the original
+/// data dir layout; every other profile lives under `<data dir>/profiles/<name>/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub data_dir: PathBuf,
+    pub active: bool,
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    UserData(UserDataError),
+    DefaultProfile,
+    AlreadyExists(String),
+    NotFound(String),
+    Io { path: PathBuf, source: std::io::Error },
+}
+
+impl Display for ProfileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UserData(err) => write!(f, "{err}"),
+            Self::DefaultProfile => write!(
+                f,
+                "the '{DEFAULT_PROFILE_NAME}' profile always exists and cannot be created or removed"
+            ),
+            Self::AlreadyExists(name) => write!(f, "profile already exists: {name}"),
+            Self::NotFound(name) => write!(f, "profile not found: {name}"),
+            Self::Io { path, .. } => write!(f, "failed to access {}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            // Displayed as is, so its source is this error's source.
+            Self::UserData(err) => err.source(),
+            Self::DefaultProfile => None,
+            Self::AlreadyExists(_) => None,
+            Self::NotFound(_) => None,
+            Self::Io { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<UserDataError> for ProfileError {
+    fn from(value: UserDataError) -> Self {
+        Self::UserData(value)
+    }
+}
+
+/// Profiles stored under one root data dir.
+pub struct ProfileRegistry {
+    root: PathBuf,
+    active: String,
+}
+
+impl ProfileRegistry {
+    pub fn from_root(root: impl AsRef<Path>, active: &str) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            active: active.to_string(),
+        }
+    }
+
+    pub fn from_environment() -> Result<Self, ProfileError> {
+        Ok(Self::from_root(
+            resolve_default_data_dir()?,
+            &active_profile_name(),
+        ))
+    }
+
+    pub fn list(&self) -> Result<Vec<Profile>, ProfileError> {
+        let mut names = Vec::new();
+        let profiles_dir = self.root.join(PROFILES_DIR_NAME);
+        match std::fs::read_dir(&profiles_dir) {
+            Ok(entries) => {
+                for entry in entries {
+                    let entry = entry.map_err(|source| ProfileError::Io {
+                        path: profiles_dir.clone(),
+                        source,
+                    })?;
+                    let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                        continue;
+                    };
+                    if entry.path().is_dir() && validate_profile_name(&name).is_ok() {
+                        names.push(name);
+                    }
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(source) => {
+                return Err(ProfileError::Io {
+                    path: profiles_dir,
+                    source,
+                });
+            }
+        }
+        names.sort();
+
+        let mut profiles = vec![self.profile(DEFAULT_PROFILE_NAME)?];
+        for name in names {
+            profiles.push(self.profile(&name)?);
+        }
+        Ok(profiles)
+    }
+
+    pub fn create(&self, name: &str) -> Result<Profile, ProfileError> {
+        if name == DEFAULT_PROFILE_NAME {
+            return Err(ProfileError::DefaultProfile);
+        }
+        let profile = self.profile(name)?;
+        if profile.data_dir.exists() {
+            return Err(ProfileError::AlreadyExists(name.to_string()));
+        }
+        UserDataManager::from_data_dir(&profile.data_dir).init()?;
+        Ok(profile)
+    }
+
+    pub fn remove(&self, name: &str) -> Result<Profile, ProfileError> {
+        if name == DEFAULT_PROFILE_NAME {
+            return Err(ProfileError::DefaultProfile);
+        }
+        let profile = self.profile(name)?;
+        if !profile.data_dir.is_dir() {
+            return Err(ProfileError::NotFound(name.to_string()));
+        }
+        std::fs::remove_dir_all(&profile.data_dir).map_err(|source| ProfileError::Io {
+            path: profile.data_dir.clone(),
+            source,
+        })?;
+        Ok(profile)
+    }
+
+    pub fn manager(&self, name: &str) -> Result<UserDataManager, ProfileError> {
+        Ok(UserDataManager::from_data_dir(profile_data_dir(&self.root, name)?))
+    }
+
+    fn profile(&self, name: &str) -> Result<Profile, ProfileError> {
+        Ok(Profile {
+            name: name.to_string(),
+            data_dir: profile_data_dir(&self.root, name)?,
+            active: name == self.active,
+        })
+    }
+}
+
+/// The profile selected by `TALLY42_PROFILE`, falling back to the default profile.
+pub fn active_profile_name() -> String {
+    match std::env::var(PROFILE_ENV_VAR) {
+        Ok(name) if !name.is_empty() => name,
+        _ => DEFAULT_PROFILE_NAME.to_string(),
+    }
+}
+
+pub(crate) fn profile_data_dir(root: &Path, name: &str) -> Result<PathBuf, UserDataError> {
+    validate_profile_name(name)?;
+    if name == DEFAULT_PROFILE_NAME {
+        return Ok(root.to_path_buf());
+    }
+    Ok(root.join(PROFILES_DIR_NAME).join(name))
+}
+
+/// Profile names become directory names, so only allow a conservative character set.
+fn validate_profile_name(name: &str) -> Result<(), UserDataError> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(UserDataError::InvalidProfileName(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use uuid::Uuid;
+
+    #[test]
+    fn validate_profile_name_rejects_path_like_names() {
+        for name in ["personal", "club-books", "ledger_2026", "v1.2"] {
+            assert!(validate_profile_name(name).is_ok(), "{name} should be valid");
+        }
+        for name in ["", ".", "..", ".hidden", "a/b", "a\\b", "../escape", "with space"] {
+            assert!(
+                matches!(
+                    validate_profile_name(name),
+                    Err(UserDataError::InvalidProfileName(ref invalid)) if invalid == name
+                ),
+                "{name} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn profile_data_dir_keeps_default_layout() {
+        let root = Path::new("/data/tally42");
+
+        assert_eq!(
+            profile_data_dir(root, DEFAULT_PROFILE_NAME).expect("default profile"),
+            root
+        );
+        assert_eq!(
+            profile_data_dir(root, "club").expect("named profile"),
+            root.join("profiles").join("club")
+        );
+    }
+
+    #[test]
+    fn create_list_and_remove_profiles() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let registry = ProfileRegistry::from_root(temp_dir.path(), "club");
+
+        registry.create("personal").expect("create personal");
+        let club = registry.create("club").expect("create club");
+        assert!(club.data_dir.join("tally42.db").is_file());
+        assert!(matches!(
+            registry.create("club"),
+            Err(ProfileError::AlreadyExists(name)) if name == "club"
+        ));
+        assert!(matches!(
+            registry.create(DEFAULT_PROFILE_NAME),
+            Err(ProfileError::DefaultProfile)
+        ));
+
+        let listed = registry.list().expect("list profiles");
+        let names: Vec<(&str, bool)> = listed
+            .iter()
+            .map(|profile| (profile.name.as_str(), profile.active))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("default", false), ("club", true), ("personal", false)]
+        );
+
+        registry.remove("personal").expect("remove personal");
+        assert!(matches!(
+            registry.remove("personal"),
+            Err(ProfileError::NotFound(name)) if name == "personal"
+        ));
+        assert_eq!(registry.list().expect("list profiles").len(), 2);
+    }
+
+    #[test]
+    fn profiles_keep_accounts_isolated() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let registry = ProfileRegistry::from_root(temp_dir.path(), DEFAULT_PROFILE_NAME);
+        registry.create("personal").expect("create personal");
+        registry.create("club").expect("create club");
+
+        let personal = registry
+            .manager("personal")
+            .expect("personal manager")
+            .open_db()
+            .expect("open personal db");
+        let club = registry
+            .manager("club")
+            .expect("club manager")
+            .open_db()
+            .expect("open club db");
+        let account_id = Uuid::parse_str("51515151-5151-5151-5151-515151515151").unwrap();
+        personal
+            .create_account(account_id, None, "checking", "USD", None)
+            .expect("create personal account");
+
+        assert_eq!(personal.list_accounts().expect("list personal").len(), 1);
+        assert!(club.list_accounts().expect("list club").is_empty());
+        let default = registry
+            .manager(DEFAULT_PROFILE_NAME)
+            .expect("default manager")
+            .open_db()
+            .expect("open default db");
+        assert!(default.list_accounts().expect("list default").is_empty());
+    }
+}
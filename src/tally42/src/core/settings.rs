@@ -0,0 +1,49 @@
+use super::db::Db;
+
+/// The `StatementLayout` new statement files are placed in.
+pub(crate) const STATEMENT_LAYOUT_SETTING: &str = "statement_layout";
+
+impl Db {
+    /// The value stored for `key`, if any.
+    pub fn setting(&self, key: &str) -> Result<Option<String>, rusqlite::Error> {
+        let mut stmt = self
+            .conn()
+            .prepare("SELECT value FROM settings WHERE key = ?1")?;
+        let mut rows = stmt.query([key])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get("value")?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store `value` for `key`, replacing any earlier value.
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), rusqlite::Error> {
+        self.conn().execute(
+            "
+            INSERT INTO settings (key, value) VALUES (?1, ?2)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value
+            ",
+            [key, value],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_setting_inserts_and_replaces() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        assert_eq!(db.setting("statement_layout").expect("read setting"), None);
+
+        db.set_setting("statement_layout", "by-account").expect("set setting");
+        db.set_setting("statement_layout", "flat").expect("replace setting");
+
+        assert_eq!(
+            db.setting("statement_layout").expect("read setting"),
+            Some("flat".to_string())
+        );
+    }
+}
@@ -1,7 +1,11 @@
-use super::db::Db;
+use super::account::AccountWriteError;
+use super::db::{is_foreign_key_violation, Db};
 use super::user_data::UserDataError;
+use rusqlite::OptionalExtension;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Display, Formatter};
 use std::path::PathBuf;
+use std::time::Duration;
 use uuid::Uuid;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -58,6 +62,111 @@ impl Statement {
     }
 }
 
+/// A statement recorded in a different currency from its account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatementCurrencyMismatch {
+    pub statement_id: Uuid,
+    pub account_name: String,
+    pub account_currency: String,
+    pub statement_currency: String,
+}
+
+/// A statement whose period is not a `YYYY-MM-DD` range with start <= end.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidStatementPeriod {
+    pub statement_id: Uuid,
+    pub period_start: String,
+    pub period_end: String,
+    /// The period as `normalize_period` reads it, if it can.
+    pub normalized: Option<(String, String)>,
+}
+
+/// `statement_files.kind` of the file recorded in `statements.file_hash`.
+pub const PRIMARY_STATEMENT_FILE_KIND: &str = "primary";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatementFile {
+    pub id: Uuid,
+    pub statement_id: Uuid,
+    pub file_hash: String,
+    pub file_size: i64,
+    pub original_filename: Option<String>,
+    pub kind: String,
+    pub attached_at: String,
+}
+
+impl StatementFile {
+    pub(crate) fn from_row(row: &rusqlite::Row<'_>) -> Result<Self, StatementListError> {
+        let id_str: String = row.get("id")?;
+        let statement_id_str: String = row.get("statement_id")?;
+
+        let id = Uuid::parse_str(&id_str).map_err(|source| StatementListError::InvalidId {
+            value: id_str.clone(),
+            source,
+        })?;
+        let statement_id = Uuid::parse_str(&statement_id_str).map_err(|source| {
+            StatementListError::InvalidStatementId {
+                value: statement_id_str.clone(),
+                source,
+            }
+        })?;
+
+        Ok(Self {
+            id,
+            statement_id,
+            file_hash: row.get("file_hash")?,
+            file_size: row.get("file_size")?,
+            original_filename: row.get("original_filename")?,
+            kind: row.get("kind")?,
+            attached_at: row.get("attached_at")?,
+        })
+    }
+}
+
+/// The start of an "imported since" window: a span of time before now, or a date.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportedSince {
+    Ago(Duration),
+    /// A `YYYY-MM-DD` date; the window starts at midnight.
+    Date(String),
+}
+
+impl ImportedSince {
+    /// Parse a relative duration such as `7d`, or a `YYYY-MM-DD` date.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Some(duration) = parse_relative_duration(value) {
+            return Some(Self::Ago(duration));
+        }
+        is_iso_date(value).then(|| Self::Date(value.to_string()))
+    }
+}
+
+/// Parse a count of hours, days or weeks, such as `24h`, `7d` or `3w`.
+pub fn parse_relative_duration(value: &str) -> Option<Duration> {
+    let unit_seconds = match value.chars().last()? {
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    let count = &value[..value.len() - 1];
+    if count.is_empty() || !count.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let seconds = count.parse::<u64>().ok()?.checked_mul(unit_seconds)?;
+    Some(Duration::from_secs(seconds))
+}
+
+fn is_iso_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(idx, byte)| match idx {
+            4 | 7 => *byte == b'-',
+            _ => byte.is_ascii_digit(),
+        })
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct AddStatementInput {
     pub institution: String,
@@ -66,6 +175,167 @@ pub struct AddStatementInput {
     pub period_end: String,
     pub currency: String,
     pub replaced_by: Option<Uuid>,
+    /// Record the statement even if its currency differs from the account's, for accounts
+    /// that genuinely hold several currencies.
+    pub allow_currency_mismatch: bool,
+}
+
+/// Days a single closing date in a filename is assumed to cover.
+const INFERRED_PERIOD_DAYS: i64 = 30;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InferredPeriod {
+    pub period_start: String,
+    pub period_end: String,
+}
+
+/// Guess a statement period from names like `2026-01-31_amex.pdf` or `statement_2026-01.pdf`.
+///
+/// A `YYYY-MM-DD` match is treated as the closing date of a period starting
+/// `INFERRED_PERIOD_DAYS` earlier; a `YYYY-MM` match covers the whole month. The first
+/// match from the left wins.
+pub fn infer_period_from_filename(file_name: &str) -> Option<InferredPeriod> {
+    let bytes = file_name.as_bytes();
+    for start in 0..bytes.len() {
+        if start > 0 && bytes[start - 1].is_ascii_digit() {
+            continue;
+        }
+        if let Some((year, month, day)) = match_date_at(bytes, start) {
+            let end = days_from_civil(year, month, day);
+            let (sy, sm, sd) = civil_from_days(end - INFERRED_PERIOD_DAYS);
+            return Some(InferredPeriod {
+                period_start: format_date(sy, sm, sd),
+                period_end: format_date(year, month, day),
+            });
+        }
+        if let Some((year, month)) = match_month_at(bytes, start) {
+            return Some(InferredPeriod {
+                period_start: format_date(year, month, 1),
+                period_end: format_date(year, month, days_in_month(year, month)),
+            });
+        }
+    }
+    None
+}
+
+/// Whether `period_start..=period_end` are real `YYYY-MM-DD` dates in order, which
+/// the range queries rely on when comparing them as text.
+pub fn is_valid_period(period_start: &str, period_end: &str) -> bool {
+    is_real_iso_date(period_start) && is_real_iso_date(period_end) && period_start <= period_end
+}
+
+/// Read a period written in a common alternate format as `YYYY-MM-DD` dates; see
+/// `normalize_period_date`. Returns `None` unless both dates parse and are in order.
+pub fn normalize_period(period_start: &str, period_end: &str) -> Option<(String, String)> {
+    let start = normalize_period_date(period_start)?;
+    let end = normalize_period_date(period_end)?;
+    (start <= end).then_some((start, end))
+}
+
+/// Read `YYYY-MM-DD`, `YYYY/MM/DD`, `YYYY.MM.DD`, `YYYYMMDD` or US-style
+/// `MM/DD/YYYY` (month and day may be one digit) as `YYYY-MM-DD`.
+pub fn normalize_period_date(value: &str) -> Option<String> {
+    let value = value.trim();
+    let parts: Vec<&str> = value.split(['-', '/', '.']).collect();
+    let (year, month, day) = match parts.as_slice() {
+        [digits] if digits.len() == 8 && digits.bytes().all(|b| b.is_ascii_digit()) => {
+            (&digits[..4], &digits[4..6], &digits[6..])
+        }
+        [year, month, day] if year.len() == 4 => (*year, *month, *day),
+        [month, day, year] if year.len() == 4 && value.contains('/') => (*year, *month, *day),
+        _ => return None,
+    };
+    if !(1..=2).contains(&month.len()) || !(1..=2).contains(&day.len()) {
+        return None;
+    }
+    let year = i64::from(parse_digits(year.as_bytes())?);
+    let month = parse_digits(month.as_bytes())?;
+    let day = parse_digits(day.as_bytes())?;
+    ((1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day))
+        .then(|| format_date(year, month, day))
+}
+
+fn is_real_iso_date(value: &str) -> bool {
+    value.len() == 10 && match_date_at(value.as_bytes(), 0).is_some()
+}
+
+fn match_date_at(bytes: &[u8], start: usize) -> Option<(i64, u32, u32)> {
+    let (year, month) = match_year_month(bytes, start)?;
+    let day = match_digits_after_dash(bytes, start + 7)?;
+    if bytes.get(start + 10).is_some_and(u8::is_ascii_digit) {
+        return None;
+    }
+    (1..=days_in_month(year, month))
+        .contains(&day)
+        .then_some((year, month, day))
+}
+
+fn match_month_at(bytes: &[u8], start: usize) -> Option<(i64, u32)> {
+    let year_month = match_year_month(bytes, start)?;
+    // Reject `YYYY-MMN...` and invalid full dates such as `2026-02-30`.
+    if bytes.get(start + 7).is_some_and(u8::is_ascii_digit)
+        || match_digits_after_dash(bytes, start + 7).is_some()
+    {
+        return None;
+    }
+    Some(year_month)
+}
+
+fn match_year_month(bytes: &[u8], start: usize) -> Option<(i64, u32)> {
+    let year = parse_digits(bytes.get(start..start + 4)?)?;
+    let month = match_digits_after_dash(bytes, start + 4)?;
+    (1..=12).contains(&month).then_some((i64::from(year), month))
+}
+
+/// Parse `-NN` at `offset`.
+fn match_digits_after_dash(bytes: &[u8], offset: usize) -> Option<u32> {
+    if bytes.get(offset) != Some(&b'-') {
+        return None;
+    }
+    parse_digits(bytes.get(offset + 1..offset + 3)?)
+}
+
+fn parse_digits(bytes: &[u8]) -> Option<u32> {
+    bytes.iter().try_fold(0u32, |acc, b| {
+        b.is_ascii_digit().then(|| acc * 10 + u32::from(b - b'0'))
+    })
+}
+
+fn format_date(year: i64, month: u32, day: u32) -> String {
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Proleptic Gregorian day counting, relative to 1970-01-01.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (i64::from(month) + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
 }
 
 #[derive(Debug)]
@@ -74,20 +344,24 @@ pub enum StatementListError {
     InvalidId { value: String, source: uuid::Error },
     InvalidAccountId { value: String, source: uuid::Error },
     InvalidReplacedById { value: String, source: uuid::Error },
+    InvalidStatementId { value: String, source: uuid::Error },
 }
 
 impl Display for StatementListError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Sql(err) => write!(f, "sqlite error while listing statements: {err}"),
-            Self::InvalidId { value, source } => {
-                write!(f, "invalid statement id UUID '{value}': {source}")
+            Self::Sql(_) => write!(f, "sqlite error while listing statements"),
+            Self::InvalidId { value, .. } => {
+                write!(f, "invalid statement id UUID '{value}'")
+            }
+            Self::InvalidAccountId { value, .. } => {
+                write!(f, "invalid statement account_id UUID '{value}'")
             }
-            Self::InvalidAccountId { value, source } => {
-                write!(f, "invalid statement account_id UUID '{value}': {source}")
+            Self::InvalidReplacedById { value, .. } => {
+                write!(f, "invalid statement replaced_by UUID '{value}'")
             }
-            Self::InvalidReplacedById { value, source } => {
-                write!(f, "invalid statement replaced_by UUID '{value}': {source}")
+            Self::InvalidStatementId { value, .. } => {
+                write!(f, "invalid statement file statement_id UUID '{value}'")
             }
         }
     }
@@ -100,6 +374,7 @@ impl std::error::Error for StatementListError {
             Self::InvalidId { source, .. } => Some(source),
             Self::InvalidAccountId { source, .. } => Some(source),
             Self::InvalidReplacedById { source, .. } => Some(source),
+            Self::InvalidStatementId { source, .. } => Some(source),
         }
     }
 }
@@ -115,14 +390,51 @@ pub enum StatementWriteError {
     Sql(rusqlite::Error),
     ReadBack(StatementListError),
     NotFound(Uuid),
+    UnknownAccount(Uuid),
+    ReferencedBy { id: Uuid, replaced_by: Vec<Uuid> },
+    CurrencyMismatch {
+        account_currency: String,
+        statement_currency: String,
+    },
+    InvalidPeriod {
+        period_start: String,
+        period_end: String,
+    },
 }
 
 impl Display for StatementWriteError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Sql(err) => write!(f, "sqlite error while writing statement: {err}"),
-            Self::ReadBack(err) => write!(f, "failed to read back statement after write: {err}"),
+            Self::Sql(_) => write!(f, "sqlite error while writing statement"),
+            Self::ReadBack(_) => write!(f, "failed to read back statement after write"),
             Self::NotFound(id) => write!(f, "statement not found: {id}"),
+            Self::UnknownAccount(id) => {
+                write!(f, "account {id} does not exist; create the account first")
+            }
+            Self::ReferencedBy { id, replaced_by } => {
+                let ids: Vec<String> = replaced_by.iter().map(Uuid::to_string).collect();
+                write!(
+                    f,
+                    "statement {id} is referenced via replaced_by from {}",
+                    ids.join(", ")
+                )
+            }
+            Self::CurrencyMismatch {
+                account_currency,
+                statement_currency,
+            } => write!(
+                f,
+                "statement currency {statement_currency} does not match account currency \
+                 {account_currency}; allow the mismatch only for multi-currency accounts"
+            ),
+            Self::InvalidPeriod {
+                period_start,
+                period_end,
+            } => write!(
+                f,
+                "invalid statement period {period_start}..{period_end}; \
+                 use YYYY-MM-DD dates with the start on or before the end"
+            ),
         }
     }
 }
@@ -133,6 +445,10 @@ impl std::error::Error for StatementWriteError {
             Self::Sql(err) => Some(err),
             Self::ReadBack(err) => Some(err),
             Self::NotFound(_) => None,
+            Self::UnknownAccount(_) => None,
+            Self::ReferencedBy { .. } => None,
+            Self::CurrencyMismatch { .. } => None,
+            Self::InvalidPeriod { .. } => None,
         }
     }
 }
@@ -143,81 +459,615 @@ impl From<rusqlite::Error> for StatementWriteError {
     }
 }
 
+#[derive(Debug)]
+pub enum StatementChainError {
+    List(StatementListError),
+    NotFound(Uuid),
+    /// `replaced_by` links that lead back to where they started, in link order.
+    Cycle(Vec<Uuid>),
+}
+
+impl Display for StatementChainError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::List(err) => write!(f, "{err}"),
+            Self::NotFound(id) => write!(f, "statement not found: {id}"),
+            Self::Cycle(ids) => {
+                let ids: Vec<String> = ids.iter().map(Uuid::to_string).collect();
+                write!(f, "replaced_by links form a cycle: {}", ids.join(" -> "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for StatementChainError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            // Displayed as is, so its source is this error's source.
+            Self::List(err) => err.source(),
+            Self::NotFound(_) => None,
+            Self::Cycle(_) => None,
+        }
+    }
+}
+
+impl From<StatementListError> for StatementChainError {
+    fn from(value: StatementListError) -> Self {
+        Self::List(value)
+    }
+}
+
+/// Statements indexed by their `replaced_by` links, so any number of chains can be
+/// walked without rebuilding the maps.
+struct ChainIndex<'a> {
+    by_id: BTreeMap<Uuid, &'a Statement>,
+    /// The statements each statement replaces, most recently imported first.
+    replaces: BTreeMap<Uuid, Vec<&'a Statement>>,
+}
+
+impl<'a> ChainIndex<'a> {
+    fn new(statements: &'a [Statement]) -> Self {
+        let by_id = statements.iter().map(|s| (s.id, s)).collect();
+        let mut replaces: BTreeMap<Uuid, Vec<&Statement>> = BTreeMap::new();
+        for statement in statements {
+            if let Some(newer) = statement.replaced_by {
+                replaces.entry(newer).or_default().push(statement);
+            }
+        }
+        for older in replaces.values_mut() {
+            older.sort_by(|a, b| (&b.imported_at, b.id).cmp(&(&a.imported_at, a.id)));
+        }
+        Self { by_id, replaces }
+    }
+
+    /// The chain containing `id`: the statement its `replaced_by` links end at, then
+    /// the rest by how many links separate them from it.
+    fn chain(&self, id: Uuid) -> Result<Vec<Statement>, StatementChainError> {
+        let mut end = *self.by_id.get(&id).ok_or(StatementChainError::NotFound(id))?;
+        let mut walked = vec![end.id];
+        while let Some(next) = end.replaced_by {
+            if let Some(start) = walked.iter().position(|seen| *seen == next) {
+                return Err(StatementChainError::Cycle(walked.split_off(start)));
+            }
+            end = *self
+                .by_id
+                .get(&next)
+                .ok_or(StatementChainError::NotFound(next))?;
+            walked.push(end.id);
+        }
+
+        let mut chain = vec![end.clone()];
+        let mut next = 0;
+        while next < chain.len() {
+            let linked = self
+                .replaces
+                .get(&chain[next].id)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            chain.extend(linked.iter().map(|statement| (*statement).clone()));
+            next += 1;
+        }
+        Ok(chain)
+    }
+}
+
+/// The statements linked to `id` through `replaced_by` in either direction, oldest
+/// import first.
+///
+/// The chain is everything whose links end at the same statement, so it may branch
+/// where two statements link to a third.
+pub(crate) fn superseded_chain_in(
+    statements: &[Statement],
+    id: Uuid,
+) -> Result<Vec<Statement>, StatementChainError> {
+    let mut chain = ChainIndex::new(statements).chain(id)?;
+    chain.sort_by(|a, b| (&a.imported_at, a.id).cmp(&(&b.imported_at, b.id)));
+    Ok(chain)
+}
+
+/// Every chain of two or more statements linked by `replaced_by`, plus any cycles found
+/// instead. Each chain starts with the statement its links end at, which supersedes the
+/// rest, followed by the others in order of how many links separate them from it.
+pub(crate) fn superseded_chains_in(
+    statements: &[Statement],
+) -> (Vec<Vec<Statement>>, Vec<Vec<Uuid>>) {
+    let index = ChainIndex::new(statements);
+    let mut chains = Vec::new();
+    let mut cycles = Vec::new();
+    let mut seen = BTreeSet::new();
+    for statement in statements.iter().filter(|s| s.replaced_by.is_some()) {
+        if seen.contains(&statement.id) {
+            continue;
+        }
+        match index.chain(statement.id) {
+            Ok(chain) => {
+                seen.extend(chain.iter().map(|s| s.id));
+                chains.push(chain);
+            }
+            Err(StatementChainError::Cycle(cycle)) => {
+                if cycle.iter().all(|id| seen.insert(*id)) {
+                    cycles.push(cycle);
+                }
+            }
+            // A dangling replaced_by is left for the foreign key check to report.
+            Err(_) => {}
+        }
+    }
+    (chains, cycles)
+}
+
 impl Db {
+    /// Statements imported at or after `cutoff`, a sqlite datetime or date string; see
+    /// `imported_since_cutoff`.
+    pub fn list_statements_imported_since(
+        &self,
+        cutoff: &str,
+    ) -> Result<Vec<Statement>, StatementListError> {
+        let mut stmt = self.conn().prepare(
+            "
+            SELECT
+              id,
+              institution,
+              account_id,
+              period_start,
+              period_end,
+              currency,
+              file_hash,
+              file_size,
+              imported_at,
+              replaced_by
+            FROM statements
+            WHERE imported_at >= ?1
+            ORDER BY imported_at, id
+            ",
+        )?;
+        let mut rows = stmt.query([cutoff])?;
+        let mut statements = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            statements.push(Statement::from_row(row)?);
+        }
+
+        Ok(statements)
+    }
+
+    /// Resolve `since` against the database clock into a value comparable with
+    /// `statements.imported_at`.
+    pub fn imported_since_cutoff(&self, since: &ImportedSince) -> Result<String, rusqlite::Error> {
+        match since {
+            ImportedSince::Ago(duration) => self.conn().query_row(
+                "SELECT datetime('now', ?1)",
+                [format!("-{} seconds", duration.as_secs())],
+                |row| row.get(0),
+            ),
+            ImportedSince::Date(date) => Ok(date.clone()),
+        }
+    }
+
     pub fn list_statements(&self) -> Result<Vec<Statement>, StatementListError> {
         let mut stmt = self.conn().prepare(
             "
             SELECT
               id,
-              institution,
-              account_id,
-              period_start,
-              period_end,
-              currency,
+              institution,
+              account_id,
+              period_start,
+              period_end,
+              currency,
+              file_hash,
+              file_size,
+              imported_at,
+              replaced_by
+            FROM statements
+            ORDER BY imported_at, id
+            ",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut statements = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            statements.push(Statement::from_row(row)?);
+        }
+
+        Ok(statements)
+    }
+
+    /// The most recently imported statement, ties broken by id.
+    pub fn latest_statement(&self) -> Result<Option<Statement>, StatementListError> {
+        let mut stmt = self.conn().prepare(
+            "
+            SELECT
+              id,
+              institution,
+              account_id,
+              period_start,
+              period_end,
+              currency,
+              file_hash,
+              file_size,
+              imported_at,
+              replaced_by
+            FROM statements
+            ORDER BY imported_at DESC, id DESC
+            LIMIT 1
+            ",
+        )?;
+        let mut rows = stmt.query([])?;
+        match rows.next()? {
+            Some(row) => Statement::from_row(row).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The `replaced_by` chain containing `id`; see `superseded_chain_in`.
+    pub fn superseded_chain(&self, id: Uuid) -> Result<Vec<Statement>, StatementChainError> {
+        superseded_chain_in(&self.list_statements()?, id)
+    }
+
+    /// Delete a statement and its `statement_files` rows, returning the removed file
+    /// rows so the caller can clean up the managed files afterwards.
+    pub fn delete_statement(&self, id: Uuid) -> Result<Vec<StatementFile>, StatementWriteError> {
+        let tx = self.conn().unchecked_transaction()?;
+        let files = self.delete_statement_in(&tx, id)?;
+        tx.commit()?;
+        Ok(files)
+    }
+
+    /// Delete superseded statements in one transaction, first clearing every
+    /// `replaced_by` link that points at them. Nothing is deleted if any delete fails.
+    pub fn delete_superseded_statements(
+        &self,
+        ids: &[Uuid],
+    ) -> Result<Vec<StatementFile>, StatementWriteError> {
+        let tx = self.conn().unchecked_transaction()?;
+        for id in ids {
+            tx.execute(
+                "UPDATE statements SET replaced_by = NULL WHERE replaced_by = ?1",
+                [id.to_string()],
+            )?;
+        }
+        let mut files = Vec::new();
+        for id in ids {
+            files.extend(self.delete_statement_in(&tx, *id)?);
+        }
+        tx.commit()?;
+        Ok(files)
+    }
+
+    fn delete_statement_in(
+        &self,
+        tx: &rusqlite::Transaction<'_>,
+        id: Uuid,
+    ) -> Result<Vec<StatementFile>, StatementWriteError> {
+        let replaced_by = {
+            let mut stmt = tx.prepare(
+                "SELECT id FROM statements WHERE replaced_by = ?1 AND id <> ?1 ORDER BY id",
+            )?;
+            let ids = stmt
+                .query_map([id.to_string()], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            ids.into_iter()
+                .map(|value| {
+                    Uuid::parse_str(&value).map_err(|source| {
+                        let err = StatementListError::InvalidId { value, source };
+                        StatementWriteError::ReadBack(err)
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        if !replaced_by.is_empty() {
+            return Err(StatementWriteError::ReferencedBy { id, replaced_by });
+        }
+
+        let files = self
+            .list_statement_files(id)
+            .map_err(StatementWriteError::ReadBack)?;
+        tx.execute(
+            "DELETE FROM statement_files WHERE statement_id = ?1",
+            [id.to_string()],
+        )?;
+        let deleted = tx.execute("DELETE FROM statements WHERE id = ?1", [id.to_string()])?;
+        if deleted == 0 {
+            return Err(StatementWriteError::NotFound(id));
+        }
+        Ok(files)
+    }
+
+    /// Statements whose currency differs from their account's, including ones recorded
+    /// with `allow_currency_mismatch`.
+    pub fn statement_currency_mismatches(
+        &self,
+    ) -> Result<Vec<StatementCurrencyMismatch>, StatementListError> {
+        let mut stmt = self.conn().prepare(
+            "
+            SELECT s.id, a.name, a.currency AS account_currency, s.currency
+            FROM statements s
+            JOIN accounts a ON a.id = s.account_id
+            WHERE UPPER(a.currency) <> UPPER(s.currency)
+            ORDER BY a.name, s.period_start, s.id
+            ",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut mismatches = Vec::new();
+        while let Some(row) = rows.next()? {
+            let id_str: String = row.get("id")?;
+            let statement_id =
+                Uuid::parse_str(&id_str).map_err(|source| StatementListError::InvalidId {
+                    value: id_str.clone(),
+                    source,
+                })?;
+            mismatches.push(StatementCurrencyMismatch {
+                statement_id,
+                account_name: row.get("name")?,
+                account_currency: row.get("account_currency")?,
+                statement_currency: row.get("currency")?,
+            });
+        }
+        Ok(mismatches)
+    }
+
+    /// Statements stored before periods were validated whose period is not a valid
+    /// `YYYY-MM-DD` range; see `is_valid_period`.
+    pub fn invalid_statement_periods(
+        &self,
+    ) -> Result<Vec<InvalidStatementPeriod>, StatementListError> {
+        let mut stmt = self.conn().prepare(
+            "SELECT id, period_start, period_end FROM statements ORDER BY imported_at, id",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut invalid = Vec::new();
+        while let Some(row) = rows.next()? {
+            let period_start: String = row.get("period_start")?;
+            let period_end: String = row.get("period_end")?;
+            if is_valid_period(&period_start, &period_end) {
+                continue;
+            }
+            let id_str: String = row.get("id")?;
+            let statement_id =
+                Uuid::parse_str(&id_str).map_err(|source| StatementListError::InvalidId {
+                    value: id_str.clone(),
+                    source,
+                })?;
+            invalid.push(InvalidStatementPeriod {
+                statement_id,
+                normalized: normalize_period(&period_start, &period_end),
+                period_start,
+                period_end,
+            });
+        }
+        Ok(invalid)
+    }
+
+    /// Rewrite every invalid period that `normalize_period` can read, in one
+    /// transaction. Returns the rewritten statements.
+    pub fn fix_statement_periods(
+        &self,
+    ) -> Result<Vec<InvalidStatementPeriod>, StatementWriteError> {
+        let fixable: Vec<InvalidStatementPeriod> = self
+            .invalid_statement_periods()
+            .map_err(StatementWriteError::ReadBack)?
+            .into_iter()
+            .filter(|period| period.normalized.is_some())
+            .collect();
+        let tx = self.conn().unchecked_transaction()?;
+        for period in &fixable {
+            let (start, end) = period.normalized.as_ref().expect("filtered above");
+            tx.execute(
+                "UPDATE statements SET period_start = ?2, period_end = ?3 WHERE id = ?1",
+                rusqlite::params![period.statement_id.to_string(), start, end],
+            )?;
+        }
+        tx.commit()?;
+        Ok(fixable)
+    }
+
+    /// Whether any statement still has a file with this hash attached.
+    pub fn statement_file_hash_in_use(&self, file_hash: &str) -> Result<bool, rusqlite::Error> {
+        self.conn().query_row(
+            "SELECT EXISTS(SELECT 1 FROM statement_files WHERE file_hash = ?1)",
+            [file_hash],
+            |row| row.get(0),
+        )
+    }
+
+    /// Insert the statement row for `input` with its primary file, identified by
+    /// `file_hash` and `file_size`.
+    pub fn create_statement(
+        &self,
+        id: Uuid,
+        input: &AddStatementInput,
+        file_hash: &str,
+        file_size: i64,
+    ) -> Result<Statement, StatementWriteError> {
+        let AddStatementInput {
+            institution,
+            account_id,
+            period_start,
+            period_end,
+            currency,
+            replaced_by,
+            allow_currency_mismatch,
+        } = input;
+        let (account_id, replaced_by) = (*account_id, *replaced_by);
+        if !is_valid_period(period_start, period_end) {
+            return Err(StatementWriteError::InvalidPeriod {
+                period_start: period_start.to_string(),
+                period_end: period_end.to_string(),
+            });
+        }
+        let id_str = id.to_string();
+        let account_id_str = account_id.to_string();
+        let replaced_by_str = replaced_by.map(|v| v.to_string());
+        let tx = self.conn().unchecked_transaction()?;
+        let account_currency: Option<String> = tx
+            .query_row(
+                "SELECT currency FROM accounts WHERE id = ?1",
+                [&account_id_str],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match account_currency {
+            None => return Err(StatementWriteError::UnknownAccount(account_id)),
+            Some(account_currency)
+                if !allow_currency_mismatch
+                    && !account_currency.eq_ignore_ascii_case(currency) =>
+            {
+                return Err(StatementWriteError::CurrencyMismatch {
+                    account_currency,
+                    statement_currency: currency.to_string(),
+                });
+            }
+            Some(_) => {}
+        }
+        let inserted = tx.execute(
+            "
+            INSERT INTO statements (
+              id,
+              institution,
+              account_id,
+              period_start,
+              period_end,
+              currency,
+              file_hash,
+              file_size,
+              replaced_by
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ",
+            rusqlite::params![
+                id_str,
+                institution,
+                account_id_str,
+                period_start,
+                period_end,
+                currency,
+                file_hash,
+                file_size,
+                replaced_by_str
+            ],
+        );
+        if let Err(err) = inserted {
+            // replaced_by is the other FK; only blame the account when it is really missing.
+            if is_foreign_key_violation(&err) && !self.account_exists(account_id)? {
+                return Err(StatementWriteError::UnknownAccount(account_id));
+            }
+            return Err(StatementWriteError::Sql(err));
+        }
+        tx.execute(
+            "
+            INSERT INTO statement_files (id, statement_id, file_hash, file_size, kind)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ",
+            rusqlite::params![
+                Uuid::new_v4().to_string(),
+                id_str,
+                file_hash,
+                file_size,
+                PRIMARY_STATEMENT_FILE_KIND
+            ],
+        )?;
+        tx.commit()?;
+        self.get_statement_by_id(id)?
+            .ok_or(StatementWriteError::NotFound(id))
+    }
+
+    fn account_exists(&self, account_id: Uuid) -> Result<bool, rusqlite::Error> {
+        self.conn().query_row(
+            "SELECT EXISTS(SELECT 1 FROM accounts WHERE id = ?1)",
+            [account_id.to_string()],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn list_statement_files(
+        &self,
+        statement_id: Uuid,
+    ) -> Result<Vec<StatementFile>, StatementListError> {
+        let mut stmt = self.conn().prepare(
+            "
+            SELECT
+              id,
+              statement_id,
               file_hash,
               file_size,
-              imported_at,
-              replaced_by
-            FROM statements
-            ORDER BY imported_at, id
+              original_filename,
+              kind,
+              attached_at
+            FROM statement_files
+            WHERE statement_id = ?1
+            ORDER BY kind <> 'primary', attached_at, id
             ",
         )?;
-        let mut rows = stmt.query([])?;
-        let mut statements = Vec::new();
+        let mut rows = stmt.query([statement_id.to_string()])?;
+        let mut files = Vec::new();
 
         while let Some(row) = rows.next()? {
-            statements.push(Statement::from_row(row)?);
+            files.push(StatementFile::from_row(row)?);
         }
 
-        Ok(statements)
+        Ok(files)
     }
 
-    pub fn create_statement(
+    pub fn create_statement_file(
         &self,
         id: Uuid,
-        institution: &str,
-        account_id: Uuid,
-        period_start: &str,
-        period_end: &str,
-        currency: &str,
+        statement_id: Uuid,
         file_hash: &str,
         file_size: i64,
-        replaced_by: Option<Uuid>,
-    ) -> Result<Statement, StatementWriteError> {
-        let id_str = id.to_string();
-        let account_id_str = account_id.to_string();
-        let replaced_by_str = replaced_by.map(|v| v.to_string());
+        original_filename: Option<&str>,
+        kind: &str,
+    ) -> Result<StatementFile, StatementWriteError> {
         self.conn().execute(
             "
-            INSERT INTO statements (
+            INSERT INTO statement_files (
               id,
-              institution,
-              account_id,
-              period_start,
-              period_end,
-              currency,
+              statement_id,
               file_hash,
               file_size,
-              replaced_by
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+              original_filename,
+              kind
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
             ",
             rusqlite::params![
-                id_str,
-                institution,
-                account_id_str,
-                period_start,
-                period_end,
-                currency,
+                id.to_string(),
+                statement_id.to_string(),
                 file_hash,
                 file_size,
-                replaced_by_str
+                original_filename,
+                kind
             ],
         )?;
-        self.get_statement_by_id(id)?
+        self.get_statement_file_by_id(id)?
             .ok_or(StatementWriteError::NotFound(id))
     }
 
-    fn get_statement_by_id(&self, id: Uuid) -> Result<Option<Statement>, StatementWriteError> {
+    fn get_statement_file_by_id(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<StatementFile>, StatementWriteError> {
+        let mut stmt = self.conn().prepare(
+            "
+            SELECT
+              id,
+              statement_id,
+              file_hash,
+              file_size,
+              original_filename,
+              kind,
+              attached_at
+            FROM statement_files
+            WHERE id = ?1
+            ",
+        )?;
+        let mut rows = stmt.query([id.to_string()])?;
+        match rows.next()? {
+            Some(row) => StatementFile::from_row(row)
+                .map(Some)
+                .map_err(StatementWriteError::ReadBack),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn get_statement_by_id(&self, id: Uuid) -> Result<Option<Statement>, StatementWriteError> {
         let mut stmt = self.conn().prepare(
             "
             SELECT
@@ -256,6 +1106,7 @@ pub enum AddStatementError {
     DuplicateFileHash { hash: String, path: PathBuf },
     RenameToFinal(std::io::Error),
     PrepareUserData(UserDataError),
+    LookupAccount(AccountWriteError),
     InsertStatement(StatementWriteError),
     InsertStatementCleanupFailed {
         insert_error: StatementWriteError,
@@ -267,14 +1118,14 @@ pub enum AddStatementError {
 impl Display for AddStatementError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::OpenSource(err) => write!(f, "failed to open source statement file: {err}"),
-            Self::CreateTempFile(err) => write!(f, "failed to create temp statement file: {err}"),
-            Self::ReadSource(err) => write!(f, "failed while reading source statement file: {err}"),
-            Self::WriteTempFile(err) => {
-                write!(f, "failed while writing managed statement file: {err}")
+            Self::OpenSource(_) => write!(f, "failed to open source statement file"),
+            Self::CreateTempFile(_) => write!(f, "failed to create temp statement file"),
+            Self::ReadSource(_) => write!(f, "failed while reading source statement file"),
+            Self::WriteTempFile(_) => {
+                write!(f, "failed while writing managed statement file")
             }
-            Self::TempFileMetadata(err) => {
-                write!(f, "failed to read temp statement file metadata: {err}")
+            Self::TempFileMetadata(_) => {
+                write!(f, "failed to read temp statement file metadata")
             }
             Self::FileTooLarge(size) => write!(f, "statement file too large for i64 size: {size}"),
             Self::DuplicateFileHash { hash, path } => write!(
@@ -282,18 +1133,22 @@ impl Display for AddStatementError {
                 "statement file with hash '{hash}' already exists at {}",
                 path.display()
             ),
-            Self::RenameToFinal(err) => write!(f, "failed to finalize managed statement file: {err}"),
-            Self::PrepareUserData(err) => {
-                write!(f, "failed to prepare user data for statement ingest: {err}")
+            Self::RenameToFinal(_) => write!(f, "failed to finalize managed statement file"),
+            Self::PrepareUserData(_) => {
+                write!(f, "failed to prepare user data for statement ingest")
             }
-            Self::InsertStatement(err) => write!(f, "failed to insert statement row: {err}"),
+            Self::LookupAccount(_) => {
+                write!(f, "failed to look up statement account")
+            }
+            Self::InsertStatement(_) => write!(f, "failed to insert statement row"),
             Self::InsertStatementCleanupFailed {
-                insert_error,
                 cleanup_error,
                 path,
+                ..
             } => write!(
                 f,
-                "failed to insert statement row ({insert_error}) and failed to remove copied file {}: {cleanup_error}",
+                "failed to insert statement row and failed to remove copied file {}: \
+                 {cleanup_error}",
                 path.display()
             ),
         }
@@ -312,15 +1167,9 @@ impl std::error::Error for AddStatementError {
             Self::DuplicateFileHash { .. } => None,
             Self::RenameToFinal(err) => Some(err),
             Self::PrepareUserData(err) => Some(err),
+            Self::LookupAccount(err) => Some(err),
             Self::InsertStatement(err) => Some(err),
-            Self::InsertStatementCleanupFailed {
-                insert_error,
-                cleanup_error,
-                ..
-            } => {
-                let _ = cleanup_error;
-                Some(insert_error)
-            }
+            Self::InsertStatementCleanupFailed { insert_error, .. } => Some(insert_error),
         }
     }
 }
@@ -341,14 +1190,17 @@ mod tests {
         let statement = db
             .create_statement(
                 statement_id,
-                "Chase",
-                account_id,
-                "2026-01-01",
-                "2026-01-31",
-                "USD",
+                &AddStatementInput {
+                    institution: "Chase".to_string(),
+                    account_id,
+                    period_start: "2026-01-01".to_string(),
+                    period_end: "2026-01-31".to_string(),
+                    currency: "USD".to_string(),
+                    replaced_by: None,
+                    allow_currency_mismatch: false,
+                },
                 "sha256:abc123",
                 4096,
-                None,
             )
             .expect("create statement");
 
@@ -376,26 +1228,32 @@ mod tests {
 
         db.create_statement(
             first_id,
-            "Bank",
-            account_id,
-            "2026-02-01",
-            "2026-02-28",
-            "USD",
+            &AddStatementInput {
+                institution: "Bank".to_string(),
+                account_id,
+                period_start: "2026-02-01".to_string(),
+                period_end: "2026-02-28".to_string(),
+                currency: "USD".to_string(),
+                replaced_by: None,
+                allow_currency_mismatch: false,
+            },
             "sha256:first",
             100,
-            None,
         )
         .expect("create first statement");
         db.create_statement(
             second_id,
-            "Bank",
-            account_id,
-            "2026-03-01",
-            "2026-03-31",
-            "USD",
+            &AddStatementInput {
+                institution: "Bank".to_string(),
+                account_id,
+                period_start: "2026-03-01".to_string(),
+                period_end: "2026-03-31".to_string(),
+                currency: "USD".to_string(),
+                replaced_by: Some(first_id),
+                allow_currency_mismatch: false,
+            },
             "sha256:second",
             200,
-            Some(first_id),
         )
         .expect("create second statement");
 
@@ -406,4 +1264,689 @@ mod tests {
             .iter()
             .any(|s| s.id == second_id && s.replaced_by == Some(first_id)));
     }
+
+    #[test]
+    fn infer_period_from_filename_handles_table_of_names() {
+        let cases: &[(&str, Option<(&str, &str)>)] = &[
+            ("2026-01-31_amex.pdf", Some(("2026-01-01", "2026-01-31"))),
+            ("statement_2026-01.pdf", Some(("2026-01-01", "2026-01-31"))),
+            ("chase-2024-02.csv", Some(("2024-02-01", "2024-02-29"))),
+            ("chase-2023-02.csv", Some(("2023-02-01", "2023-02-28"))),
+            ("2026-03-15.pdf", Some(("2026-02-13", "2026-03-15"))),
+            ("amex_2026-01-05_closing.pdf", Some(("2025-12-06", "2026-01-05"))),
+            ("2026-01-31_2026-02-28.pdf", Some(("2026-01-01", "2026-01-31"))),
+            ("statement-2026-13.pdf", None),
+            ("statement-2026-02-30.pdf", None),
+            ("acct12026-01.pdf", None),
+            ("2026-011.pdf", None),
+            ("statement.pdf", None),
+            ("", None),
+        ];
+
+        for (file_name, expected) in cases {
+            let expected = expected.map(|(start, end)| InferredPeriod {
+                period_start: start.to_string(),
+                period_end: end.to_string(),
+            });
+            assert_eq!(
+                infer_period_from_filename(file_name),
+                expected,
+                "file name: {file_name}"
+            );
+        }
+    }
+
+    #[test]
+    fn civil_day_conversion_round_trips() {
+        for (year, month, day) in [(1970, 1, 1), (2000, 2, 29), (2026, 12, 31), (1969, 12, 31)] {
+            let days = days_from_civil(year, month, day);
+            assert_eq!(civil_from_days(days), (year, month, day));
+        }
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+    }
+
+    #[test]
+    fn create_statement_records_primary_statement_file() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("14141414-1414-1414-1414-141414141414").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let statement_id = Uuid::parse_str("15151515-1515-1515-1515-151515151515").unwrap();
+        db.create_statement(
+            statement_id,
+            &AddStatementInput {
+                institution: "Chase".to_string(),
+                account_id,
+                period_start: "2026-01-01".to_string(),
+                period_end: "2026-01-31".to_string(),
+                currency: "USD".to_string(),
+                replaced_by: None,
+                allow_currency_mismatch: false,
+            },
+            "primaryhash",
+            42,
+        )
+        .expect("create statement");
+
+        let files = db.list_statement_files(statement_id).expect("list files");
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].statement_id, statement_id);
+        assert_eq!(files[0].file_hash, "primaryhash");
+        assert_eq!(files[0].file_size, 42);
+        assert_eq!(files[0].original_filename, None);
+        assert_eq!(files[0].kind, PRIMARY_STATEMENT_FILE_KIND);
+    }
+
+    #[test]
+    fn create_statement_reports_unknown_account() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let missing = Uuid::parse_str("cdcdcdcd-0000-0000-0000-000000000001").unwrap();
+
+        let err = db
+            .create_statement(
+                Uuid::parse_str("cdcdcdcd-0000-0000-0000-000000000002").unwrap(),
+                &AddStatementInput {
+                    institution: "Chase".to_string(),
+                    account_id: missing,
+                    period_start: "2026-01-01".to_string(),
+                    period_end: "2026-01-31".to_string(),
+                    currency: "USD".to_string(),
+                    replaced_by: None,
+                    allow_currency_mismatch: false,
+                },
+                "sha256:abc",
+                1,
+            )
+            .expect_err("missing account should fail");
+
+        assert!(matches!(err, StatementWriteError::UnknownAccount(id) if id == missing));
+        assert!(err.to_string().contains("create the account first"));
+        assert!(db.list_statements().expect("list statements").is_empty());
+    }
+
+    #[test]
+    fn create_statement_keeps_sql_error_for_missing_replacement() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("cdcdcdcd-0000-0000-0000-000000000003").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+
+        let err = db
+            .create_statement(
+                Uuid::parse_str("cdcdcdcd-0000-0000-0000-000000000004").unwrap(),
+                &AddStatementInput {
+                    institution: "Chase".to_string(),
+                    account_id,
+                    period_start: "2026-01-01".to_string(),
+                    period_end: "2026-01-31".to_string(),
+                    currency: "USD".to_string(),
+                    replaced_by: Some(Uuid::parse_str("cdcdcdcd-0000-0000-0000-000000000005").unwrap()),
+                    allow_currency_mismatch: false,
+                },
+                "sha256:abc",
+                1,
+            )
+            .expect_err("missing replacement should fail");
+
+        assert!(matches!(err, StatementWriteError::Sql(_)));
+    }
+
+    #[test]
+    fn insert_cleanup_failure_reports_the_insert_error_once() {
+        let account_id = Uuid::parse_str("cdcdcdcd-0000-0000-0000-000000000040").unwrap();
+        let err = AddStatementError::InsertStatementCleanupFailed {
+            insert_error: StatementWriteError::UnknownAccount(account_id),
+            cleanup_error: std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied"),
+            path: PathBuf::from("statements/abc.pdf"),
+        };
+
+        let chain: Vec<String> = std::iter::successors(
+            Some(&err as &dyn std::error::Error),
+            |err| err.source(),
+        )
+        .map(ToString::to_string)
+        .collect();
+        assert_eq!(
+            chain,
+            [
+                "failed to insert statement row and failed to remove copied file \
+                 statements/abc.pdf: denied"
+                    .to_string(),
+                format!("account {account_id} does not exist; create the account first"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_relative_duration_reads_hours_days_and_weeks() {
+        let hours = |count: u64| Some(Duration::from_secs(count * 60 * 60));
+        assert_eq!(parse_relative_duration("24h"), hours(24));
+        assert_eq!(parse_relative_duration("7d"), hours(7 * 24));
+        assert_eq!(parse_relative_duration("3w"), hours(3 * 7 * 24));
+        assert_eq!(parse_relative_duration("0d"), hours(0));
+        for invalid in ["", "d", "7", "7m", "-7d", "1.5d", "7 d"] {
+            assert_eq!(parse_relative_duration(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn imported_since_parse_accepts_durations_and_dates() {
+        assert_eq!(
+            ImportedSince::parse("7d"),
+            Some(ImportedSince::Ago(Duration::from_secs(7 * 24 * 60 * 60)))
+        );
+        assert_eq!(
+            ImportedSince::parse(" 2026-01-01 "),
+            Some(ImportedSince::Date("2026-01-01".to_string()))
+        );
+        assert_eq!(ImportedSince::parse("2026-1-01"), None);
+        assert_eq!(ImportedSince::parse("last week"), None);
+    }
+
+    #[test]
+    fn list_statements_imported_since_uses_index_and_cutoff() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("cdcdcdcd-0000-0000-0000-000000000010").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let mut ids = Vec::new();
+        for (idx, imported_at) in [
+            "2025-12-31 23:59:59",
+            "2026-01-01 00:00:00",
+            "2026-01-05 12:00:00",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let id = create_test_statement(
+                &db,
+                &format!("cdcdcdcd-0000-0000-0000-00000000002{idx}"),
+                account_id,
+                &format!("sha256:imported{idx}"),
+                None,
+            );
+            db.conn()
+                .execute(
+                    "UPDATE statements SET imported_at = ?1 WHERE id = ?2",
+                    [imported_at, &id.to_string()],
+                )
+                .expect("set imported_at");
+            ids.push(id);
+        }
+
+        let cutoff = db
+            .imported_since_cutoff(&ImportedSince::Date("2026-01-01".to_string()))
+            .expect("cutoff");
+        let since: Vec<Uuid> = db
+            .list_statements_imported_since(&cutoff)
+            .expect("list statements")
+            .iter()
+            .map(|statement| statement.id)
+            .collect();
+        assert_eq!(since, ids[1..]);
+
+        let recent = db
+            .imported_since_cutoff(&ImportedSince::Ago(Duration::from_secs(60 * 60)))
+            .expect("relative cutoff");
+        assert!(db
+            .list_statements_imported_since(&recent)
+            .expect("list statements")
+            .is_empty());
+
+        let plan: String = db
+            .conn()
+            .query_row(
+                "EXPLAIN QUERY PLAN SELECT id FROM statements WHERE imported_at >= ?1",
+                [&cutoff],
+                |row| row.get(3),
+            )
+            .expect("query plan");
+        assert!(plan.contains("statements_imported_at_idx"), "{plan}");
+    }
+
+    #[test]
+    fn normalize_period_date_reads_common_formats() {
+        for (value, expected) in [
+            ("2026-01-31", Some("2026-01-31")),
+            (" 2026/1/5 ", Some("2026-01-05")),
+            ("2026.12.01", Some("2026-12-01")),
+            ("20260131", Some("2026-01-31")),
+            ("20260é1", None),
+            ("01/31/2026", Some("2026-01-31")),
+            ("2/29/2024", Some("2024-02-29")),
+            ("2/29/2026", None),
+            ("31/01/2026", None),
+            ("01-31-2026", None),
+            ("2026-13-01", None),
+            ("Jan 31 2026", None),
+            ("", None),
+        ] {
+            assert_eq!(normalize_period_date(value).as_deref(), expected, "{value}");
+        }
+        assert!(is_valid_period("2026-01-01", "2026-01-01"));
+        assert!(!is_valid_period("2026-01-31", "2026-01-01"));
+        assert!(!is_valid_period("2026-02-30", "2026-03-01"));
+        assert_eq!(normalize_period("01/31/2026", "2026-01-01"), None);
+    }
+
+    #[test]
+    fn create_statement_rejects_invalid_periods() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("cdcdcdcd-0000-0000-0000-000000000030").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+
+        for (start, end) in [("01/01/2026", "01/31/2026"), ("2026-01-31", "2026-01-01")] {
+            let err = db
+                .create_statement(
+                    Uuid::new_v4(),
+                    &AddStatementInput {
+                        institution: "Chase".to_string(),
+                        account_id,
+                        period_start: start.to_string(),
+                        period_end: end.to_string(),
+                        currency: "USD".to_string(),
+                        replaced_by: None,
+                        allow_currency_mismatch: false,
+                    },
+                    "sha256:period",
+                    1,
+                )
+                .expect_err("invalid period should fail");
+            assert!(matches!(
+                err,
+                StatementWriteError::InvalidPeriod { ref period_start, ref period_end }
+                    if period_start == start && period_end == end
+            ));
+        }
+        assert!(db.list_statements().expect("list statements").is_empty());
+    }
+
+    #[test]
+    fn fix_statement_periods_rewrites_legacy_rows_it_can_read() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("cdcdcdcd-0000-0000-0000-000000000031").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let ids: Vec<Uuid> = (0..3)
+            .map(|idx| {
+                create_test_statement(
+                    &db,
+                    &format!("cdcdcdcd-0000-0000-0000-00000000004{idx}"),
+                    account_id,
+                    &format!("sha256:legacy{idx}"),
+                    None,
+                )
+            })
+            .collect();
+        // Rows stored before periods were validated.
+        for (id, start, end) in [
+            (ids[1], "01/01/2026", "01/31/2026"),
+            (ids[2], "2026-01-31", "sometime"),
+        ] {
+            db.conn()
+                .execute(
+                    "UPDATE statements SET period_start = ?2, period_end = ?3 WHERE id = ?1",
+                    [id.to_string(), start.to_string(), end.to_string()],
+                )
+                .expect("store legacy period");
+        }
+
+        let invalid = db.invalid_statement_periods().expect("scan periods");
+        assert_eq!(
+            invalid,
+            vec![
+                InvalidStatementPeriod {
+                    statement_id: ids[1],
+                    period_start: "01/01/2026".to_string(),
+                    period_end: "01/31/2026".to_string(),
+                    normalized: Some(("2026-01-01".to_string(), "2026-01-31".to_string())),
+                },
+                InvalidStatementPeriod {
+                    statement_id: ids[2],
+                    period_start: "2026-01-31".to_string(),
+                    period_end: "sometime".to_string(),
+                    normalized: None,
+                },
+            ]
+        );
+
+        let fixed = db.fix_statement_periods().expect("fix periods");
+        assert_eq!(fixed, invalid[..1]);
+        let statement = db.get_statement_by_id(ids[1]).expect("get statement").unwrap();
+        assert_eq!(
+            (statement.period_start.as_str(), statement.period_end.as_str()),
+            ("2026-01-01", "2026-01-31")
+        );
+        assert_eq!(db.invalid_statement_periods().expect("scan periods"), invalid[1..]);
+    }
+
+    #[test]
+    fn create_statement_rejects_currency_mismatch_unless_allowed() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("cdcdcdcd-0000-0000-0000-000000000006").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let create = |id: &str, currency: &str, allow_currency_mismatch: bool| {
+            db.create_statement(
+                Uuid::parse_str(id).unwrap(),
+                &AddStatementInput {
+                    institution: "Chase".to_string(),
+                    account_id,
+                    period_start: "2026-01-01".to_string(),
+                    period_end: "2026-01-31".to_string(),
+                    currency: currency.to_string(),
+                    replaced_by: None,
+                    allow_currency_mismatch,
+                },
+                &format!("sha256:{id}"),
+                1,
+            )
+        };
+
+        let err = create("cdcdcdcd-0000-0000-0000-000000000007", "EUR", false)
+            .expect_err("currency mismatch should fail");
+        assert!(matches!(
+            err,
+            StatementWriteError::CurrencyMismatch {
+                ref account_currency,
+                ref statement_currency,
+            } if account_currency == "USD" && statement_currency == "EUR"
+        ));
+        assert!(db.list_statements().expect("list statements").is_empty());
+
+        create("cdcdcdcd-0000-0000-0000-000000000008", "usd", false).expect("same currency");
+        let allowed = create("cdcdcdcd-0000-0000-0000-000000000009", "EUR", true)
+            .expect("allowed mismatch");
+        assert_eq!(allowed.currency, "EUR");
+    }
+
+    #[test]
+    fn statement_currency_mismatches_lists_existing_mismatches() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("cdcdcdcd-0000-0000-0000-00000000000a").unwrap();
+        db.create_account(account_id, None, "travel-card", "USD", None)
+            .expect("create account");
+        create_test_statement(
+            &db,
+            "cdcdcdcd-0000-0000-0000-00000000000b",
+            account_id,
+            "sha256:usd",
+            None,
+        );
+        let mismatched = Uuid::parse_str("cdcdcdcd-0000-0000-0000-00000000000c").unwrap();
+        db.create_statement(
+            mismatched,
+            &AddStatementInput {
+                institution: "Chase".to_string(),
+                account_id,
+                period_start: "2026-02-01".to_string(),
+                period_end: "2026-02-28".to_string(),
+                currency: "EUR".to_string(),
+                replaced_by: None,
+                allow_currency_mismatch: true,
+            },
+            "sha256:eur",
+            1,
+        )
+        .expect("allowed mismatch");
+
+        assert_eq!(
+            db.statement_currency_mismatches().expect("scan"),
+            vec![StatementCurrencyMismatch {
+                statement_id: mismatched,
+                account_name: "travel-card".to_string(),
+                account_currency: "USD".to_string(),
+                statement_currency: "EUR".to_string(),
+            }]
+        );
+    }
+
+    fn create_test_statement(
+        db: &Db,
+        id: &str,
+        account_id: Uuid,
+        file_hash: &str,
+        replaced_by: Option<Uuid>,
+    ) -> Uuid {
+        let id = Uuid::parse_str(id).unwrap();
+        db.create_statement(
+            id,
+            &AddStatementInput {
+                institution: "Chase".to_string(),
+                account_id,
+                period_start: "2026-01-01".to_string(),
+                period_end: "2026-01-31".to_string(),
+                currency: "USD".to_string(),
+                replaced_by,
+                allow_currency_mismatch: false,
+            },
+            file_hash,
+            1,
+        )
+        .expect("create statement");
+        id
+    }
+
+    #[test]
+    fn delete_statement_removes_row_and_file_rows() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("efefefef-0000-0000-0000-000000000001").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let id = create_test_statement(
+            &db,
+            "efefefef-0000-0000-0000-000000000002",
+            account_id,
+            "sha256:aa",
+            None,
+        );
+
+        let files = db.delete_statement(id).expect("delete statement");
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_hash, "sha256:aa");
+        assert!(db.list_statements().expect("list statements").is_empty());
+        assert!(db.list_statement_files(id).expect("list files").is_empty());
+        assert!(matches!(
+            db.delete_statement(id),
+            Err(StatementWriteError::NotFound(missing)) if missing == id
+        ));
+    }
+
+    #[test]
+    fn delete_statement_refuses_when_referenced_by_replaced_by() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("efefefef-0000-0000-0000-000000000003").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let original = create_test_statement(
+            &db,
+            "efefefef-0000-0000-0000-000000000004",
+            account_id,
+            "sha256:aa",
+            None,
+        );
+        let replacement = create_test_statement(
+            &db,
+            "efefefef-0000-0000-0000-000000000005",
+            account_id,
+            "sha256:bb",
+            Some(original),
+        );
+
+        let err = db
+            .delete_statement(original)
+            .expect_err("referenced statement should not be deleted");
+
+        assert!(matches!(
+            err,
+            StatementWriteError::ReferencedBy { id, ref replaced_by }
+                if id == original && replaced_by == &vec![replacement]
+        ));
+        assert_eq!(db.list_statements().expect("list statements").len(), 2);
+    }
+
+    #[test]
+    fn latest_statement_orders_by_imported_at_then_id() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        assert_eq!(db.latest_statement().expect("latest of none"), None);
+        let account_id = Uuid::parse_str("efefefef-0000-0000-0000-000000000006").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let older = create_test_statement(
+            &db,
+            "efefefef-0000-0000-0000-00000000000f",
+            account_id,
+            "sha256:aa",
+            None,
+        );
+        let low_id = create_test_statement(
+            &db,
+            "efefefef-0000-0000-0000-000000000007",
+            account_id,
+            "sha256:bb",
+            None,
+        );
+        let high_id = create_test_statement(
+            &db,
+            "efefefef-0000-0000-0000-000000000008",
+            account_id,
+            "sha256:cc",
+            None,
+        );
+        db.conn()
+            .execute(
+                "UPDATE statements SET imported_at = '2026-01-01 00:00:00' WHERE id = ?1",
+                [older.to_string()],
+            )
+            .expect("backdate statement");
+        db.conn()
+            .execute(
+                "UPDATE statements SET imported_at = '2026-02-01 00:00:00' WHERE id <> ?1",
+                [older.to_string()],
+            )
+            .expect("set shared timestamp");
+
+        let latest = db.latest_statement().expect("latest").expect("some statement");
+
+        assert_eq!(latest.id, high_id);
+        assert_ne!(latest.id, low_id);
+    }
+
+    /// Create `ids` imported a day apart, each replaced by the one after it.
+    fn create_test_chain(db: &Db, account_id: Uuid, ids: &[&str]) -> Vec<Uuid> {
+        let mut chain: Vec<Uuid> = Vec::new();
+        for (day, id) in ids.iter().enumerate().rev() {
+            let replaced_by = chain.last().copied();
+            let hash = format!("sha256:{day}");
+            let id = create_test_statement(db, id, account_id, &hash, replaced_by);
+            db.conn()
+                .execute(
+                    "UPDATE statements SET imported_at = ?1 WHERE id = ?2",
+                    [format!("2026-01-0{} 00:00:00", day + 1), id.to_string()],
+                )
+                .expect("set imported_at");
+            chain.push(id);
+        }
+        chain.reverse();
+        chain
+    }
+
+    #[test]
+    fn superseded_chain_walks_links_both_ways_oldest_first() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("abababab-0000-0000-0000-000000000001").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let chain = create_test_chain(
+            &db,
+            account_id,
+            &[
+                "abababab-0000-0000-0000-000000000002",
+                "abababab-0000-0000-0000-000000000003",
+                "abababab-0000-0000-0000-000000000004",
+            ],
+        );
+        let lone = create_test_statement(
+            &db,
+            "abababab-0000-0000-0000-000000000005",
+            account_id,
+            "sha256:lone",
+            None,
+        );
+
+        for id in &chain {
+            let found: Vec<Uuid> = db
+                .superseded_chain(*id)
+                .expect("chain")
+                .iter()
+                .map(|statement| statement.id)
+                .collect();
+            assert_eq!(found, chain);
+        }
+        let alone = db.superseded_chain(lone).expect("chain");
+        assert_eq!(alone.len(), 1);
+        assert!(matches!(
+            db.superseded_chain(account_id),
+            Err(StatementChainError::NotFound(missing)) if missing == account_id
+        ));
+    }
+
+    #[test]
+    fn superseded_chain_reports_cycles() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("abababab-0000-0000-0000-000000000011").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let chain = create_test_chain(
+            &db,
+            account_id,
+            &[
+                "abababab-0000-0000-0000-000000000012",
+                "abababab-0000-0000-0000-000000000013",
+            ],
+        );
+        db.conn()
+            .execute(
+                "UPDATE statements SET replaced_by = ?1 WHERE id = ?2",
+                [chain[0].to_string(), chain[1].to_string()],
+            )
+            .expect("close the loop");
+
+        let err = db.superseded_chain(chain[0]).expect_err("cycle");
+        assert!(matches!(err, StatementChainError::Cycle(ref ids) if ids == &chain));
+        assert!(err.to_string().contains(" -> "));
+        let (chains, cycles) = superseded_chains_in(&db.list_statements().unwrap());
+        assert!(chains.is_empty());
+        assert_eq!(cycles, vec![chain]);
+    }
+
+    #[test]
+    fn delete_superseded_statements_keeps_the_head() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let account_id = Uuid::parse_str("abababab-0000-0000-0000-000000000021").unwrap();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let chain = create_test_chain(
+            &db,
+            account_id,
+            &[
+                "abababab-0000-0000-0000-000000000022",
+                "abababab-0000-0000-0000-000000000023",
+                "abababab-0000-0000-0000-000000000024",
+            ],
+        );
+
+        let files = db
+            .delete_superseded_statements(&chain[..2])
+            .expect("delete superseded");
+
+        assert_eq!(files.len(), 2);
+        let remaining = db.list_statements().expect("list statements");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, chain[2]);
+        assert_eq!(remaining[0].replaced_by, None);
+    }
 }
@@ -146,12 +146,12 @@ pub enum TransactionListError {
 impl Display for TransactionListError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Sql(err) => write!(f, "sqlite error while listing transactions: {err}"),
-            Self::InvalidId { value, source } => {
-                write!(f, "invalid transaction id UUID '{value}': {source}")
+            Self::Sql(_) => write!(f, "sqlite error while listing transactions"),
+            Self::InvalidId { value, .. } => {
+                write!(f, "invalid transaction id UUID '{value}'")
             }
-            Self::InvalidStatementId { value, source } => {
-                write!(f, "invalid transaction statement_id UUID '{value}': {source}")
+            Self::InvalidStatementId { value, .. } => {
+                write!(f, "invalid transaction statement_id UUID '{value}'")
             }
         }
     }
@@ -183,8 +183,8 @@ pub enum TransactionWriteError {
 impl Display for TransactionWriteError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Sql(err) => write!(f, "sqlite error while writing transaction: {err}"),
-            Self::ReadBack(err) => write!(f, "failed to read back transaction after write: {err}"),
+            Self::Sql(_) => write!(f, "sqlite error while writing transaction"),
+            Self::ReadBack(_) => write!(f, "failed to read back transaction after write"),
             Self::NotFound(id) => write!(f, "transaction not found: {id}"),
         }
     }
@@ -218,15 +218,15 @@ pub enum PostingListError {
 impl Display for PostingListError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Sql(err) => write!(f, "sqlite error while listing postings: {err}"),
-            Self::InvalidId { value, source } => {
-                write!(f, "invalid posting id UUID '{value}': {source}")
+            Self::Sql(_) => write!(f, "sqlite error while listing postings"),
+            Self::InvalidId { value, .. } => {
+                write!(f, "invalid posting id UUID '{value}'")
             }
-            Self::InvalidTransactionId { value, source } => {
-                write!(f, "invalid posting transaction_id UUID '{value}': {source}")
+            Self::InvalidTransactionId { value, .. } => {
+                write!(f, "invalid posting transaction_id UUID '{value}'")
             }
-            Self::InvalidAccountId { value, source } => {
-                write!(f, "invalid posting account_id UUID '{value}': {source}")
+            Self::InvalidAccountId { value, .. } => {
+                write!(f, "invalid posting account_id UUID '{value}'")
             }
             Self::InvalidDirection { value } => {
                 write!(f, "invalid posting direction '{value}'")
@@ -263,8 +263,8 @@ pub enum PostingWriteError {
 impl Display for PostingWriteError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Sql(err) => write!(f, "sqlite error while writing posting: {err}"),
-            Self::ReadBack(err) => write!(f, "failed to read back posting after write: {err}"),
+            Self::Sql(_) => write!(f, "sqlite error while writing posting"),
+            Self::ReadBack(_) => write!(f, "failed to read back posting after write"),
             Self::NotFound(id) => write!(f, "posting not found: {id}"),
         }
     }
@@ -298,15 +298,15 @@ pub enum CreateTransactionWithPostingsError {
 impl Display for CreateTransactionWithPostingsError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Sql(err) => write!(
+            Self::Sql(_) => write!(
                 f,
-                "sqlite error while creating transaction with postings: {err}"
+                "sqlite error while creating transaction with postings"
             ),
-            Self::ReadBackTransaction(err) => {
-                write!(f, "failed to read back transaction after atomic write: {err}")
+            Self::ReadBackTransaction(_) => {
+                write!(f, "failed to read back transaction after atomic write")
             }
-            Self::ReadBackPosting(err) => {
-                write!(f, "failed to read back posting after atomic write: {err}")
+            Self::ReadBackPosting(_) => {
+                write!(f, "failed to read back posting after atomic write")
             }
             Self::TransactionNotFound(id) => {
                 write!(f, "transaction not found after atomic write: {id}")
@@ -379,7 +379,7 @@ impl Display for AddTransactionError {
             Self::AmountOverflow { currency } => {
                 write!(f, "posting totals overflowed while validating currency {currency}")
             }
-            Self::Write(err) => write!(f, "failed to create transaction: {err}"),
+            Self::Write(_) => write!(f, "failed to create transaction"),
         }
     }
 }
@@ -491,6 +491,48 @@ impl Db {
         Ok(transactions)
     }
 
+    /// Search descriptions for every whitespace-separated word in `query`, ranked by
+    /// bm25, using the FTS5 index that migration 0006 creates. FTS5 is required; the
+    /// bundled sqlite always has it, so there is no non-indexed fallback.
+    pub fn search_transactions(
+        &self,
+        query: &str,
+    ) -> Result<Vec<Transaction>, TransactionListError> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Quote each word as a phrase so user input can't hit FTS5 query syntax.
+        let match_query = words
+            .iter()
+            .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut stmt = self.conn().prepare(
+            "
+            SELECT
+              t.id,
+              t.statement_id,
+              t.description,
+              t.posted_at,
+              t.created_at
+            FROM transactions_fts
+            JOIN transactions t ON t.rowid = transactions_fts.rowid
+            WHERE transactions_fts MATCH ?1
+            ORDER BY bm25(transactions_fts), t.posted_at, t.id
+            ",
+        )?;
+        let mut rows = stmt.query([match_query])?;
+        let mut transactions = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            transactions.push(Transaction::from_row(row)?);
+        }
+
+        Ok(transactions)
+    }
+
     pub fn create_transaction(
         &self,
         id: Uuid,
@@ -695,6 +737,7 @@ impl Db {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::statement::AddStatementInput;
     use crate::core::db::Db;
 
     #[test]
@@ -722,14 +765,17 @@ mod tests {
         let statement_id = Uuid::parse_str("19191919-1919-1919-1919-191919191919").unwrap();
         db.create_statement(
             statement_id,
-            "Bank",
-            account_id,
-            "2026-02-01",
-            "2026-02-28",
-            "USD",
+            &AddStatementInput {
+                institution: "Bank".to_string(),
+                account_id,
+                period_start: "2026-02-01".to_string(),
+                period_end: "2026-02-28".to_string(),
+                currency: "USD".to_string(),
+                replaced_by: None,
+                allow_currency_mismatch: false,
+            },
             "sha256:tx-stmt",
             123,
-            None,
         )
         .expect("create statement");
 
@@ -992,4 +1038,93 @@ mod tests {
         assert!(core.db_mut().list_transactions().expect("list tx").is_empty());
         assert!(core.db_mut().list_postings().expect("list postings").is_empty());
     }
+
+    fn insert_described_transaction(db: &Db, id: &str, description: &str, posted_at: &str) -> Uuid {
+        let id = Uuid::parse_str(id).unwrap();
+        db.create_transaction(id, None, Some(description), posted_at)
+            .expect("create transaction");
+        id
+    }
+
+    #[test]
+    fn search_transactions_indexes_inserted_descriptions() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let coffee = insert_described_transaction(
+            &db,
+            "81818181-8181-8181-8181-818181818181",
+            "Blue Bottle Coffee",
+            "2026-01-02",
+        );
+        insert_described_transaction(
+            &db,
+            "82828282-8282-8282-8282-828282828282",
+            "Whole Foods Market",
+            "2026-01-03",
+        );
+
+        let found = db.search_transactions("coffee").expect("search transactions");
+
+        let ids: Vec<Uuid> = found.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![coffee]);
+    }
+
+    #[test]
+    fn search_transactions_follows_description_updates() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let id = insert_described_transaction(
+            &db,
+            "83838383-8383-8383-8383-838383838383",
+            "SQ *PENDING MERCHANT",
+            "2026-01-04",
+        );
+
+        db.conn()
+            .execute(
+                "UPDATE transactions SET description = 'Tartine Bakery' WHERE id = ?1",
+                [id.to_string()],
+            )
+            .expect("update description");
+
+        assert!(db
+            .search_transactions("pending")
+            .expect("search old description")
+            .is_empty());
+        let found = db.search_transactions("tartine").expect("search new description");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, id);
+    }
+
+    #[test]
+    fn search_transactions_requires_every_word() {
+        let db = Db::open_for_tests().expect("open in-memory db");
+        let both = insert_described_transaction(
+            &db,
+            "84848484-8484-8484-8484-848484848484",
+            "Costco Gas Station",
+            "2026-01-05",
+        );
+        insert_described_transaction(
+            &db,
+            "85858585-8585-8585-8585-858585858585",
+            "Costco Wholesale",
+            "2026-01-06",
+        );
+        insert_described_transaction(
+            &db,
+            "86868686-8686-8686-8686-868686868686",
+            "Shell Gas",
+            "2026-01-07",
+        );
+
+        let found = db.search_transactions("costco gas").expect("multi-word search");
+
+        let ids: Vec<Uuid> = found.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![both]);
+        assert!(db
+            .search_transactions("   ")
+            .expect("blank search")
+            .is_empty());
+        db.search_transactions("\"costco* -OR:")
+            .expect("fts syntax characters are treated as text");
+    }
 }
@@ -1,19 +1,58 @@
 use super::db::{Db, DbError};
-use super::statement::{AddStatementError, AddStatementInput, Statement};
-use sha2::{Digest, Sha256};
+use super::profile::{active_profile_name, profile_data_dir};
+use super::settings::STATEMENT_LAYOUT_SETTING;
+use super::statement::{
+    superseded_chains_in, AddStatementError, AddStatementInput, Statement, StatementFile,
+    StatementListError, StatementWriteError,
+};
+use super::file_hash::{hash_file, parse_file_hash, FileHashError, FileHasher, HashAlgorithm};
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
 const APP_DIR_NAME: &str = "tally42";
 const DB_FILE_NAME: &str = "tally42.db";
 const STATEMENTS_DIR_NAME: &str = "statements";
+const TEMP_STATEMENT_PREFIX: &str = ".tmp-statement-";
+const MANIFEST_FILE_NAME: &str = "manifest.txt";
+const MANIFEST_SIZE_PREFIX: &str = "# size ";
+/// Temp files younger than this may belong to a concurrent import and are left alone.
+pub(crate) const STALE_TEMP_STATEMENT_AGE: Duration = Duration::from_secs(24 * 60 * 60);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UserDataManager {
     data_dir: PathBuf,
     db_path: PathBuf,
+    hash_algorithm: HashAlgorithm,
+    statement_layout: StatementLayout,
+}
+
+/// Where new statement files are placed under `statements/`. Lookups always search
+/// every layout, so switching layouts never hides existing files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatementLayout {
+    #[default]
+    Flat,
+    ByAccount,
+}
+
+impl StatementLayout {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Flat => "flat",
+            Self::ByAccount => "by-account",
+        }
+    }
+
+    /// The layout `as_str` names.
+    pub fn parse(value: &str) -> Option<Self> {
+        [Self::Flat, Self::ByAccount]
+            .into_iter()
+            .find(|layout| layout.as_str() == value)
+    }
 }
 
 #[derive(Debug)]
@@ -22,6 +61,10 @@ pub enum UserDataError {
     CreateDataDir(std::io::Error),
     DeleteDatabase(std::io::Error),
     OpenDb(DbError),
+    CleanupTempFiles(std::io::Error),
+    InvalidProfileName(String),
+    ReadStatementLayout(rusqlite::Error),
+    InvalidStatementLayout(String),
 }
 
 impl Display for UserDataError {
@@ -31,25 +74,434 @@ impl Display for UserDataError {
                 f,
                 "could not resolve user data directory: HOME is not set and XDG_DATA_HOME is absent"
             ),
-            Self::CreateDataDir(err) => write!(f, "failed to create data directory: {err}"),
-            Self::DeleteDatabase(err) => write!(f, "failed to delete sqlite database: {err}"),
-            Self::OpenDb(err) => write!(f, "failed to initialize sqlite database: {err}"),
+            Self::CreateDataDir(_) => write!(f, "failed to create data directory"),
+            Self::DeleteDatabase(_) => write!(f, "failed to delete sqlite database"),
+            Self::OpenDb(_) => write!(f, "failed to initialize sqlite database"),
+            Self::CleanupTempFiles(_) => {
+                write!(f, "failed to clean up stale temp statement files")
+            }
+            Self::InvalidProfileName(name) => write!(
+                f,
+                "invalid profile name '{name}': use letters, digits, '-', '_' or '.'"
+            ),
+            Self::ReadStatementLayout(_) => write!(f, "failed to read the statement layout"),
+            Self::InvalidStatementLayout(value) => write!(
+                f,
+                "unknown statement layout '{value}' in settings, expected flat or by-account"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UserDataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::CreateDataDir(err) => Some(err),
+            Self::DeleteDatabase(err) => Some(err),
+            Self::OpenDb(err) => Some(err),
+            Self::CleanupTempFiles(err) => Some(err),
+            Self::ReadStatementLayout(err) => Some(err),
+            Self::MissingHomeDir
+            | Self::InvalidProfileName(_)
+            | Self::InvalidStatementLayout(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReshardSummary {
+    pub layout: StatementLayout,
+    pub moved: usize,
+    pub unchanged: usize,
+}
+
+#[derive(Debug)]
+pub enum ReshardError {
+    OpenDb(UserDataError),
+    Sql(rusqlite::Error),
+    FileNotFound { file_hash: String },
+    Io { path: PathBuf, source: std::io::Error },
+}
+
+impl Display for ReshardError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OpenDb(_) => write!(f, "failed to open database for reshard"),
+            Self::Sql(_) => write!(f, "sqlite error while listing statement files"),
+            Self::FileNotFound { file_hash } => {
+                write!(f, "no statement file found for hash '{file_hash}'")
+            }
+            Self::Io { path, .. } => {
+                write!(f, "failed to move statement file {}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReshardError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::OpenDb(err) => Some(err),
+            Self::Sql(err) => Some(err),
+            Self::FileNotFound { .. } => None,
+            Self::Io { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for ReshardError {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sql(value)
+    }
+}
+
+/// One `replaced_by` chain after pruning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrunedChain {
+    /// The statement the chain's `replaced_by` links end at, which supersedes the rest.
+    pub head: Statement,
+    /// Deleted statements, oldest import first.
+    pub removed: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneSummary {
+    pub chains: Vec<PrunedChain>,
+    pub removed_files: Vec<PathBuf>,
+    /// Chains left alone because their `replaced_by` links loop.
+    pub cycles: Vec<Vec<Uuid>>,
+}
+
+#[derive(Debug)]
+pub enum PruneStatementsError {
+    OpenDb(UserDataError),
+    List(StatementListError),
+    Delete(StatementWriteError),
+    CheckFileInUse(rusqlite::Error),
+    RemoveFile { path: PathBuf, source: std::io::Error },
+}
+
+impl Display for PruneStatementsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OpenDb(_) => write!(f, "failed to open database for prune"),
+            Self::List(_) => write!(f, "failed to list statements to prune"),
+            Self::Delete(_) => write!(f, "failed to delete superseded statements"),
+            Self::CheckFileInUse(_) => {
+                write!(f, "failed to check whether a statement file is still used")
+            }
+            Self::RemoveFile { path, .. } => write!(
+                f,
+                "statement rows deleted but failed to remove file {}",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PruneStatementsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::OpenDb(err) => Some(err),
+            Self::List(err) => Some(err),
+            Self::Delete(err) => Some(err),
+            Self::CheckFileInUse(err) => Some(err),
+            Self::RemoveFile { source, .. } => Some(source),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndoneStatement {
+    pub statement: Statement,
+    pub removed_files: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum UndoStatementError {
+    OpenDb(UserDataError),
+    Find(StatementListError),
+    Delete(StatementWriteError),
+    RemoveFile { path: PathBuf, source: std::io::Error },
+}
+
+impl Display for UndoStatementError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OpenDb(_) => write!(f, "failed to open database for undo"),
+            Self::Find(_) => write!(f, "failed to find the latest statement"),
+            Self::Delete(_) => write!(f, "failed to delete statement"),
+            Self::RemoveFile { path, .. } => write!(
+                f,
+                "statement row deleted but failed to remove file {}",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UndoStatementError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::OpenDb(err) => Some(err),
+            Self::Find(err) => Some(err),
+            Self::Delete(err) => Some(err),
+            Self::RemoveFile { source, .. } => Some(source),
+        }
+    }
+}
+
+struct StoredFile {
+    path: PathBuf,
+    file_hash: String,
+    file_size: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationSummary {
+    pub source_dir: PathBuf,
+    pub target_dir: PathBuf,
+    pub statement_rows: i64,
+    pub statement_files: usize,
+    pub statement_bytes: u64,
+    pub source_removed: bool,
+}
+
+#[derive(Debug)]
+pub enum MigrateDataDirError {
+    SameDirectory(PathBuf),
+    SourceDbMissing(PathBuf),
+    TargetNotEmpty(PathBuf),
+    CreateTargetDir(std::io::Error),
+    OpenSourceDb(DbError),
+    BackupDb(rusqlite::Error),
+    CopyStatementFile { path: PathBuf, source: std::io::Error },
+    Verify(DataDirVerifyError),
+    RemoveSource { path: PathBuf, source: std::io::Error },
+}
+
+impl Display for MigrateDataDirError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SameDirectory(path) => write!(
+                f,
+                "target data directory is the same as the source: {}",
+                path.display()
+            ),
+            Self::SourceDbMissing(path) => {
+                write!(f, "source sqlite database not found at {}", path.display())
+            }
+            Self::TargetNotEmpty(path) => write!(
+                f,
+                "target data directory exists and is not empty: {}",
+                path.display()
+            ),
+            Self::CreateTargetDir(_) => {
+                write!(f, "failed to create target data directory")
+            }
+            Self::OpenSourceDb(_) => write!(f, "failed to open source sqlite database"),
+            Self::BackupDb(_) => write!(f, "failed to back up sqlite database to target"),
+            Self::CopyStatementFile { path, .. } => write!(
+                f,
+                "failed to copy statement file {}",
+                path.display()
+            ),
+            Self::Verify(_) => write!(f, "copied data failed verification"),
+            Self::RemoveSource { path, .. } => write!(
+                f,
+                "data copied and verified but failed to remove source {}",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrateDataDirError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::SameDirectory(_) => None,
+            Self::SourceDbMissing(_) => None,
+            Self::TargetNotEmpty(_) => None,
+            Self::CreateTargetDir(err) => Some(err),
+            Self::OpenSourceDb(err) => Some(err),
+            Self::BackupDb(err) => Some(err),
+            Self::CopyStatementFile { source, .. } => Some(source),
+            Self::Verify(err) => Some(err),
+            Self::RemoveSource { source, .. } => Some(source),
+        }
+    }
+}
+
+/// One managed statement file as recorded in `manifest.txt`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// Path relative to the data dir, e.g. `statements/<digest>.pdf`.
+    pub path: PathBuf,
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// Differences between `manifest.txt` and the statement files currently on disk.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestCheck {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub changed: Vec<PathBuf>,
+    pub unchanged: usize,
+}
+
+impl ManifestCheck {
+    pub fn is_clean(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Missing(PathBuf),
+    Io { path: PathBuf, source: std::io::Error },
+    Parse { line_number: usize, line: String },
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(path) => write!(
+                f,
+                "no manifest at {}; write one with write-manifest first",
+                path.display()
+            ),
+            Self::Io { path, .. } => write!(f, "failed to access {}", path.display()),
+            Self::Parse { line_number, line } => {
+                write!(f, "invalid manifest line {line_number}: '{line}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Missing(_) => None,
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DataDirVerifyError {
+    Io { path: PathBuf, source: std::io::Error },
+    Sql(rusqlite::Error),
+    StatementRowCountMismatch { source: i64, target: i64 },
+    StatementFileCountMismatch { source: usize, target: usize },
+    StatementFileMissing { file_name: String },
+    StatementFileHashMismatch { file_name: String },
+    StatementFileNotFoundForRow { file_hash: String },
+    StatementFileContentMismatch { file_hash: String },
+    InvalidFileHash(FileHashError),
+}
+
+impl Display for DataDirVerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, .. } => write!(f, "failed to read {}", path.display()),
+            Self::Sql(_) => write!(f, "sqlite error while verifying database"),
+            Self::StatementRowCountMismatch { source, target } => write!(
+                f,
+                "statement row count mismatch: source={source}, target={target}"
+            ),
+            Self::StatementFileCountMismatch { source, target } => write!(
+                f,
+                "statement file count mismatch: source={source}, target={target}"
+            ),
+            Self::StatementFileMissing { file_name } => {
+                write!(f, "statement file missing at target: {file_name}")
+            }
+            Self::StatementFileHashMismatch { file_name } => {
+                write!(f, "statement file hash mismatch at target: {file_name}")
+            }
+            Self::StatementFileNotFoundForRow { file_hash } => write!(
+                f,
+                "no statement file found at target for statement row with hash '{file_hash}'"
+            ),
+            Self::StatementFileContentMismatch { file_hash } => write!(
+                f,
+                "statement file content at target does not match hash '{file_hash}'"
+            ),
+            Self::InvalidFileHash(_) => write!(f, "invalid statement file hash"),
+        }
+    }
+}
+
+impl std::error::Error for DataDirVerifyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Sql(err) => Some(err),
+            Self::StatementRowCountMismatch { .. } => None,
+            Self::StatementFileCountMismatch { .. } => None,
+            Self::StatementFileMissing { .. } => None,
+            Self::StatementFileHashMismatch { .. } => None,
+            Self::StatementFileNotFoundForRow { .. } => None,
+            Self::StatementFileContentMismatch { .. } => None,
+            Self::InvalidFileHash(err) => Some(err),
         }
     }
 }
 
-impl std::error::Error for UserDataError {}
+impl From<rusqlite::Error> for DataDirVerifyError {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sql(value)
+    }
+}
 
 impl UserDataManager {
     pub fn from_data_dir(data_dir: impl AsRef<Path>) -> Self {
         let data_dir = data_dir.as_ref().to_path_buf();
         let db_path = data_dir.join(DB_FILE_NAME);
-        Self { data_dir, db_path }
+        Self {
+            data_dir,
+            db_path,
+            hash_algorithm: HashAlgorithm::default(),
+            statement_layout: StatementLayout::default(),
+        }
+    }
+
+    pub fn with_statement_layout(mut self, statement_layout: StatementLayout) -> Self {
+        self.statement_layout = statement_layout;
+        self
+    }
+
+    /// Use the layout `reshard_statements` last stored in `db`, if it stored one.
+    pub(crate) fn with_stored_statement_layout(self, db: &Db) -> Result<Self, UserDataError> {
+        let stored = db
+            .setting(STATEMENT_LAYOUT_SETTING)
+            .map_err(UserDataError::ReadStatementLayout)?;
+        match stored {
+            Some(value) => match StatementLayout::parse(&value) {
+                Some(layout) => Ok(self.with_statement_layout(layout)),
+                None => Err(UserDataError::InvalidStatementLayout(value)),
+            },
+            None => Ok(self),
+        }
+    }
+
+    /// Algorithm used to hash newly imported statement files. Existing hashes keep
+    /// whatever algorithm their prefix names.
+    pub fn with_hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
     }
 
+    /// Data dir for the active profile (`TALLY42_PROFILE`, else the default profile).
     pub fn from_environment() -> Result<Self, UserDataError> {
-        let data_dir = resolve_default_data_dir()?;
-        Ok(Self::from_data_dir(data_dir))
+        Self::for_profile(&active_profile_name())
+    }
+
+    pub fn for_profile(name: &str) -> Result<Self, UserDataError> {
+        let root = resolve_default_data_dir()?;
+        Ok(Self::from_data_dir(profile_data_dir(&root, name)?))
     }
 
     pub fn init(&self) -> Result<(), UserDataError> {
@@ -63,6 +515,11 @@ impl UserDataManager {
         Db::open(&self.db_path).map_err(UserDataError::OpenDb)
     }
 
+    /// Open the existing database as is: nothing is created and no migrations run.
+    pub fn open_db_read_only(&self) -> Result<Db, UserDataError> {
+        Db::open_read_only(&self.db_path).map_err(UserDataError::OpenDb)
+    }
+
     pub fn add_statement(
         &self,
         source_path: impl AsRef<Path>,
@@ -70,14 +527,81 @@ impl UserDataManager {
     ) -> Result<Statement, AddStatementError> {
         let source_path = source_path.as_ref();
         let db = self.open_db().map_err(AddStatementError::PrepareUserData)?;
-        let statements_dir = self.statements_dir();
+        let account_dir = self.account_dir_for(&db, input.account_id)?;
+        let stored = self.store_statement_file(source_path, account_dir.as_deref())?;
+
+        let statement_id = Uuid::new_v4();
+        let insert_result = db.create_statement(
+            statement_id,
+            &input,
+            &stored.file_hash,
+            stored.file_size,
+        );
+        finish_stored_file(stored.path, insert_result)
+    }
+
+    /// Attach an additional file (e.g. a CSV export) to an existing statement, with the
+    /// same hashing, duplicate detection, and rollback behavior as `add_statement`.
+    pub fn attach_file(
+        &self,
+        statement_id: Uuid,
+        source_path: impl AsRef<Path>,
+        kind: &str,
+    ) -> Result<StatementFile, AddStatementError> {
+        let source_path = source_path.as_ref();
+        let db = self.open_db().map_err(AddStatementError::PrepareUserData)?;
+        let account_id = db
+            .get_statement_by_id(statement_id)
+            .map_err(AddStatementError::InsertStatement)?
+            .map(|statement| statement.account_id);
+        let account_dir = match account_id {
+            Some(account_id) => self.account_dir_for(&db, account_id)?,
+            None => None,
+        };
+        let stored = self.store_statement_file(source_path, account_dir.as_deref())?;
+
+        let original_filename = source_path.file_name().and_then(|name| name.to_str());
+        let insert_result = db.create_statement_file(
+            Uuid::new_v4(),
+            statement_id,
+            &stored.file_hash,
+            stored.file_size,
+            original_filename,
+            kind,
+        );
+        finish_stored_file(stored.path, insert_result)
+    }
+
+    /// Subdirectory for `account_id` under the by-account layout, `None` when flat.
+    /// An unknown account falls back to flat; the insert will reject it anyway.
+    fn account_dir_for(
+        &self,
+        db: &Db,
+        account_id: Uuid,
+    ) -> Result<Option<String>, AddStatementError> {
+        if self.statement_layout == StatementLayout::Flat {
+            return Ok(None);
+        }
+        let account = db
+            .get_account_by_id(account_id)
+            .map_err(AddStatementError::LookupAccount)?;
+        Ok(account.map(|account| account_dir_name(&account.name)))
+    }
 
+    /// Stream `source_path` into the statements dir under its content hash. The caller
+    /// must record the file in the database or remove it via `finish_stored_file`.
+    fn store_statement_file(
+        &self,
+        source_path: &Path,
+        account_dir: Option<&str>,
+    ) -> Result<StoredFile, AddStatementError> {
+        let statements_dir = self.statements_dir();
         let mut source = std::fs::File::open(source_path).map_err(AddStatementError::OpenSource)?;
-        let temp_path = statements_dir.join(format!(".tmp-statement-{}", Uuid::new_v4()));
+        let temp_path = statements_dir.join(format!("{TEMP_STATEMENT_PREFIX}{}", Uuid::new_v4()));
         let mut temp_file =
             std::fs::File::create(&temp_path).map_err(AddStatementError::CreateTempFile)?;
 
-        let mut hasher = Sha256::new();
+        let mut hasher = FileHasher::new(self.hash_algorithm);
         let mut buf = [0u8; 8192];
         loop {
             let n = source.read(&mut buf).map_err(AddStatementError::ReadSource)?;
@@ -97,8 +621,8 @@ impl UserDataManager {
             .len();
         let file_size = i64::try_from(file_size_u64)
             .map_err(|_| AddStatementError::FileTooLarge(file_size_u64))?;
-        let file_hash = format!("{:x}", hasher.finalize());
-        let final_path = self.statement_file_path_for_source(&file_hash, source_path);
+        let file_hash = hasher.finalize();
+        let final_path = self.statement_file_path_for_source(&file_hash, source_path, account_dir);
         drop(temp_file);
 
         let duplicate_path = self.find_statement_file_path(&file_hash);
@@ -110,109 +634,742 @@ impl UserDataManager {
             });
         }
 
+        if let Some(parent) = final_path.parent() {
+            std::fs::create_dir_all(parent).map_err(AddStatementError::RenameToFinal)?;
+        }
         std::fs::rename(&temp_path, &final_path).map_err(AddStatementError::RenameToFinal)?;
 
-        let statement_id = Uuid::new_v4();
-        let insert_result = db.create_statement(
-            statement_id,
-            &input.institution,
-            input.account_id,
-            &input.period_start,
-            &input.period_end,
-            &input.currency,
-            &file_hash,
+        Ok(StoredFile {
+            path: final_path,
+            file_hash,
             file_size,
-            input.replaced_by,
-        );
-
-        match insert_result {
-            Ok(statement) => Ok(statement),
-            Err(insert_error) => match std::fs::remove_file(&final_path) {
-                Ok(()) => Err(AddStatementError::InsertStatement(insert_error)),
-                Err(cleanup_error) => Err(AddStatementError::InsertStatementCleanupFailed {
-                    insert_error,
-                    cleanup_error,
-                    path: final_path,
-                }),
-            },
-        }
+        })
     }
 
-    pub fn delete_db(&self) -> Result<bool, UserDataError> {
-        match std::fs::remove_file(&self.db_path) {
-            Ok(()) => Ok(true),
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
-            Err(err) => Err(UserDataError::DeleteDatabase(err)),
+    /// Remove `.tmp-statement-*` files whose mtime is older than `max_age`, returning
+    /// the removed paths.
+    pub fn cleanup_stale_temp_files(
+        &self,
+        max_age: Duration,
+    ) -> Result<Vec<PathBuf>, UserDataError> {
+        let mut removed = Vec::new();
+        for path in self.stale_temp_files(max_age)? {
+            match std::fs::remove_file(&path) {
+                Ok(()) => removed.push(path),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(UserDataError::CleanupTempFiles(err)),
+            }
         }
+        Ok(removed)
     }
 
-    pub fn data_dir(&self) -> &Path {
-        &self.data_dir
-    }
+    /// The `.tmp-statement-*` files whose mtime is older than `max_age`, sorted.
+    pub fn stale_temp_files(&self, max_age: Duration) -> Result<Vec<PathBuf>, UserDataError> {
+        let entries = match std::fs::read_dir(self.statements_dir()) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(UserDataError::CleanupTempFiles(err)),
+        };
 
-    pub fn db_path(&self) -> &Path {
-        &self.db_path
-    }
+        let now = SystemTime::now();
+        let mut stale = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(UserDataError::CleanupTempFiles)?;
+            let is_temp = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with(TEMP_STATEMENT_PREFIX));
+            if !is_temp {
+                continue;
+            }
 
-    pub fn statements_dir(&self) -> PathBuf {
-        self.data_dir.join(STATEMENTS_DIR_NAME)
+            let modified = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .map_err(UserDataError::CleanupTempFiles)?;
+            // An mtime in the future counts as fresh.
+            let age = now.duration_since(modified).unwrap_or(Duration::ZERO);
+            if age >= max_age {
+                stale.push(entry.path());
+            }
+        }
+        stale.sort();
+        Ok(stale)
     }
 
-    pub fn statement_file_path(&self, file_hash: &str) -> PathBuf {
-        self.find_statement_file_path(file_hash)
-            .unwrap_or_else(|| self.statements_dir().join(file_hash))
+    pub fn last_imported_statement(&self) -> Result<Option<Statement>, UndoStatementError> {
+        let db = self.open_db().map_err(UndoStatementError::OpenDb)?;
+        db.latest_statement().map_err(UndoStatementError::Find)
     }
 
-    fn statement_file_path_for_source(&self, file_hash: &str, source_path: &Path) -> PathBuf {
-        match source_path.extension() {
-            Some(ext) if !ext.is_empty() => self
-                .statements_dir()
-                .join(format!("{file_hash}.{}", ext.to_string_lossy())),
-            _ => self.statements_dir().join(file_hash),
+    /// Remove the most recently imported statement and its managed files. The rows are
+    /// deleted first so a crash part-way leaves at worst an unreferenced file, never a
+    /// row pointing at a missing file.
+    pub fn undo_last_statement(&self) -> Result<Option<UndoneStatement>, UndoStatementError> {
+        let db = self.open_db().map_err(UndoStatementError::OpenDb)?;
+        let Some(statement) = db.latest_statement().map_err(UndoStatementError::Find)? else {
+            return Ok(None);
+        };
+        let files = db
+            .delete_statement(statement.id)
+            .map_err(UndoStatementError::Delete)?;
+
+        let mut index = self.statement_file_index();
+        let mut removed_files = Vec::new();
+        for file in files {
+            let Some(path) = index.remove(statement_file_stem(&file.file_hash)) else {
+                continue;
+            };
+            std::fs::remove_file(&path).map_err(|source| UndoStatementError::RemoveFile {
+                path: path.clone(),
+                source,
+            })?;
+            removed_files.push(path);
         }
+        Ok(Some(UndoneStatement {
+            statement,
+            removed_files,
+        }))
     }
 
-    fn find_statement_file_path(&self, file_hash: &str) -> Option<PathBuf> {
-        let exact = self.statements_dir().join(file_hash);
-        if exact.exists() {
-            return Some(exact);
-        }
+    /// Delete all but `keep_latest` statements of every `replaced_by` chain, one
+    /// transaction per chain. The statement the chain's links end at is always kept,
+    /// then those the fewest links away from it, whatever order they were imported
+    /// in. With `delete_files`, managed files no remaining row refers to are removed
+    /// as well.
+    ///
+    /// Chains whose links form a cycle are reported in the summary and left untouched.
+    pub fn prune_superseded_statements(
+        &self,
+        keep_latest: usize,
+        delete_files: bool,
+    ) -> Result<PruneSummary, PruneStatementsError> {
+        let keep_latest = keep_latest.max(1);
+        let db = self.open_db().map_err(PruneStatementsError::OpenDb)?;
+        let statements = db.list_statements().map_err(PruneStatementsError::List)?;
+        let (chains, cycles) = superseded_chains_in(&statements);
 
-        let entries = std::fs::read_dir(self.statements_dir()).ok()?;
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_file() {
+        let mut summary = PruneSummary {
+            cycles,
+            ..PruneSummary::default()
+        };
+        let mut index = if delete_files {
+            self.statement_file_index()
+        } else {
+            HashMap::new()
+        };
+        for mut chain in chains {
+            if chain.len() <= keep_latest {
                 continue;
             }
-            let stem_matches = path
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .map(|s| s == file_hash)
-                .unwrap_or(false);
-            if stem_matches {
-                return Some(path);
+            let mut removed = chain.split_off(keep_latest);
+            removed.sort_by(|a, b| (&a.imported_at, a.id).cmp(&(&b.imported_at, b.id)));
+            let ids: Vec<Uuid> = removed.iter().map(|statement| statement.id).collect();
+            let files = db
+                .delete_superseded_statements(&ids)
+                .map_err(PruneStatementsError::Delete)?;
+
+            if delete_files {
+                for file in files {
+                    if db
+                        .statement_file_hash_in_use(&file.file_hash)
+                        .map_err(PruneStatementsError::CheckFileInUse)?
+                    {
+                        continue;
+                    }
+                    let Some(path) = index.remove(statement_file_stem(&file.file_hash)) else {
+                        continue;
+                    };
+                    std::fs::remove_file(&path).map_err(|source| {
+                        PruneStatementsError::RemoveFile {
+                            path: path.clone(),
+                            source,
+                        }
+                    })?;
+                    summary.removed_files.push(path);
+                }
             }
+            summary.chains.push(PrunedChain {
+                head: chain.swap_remove(0),
+                removed,
+            });
         }
-        None
+        Ok(summary)
     }
-}
 
-fn resolve_default_data_dir() -> Result<PathBuf, UserDataError> {
-    if let Ok(xdg_data_home) = std::env::var("XDG_DATA_HOME") {
-        return Ok(PathBuf::from(xdg_data_home).join(APP_DIR_NAME));
+    pub fn delete_db(&self) -> Result<bool, UserDataError> {
+        match std::fs::remove_file(&self.db_path) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(UserDataError::DeleteDatabase(err)),
+        }
     }
 
-    if let Ok(home) = std::env::var("HOME") {
-        return Ok(PathBuf::from(home).join(".local").join("share").join(APP_DIR_NAME));
-    }
+    /// Copy the database and managed statement files into `target`, verify the copy,
+    /// and only then (optionally) remove the source. Any failure before removal leaves
+    /// the source untouched and discards whatever was written to `target`.
+    pub fn migrate_data_dir(
+        &self,
+        target: &Path,
+        remove_source: bool,
+    ) -> Result<MigrationSummary, MigrateDataDirError> {
+        if target == self.data_dir
+            || matches!(
+                (target.canonicalize(), self.data_dir.canonicalize()),
+                (Ok(a), Ok(b)) if a == b
+            )
+        {
+            return Err(MigrateDataDirError::SameDirectory(target.to_path_buf()));
+        }
+        if !self.db_path.is_file() {
+            return Err(MigrateDataDirError::SourceDbMissing(self.db_path.clone()));
+        }
 
-    Err(UserDataError::MissingHomeDir)
-}
+        let created_target = match std::fs::read_dir(target) {
+            Ok(mut entries) => {
+                if entries.next().is_some() {
+                    return Err(MigrateDataDirError::TargetNotEmpty(target.to_path_buf()));
+                }
+                false
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => true,
+            Err(err) => return Err(MigrateDataDirError::CreateTargetDir(err)),
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use super::super::statement::StatementWriteError;
+        let target_manager = Self::from_data_dir(target);
+        let mut summary = match self.copy_data_dir_to(&target_manager) {
+            Ok(summary) => summary,
+            Err(err) => {
+                target_manager.discard_partial_copy(created_target);
+                return Err(err);
+            }
+        };
+
+        if remove_source {
+            self.remove_data_dir_contents()?;
+            summary.source_removed = true;
+        }
+        Ok(summary)
+    }
+
+    fn copy_data_dir_to(
+        &self,
+        target: &UserDataManager,
+    ) -> Result<MigrationSummary, MigrateDataDirError> {
+        std::fs::create_dir_all(target.statements_dir())
+            .map_err(MigrateDataDirError::CreateTargetDir)?;
+
+        let db = Db::open(&self.db_path).map_err(MigrateDataDirError::OpenSourceDb)?;
+        db.conn()
+            .backup(rusqlite::MAIN_DB, &target.db_path, None)
+            .map_err(MigrateDataDirError::BackupDb)?;
+        drop(db);
+
+        let statements_dir = self.statements_dir();
+        let files = managed_statement_files(&statements_dir).map_err(|source| {
+            MigrateDataDirError::CopyStatementFile {
+                path: statements_dir.clone(),
+                source,
+            }
+        })?;
+        let mut statement_bytes = 0u64;
+        for relative in &files {
+            let path = statements_dir.join(relative);
+            let dest = target.statements_dir().join(relative);
+            let copy_result = dest
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|()| std::fs::copy(&path, &dest));
+            statement_bytes += copy_result
+                .map_err(|source| MigrateDataDirError::CopyStatementFile { path, source })?;
+        }
+
+        let statement_rows = self
+            .verify_data_dir_copy(target)
+            .map_err(MigrateDataDirError::Verify)?;
+
+        Ok(MigrationSummary {
+            source_dir: self.data_dir.clone(),
+            target_dir: target.data_dir.clone(),
+            statement_rows,
+            statement_files: files.len(),
+            statement_bytes,
+            source_removed: false,
+        })
+    }
+
+    /// Check that `target` holds the same statement rows and files as this data dir,
+    /// returning the number of statement rows.
+    fn verify_data_dir_copy(&self, target: &UserDataManager) -> Result<i64, DataDirVerifyError> {
+        let count_statements = |path: &Path| -> Result<i64, DataDirVerifyError> {
+            let conn = rusqlite::Connection::open(path)?;
+            Ok(conn.query_row("SELECT COUNT(*) FROM statements", [], |row| row.get(0))?)
+        };
+        let source_rows = count_statements(&self.db_path)?;
+        let target_rows = count_statements(&target.db_path)?;
+        if source_rows != target_rows {
+            return Err(DataDirVerifyError::StatementRowCountMismatch {
+                source: source_rows,
+                target: target_rows,
+            });
+        }
+
+        let list_files = |dir: &Path| {
+            managed_statement_files(dir).map_err(|source| DataDirVerifyError::Io {
+                path: dir.to_path_buf(),
+                source,
+            })
+        };
+        let source_files = list_files(&self.statements_dir())?;
+        let target_files = list_files(&target.statements_dir())?;
+        if source_files.len() != target_files.len() {
+            return Err(DataDirVerifyError::StatementFileCountMismatch {
+                source: source_files.len(),
+                target: target_files.len(),
+            });
+        }
+
+        for relative in &source_files {
+            let source_path = self.statements_dir().join(relative);
+            let target_path = target.statements_dir().join(relative);
+            let file_name = relative.to_string_lossy().into_owned();
+            if !target_path.is_file() {
+                return Err(DataDirVerifyError::StatementFileMissing { file_name });
+            }
+            let hash_of = |path: &Path| {
+                hash_file(path, HashAlgorithm::Sha256).map_err(|source| DataDirVerifyError::Io {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            };
+            if hash_of(&source_path)? != hash_of(&target_path)? {
+                return Err(DataDirVerifyError::StatementFileHashMismatch { file_name });
+            }
+        }
+
+        let conn = rusqlite::Connection::open(&target.db_path)?;
+        let mut stmt = conn.prepare(
+            "
+            SELECT file_hash FROM statements
+            UNION
+            SELECT file_hash FROM statement_files
+            ORDER BY file_hash
+            ",
+        )?;
+        let hashes = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        let index = target.statement_file_index();
+        for file_hash in hashes {
+            let (algorithm, _) =
+                parse_file_hash(&file_hash).map_err(DataDirVerifyError::InvalidFileHash)?;
+            let Some(path) = index.get(statement_file_stem(&file_hash)).cloned() else {
+                return Err(DataDirVerifyError::StatementFileNotFoundForRow { file_hash });
+            };
+            let actual = hash_file(&path, algorithm)
+                .map_err(|source| DataDirVerifyError::Io { path, source })?;
+            if parse_file_hash(&actual).map(|(_, digest)| digest)
+                != parse_file_hash(&file_hash).map(|(_, digest)| digest)
+            {
+                return Err(DataDirVerifyError::StatementFileContentMismatch { file_hash });
+            }
+        }
+
+        Ok(target_rows)
+    }
+
+    fn discard_partial_copy(&self, created_dir: bool) {
+        if created_dir {
+            let _ = std::fs::remove_dir_all(&self.data_dir);
+        } else {
+            let _ = std::fs::remove_dir_all(self.statements_dir());
+            let _ = std::fs::remove_file(&self.db_path);
+        }
+    }
+
+    fn remove_data_dir_contents(&self) -> Result<(), MigrateDataDirError> {
+        let statements_dir = self.statements_dir();
+        match std::fs::remove_dir_all(&statements_dir) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(source) => {
+                return Err(MigrateDataDirError::RemoveSource {
+                    path: statements_dir,
+                    source,
+                });
+            }
+        }
+        std::fs::remove_file(&self.db_path).map_err(|source| MigrateDataDirError::RemoveSource {
+            path: self.db_path.clone(),
+            source,
+        })?;
+        // Only succeeds if nothing else lives in the data dir, which is what we want.
+        let _ = std::fs::remove_dir(&self.data_dir);
+        Ok(())
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.data_dir.join(MANIFEST_FILE_NAME)
+    }
+
+    /// Write `manifest.txt` listing every managed statement file in `sha256sum` format
+    /// (so `sha256sum -c manifest.txt` works from the data dir), with sizes recorded in
+    /// comment lines. Does not touch the database.
+    pub fn write_manifest(&self) -> Result<Vec<ManifestEntry>, ManifestError> {
+        let entries = self.current_manifest_entries()?;
+        let mut contents = String::from("# tally42 statement manifest (sha256sum format)\n");
+        for entry in &entries {
+            let path = entry.path.to_string_lossy();
+            contents.push_str(&format!("{MANIFEST_SIZE_PREFIX}{} {path}\n", entry.size));
+            contents.push_str(&format!("{}  {path}\n", entry.sha256));
+        }
+
+        let manifest_path = self.manifest_path();
+        std::fs::create_dir_all(&self.data_dir).map_err(|source| ManifestError::Io {
+            path: self.data_dir.clone(),
+            source,
+        })?;
+        std::fs::write(&manifest_path, contents).map_err(|source| ManifestError::Io {
+            path: manifest_path,
+            source,
+        })?;
+        Ok(entries)
+    }
+
+    /// Compare the statement files on disk against an existing `manifest.txt`.
+    pub fn check_manifest(&self) -> Result<ManifestCheck, ManifestError> {
+        let manifest_path = self.manifest_path();
+        let contents = match std::fs::read_to_string(&manifest_path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(ManifestError::Missing(manifest_path));
+            }
+            Err(source) => {
+                return Err(ManifestError::Io {
+                    path: manifest_path,
+                    source,
+                });
+            }
+        };
+        let recorded = parse_manifest(&contents)?;
+        let current = self.current_manifest_entries()?;
+
+        let mut check = ManifestCheck::default();
+        for entry in &current {
+            match recorded.iter().find(|recorded| recorded.path == entry.path) {
+                None => check.added.push(entry.path.clone()),
+                Some(recorded)
+                    if recorded.sha256 != entry.sha256
+                        || recorded.size.is_some_and(|size| size != entry.size) =>
+                {
+                    check.changed.push(entry.path.clone())
+                }
+                Some(_) => check.unchanged += 1,
+            }
+        }
+        for recorded in &recorded {
+            if !current.iter().any(|entry| entry.path == recorded.path) {
+                check.removed.push(recorded.path.clone());
+            }
+        }
+        check.removed.sort();
+        Ok(check)
+    }
+
+    fn current_manifest_entries(&self) -> Result<Vec<ManifestEntry>, ManifestError> {
+        let statements_dir = self.statements_dir();
+        let files = managed_statement_files(&statements_dir).map_err(|source| {
+            ManifestError::Io {
+                path: statements_dir.clone(),
+                source,
+            }
+        })?;
+
+        let mut entries = Vec::with_capacity(files.len());
+        for relative in files {
+            let path = statements_dir.join(&relative);
+            let io_error = |source| ManifestError::Io {
+                path: path.clone(),
+                source,
+            };
+            let size = std::fs::metadata(&path).map_err(io_error)?.len();
+            let hash = hash_file(&path, HashAlgorithm::Sha256).map_err(io_error)?;
+            entries.push(ManifestEntry {
+                path: Path::new(STATEMENTS_DIR_NAME).join(relative),
+                sha256: statement_file_stem(&hash).to_string(),
+                size,
+            });
+        }
+        Ok(entries)
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    pub fn db_path(&self) -> &Path {
+        &self.db_path
+    }
+
+    pub fn statements_dir(&self) -> PathBuf {
+        self.data_dir.join(STATEMENTS_DIR_NAME)
+    }
+
+    pub fn statement_file_path(&self, file_hash: &str) -> PathBuf {
+        self.find_statement_file_path(file_hash)
+            .unwrap_or_else(|| self.statements_dir().join(statement_file_stem(file_hash)))
+    }
+
+    fn statement_file_path_for_source(
+        &self,
+        file_hash: &str,
+        source_path: &Path,
+        account_dir: Option<&str>,
+    ) -> PathBuf {
+        let file_hash = statement_file_stem(file_hash);
+        let dir = match account_dir {
+            Some(account_dir) => self.statements_dir().join(account_dir),
+            None => self.statements_dir(),
+        };
+        match source_path.extension() {
+            Some(ext) if !ext.is_empty() => {
+                dir.join(format!("{file_hash}.{}", ext.to_string_lossy()))
+            }
+            _ => dir.join(file_hash),
+        }
+    }
+
+    /// Search both layouts, so renamed accounts and re-sharded stores still resolve.
+    fn find_statement_file_path(&self, file_hash: &str) -> Option<PathBuf> {
+        self.statement_file_index().remove(statement_file_stem(file_hash))
+    }
+
+    /// Map the hash stem of every managed statement file to its path, in either
+    /// layout. Operations resolving many hashes build this once instead of walking
+    /// the statements dir per lookup.
+    fn statement_file_index(&self) -> HashMap<String, PathBuf> {
+        let statements_dir = self.statements_dir();
+        let mut index = HashMap::new();
+        for relative in managed_statement_files(&statements_dir).unwrap_or_default() {
+            let Some(stem) = relative.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            index
+                .entry(stem.to_string())
+                .or_insert_with(|| statements_dir.join(&relative));
+        }
+        index
+    }
+
+    /// Move every managed statement file into `layout`, then drop emptied account dirs.
+    /// The layout is stored in the database, so later imports place files in it too.
+    pub fn reshard_statements(
+        &self,
+        layout: StatementLayout,
+    ) -> Result<ReshardSummary, ReshardError> {
+        let db = self.open_db().map_err(ReshardError::OpenDb)?;
+        let mut stmt = db.conn().prepare(
+            "
+            SELECT statement_files.file_hash, accounts.name
+            FROM statement_files
+            JOIN statements ON statements.id = statement_files.statement_id
+            JOIN accounts ON accounts.id = statements.account_id
+            ORDER BY statement_files.file_hash
+            ",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Resolve every file up front so a missing one aborts before anything moves.
+        let statements_dir = self.statements_dir();
+        let mut index = self.statement_file_index();
+        let mut moves = Vec::with_capacity(rows.len());
+        for (file_hash, account_name) in rows {
+            let current = index
+                .remove(statement_file_stem(&file_hash))
+                .ok_or(ReshardError::FileNotFound { file_hash })?;
+            let dir = match layout {
+                StatementLayout::Flat => statements_dir.clone(),
+                StatementLayout::ByAccount => statements_dir.join(account_dir_name(&account_name)),
+            };
+            let desired = dir.join(current.file_name().unwrap_or_default());
+            moves.push((current, desired));
+        }
+
+        let mut summary = ReshardSummary {
+            layout,
+            moved: 0,
+            unchanged: 0,
+        };
+        for (current, desired) in moves {
+            if current == desired {
+                summary.unchanged += 1;
+                continue;
+            }
+            let io_err = |path: &Path| {
+                let path = path.to_path_buf();
+                move |source| ReshardError::Io { path, source }
+            };
+            if let Some(parent) = desired.parent() {
+                std::fs::create_dir_all(parent).map_err(io_err(parent))?;
+            }
+            std::fs::rename(&current, &desired).map_err(io_err(&current))?;
+            summary.moved += 1;
+        }
+        db.set_setting(STATEMENT_LAYOUT_SETTING, layout.as_str())?;
+
+        let entries = std::fs::read_dir(&statements_dir).map_err(|source| ReshardError::Io {
+            path: statements_dir.clone(),
+            source,
+        })?;
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                // Fails (and is ignored) unless the directory is now empty.
+                let _ = std::fs::remove_dir(entry.path());
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+pub(crate) fn resolve_default_data_dir() -> Result<PathBuf, UserDataError> {
+    if let Ok(xdg_data_home) = std::env::var("XDG_DATA_HOME") {
+        return Ok(PathBuf::from(xdg_data_home).join(APP_DIR_NAME));
+    }
+
+    if let Ok(home) = std::env::var("HOME") {
+        return Ok(PathBuf::from(home).join(".local").join("share").join(APP_DIR_NAME));
+    }
+
+    Err(UserDataError::MissingHomeDir)
+}
+
+/// Keep the stored file if its row was inserted, otherwise remove it again.
+fn finish_stored_file<T>(
+    path: PathBuf,
+    insert_result: Result<T, StatementWriteError>,
+) -> Result<T, AddStatementError> {
+    match insert_result {
+        Ok(row) => Ok(row),
+        Err(insert_error) => match std::fs::remove_file(&path) {
+            Ok(()) => Err(AddStatementError::InsertStatement(insert_error)),
+            Err(cleanup_error) => Err(AddStatementError::InsertStatementCleanupFailed {
+                insert_error,
+                cleanup_error,
+                path,
+            }),
+        },
+    }
+}
+
+/// Sorted paths (relative to `dir`) of the managed statement files, in either layout,
+/// excluding in-flight temp files.
+fn managed_statement_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_managed_statement_files(dir, Path::new(""), true, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn collect_managed_statement_files(
+    root: &Path,
+    relative: &Path,
+    descend: bool,
+    files: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
+    let entries = match std::fs::read_dir(root.join(relative)) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        let path = relative.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() && descend {
+            collect_managed_statement_files(root, &path, false, files)?;
+            continue;
+        }
+        let is_temp = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with(TEMP_STATEMENT_PREFIX));
+        if file_type.is_file() && !is_temp {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// A `manifest.txt` line; `size` is `None` for entries without a size comment
+/// (e.g. hand-edited ones), which are then compared by hash only.
+struct RecordedManifestEntry {
+    path: PathBuf,
+    sha256: String,
+    size: Option<u64>,
+}
+
+fn parse_manifest(contents: &str) -> Result<Vec<RecordedManifestEntry>, ManifestError> {
+    let mut sizes = std::collections::HashMap::new();
+    let mut entries = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let parse_error = || ManifestError::Parse {
+            line_number: index + 1,
+            line: line.to_string(),
+        };
+        if let Some(rest) = line.strip_prefix(MANIFEST_SIZE_PREFIX) {
+            let (size, path) = rest.split_once(' ').ok_or_else(parse_error)?;
+            let size = size.parse::<u64>().map_err(|_| parse_error())?;
+            sizes.insert(path.to_string(), size);
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (sha256, path) = line.split_once("  ").ok_or_else(parse_error)?;
+        if sha256.is_empty() || path.is_empty() {
+            return Err(parse_error());
+        }
+        entries.push(RecordedManifestEntry {
+            path: PathBuf::from(path),
+            sha256: sha256.to_string(),
+            size: sizes.get(path).copied(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Directory name used for an account under the by-account layout.
+fn account_dir_name(account_name: &str) -> String {
+    let name: String = account_name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match name.trim_start_matches('.') {
+        "" => "_".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Managed files are named by hex digest alone, so `sha256:<hex>` and legacy `<hex>`
+/// resolve to the same file.
+fn statement_file_stem(file_hash: &str) -> &str {
+    parse_file_hash(file_hash).map_or(file_hash, |(_, digest)| digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::statement::PRIMARY_STATEMENT_FILE_KIND;
     use sha2::{Digest, Sha256};
     use tempfile::tempdir;
 
@@ -281,7 +1438,7 @@ mod tests {
             .conn()
             .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| row.get(0))
             .expect("count applied migrations");
-        assert_eq!(applied_count, 4);
+        assert_eq!(applied_count, 9);
         assert!(manager.db_path().is_file());
         assert!(manager.statements_dir().is_dir());
     }
@@ -291,7 +1448,7 @@ mod tests {
     }
 
     fn sha256_hex(bytes: &[u8]) -> String {
-        format!("{:x}", Sha256::digest(bytes))
+        format!("sha256:{:x}", Sha256::digest(bytes))
     }
 
     fn sample_add_input(account_id: Uuid) -> AddStatementInput {
@@ -302,6 +1459,7 @@ mod tests {
             period_end: "2026-01-31".to_string(),
             currency: "USD".to_string(),
             replaced_by: None,
+            allow_currency_mismatch: false,
         }
     }
 
@@ -340,6 +1498,43 @@ mod tests {
         assert_eq!(statements[0].id, created.id);
     }
 
+    #[test]
+    fn add_statement_honors_currency_mismatch_override() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("state"));
+        let source_path = temp_dir.path().join("statement.pdf");
+        write_test_file(&source_path, b"euro statement");
+
+        let account_id = Uuid::parse_str("21212121-2121-2121-2121-212121212122").unwrap();
+        let db = manager.open_db().expect("open db");
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        drop(db);
+
+        let input = AddStatementInput {
+            currency: "EUR".to_string(),
+            ..sample_add_input(account_id)
+        };
+        let err = manager
+            .add_statement(&source_path, input.clone())
+            .expect_err("mismatched currency");
+        assert!(matches!(
+            err,
+            AddStatementError::InsertStatement(StatementWriteError::CurrencyMismatch { .. })
+        ));
+
+        let created = manager
+            .add_statement(
+                &source_path,
+                AddStatementInput {
+                    allow_currency_mismatch: true,
+                    ..input
+                },
+            )
+            .expect("add statement with override");
+        assert_eq!(created.currency, "EUR");
+    }
+
     #[test]
     fn add_statement_fails_on_duplicate_hash_without_overwriting() {
         let temp_dir = tempdir().expect("create temp dir");
@@ -392,8 +1587,8 @@ mod tests {
 
         assert!(matches!(
             err,
-            AddStatementError::InsertStatement(StatementWriteError::Sql(_))
-                | AddStatementError::InsertStatementCleanupFailed { .. }
+            AddStatementError::InsertStatement(StatementWriteError::UnknownAccount(id))
+                if id == missing_account_id
         ));
         assert!(!manager.statement_file_path(&expected_hash).exists());
 
@@ -401,4 +1596,728 @@ mod tests {
         let statements = db.list_statements().expect("list statements");
         assert!(statements.is_empty());
     }
+
+    #[test]
+    fn attach_file_stores_file_and_lists_it_with_primary() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, statement) = manager_with_statement(temp_dir.path(), b"%PDF primary");
+        let csv_path = temp_dir.path().join("export.csv");
+        let bytes = b"date,amount\n2026-01-02,-4.50\n";
+        write_test_file(&csv_path, bytes);
+
+        let attached = manager
+            .attach_file(statement.id, &csv_path, "csv")
+            .expect("attach csv");
+
+        let expected_hash = sha256_hex(bytes);
+        assert_eq!(attached.statement_id, statement.id);
+        assert_eq!(attached.file_hash, expected_hash);
+        assert_eq!(attached.file_size, bytes.len() as i64);
+        assert_eq!(attached.original_filename.as_deref(), Some("export.csv"));
+        assert_eq!(attached.kind, "csv");
+        assert_eq!(
+            std::fs::read(manager.statement_file_path(&expected_hash)).expect("read stored csv"),
+            bytes
+        );
+
+        let db = manager.open_db().expect("open db");
+        let files = db.list_statement_files(statement.id).expect("list files");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].kind, PRIMARY_STATEMENT_FILE_KIND);
+        assert_eq!(files[0].file_hash, statement.file_hash);
+        assert_eq!(files[1], attached);
+    }
+
+    #[test]
+    fn attach_file_rejects_hash_already_used_by_another_statement() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, first) = manager_with_statement(temp_dir.path(), b"shared bytes");
+        let other_path = temp_dir.path().join("other.pdf");
+        write_test_file(&other_path, b"other statement");
+        let second = manager
+            .add_statement(&other_path, sample_add_input(first.account_id))
+            .expect("add second statement");
+        let duplicate_path = temp_dir.path().join("copy.pdf");
+        write_test_file(&duplicate_path, b"shared bytes");
+
+        let err = manager
+            .attach_file(second.id, &duplicate_path, "pdf")
+            .expect_err("duplicate hash should fail");
+
+        assert!(matches!(
+            err,
+            AddStatementError::DuplicateFileHash { ref hash, .. } if hash == &first.file_hash
+        ));
+        let db = manager.open_db().expect("open db");
+        assert_eq!(db.list_statement_files(second.id).expect("list files").len(), 1);
+    }
+
+    #[test]
+    fn attach_file_rolls_back_copied_file_if_db_insert_fails() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("state"));
+        let source_path = temp_dir.path().join("export.csv");
+        let bytes = b"orphan attachment";
+        write_test_file(&source_path, bytes);
+        let expected_hash = sha256_hex(bytes);
+
+        let missing_statement_id =
+            Uuid::parse_str("eeeeeeee-eeee-eeee-eeee-eeeeeeeeeeee").unwrap();
+        let err = manager
+            .attach_file(missing_statement_id, &source_path, "csv")
+            .expect_err("attach should fail on missing statement FK");
+
+        assert!(matches!(
+            err,
+            AddStatementError::InsertStatement(StatementWriteError::Sql(_))
+                | AddStatementError::InsertStatementCleanupFailed { .. }
+        ));
+        assert!(!manager.statement_file_path(&expected_hash).exists());
+    }
+
+    #[test]
+    fn add_statement_with_blake3_writes_prefixed_hash() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("state"))
+            .with_hash_algorithm(HashAlgorithm::Blake3);
+        let source_path = temp_dir.path().join("statement.pdf");
+        let bytes = b"%PDF blake3";
+        write_test_file(&source_path, bytes);
+
+        let account_id = Uuid::parse_str("51515151-5151-5151-5151-515151515151").unwrap();
+        let db = manager.open_db().expect("open db");
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        drop(db);
+
+        let created = manager
+            .add_statement(&source_path, sample_add_input(account_id))
+            .expect("add statement");
+
+        let digest = blake3::hash(bytes).to_hex().to_string();
+        assert_eq!(created.file_hash, format!("blake3:{digest}"));
+        let stored_path = manager.statement_file_path(&created.file_hash);
+        assert_eq!(stored_path, manager.statements_dir().join(format!("{digest}.pdf")));
+        assert_eq!(std::fs::read(stored_path).expect("read stored file"), bytes);
+    }
+
+    #[test]
+    fn mixed_prefix_hashes_resolve_and_verify() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("old"));
+        let account_id = Uuid::parse_str("52525252-5252-5252-5252-525252525252").unwrap();
+        let db = manager.open_db().expect("open db");
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+
+        // A legacy row written before hashes carried a prefix.
+        let legacy_bytes = b"legacy statement";
+        let legacy_digest = format!("{:x}", Sha256::digest(legacy_bytes));
+        write_test_file(
+            &manager.statements_dir().join(format!("{legacy_digest}.pdf")),
+            legacy_bytes,
+        );
+        db.create_statement(
+            Uuid::new_v4(),
+            &AddStatementInput {
+                institution: "Chase".to_string(),
+                account_id,
+                period_start: "2025-12-01".to_string(),
+                period_end: "2025-12-31".to_string(),
+                currency: "USD".to_string(),
+                replaced_by: None,
+                allow_currency_mismatch: false,
+            },
+            &legacy_digest,
+            legacy_bytes.len() as i64,
+        )
+        .expect("insert legacy statement");
+        drop(db);
+
+        let sha_path = temp_dir.path().join("sha.pdf");
+        write_test_file(&sha_path, b"sha256 statement");
+        let sha = manager
+            .add_statement(&sha_path, sample_add_input(account_id))
+            .expect("add sha256 statement");
+        let blake_path = temp_dir.path().join("blake.pdf");
+        write_test_file(&blake_path, b"blake3 statement");
+        let blake = manager
+            .clone()
+            .with_hash_algorithm(HashAlgorithm::Blake3)
+            .add_statement(&blake_path, sample_add_input(account_id))
+            .expect("add blake3 statement");
+
+        for file_hash in [legacy_digest.as_str(), &sha.file_hash, &blake.file_hash] {
+            assert!(
+                manager.find_statement_file_path(file_hash).is_some(),
+                "hash {file_hash} should resolve"
+            );
+        }
+        assert_eq!(
+            manager.find_statement_file_path(&format!("sha256:{legacy_digest}")),
+            manager.find_statement_file_path(&legacy_digest)
+        );
+
+        let duplicate_path = temp_dir.path().join("legacy-again.pdf");
+        write_test_file(&duplicate_path, legacy_bytes);
+        let err = manager
+            .add_statement(&duplicate_path, sample_add_input(account_id))
+            .expect_err("prefixed import of legacy content should be a duplicate");
+        assert!(matches!(err, AddStatementError::DuplicateFileHash { .. }));
+
+        let summary = manager
+            .migrate_data_dir(&temp_dir.path().join("new"), false)
+            .expect("mixed-prefix data dir should verify");
+        assert_eq!(summary.statement_rows, 3);
+    }
+
+    #[test]
+    fn add_statement_by_account_stores_under_account_dir() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("state"))
+            .with_statement_layout(StatementLayout::ByAccount);
+        let source_path = temp_dir.path().join("statement.pdf");
+        write_test_file(&source_path, b"by account");
+
+        let account_id = Uuid::parse_str("61616161-6161-6161-6161-616161616161").unwrap();
+        let db = manager.open_db().expect("open db");
+        db.create_account(account_id, None, "amex gold/travel", "USD", None)
+            .expect("create account");
+        drop(db);
+
+        let created = manager
+            .add_statement(&source_path, sample_add_input(account_id))
+            .expect("add statement");
+
+        let digest = created.file_hash.trim_start_matches("sha256:");
+        let expected = manager
+            .statements_dir()
+            .join("amex_gold_travel")
+            .join(format!("{digest}.pdf"));
+        assert!(expected.is_file());
+        assert_eq!(manager.statement_file_path(&created.file_hash), expected);
+
+        // Renaming the account must not break lookup by hash.
+        let db = manager.open_db().expect("reopen db");
+        db.rename_account(account_id, "platinum").expect("rename account");
+        assert_eq!(manager.find_statement_file_path(&created.file_hash), Some(expected));
+    }
+
+    #[test]
+    fn statement_file_index_covers_both_layouts() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("state"));
+        let statements_dir = manager.statements_dir();
+        std::fs::create_dir_all(statements_dir.join("checking")).expect("create account dir");
+        write_test_file(&statements_dir.join("aaaa.pdf"), b"flat");
+        write_test_file(&statements_dir.join("checking").join("bbbb.csv"), b"sharded");
+        write_test_file(&statements_dir.join(format!("{TEMP_STATEMENT_PREFIX}cccc")), b"tmp");
+
+        let index = manager.statement_file_index();
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.get("aaaa"), Some(&statements_dir.join("aaaa.pdf")));
+        assert_eq!(
+            index.get("bbbb"),
+            Some(&statements_dir.join("checking").join("bbbb.csv"))
+        );
+    }
+
+    #[test]
+    fn reshard_statements_moves_files_between_layouts() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, statement) = manager_with_statement(temp_dir.path(), b"reshard me");
+        let flat_path = manager
+            .find_statement_file_path(&statement.file_hash)
+            .expect("flat statement file");
+        let file_name = flat_path.file_name().expect("file name").to_owned();
+        assert_eq!(flat_path.parent(), Some(manager.statements_dir().as_path()));
+
+        let summary = manager
+            .reshard_statements(StatementLayout::ByAccount)
+            .expect("reshard to by-account");
+        assert_eq!(summary.moved, 1);
+        assert_eq!(summary.unchanged, 0);
+        let sharded_path = manager.statements_dir().join("checking").join(&file_name);
+        assert!(sharded_path.is_file());
+        assert!(!flat_path.exists());
+        assert_eq!(
+            manager.find_statement_file_path(&statement.file_hash),
+            Some(sharded_path.clone())
+        );
+
+        let again = manager
+            .reshard_statements(StatementLayout::ByAccount)
+            .expect("reshard is idempotent");
+        assert_eq!((again.moved, again.unchanged), (0, 1));
+
+        let back = manager
+            .reshard_statements(StatementLayout::Flat)
+            .expect("reshard back to flat");
+        assert_eq!(back.moved, 1);
+        assert!(flat_path.is_file());
+        assert!(!manager.statements_dir().join("checking").exists());
+    }
+
+    #[test]
+    fn migrate_data_dir_preserves_account_subdirectories() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, statement) = manager_with_statement(temp_dir.path(), b"sharded copy");
+        manager
+            .reshard_statements(StatementLayout::ByAccount)
+            .expect("reshard to by-account");
+        let target_dir = temp_dir.path().join("new");
+
+        let summary = manager
+            .migrate_data_dir(&target_dir, false)
+            .expect("migrate sharded data dir");
+
+        assert_eq!(summary.statement_files, 1);
+        let target = UserDataManager::from_data_dir(&target_dir);
+        let copied = target
+            .find_statement_file_path(&statement.file_hash)
+            .expect("copied statement file");
+        assert_eq!(
+            copied.parent(),
+            Some(target.statements_dir().join("checking").as_path())
+        );
+    }
+
+    fn manager_with_statement(root: &Path, bytes: &[u8]) -> (UserDataManager, Statement) {
+        let manager = UserDataManager::from_data_dir(root.join("old"));
+        let source_path = root.join("statement.pdf");
+        write_test_file(&source_path, bytes);
+
+        let account_id = Uuid::parse_str("31313131-3131-3131-3131-313131313131").unwrap();
+        let db = manager.open_db().expect("open db");
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        drop(db);
+
+        let statement = manager
+            .add_statement(&source_path, sample_add_input(account_id))
+            .expect("add statement");
+        (manager, statement)
+    }
+
+    #[test]
+    fn migrate_data_dir_copies_and_verifies_everything() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let bytes = b"%PDF-1.7 migrate me";
+        let (manager, statement) = manager_with_statement(temp_dir.path(), bytes);
+        let target_dir = temp_dir.path().join("new");
+
+        let summary = manager
+            .migrate_data_dir(&target_dir, false)
+            .expect("migrate data dir");
+
+        assert_eq!(summary.source_dir, manager.data_dir());
+        assert_eq!(summary.target_dir, target_dir);
+        assert_eq!(summary.statement_rows, 1);
+        assert_eq!(summary.statement_files, 1);
+        assert_eq!(summary.statement_bytes, bytes.len() as u64);
+        assert!(!summary.source_removed);
+        assert!(manager.db_path().is_file());
+
+        let target = UserDataManager::from_data_dir(&target_dir);
+        let db = target.open_db().expect("open target db");
+        let statements = db.list_statements().expect("list target statements");
+        assert_eq!(statements, vec![statement.clone()]);
+        let stored = target
+            .find_statement_file_path(&statement.file_hash)
+            .expect("statement file at target");
+        assert_eq!(std::fs::read(stored).expect("read target file"), bytes);
+    }
+
+    #[test]
+    fn migrate_data_dir_removes_source_after_verification() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, _) = manager_with_statement(temp_dir.path(), b"remove source");
+        let target_dir = temp_dir.path().join("new");
+
+        let summary = manager
+            .migrate_data_dir(&target_dir, true)
+            .expect("migrate data dir");
+
+        assert!(summary.source_removed);
+        assert!(!manager.data_dir().exists());
+        assert!(UserDataManager::from_data_dir(&target_dir).db_path().is_file());
+    }
+
+    #[test]
+    fn migrate_data_dir_rejects_non_empty_target() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, _) = manager_with_statement(temp_dir.path(), b"occupied");
+        let target_dir = temp_dir.path().join("new");
+        std::fs::create_dir_all(&target_dir).expect("create target dir");
+        write_test_file(&target_dir.join("keep.txt"), b"keep");
+
+        let err = manager
+            .migrate_data_dir(&target_dir, true)
+            .expect_err("non-empty target should fail");
+
+        assert!(matches!(err, MigrateDataDirError::TargetNotEmpty(_)));
+        assert!(target_dir.join("keep.txt").is_file());
+        assert!(manager.db_path().is_file());
+    }
+
+    #[test]
+    fn migrate_data_dir_failed_verification_leaves_source_untouched() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, statement) = manager_with_statement(temp_dir.path(), b"goes missing");
+        let stored = manager
+            .find_statement_file_path(&statement.file_hash)
+            .expect("stored statement file");
+        std::fs::remove_file(&stored).expect("remove stored statement file");
+        let target_dir = temp_dir.path().join("new");
+
+        let err = manager
+            .migrate_data_dir(&target_dir, true)
+            .expect_err("verification should fail");
+
+        assert!(matches!(
+            err,
+            MigrateDataDirError::Verify(DataDirVerifyError::StatementFileNotFoundForRow { .. })
+        ));
+        assert!(manager.db_path().is_file());
+        assert!(!target_dir.exists());
+    }
+
+    #[test]
+    fn verify_data_dir_copy_detects_corrupted_file() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, statement) = manager_with_statement(temp_dir.path(), b"original bytes");
+        let target_dir = temp_dir.path().join("new");
+        manager
+            .migrate_data_dir(&target_dir, false)
+            .expect("migrate data dir");
+        let target = UserDataManager::from_data_dir(&target_dir);
+        let copied = target
+            .find_statement_file_path(&statement.file_hash)
+            .expect("copied statement file");
+        write_test_file(&copied, b"tampered bytes");
+
+        let err = manager
+            .verify_data_dir_copy(&target)
+            .expect_err("corrupted copy should fail verification");
+
+        assert!(matches!(
+            err,
+            DataDirVerifyError::StatementFileHashMismatch { .. }
+        ));
+    }
+
+    fn write_temp_file_with_age(dir: &Path, name: &str, age: Duration) -> PathBuf {
+        let path = dir.join(format!("{TEMP_STATEMENT_PREFIX}{name}"));
+        let file = std::fs::File::create(&path).expect("create temp statement file");
+        file.set_modified(SystemTime::now() - age)
+            .expect("set temp statement file mtime");
+        path
+    }
+
+    #[test]
+    fn cleanup_stale_temp_files_removes_only_old_temp_files() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("state"));
+        manager.init().expect("init user data");
+        let statements_dir = manager.statements_dir();
+        let old = write_temp_file_with_age(&statements_dir, "old", Duration::from_secs(7200));
+        let fresh = write_temp_file_with_age(&statements_dir, "fresh", Duration::from_secs(60));
+        let managed = statements_dir.join("abc123.pdf");
+        write_test_file(&managed, b"managed");
+        std::fs::File::options()
+            .write(true)
+            .open(&managed)
+            .expect("open managed file")
+            .set_modified(SystemTime::now() - Duration::from_secs(7200))
+            .expect("set managed file mtime");
+
+        let stale = manager
+            .stale_temp_files(Duration::from_secs(3600))
+            .expect("list stale temp files");
+        assert_eq!(stale, vec![old.clone()]);
+        assert!(old.is_file());
+
+        let removed = manager
+            .cleanup_stale_temp_files(Duration::from_secs(3600))
+            .expect("cleanup stale temp files");
+
+        assert_eq!(removed, vec![old.clone()]);
+        assert!(!old.exists());
+        assert!(fresh.is_file());
+        assert!(managed.is_file());
+    }
+
+    #[test]
+    fn cleanup_stale_temp_files_handles_missing_statements_dir() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("missing"));
+
+        let removed = manager
+            .cleanup_stale_temp_files(Duration::ZERO)
+            .expect("cleanup with no statements dir");
+
+        assert!(removed.is_empty());
+    }
+
+    fn write_statements_fixture(manager: &UserDataManager) {
+        let statements_dir = manager.statements_dir();
+        std::fs::create_dir_all(statements_dir.join("checking")).expect("create account dir");
+        write_test_file(&statements_dir.join("aaaa.pdf"), b"flat statement");
+        write_test_file(&statements_dir.join("checking").join("bbbb.csv"), b"by account");
+        write_test_file(&statements_dir.join("cccc.pdf"), b"to be removed");
+        write_test_file(
+            &statements_dir.join(format!("{TEMP_STATEMENT_PREFIX}partial")),
+            b"in flight",
+        );
+    }
+
+    #[test]
+    fn write_manifest_lists_files_in_sha256sum_format() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("state"));
+        write_statements_fixture(&manager);
+
+        let entries = manager.write_manifest().expect("write manifest");
+
+        let paths: Vec<PathBuf> = entries.iter().map(|entry| entry.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("statements/aaaa.pdf"),
+                PathBuf::from("statements/cccc.pdf"),
+                PathBuf::from("statements/checking/bbbb.csv"),
+            ]
+        );
+        let contents = std::fs::read_to_string(manager.manifest_path()).expect("read manifest");
+        let flat_hash = sha256_hex(b"flat statement");
+        let flat_digest = flat_hash.trim_start_matches("sha256:");
+        assert!(contents.contains("# size 14 statements/aaaa.pdf\n"));
+        assert!(contents.contains(&format!("{flat_digest}  statements/aaaa.pdf\n")));
+        assert!(!contents.contains(TEMP_STATEMENT_PREFIX));
+        assert!(manager.check_manifest().expect("check manifest").is_clean());
+    }
+
+    #[test]
+    fn check_manifest_reports_added_removed_and_changed_files() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("state"));
+        write_statements_fixture(&manager);
+        manager.write_manifest().expect("write manifest");
+
+        let statements_dir = manager.statements_dir();
+        write_test_file(&statements_dir.join("aaaa.pdf"), b"flat statement, edited");
+        std::fs::remove_file(statements_dir.join("cccc.pdf")).expect("remove file");
+        write_test_file(&statements_dir.join("dddd.pdf"), b"new statement");
+
+        let check = manager.check_manifest().expect("check manifest");
+
+        assert!(!check.is_clean());
+        assert_eq!(check.added, vec![PathBuf::from("statements/dddd.pdf")]);
+        assert_eq!(check.removed, vec![PathBuf::from("statements/cccc.pdf")]);
+        assert_eq!(check.changed, vec![PathBuf::from("statements/aaaa.pdf")]);
+        assert_eq!(check.unchanged, 1);
+    }
+
+    #[test]
+    fn check_manifest_accepts_plain_sha256sum_lines() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("state"));
+        std::fs::create_dir_all(manager.statements_dir()).expect("create statements dir");
+        write_test_file(&manager.statements_dir().join("aaaa.pdf"), b"flat statement");
+        let hash = sha256_hex(b"flat statement");
+        std::fs::write(
+            manager.manifest_path(),
+            format!("{}  statements/aaaa.pdf\n", hash.trim_start_matches("sha256:")),
+        )
+        .expect("write manifest");
+
+        let check = manager.check_manifest().expect("check manifest");
+
+        assert!(check.is_clean());
+        assert_eq!(check.unchanged, 1);
+    }
+
+    #[test]
+    fn check_manifest_requires_existing_manifest() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("state"));
+
+        let err = manager.check_manifest().expect_err("missing manifest should fail");
+
+        assert!(matches!(err, ManifestError::Missing(path) if path == manager.manifest_path()));
+    }
+
+    #[test]
+    fn undo_last_statement_removes_only_latest_import() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, first) = manager_with_statement(temp_dir.path(), b"first import");
+        let db = manager.open_db().expect("open db");
+        db.conn()
+            .execute(
+                "UPDATE statements SET imported_at = '2026-01-01 00:00:00' WHERE id = ?1",
+                [first.id.to_string()],
+            )
+            .expect("backdate first statement");
+        let second_path = temp_dir.path().join("second.pdf");
+        write_test_file(&second_path, b"second import");
+        let second = manager
+            .add_statement(&second_path, sample_add_input(first.account_id))
+            .expect("add second statement");
+        let second_file = manager.statement_file_path(&second.file_hash);
+        assert!(second_file.is_file());
+
+        let undone = manager
+            .undo_last_statement()
+            .expect("undo")
+            .expect("a statement was undone");
+
+        assert_eq!(undone.statement.id, second.id);
+        assert_eq!(undone.removed_files, vec![second_file.clone()]);
+        assert!(!second_file.exists());
+        assert!(manager.statement_file_path(&first.file_hash).is_file());
+        let remaining = db.list_statements().expect("list statements");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, first.id);
+    }
+
+    #[test]
+    fn undo_last_statement_with_no_statements_is_none() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let manager = UserDataManager::from_data_dir(temp_dir.path().join("state"));
+
+        assert_eq!(manager.undo_last_statement().expect("undo"), None);
+        assert_eq!(manager.last_imported_statement().expect("latest"), None);
+    }
+
+    /// Add three statements for one account, imported a day apart, each replacing the
+    /// one before it.
+    fn manager_with_chain(root: &Path) -> (UserDataManager, Vec<Statement>) {
+        let (manager, first) = manager_with_statement(root, b"january v1");
+        let mut chain = vec![first];
+        for (name, bytes) in [("v2.pdf", b"january v2"), ("v3.pdf", b"january v3")] {
+            let path = root.join(name);
+            write_test_file(&path, bytes);
+            let statement = manager
+                .add_statement(&path, sample_add_input(chain[0].account_id))
+                .expect("add replacement statement");
+            chain.push(statement);
+        }
+        let db = manager.open_db().expect("open db");
+        for (day, statement) in chain.iter().enumerate() {
+            db.conn()
+                .execute(
+                    "UPDATE statements SET imported_at = ?1 WHERE id = ?2",
+                    [format!("2026-02-0{} 00:00:00", day + 1), statement.id.to_string()],
+                )
+                .expect("backdate statement");
+        }
+        for pair in chain.windows(2) {
+            db.conn()
+                .execute(
+                    "UPDATE statements SET replaced_by = ?1 WHERE id = ?2",
+                    [pair[1].id.to_string(), pair[0].id.to_string()],
+                )
+                .expect("link statements");
+        }
+        (manager, chain)
+    }
+
+    #[test]
+    fn prune_superseded_statements_keeps_only_the_head() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, chain) = manager_with_chain(temp_dir.path());
+        let files: Vec<PathBuf> = chain
+            .iter()
+            .map(|statement| manager.statement_file_path(&statement.file_hash))
+            .collect();
+
+        let summary = manager
+            .prune_superseded_statements(1, true)
+            .expect("prune");
+
+        assert_eq!(summary.chains.len(), 1);
+        assert_eq!(summary.chains[0].head.id, chain[2].id);
+        let removed: Vec<Uuid> = summary.chains[0].removed.iter().map(|s| s.id).collect();
+        assert_eq!(removed, vec![chain[0].id, chain[1].id]);
+        assert_eq!(summary.removed_files, files[..2].to_vec());
+        assert!(summary.cycles.is_empty());
+        assert!(!files[0].exists() && !files[1].exists());
+        assert!(files[2].is_file());
+        let remaining = manager.open_db().unwrap().list_statements().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, chain[2].id);
+    }
+
+    #[test]
+    fn prune_superseded_statements_honours_keep_latest_and_keeps_files() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, chain) = manager_with_chain(temp_dir.path());
+
+        let summary = manager
+            .prune_superseded_statements(2, false)
+            .expect("prune");
+
+        assert_eq!(summary.chains[0].removed.len(), 1);
+        assert_eq!(summary.chains[0].removed[0].id, chain[0].id);
+        assert!(summary.removed_files.is_empty());
+        assert!(manager.statement_file_path(&chain[0].file_hash).is_file());
+        assert_eq!(manager.open_db().unwrap().list_statements().unwrap().len(), 2);
+
+        let again = manager.prune_superseded_statements(2, false).expect("prune");
+        assert_eq!(again, PruneSummary::default());
+    }
+
+    #[test]
+    fn prune_superseded_statements_follows_links_not_import_order() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, chain) = manager_with_chain(temp_dir.path());
+        let db = manager.open_db().expect("open db");
+        db.conn()
+            .execute(
+                "UPDATE statements SET imported_at = '2026-03-01 00:00:00' WHERE id = ?1",
+                [chain[0].id.to_string()],
+            )
+            .expect("reimport the oldest statement");
+
+        let summary = manager
+            .prune_superseded_statements(2, false)
+            .expect("prune");
+
+        assert_eq!(summary.chains[0].head.id, chain[2].id);
+        let removed: Vec<Uuid> = summary.chains[0].removed.iter().map(|s| s.id).collect();
+        assert_eq!(removed, vec![chain[0].id]);
+        let statements = db.list_statements().unwrap();
+        let mut remaining: Vec<Uuid> = statements.iter().map(|s| s.id).collect();
+        remaining.sort();
+        let mut kept = vec![chain[1].id, chain[2].id];
+        kept.sort();
+        assert_eq!(remaining, kept);
+    }
+
+    #[test]
+    fn prune_superseded_statements_reports_cycles_without_deleting() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let (manager, chain) = manager_with_chain(temp_dir.path());
+        let db = manager.open_db().expect("open db");
+        db.conn()
+            .execute(
+                "UPDATE statements SET replaced_by = ?1 WHERE id = ?2",
+                [chain[0].id.to_string(), chain[2].id.to_string()],
+            )
+            .expect("close the loop");
+
+        let summary = manager
+            .prune_superseded_statements(1, true)
+            .expect("prune");
+
+        assert!(summary.chains.is_empty());
+        assert_eq!(summary.cycles.len(), 1);
+        let mut cycle = summary.cycles[0].clone();
+        cycle.sort();
+        let mut expected: Vec<Uuid> = chain.iter().map(|s| s.id).collect();
+        expected.sort();
+        assert_eq!(cycle, expected);
+        assert_eq!(db.list_statements().unwrap().len(), 3);
+    }
 }
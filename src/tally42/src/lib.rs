@@ -0,0 +1,22 @@
+//! Account and statement engine behind the `tally42` CLI.
+//!
+//! A data dir holds the sqlite database and the managed statement files; open one with
+//! [`UserDataManager`] (or [`Core`] for the higher-level operations the CLI uses).
+//!
+//! ```
+//! use tally42_core::UserDataManager;
+//! use uuid::Uuid;
+//!
+//! let dir = tempfile::tempdir()?;
+//! let user_data = UserDataManager::from_data_dir(dir.path());
+//! let db = user_data.open_db()?;
+//!
+//! db.create_account(Uuid::new_v4(), None, "checking", "USD", None)?;
+//! let accounts = db.list_accounts()?;
+//! assert_eq!(accounts[0].name, "checking");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod core;
+
+pub use crate::core::*;
@@ -1,22 +1,173 @@
-mod core;
+mod render;
 
-use core::{Account, Core, VersionInfo};
+use render::OutputFormat;
+use tally42_core::user_data::ManifestError;
+use tally42_core::{
+    bucket_amounts, format_minor_units, infer_period_from_filename, parse_bucket_edges, Account,
+    AccountCoverage, AddStatementInput, AmountBucket, ChartImportSummary, Core, CoreError,
+    CurrencyPrecision, DbLock, DbSizeInfo, HashAlgorithm, ImportedSince, InvalidStatementPeriod,
+    ManifestCheck, ManifestEntry, MigrationStatus, MigrationSummary, Profile, PruneSummary,
+    ReshardSummary, Statement, StatementLayout, Transaction, UndoneStatement, VersionInfo,
+    YearMonth, DEFAULT_HISTOGRAM_EDGES, PROFILE_ENV_VAR,
+};
 use tli42::cmd::CmdBuilder;
-use tli42::repl::{Action, CommandInputs, CompletionItem, HandlerError, Repl, ReplError};
+use tli42::repl::{Action, CommandInputs, HandlerError, Repl, ReplError};
 
 fn main() {
-    let mut repl = build_repl_or_exit();
-    repl.run().unwrap_or_else(|err| {
-        eprintln!("error: repl runtime failed: {err}");
-        std::process::exit(1);
-    });
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (args, check_args) = split_check_args(&args);
+    match profile_from_args(args) {
+        // Every command resolves its data dir from the environment, so this covers them all.
+        Ok(Some(profile)) => std::env::set_var(PROFILE_ENV_VAR, profile),
+        Ok(None) => {}
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(2);
+        }
+    }
+    if let Some(check_args) = check_args {
+        let options = check_options_from_args(check_args).unwrap_or_else(|err| {
+            eprintln!("error: {err}");
+            std::process::exit(2);
+        });
+        std::process::exit(check_command(&options));
+    }
+    match run() {
+        Ok(status) => std::process::exit(status),
+        Err(err) => {
+            print_error_chain(&err);
+            std::process::exit(1);
+        }
+    }
 }
 
-fn build_repl_or_exit() -> Repl {
-    build_repl().unwrap_or_else(|err| {
-        eprintln!("error: failed to build repl: {err:?}");
-        std::process::exit(1);
-    })
+#[derive(Debug)]
+enum CliError {
+    BuildRepl(ReplError),
+    Repl(std::io::Error),
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // ReplError has no Display of its own.
+            Self::BuildRepl(err) => write!(f, "failed to build repl: {err:?}"),
+            Self::Repl(_) => write!(f, "repl runtime failed"),
+        }
+    }
+}
+
+impl std::error::Error for CliError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::BuildRepl(_) => None,
+            Self::Repl(err) => Some(err),
+        }
+    }
+}
+
+/// Run the REPL, returning the last command's status.
+fn run() -> Result<i32, CliError> {
+    let mut repl = build_repl().map_err(CliError::BuildRepl)?;
+    repl.run().map_err(CliError::Repl)
+}
+
+/// The message of `err` followed by the message of each error it wraps.
+fn error_chain(err: &dyn std::error::Error) -> Vec<String> {
+    std::iter::successors(Some(err), |err| err.source())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// `err` and its causes, one per line, each cause indented under the error it caused.
+fn format_error_chain(err: &dyn std::error::Error) -> String {
+    let mut out = String::new();
+    for (depth, message) in error_chain(err).into_iter().enumerate() {
+        if depth == 0 {
+            out.push_str(&message);
+        } else {
+            out.push_str(&format!("\n{:indent$}caused by: {message}", "", indent = depth * 2));
+        }
+    }
+    out
+}
+
+fn print_error_chain(err: &dyn std::error::Error) {
+    eprintln!("error: {}", format_error_chain(err));
+}
+
+/// Report a core error from a REPL command with its full chain of causes.
+fn handler_error(err: impl std::error::Error) -> HandlerError {
+    HandlerError(format_error_chain(&err))
+}
+
+/// Parse `--profile <NAME>` / `--profile=<NAME>`, the only command-line option.
+fn profile_from_args(args: &[String]) -> Result<Option<&str>, String> {
+    let mut profile = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--profile" {
+            let name = iter
+                .next()
+                .ok_or_else(|| "--profile requires a profile name".to_string())?;
+            profile = Some(name.as_str());
+        } else if let Some(name) = arg.strip_prefix("--profile=") {
+            profile = Some(name);
+        } else {
+            return Err(format!("unexpected argument: {arg}"));
+        }
+    }
+    Ok(profile)
+}
+
+/// Split off the arguments of a `check` subcommand, which runs the health checks
+/// instead of the REPL. Options before `check` are still parsed as usual.
+fn split_check_args(args: &[String]) -> (&[String], Option<&[String]>) {
+    let mut idx = 0;
+    while idx < args.len() {
+        match args[idx].as_str() {
+            "--profile" => idx += 2,
+            "check" => return (&args[..idx], Some(&args[idx + 1..])),
+            _ => idx += 1,
+        }
+    }
+    (args, None)
+}
+
+/// Parse the options of `tally42 check`: `--json`, `--skip <NAME>` (repeatable; one
+/// of db, migrations, statements, coverage, currency, periods or temp-files),
+/// `--months <N>` and `--remove-temp-files`.
+fn check_options_from_args(args: &[String]) -> Result<CheckOptions, String> {
+    let mut options = CheckOptions::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--remove-temp-files" => options.remove_temp_files = true,
+            "--skip" => {
+                let name = iter
+                    .next()
+                    .ok_or_else(|| "--skip requires a check name".to_string())?;
+                options.skip.push(HealthCheck::parse(name)?);
+            }
+            "--months" => {
+                let months = iter
+                    .next()
+                    .ok_or_else(|| "--months requires a number of months".to_string())?;
+                options.months = parse_check_months(months)?;
+            }
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_check_months(value: &str) -> Result<u32, String> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|months| *months > 0)
+        .ok_or_else(|| format!("invalid number of months: {value}"))
 }
 
 fn build_repl() -> Result<Repl, ReplError> {
@@ -45,20 +196,59 @@ fn register_root_commands(repl: &mut Repl, write_mode_id: u32) -> Result<(), Rep
         Box::new(move |_, _| Ok(Action::PushMode(write_mode_id))),
     )?;
 
-    let mut show_accounts = CmdBuilder::new();
-    show_accounts
-        .literal_with_doc("show", "display read-only information")
-        .literal_with_doc("accounts", "list accounts")
-        .command_doc("list all accounts in the database");
-    let show_accounts_cmd = show_accounts.build();
-    repl.register_mode_command(
-        0,
-        &show_accounts_cmd,
-        Box::new(|_, _| {
-            show_accounts_command()?;
-            Ok(Action::None)
-        }),
-    )?;
+    for formatted in [false, true] {
+        let mut show_accounts = CmdBuilder::new();
+        show_accounts
+            .literal_with_doc("show", "display read-only information")
+            .literal_with_doc("accounts", "list accounts");
+        if formatted {
+            show_accounts
+                .labeled_arg_with_doc("format", "csv or json with every column")
+                .command_doc("export all accounts as CSV or JSON");
+        } else {
+            show_accounts.command_doc("list all accounts in the database");
+        }
+        let show_accounts_cmd = show_accounts.build();
+        repl.register_mode_command(
+            0,
+            &show_accounts_cmd,
+            Box::new(|_, inputs| {
+                show_accounts_command(inputs)?;
+                Ok(Action::None)
+            }),
+        )?;
+    }
+
+    for (formatted, since) in [(false, false), (true, false), (false, true), (true, true)] {
+        let mut show_statements = CmdBuilder::new();
+        show_statements
+            .literal_with_doc("show", "display read-only information")
+            .literal_with_doc("statements", "list imported statements");
+        if formatted {
+            show_statements.labeled_arg_with_doc("format", "csv or json with every column");
+        }
+        if since {
+            show_statements.labeled_arg_with_doc(
+                "imported-since",
+                "a duration like 7d, 24h or 3w, or a date like 2026-01-01",
+            );
+        }
+        show_statements.command_doc(match (formatted, since) {
+            (false, false) => "list all imported statements",
+            (true, false) => "export all statements as CSV or JSON",
+            (false, true) => "list statements imported within a window",
+            (true, true) => "export statements imported within a window",
+        });
+        let show_statements_cmd = show_statements.build();
+        repl.register_mode_command(
+            0,
+            &show_statements_cmd,
+            Box::new(|_, inputs| {
+                show_statements_command(inputs)?;
+                Ok(Action::None)
+            }),
+        )?;
+    }
 
     let mut show_version = CmdBuilder::new();
     show_version
@@ -75,6 +265,159 @@ fn register_root_commands(repl: &mut Repl, write_mode_id: u32) -> Result<(), Rep
         }),
     )?;
 
+    for (json, doc) in [
+        (false, "list months with no statement for each account"),
+        (true, "list statement coverage gaps as JSON"),
+    ] {
+        let mut show_coverage = CmdBuilder::new();
+        show_coverage
+            .literal_with_doc("show", "display read-only information")
+            .literal_with_doc("coverage", "find months missing a monthly statement");
+        if json {
+            show_coverage.literal_with_doc("json", "print machine-readable JSON");
+        }
+        show_coverage.command_doc(doc);
+        let show_coverage_cmd = show_coverage.build();
+        repl.register_mode_command(
+            0,
+            &show_coverage_cmd,
+            Box::new(move |_, _| {
+                show_coverage_command(json)?;
+                Ok(Action::None)
+            }),
+        )?;
+    }
+
+    for variant in 0..32 {
+        let (from, to, currency, buckets, places) = (
+            variant & 1 != 0,
+            variant & 2 != 0,
+            variant & 4 != 0,
+            variant & 8 != 0,
+            variant & 16 != 0,
+        );
+        // Decimal places are only overridden for the currency being shown.
+        if places && !currency {
+            continue;
+        }
+        let mut show_histogram = CmdBuilder::new();
+        show_histogram
+            .literal_with_doc("show", "display read-only information")
+            .literal_with_doc("histogram", "bucket transaction amounts by size");
+        if from {
+            show_histogram.labeled_arg_with_doc("from", "first posting date, YYYY-MM-DD");
+        }
+        if to {
+            show_histogram.labeled_arg_with_doc("to", "last posting date, YYYY-MM-DD");
+        }
+        if currency {
+            show_histogram.labeled_arg_with_doc("currency", "only count postings in this currency");
+        }
+        if places {
+            show_histogram.labeled_arg_with_doc("places", "decimal places of the currency, 0-18");
+        }
+        if buckets {
+            show_histogram.labeled_arg_with_doc("buckets", "increasing edges, e.g. 0,10,25,50");
+        }
+        show_histogram.command_doc("count and total transactions per amount bucket and currency");
+        let show_histogram_cmd = show_histogram.build();
+        repl.register_mode_command(
+            0,
+            &show_histogram_cmd,
+            Box::new(|_, inputs| {
+                show_histogram_command(inputs)?;
+                Ok(Action::None)
+            }),
+        )?;
+    }
+
+    let mut show_manifest = CmdBuilder::new();
+    show_manifest
+        .literal_with_doc("show", "display read-only information")
+        .literal_with_doc("manifest", "check statement files against manifest.txt")
+        .command_doc("report statement files added, removed, or changed since the manifest");
+    let show_manifest_cmd = show_manifest.build();
+    repl.register_mode_command(
+        0,
+        &show_manifest_cmd,
+        Box::new(|_, _| {
+            show_manifest_command()?;
+            Ok(Action::None)
+        }),
+    )?;
+
+    for (json, skip) in [(false, false), (false, true), (true, false), (true, true)] {
+        let mut show_health = CmdBuilder::new();
+        show_health
+            .literal_with_doc("show", "display read-only information")
+            .literal_with_doc("health", "run the health checks used by `tally42 check`");
+        if json {
+            show_health.literal_with_doc("json", "print machine-readable JSON");
+        }
+        if skip {
+            show_health.labeled_arg_with_doc(
+                "skip",
+                "db, migrations, statements, coverage, currency, periods, or temp-files",
+            );
+        }
+        show_health.command_doc("check the database, migrations, statement files, and coverage");
+        let show_health_cmd = show_health.build();
+        repl.register_mode_command(
+            0,
+            &show_health_cmd,
+            Box::new(move |_, inputs| {
+                show_health_command(inputs, json)?;
+                Ok(Action::None)
+            }),
+        )?;
+    }
+
+    let mut show_migrations = CmdBuilder::new();
+    show_migrations
+        .literal_with_doc("show", "display read-only information")
+        .literal_with_doc("migrations", "list schema migrations")
+        .command_doc("list schema migrations with when they ran and whether they changed since");
+    let show_migrations_cmd = show_migrations.build();
+    repl.register_mode_command(
+        0,
+        &show_migrations_cmd,
+        Box::new(|_, _| {
+            show_migrations_command()?;
+            Ok(Action::None)
+        }),
+    )?;
+
+    let mut show_profiles = CmdBuilder::new();
+    show_profiles
+        .literal_with_doc("show", "display read-only information")
+        .literal_with_doc("profiles", "list profiles")
+        .command_doc("list profiles and their data directories");
+    let show_profiles_cmd = show_profiles.build();
+    repl.register_mode_command(
+        0,
+        &show_profiles_cmd,
+        Box::new(|_, _| {
+            show_profiles_command()?;
+            Ok(Action::None)
+        }),
+    )?;
+
+    let mut search_transactions = CmdBuilder::new();
+    search_transactions
+        .literal_with_doc("show", "display read-only information")
+        .literal_with_doc("transactions", "list transactions")
+        .labeled_arg_with_doc("matching", "words that must all appear in the description")
+        .command_doc("search transaction descriptions using the database index");
+    let search_transactions_cmd = search_transactions.build();
+    repl.register_mode_command(
+        0,
+        &search_transactions_cmd,
+        Box::new(|_, inputs| {
+            search_transactions_command(inputs)?;
+            Ok(Action::None)
+        }),
+    )?;
+
     Ok(())
 }
 
@@ -96,6 +439,105 @@ fn register_write_mode_commands(repl: &mut Repl, write_mode_id: u32) -> Result<(
         }),
     )?;
 
+    for update_notes in [false, true] {
+        let mut import_accounts = CmdBuilder::new();
+        import_accounts
+            .literal_with_doc("import-accounts", "create accounts from a chart-of-accounts file")
+            .positional_arg_with_doc("file", "TOML file of nested [[account]] tables");
+        if update_notes {
+            import_accounts
+                .literal_with_doc("update-notes", "replace the notes of existing accounts")
+                .command_doc("create missing accounts and update notes of existing ones");
+        } else {
+            import_accounts.command_doc("create missing accounts, skipping existing ones");
+        }
+        let import_accounts_cmd = import_accounts.build();
+        repl.register_mode_command(
+            write_mode_id,
+            &import_accounts_cmd,
+            Box::new(move |_, inputs| {
+                import_accounts_command(inputs, update_notes)?;
+                Ok(Action::None)
+            }),
+        )?;
+    }
+
+    for variant in 0..64 {
+        let (allow_currency_mismatch, hash, infer_period, confirmed, from, to) = (
+            variant & 1 != 0,
+            variant & 2 != 0,
+            variant & 4 != 0,
+            variant & 8 != 0,
+            variant & 16 != 0,
+            variant & 32 != 0,
+        );
+        // Without infer-period the period is required and there is nothing to confirm.
+        if !infer_period && (confirmed || from || to) {
+            continue;
+        }
+        let mut import_statement = CmdBuilder::new();
+        import_statement
+            .literal_with_doc("import-statement", "copy in a statement file and record it")
+            .positional_arg_with_doc("file", "statement file to import")
+            .labeled_arg_with_doc("account", "name or id of the account the statement is for")
+            .labeled_arg_with_doc("institution", "bank or card issuer");
+        if !infer_period || from {
+            import_statement.labeled_arg_with_doc("from", "first day of the period, YYYY-MM-DD");
+        }
+        if !infer_period || to {
+            import_statement.labeled_arg_with_doc("to", "last day of the period, YYYY-MM-DD");
+        }
+        import_statement.labeled_arg_with_doc("currency", "statement currency");
+        if hash {
+            import_statement.labeled_arg_with_doc("hash", "file hash algorithm, sha256 or blake3");
+        }
+        if infer_period {
+            import_statement.literal_with_doc(
+                "infer-period",
+                "read missing period dates from the file name",
+            );
+        }
+        if confirmed {
+            import_statement.literal_with_doc("yes", "import with the inferred period");
+        }
+        if allow_currency_mismatch {
+            import_statement
+                .literal_with_doc(
+                    "allow-currency-mismatch",
+                    "accept a currency other than the account's",
+                )
+                .command_doc("import a statement in a currency the account does not use");
+        } else if infer_period && !confirmed {
+            import_statement.command_doc("show the period a statement would be imported with");
+        } else {
+            import_statement.command_doc("import a statement for an account");
+        }
+        let import_statement_cmd = import_statement.build();
+        repl.register_mode_command(
+            write_mode_id,
+            &import_statement_cmd,
+            Box::new(move |_, inputs| {
+                import_statement_command(inputs, allow_currency_mismatch, infer_period, confirmed)?;
+                Ok(Action::None)
+            }),
+        )?;
+    }
+
+    let mut export_accounts = CmdBuilder::new();
+    export_accounts
+        .literal_with_doc("export-accounts", "write accounts to a chart-of-accounts file")
+        .positional_arg_with_doc("file", "path to write; replaced if it exists")
+        .command_doc("write every account in the format read by import-accounts");
+    let export_accounts_cmd = export_accounts.build();
+    repl.register_mode_command(
+        write_mode_id,
+        &export_accounts_cmd,
+        Box::new(|_, inputs| {
+            export_accounts_command(inputs)?;
+            Ok(Action::None)
+        }),
+    )?;
+
     let mut init = CmdBuilder::new();
     init.literal_with_doc("init", "initialize the tally database")
         .command_doc("create the tally database and schema");
@@ -123,359 +565,2670 @@ fn register_write_mode_commands(repl: &mut Repl, write_mode_id: u32) -> Result<(
         }),
     )?;
 
+    let mut create_profile = CmdBuilder::new();
+    create_profile
+        .literal_with_doc("create", "create data in the tally database")
+        .literal_with_doc("profile", "create a separate ledger")
+        .positional_arg_with_doc("name", "profile name")
+        .command_doc("create a profile with its own database and statements");
+    let create_profile_cmd = create_profile.build();
+    repl.register_mode_command(
+        write_mode_id,
+        &create_profile_cmd,
+        Box::new(|_, inputs| {
+            create_profile_command(inputs)?;
+            Ok(Action::None)
+        }),
+    )?;
+
+    let mut remove_profile = CmdBuilder::new();
+    remove_profile
+        .literal_with_doc("remove-profile", "delete a profile and all of its data")
+        .positional_arg_with_doc("name", "profile name")
+        .command_doc("delete a profile's database and statement files");
+    let remove_profile_cmd = remove_profile.build();
+    repl.register_mode_command(
+        write_mode_id,
+        &remove_profile_cmd,
+        Box::new(|_, inputs| {
+            remove_profile_command(inputs)?;
+            Ok(Action::None)
+        }),
+    )?;
+
+    let mut undo_statement = CmdBuilder::new();
+    undo_statement
+        .literal_with_doc("undo-statement", "remove the most recent statement import")
+        .command_doc("show the most recently imported statement that undo would remove");
+    let undo_statement_cmd = undo_statement.build();
+    repl.register_mode_command(
+        write_mode_id,
+        &undo_statement_cmd,
+        Box::new(|_, _| {
+            undo_statement_command(false)?;
+            Ok(Action::None)
+        }),
+    )?;
+
+    let mut undo_statement_confirmed = CmdBuilder::new();
+    undo_statement_confirmed
+        .literal_with_doc("undo-statement", "remove the most recent statement import")
+        .literal_with_doc("yes", "confirm removing the statement row and its files")
+        .command_doc("delete the most recently imported statement and its managed files");
+    let undo_statement_confirmed_cmd = undo_statement_confirmed.build();
+    repl.register_mode_command(
+        write_mode_id,
+        &undo_statement_confirmed_cmd,
+        Box::new(|_, _| {
+            undo_statement_command(true)?;
+            Ok(Action::None)
+        }),
+    )?;
+
+    for confirmed in [false, true] {
+        let mut fix_periods = CmdBuilder::new();
+        fix_periods.literal_with_doc(
+            "fix-periods",
+            "rewrite statement periods stored in other date formats",
+        );
+        if confirmed {
+            fix_periods
+                .literal_with_doc("yes", "confirm rewriting the periods")
+                .command_doc("rewrite every statement period that can be read as dates");
+        } else {
+            fix_periods.command_doc("list invalid statement periods and how they would change");
+        }
+        let fix_periods_cmd = fix_periods.build();
+        repl.register_mode_command(
+            write_mode_id,
+            &fix_periods_cmd,
+            Box::new(move |_, _| {
+                fix_periods_command(confirmed)?;
+                Ok(Action::None)
+            }),
+        )?;
+    }
+
+    for (keep_latest, delete_files) in [(false, false), (false, true), (true, false), (true, true)]
+    {
+        let mut prune = CmdBuilder::new();
+        prune.literal_with_doc(
+            "prune-statements",
+            "delete statements superseded through replaced_by",
+        );
+        if keep_latest {
+            prune.labeled_arg_with_doc("keep-latest", "latest replacements to keep per chain");
+        }
+        if delete_files {
+            prune.literal_with_doc(
+                "delete-files",
+                "also remove managed files no remaining statement uses",
+            );
+        }
+        prune.command_doc("delete all but the newest statement of each replaced_by chain");
+        let prune_cmd = prune.build();
+        repl.register_mode_command(
+            write_mode_id,
+            &prune_cmd,
+            Box::new(move |_, inputs| {
+                prune_statements_command(inputs, delete_files)?;
+                Ok(Action::None)
+            }),
+        )?;
+    }
+
+    let mut vacuum_db = CmdBuilder::new();
+    vacuum_db
+        .literal_with_doc("vacuum-db", "shrink the tally database file")
+        .command_doc("rebuild the database file to reclaim space from deleted rows");
+    let vacuum_db_cmd = vacuum_db.build();
+    repl.register_mode_command(
+        write_mode_id,
+        &vacuum_db_cmd,
+        Box::new(|_, _| {
+            vacuum_db_command()?;
+            Ok(Action::None)
+        }),
+    )?;
+
+    let mut write_manifest = CmdBuilder::new();
+    write_manifest
+        .literal_with_doc("write-manifest", "record hashes of managed statement files")
+        .command_doc("write manifest.txt with the sha256 and size of every statement file");
+    let write_manifest_cmd = write_manifest.build();
+    repl.register_mode_command(
+        write_mode_id,
+        &write_manifest_cmd,
+        Box::new(|_, _| {
+            write_manifest_command()?;
+            Ok(Action::None)
+        }),
+    )?;
+
+    let mut move_data = CmdBuilder::new();
+    move_data
+        .literal_with_doc("move-data", "move the data directory to a new location")
+        .positional_arg_with_doc("target", "new data directory path")
+        .command_doc("copy and verify the database and statements at the target");
+    let move_data_cmd = move_data.build();
+    repl.register_mode_command(
+        write_mode_id,
+        &move_data_cmd,
+        Box::new(|_, inputs| {
+            move_data_command(inputs, false)?;
+            Ok(Action::None)
+        }),
+    )?;
+
+    let mut move_data_remove_source = CmdBuilder::new();
+    move_data_remove_source
+        .literal_with_doc("move-data", "move the data directory to a new location")
+        .positional_arg_with_doc("target", "new data directory path")
+        .literal_with_doc("remove-source", "delete the old data once the copy is verified")
+        .command_doc("move the database and statements, removing the source after verification");
+    let move_data_remove_source_cmd = move_data_remove_source.build();
+    repl.register_mode_command(
+        write_mode_id,
+        &move_data_remove_source_cmd,
+        Box::new(|_, inputs| {
+            move_data_command(inputs, true)?;
+            Ok(Action::None)
+        }),
+    )?;
+
+    for (layout, doc) in [
+        (StatementLayout::Flat, "store all statement files in one directory"),
+        (StatementLayout::ByAccount, "store statement files in per-account directories"),
+    ] {
+        let mut reshard = CmdBuilder::new();
+        reshard
+            .literal_with_doc("reshard-statements", "move statement files into a new layout")
+            .literal_with_doc(layout.as_str(), doc)
+            .command_doc("move existing statement files into the chosen layout");
+        let reshard_cmd = reshard.build();
+        repl.register_mode_command(
+            write_mode_id,
+            &reshard_cmd,
+            Box::new(move |_, _| {
+                reshard_statements_command(layout)?;
+                Ok(Action::None)
+            }),
+        )?;
+    }
+
     Ok(())
 }
 
 fn init_command() -> Result<(), HandlerError> {
-    let core = Core::from_environment().map_err(|err| HandlerError(err.to_string()))?;
+    let core = Core::from_environment().map_err(handler_error)?;
     core.init()
-        .map_err(|err| HandlerError(err.to_string()))?;
+        .map_err(handler_error)?;
     println!("initialized database at {}", core.db_path().display());
     Ok(())
 }
 
 fn delete_db_command() -> Result<(), HandlerError> {
-    match Core::delete_db_from_environment().map_err(|err| HandlerError(err.to_string()))? {
+    match Core::delete_db_from_environment().map_err(handler_error)? {
         (path, true) => println!("deleted database at {}", path.display()),
         (path, false) => println!("database not found at {}", path.display()),
     };
     Ok(())
 }
 
-fn show_accounts_command() -> Result<(), HandlerError> {
-    let core = Core::from_environment().map_err(|err| HandlerError(err.to_string()))?;
-    let accounts = core.list_accounts().map_err(|err| HandlerError(err.to_string()))?;
-    print!("{}", format_accounts(&accounts));
+fn move_data_command(inputs: &CommandInputs, remove_source: bool) -> Result<(), HandlerError> {
+    let target = inputs
+        .positionals
+        .first()
+        .ok_or_else(|| HandlerError("missing required input: target".to_string()))?;
+    let (summary, stale) = Core::migrate_data_dir_from_environment(target, remove_source)
+        .map_err(handler_error)?;
+    warn_stale_lock(stale.as_ref());
+    print!("{}", format_migration_summary(&summary));
     Ok(())
 }
 
-fn show_version_command() -> Result<(), HandlerError> {
-    let core = Core::from_environment().map_err(|err| HandlerError(err.to_string()))?;
-    let info = core.version_info().map_err(|err| HandlerError(err.to_string()))?;
-    print!("{}", format_version_info(&info));
+fn reshard_statements_command(layout: StatementLayout) -> Result<(), HandlerError> {
+    let core = Core::from_environment().map_err(handler_error)?;
+    let (summary, stale) = core
+        .reshard_statements(layout)
+        .map_err(handler_error)?;
+    warn_stale_lock(stale.as_ref());
+    print!("{}", format_reshard_summary(&summary));
     Ok(())
 }
 
-fn create_account_command(inputs: &CommandInputs) -> Result<(), HandlerError> {
+fn create_profile_command(inputs: &CommandInputs) -> Result<(), HandlerError> {
     let name = inputs
-        .labeled
-        .get("name")
-        .ok_or_else(|| HandlerError("missing required labeled input: name".to_string()))?;
-    let currency = inputs
-        .labeled
-        .get("currency")
-        .ok_or_else(|| HandlerError("missing required labeled input: currency".to_string()))?;
-    let note = inputs
-        .labeled
-        .get("note")
-        .ok_or_else(|| HandlerError("missing required labeled input: note".to_string()))?;
+        .positionals
+        .first()
+        .ok_or_else(|| HandlerError("missing required input: name".to_string()))?;
+    let profile =
+        Core::create_profile_from_environment(name).map_err(handler_error)?;
+    println!(
+        "created profile {} at {}",
+        profile.name,
+        profile.data_dir.display()
+    );
+    Ok(())
+}
 
-    let core = Core::from_environment().map_err(|err| HandlerError(err.to_string()))?;
-    let account = core
-        .create_account(name, currency, note)
-        .map_err(|err| HandlerError(err.to_string()))?;
-    print!("{}", format_created_account(&account));
+fn remove_profile_command(inputs: &CommandInputs) -> Result<(), HandlerError> {
+    let name = inputs
+        .positionals
+        .first()
+        .ok_or_else(|| HandlerError("missing required input: name".to_string()))?;
+    let profile =
+        Core::remove_profile_from_environment(name).map_err(handler_error)?;
+    println!(
+        "removed profile {} ({})",
+        profile.name,
+        profile.data_dir.display()
+    );
     Ok(())
 }
 
-fn format_accounts(accounts: &[Account]) -> String {
-    if accounts.is_empty() {
-        return "accounts: (none)\n".to_string();
+fn show_coverage_command(json: bool) -> Result<(), HandlerError> {
+    let core = Core::from_environment().map_err(handler_error)?;
+    let coverage = core
+        .monthly_coverage()
+        .map_err(handler_error)?;
+    if json {
+        println!("{}", format_coverage_json(&coverage));
+    } else {
+        print!("{}", format_coverage(&coverage));
     }
+    Ok(())
+}
 
-    let width = accounts.iter().map(|account| account.name.len()).max().unwrap_or(0);
-    let mut out = String::from("accounts:\n");
-    for account in accounts {
-        let status = if account.is_closed { "closed" } else { "open" };
-        out.push_str(&format!(
-            "  {:<width$}  {}  {}\n",
-            account.name,
-            account.currency,
-            status,
-            width = width
-        ));
+fn show_histogram_command(inputs: &CommandInputs) -> Result<(), HandlerError> {
+    let currency = inputs.labeled.get("currency").map(String::as_str);
+    let mut precision = CurrencyPrecision::new();
+    if let (Some(currency), Some(places)) = (currency, inputs.labeled.get("places")) {
+        let places = places
+            .parse()
+            .map_err(|_| HandlerError(format!("invalid places '{places}': use a number")))?;
+        precision.set(currency, places).map_err(handler_error)?;
+    }
+    let edges = inputs
+        .labeled
+        .get("buckets")
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_HISTOGRAM_EDGES);
+    // Check the edges before touching the database; each currency reads them again in its
+    // own decimal places below.
+    parse_bucket_edges(edges, precision.places(currency.unwrap_or_default()))
+        .map_err(handler_error)?;
+    let core = Core::from_environment().map_err(handler_error)?;
+    let amounts = core
+        .transaction_amounts(
+            inputs.labeled.get("from").map(String::as_str),
+            inputs.labeled.get("to").map(String::as_str),
+            currency,
+        )
+        .map_err(handler_error)?;
+    if amounts.is_empty() {
+        println!("histogram: (no transactions)");
+        return Ok(());
     }
-    out
-}
 
-fn format_created_account(account: &Account) -> String {
-    format!("created account {} ({})\n", account.name, account.currency)
+    let mut out = String::new();
+    for (currency, amounts) in &amounts {
+        let places = precision.places(currency);
+        let currency_edges = parse_bucket_edges(edges, places)
+            .map_err(|err| HandlerError(format!("{currency}: {err}")))?;
+        let buckets = bucket_amounts(amounts, &currency_edges);
+        out.push_str(&format_histogram(currency, &buckets, places));
+    }
+    print!("{out}");
+    Ok(())
 }
 
-fn format_version_info(info: &VersionInfo) -> String {
-    format!(
-        "tally42 version: {}\ndb schema version: {}\ndata dir: {}\n",
-        info.app_version,
-        info.schema_version,
-        info.data_dir.display()
-    )
+fn show_profiles_command() -> Result<(), HandlerError> {
+    let profiles =
+        Core::list_profiles_from_environment().map_err(handler_error)?;
+    print!("{}", format_profiles(&profiles));
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tli42::repl::RunOnceOutcome;
+fn undo_statement_command(confirmed: bool) -> Result<(), HandlerError> {
+    let core = Core::from_environment().map_err(handler_error)?;
+    if !confirmed {
+        let latest = core
+            .last_imported_statement()
+            .map_err(handler_error)?;
+        print!("{}", format_undo_preview(latest.as_ref()));
+        return Ok(());
+    }
 
-    #[test]
-    fn write_command_pushes_write_mode() {
-        let mut repl = build_repl().expect("repl should build");
+    let undone = core
+        .undo_last_statement()
+        .map_err(handler_error)?;
+    print!("{}", format_undone_statement(undone.as_ref()));
+    Ok(())
+}
 
-        let outcome = repl.run_once("write").expect("run_once should succeed");
-        assert_eq!(outcome, RunOnceOutcome::ActionApplied(Action::PushMode(1)));
-        assert_eq!(repl.current_mode_id().expect("current mode id"), 1);
+fn fix_periods_command(confirmed: bool) -> Result<(), HandlerError> {
+    let core = Core::from_environment().map_err(handler_error)?;
+    let invalid = core.invalid_statement_periods().map_err(handler_error)?;
+    if !confirmed {
+        print!("{}", format_period_fixes(&invalid, false));
+        return Ok(());
     }
 
-    #[test]
-    fn question_shows_annotated_write_mode_completions() {
-        let mut repl = build_repl().expect("repl should build");
-        repl.run_once("write").expect("enter write mode");
+    let fixed = core.fix_statement_periods().map_err(handler_error)?;
+    let unfixable = invalid.into_iter().filter(|period| period.normalized.is_none());
+    let periods: Vec<InvalidStatementPeriod> = fixed.into_iter().chain(unfixable).collect();
+    print!("{}", format_period_fixes(&periods, true));
+    Ok(())
+}
 
-        let outcome = repl.run_once("?").expect("completion should succeed");
-        assert_eq!(
-            outcome,
-            RunOnceOutcome::Completions(vec![
-                CompletionItem {
-                    token: "create".to_string(),
-                    doc: Some("create data in the tally database".to_string()),
-                },
-                CompletionItem {
-                    token: "delete-db".to_string(),
-                    doc: Some("delete the tally database file".to_string()),
-                },
-                CompletionItem {
-                    token: "init".to_string(),
-                    doc: Some("initialize the tally database".to_string()),
-                },
-            ])
+fn prune_statements_command(
+    inputs: &CommandInputs,
+    delete_files: bool,
+) -> Result<(), HandlerError> {
+    let keep_latest = match inputs.labeled.get("keep-latest") {
+        Some(value) => match value.parse::<usize>() {
+            Ok(keep_latest) if keep_latest > 0 => keep_latest,
+            _ => {
+                return Err(HandlerError(format!(
+                    "keep-latest must be a positive number, not '{value}'"
+                )))
+            }
+        },
+        None => 1,
+    };
+    let core = Core::from_environment().map_err(handler_error)?;
+    let (summary, stale) = core
+        .prune_superseded_statements(keep_latest, delete_files)
+        .map_err(handler_error)?;
+    warn_stale_lock(stale.as_ref());
+    print!("{}", format_prune_summary(&summary));
+    Ok(())
+}
+
+fn warn_stale_lock(stale: Option<&DbLock>) {
+    if let Some(lock) = stale {
+        eprintln!(
+            "warning: took over stale lock '{}' held by {} since {}",
+            lock.name, lock.holder, lock.acquired_at
         );
     }
+}
 
-    #[test]
-    fn question_shows_annotated_root_completions() {
-        let mut repl = build_repl().expect("repl should build");
+fn write_manifest_command() -> Result<(), HandlerError> {
+    let (path, entries) =
+        Core::write_manifest_from_environment().map_err(handler_error)?;
+    print!("{}", format_written_manifest(&path, &entries));
+    Ok(())
+}
+
+fn show_manifest_command() -> Result<(), HandlerError> {
+    let check =
+        Core::check_manifest_from_environment().map_err(handler_error)?;
+    print!("{}", format_manifest_check(&check));
+    Ok(())
+}
+
+fn show_health_command(inputs: &CommandInputs, json: bool) -> Result<(), HandlerError> {
+    let mut options = CheckOptions {
+        json,
+        ..CheckOptions::default()
+    };
+    if let Some(name) = inputs.labeled.get("skip") {
+        options.skip.push(HealthCheck::parse(name).map_err(HandlerError)?);
+    }
+    match check_command(&options) {
+        0 => Ok(()),
+        code => Err(HandlerError(format!("health checks failed (exit code {code})"))),
+    }
+}
+
+/// Run the health checks and print the report, returning the exit code.
+fn check_command(options: &CheckOptions) -> i32 {
+    // Read-only, so probing never creates or migrates the database it reports on.
+    let results = run_health_checks(
+        Core::open_read_only_from_environment(),
+        Core::check_manifest_from_environment,
+        |remove| {
+            if remove {
+                Core::cleanup_stale_temp_files_from_environment()
+            } else {
+                Core::stale_temp_files_from_environment()
+            }
+        },
+        options,
+    );
+    if options.json {
+        println!("{}", format_check_report_json(&results));
+    } else {
+        print!("{}", format_check_report(&results));
+    }
+    health_exit_code(&results)
+}
+
+/// One probe run by `tally42 check`. Each failed probe sets its own bit in the exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HealthCheck {
+    Db,
+    Migrations,
+    Statements,
+    Coverage,
+    Currency,
+    Periods,
+    TempFiles,
+}
+
+impl HealthCheck {
+    const ALL: [Self; 7] = [
+        Self::Db,
+        Self::Migrations,
+        Self::Statements,
+        Self::Coverage,
+        Self::Currency,
+        Self::Periods,
+        Self::TempFiles,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Db => "db",
+            Self::Migrations => "migrations",
+            Self::Statements => "statements",
+            Self::Coverage => "coverage",
+            Self::Currency => "currency",
+            Self::Periods => "periods",
+            Self::TempFiles => "temp-files",
+        }
+    }
+
+    fn exit_bit(self) -> i32 {
+        match self {
+            Self::Db => 1,
+            Self::Migrations => 2,
+            Self::Statements => 4,
+            Self::Coverage => 8,
+            Self::Currency => 16,
+            Self::Periods => 32,
+            Self::TempFiles => 64,
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|check| check.name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "unknown check: {name} \
+                     (expected db, migrations, statements, coverage, currency, periods, \
+                     or temp-files)"
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CheckOptions {
+    json: bool,
+    skip: Vec<HealthCheck>,
+    /// How many of the most recent expected months must have statements.
+    months: u32,
+    /// Remove the stale temp files the temp-files check finds instead of failing.
+    remove_temp_files: bool,
+}
+
+impl Default for CheckOptions {
+    fn default() -> Self {
+        Self {
+            json: false,
+            skip: Vec::new(),
+            months: 3,
+            remove_temp_files: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CheckStatus {
+    Pass,
+    Fail,
+    Skip,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CheckResult {
+    check: HealthCheck,
+    status: CheckStatus,
+    detail: String,
+}
+
+impl CheckResult {
+    fn new(check: HealthCheck, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            check,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Run every check not skipped in `options`. The manifest and temp files are read
+/// separately from the database so their checks still run when it cannot be opened.
+/// `stale_temp_files` is called with whether to remove the files it finds.
+fn run_health_checks(
+    core: Result<Core, CoreError>,
+    check_manifest: impl Fn() -> Result<ManifestCheck, CoreError>,
+    stale_temp_files: impl Fn(bool) -> Result<Vec<std::path::PathBuf>, CoreError>,
+    options: &CheckOptions,
+) -> Vec<CheckResult> {
+    HealthCheck::ALL
+        .into_iter()
+        .map(|check| {
+            if options.skip.contains(&check) {
+                return CheckResult::new(check, CheckStatus::Skip, "skipped");
+            }
+            match (&core, check) {
+                (_, HealthCheck::Statements) => statements_check(check_manifest()),
+                (_, HealthCheck::TempFiles) => temp_files_check(
+                    stale_temp_files(options.remove_temp_files),
+                    options.remove_temp_files,
+                ),
+                (Err(err), HealthCheck::Db) => {
+                    CheckResult::new(check, CheckStatus::Fail, error_chain(&err).join(": "))
+                }
+                (Err(_), _) => CheckResult::new(check, CheckStatus::Skip, "database unavailable"),
+                (Ok(core), HealthCheck::Db) => db_check(core),
+                (Ok(core), HealthCheck::Migrations) => migrations_check(core),
+                (Ok(core), HealthCheck::Coverage) => coverage_check(core, options.months),
+                (Ok(core), HealthCheck::Currency) => currency_check(core),
+                (Ok(core), HealthCheck::Periods) => periods_check(core),
+            }
+        })
+        .collect()
+}
+
+fn temp_files_check(
+    stale: Result<Vec<std::path::PathBuf>, CoreError>,
+    removed: bool,
+) -> CheckResult {
+    let check = HealthCheck::TempFiles;
+    let stale = match stale {
+        Ok(stale) => stale,
+        Err(err) => return CheckResult::new(check, CheckStatus::Fail, error_chain(&err).join(": ")),
+    };
+    if stale.is_empty() {
+        return CheckResult::new(check, CheckStatus::Pass, "no stale temp statement files");
+    }
+    let paths = stale
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    if removed {
+        return CheckResult::new(check, CheckStatus::Pass, format!("removed {paths}"));
+    }
+    CheckResult::new(
+        check,
+        CheckStatus::Fail,
+        format!("stale {paths}; run tally42 check --remove-temp-files"),
+    )
+}
+
+fn periods_check(core: &Core) -> CheckResult {
+    let check = HealthCheck::Periods;
+    let invalid = match core.invalid_statement_periods() {
+        Ok(invalid) => invalid,
+        Err(err) => {
+            return CheckResult::new(check, CheckStatus::Fail, error_chain(&err).join(": "))
+        }
+    };
+    if invalid.is_empty() {
+        return CheckResult::new(check, CheckStatus::Pass, "statement periods are valid");
+    }
+    let detail = invalid
+        .iter()
+        .map(|period| {
+            format!("{} {}..{}", period.statement_id, period.period_start, period.period_end)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    CheckResult::new(
+        check,
+        CheckStatus::Fail,
+        format!("invalid {detail}; run fix-periods in write mode"),
+    )
+}
+
+fn db_check(core: &Core) -> CheckResult {
+    match core.version_info() {
+        Ok(info) => CheckResult::new(
+            HealthCheck::Db,
+            CheckStatus::Pass,
+            format!("schema version {} at {}", info.schema_version, core.db_path().display()),
+        ),
+        Err(err) => {
+            CheckResult::new(HealthCheck::Db, CheckStatus::Fail, error_chain(&err).join(": "))
+        }
+    }
+}
+
+fn migrations_check(core: &Core) -> CheckResult {
+    let check = HealthCheck::Migrations;
+    let statuses = match core.migration_status() {
+        Ok(statuses) => statuses,
+        Err(err) => return CheckResult::new(check, CheckStatus::Fail, error_chain(&err).join(": ")),
+    };
+    let pending = statuses.iter().filter(|status| !status.applied).count();
+    let changed = statuses
+        .iter()
+        .filter(|status| status.checksum_matches == Some(false))
+        .count();
+    if pending == 0 && changed == 0 {
+        return CheckResult::new(
+            check,
+            CheckStatus::Pass,
+            format!("{} applied", statuses.len()),
+        );
+    }
+    CheckResult::new(
+        check,
+        CheckStatus::Fail,
+        format!("{pending} pending, {changed} changed since applied"),
+    )
+}
+
+fn statements_check(manifest: Result<ManifestCheck, CoreError>) -> CheckResult {
+    let check = HealthCheck::Statements;
+    match manifest {
+        Ok(manifest) if manifest.is_clean() => CheckResult::new(
+            check,
+            CheckStatus::Pass,
+            format!("{} files match the manifest", manifest.unchanged),
+        ),
+        Ok(manifest) => CheckResult::new(
+            check,
+            CheckStatus::Fail,
+            format!(
+                "{} added, {} removed, {} changed since the manifest",
+                manifest.added.len(),
+                manifest.removed.len(),
+                manifest.changed.len()
+            ),
+        ),
+        Err(CoreError::Manifest(ManifestError::Missing(_))) => {
+            CheckResult::new(check, CheckStatus::Skip, "no manifest; run write-manifest")
+        }
+        Err(err) => CheckResult::new(check, CheckStatus::Fail, error_chain(&err).join(": ")),
+    }
+}
+
+fn coverage_check(core: &Core, months: u32) -> CheckResult {
+    let check = HealthCheck::Coverage;
+    let coverage = match core.monthly_coverage() {
+        Ok(coverage) => coverage,
+        Err(err) => return CheckResult::new(check, CheckStatus::Fail, error_chain(&err).join(": ")),
+    };
+    let gaps = recent_coverage_gaps(&coverage, months);
+    if gaps.is_empty() {
+        return CheckResult::new(
+            check,
+            CheckStatus::Pass,
+            format!("no gaps in the last {months} months"),
+        );
+    }
+    let detail = gaps
+        .iter()
+        .map(|(account, month)| format!("{account} {month}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    CheckResult::new(check, CheckStatus::Fail, format!("missing {detail}"))
+}
+
+fn currency_check(core: &Core) -> CheckResult {
+    let check = HealthCheck::Currency;
+    let mismatches = match core.statement_currency_mismatches() {
+        Ok(mismatches) => mismatches,
+        Err(err) => {
+            return CheckResult::new(check, CheckStatus::Fail, error_chain(&err).join(": "))
+        }
+    };
+    if mismatches.is_empty() {
+        return CheckResult::new(
+            check,
+            CheckStatus::Pass,
+            "statement currencies match their accounts",
+        );
+    }
+    let detail = mismatches
+        .iter()
+        .map(|mismatch| {
+            format!(
+                "{} {} on {} account {}",
+                mismatch.statement_id,
+                mismatch.statement_currency,
+                mismatch.account_currency,
+                mismatch.account_name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    CheckResult::new(check, CheckStatus::Fail, format!("mismatched {detail}"))
+}
+
+/// Missing months among the last `months` months each account is expected to cover.
+fn recent_coverage_gaps(coverage: &[AccountCoverage], months: u32) -> Vec<(&str, YearMonth)> {
+    let mut gaps = Vec::new();
+    for entry in coverage {
+        let mut since = entry.last;
+        for _ in 1..months {
+            since = since.previous();
+        }
+        gaps.extend(
+            entry
+                .missing
+                .iter()
+                .filter(|month| **month >= since)
+                .map(|month| (entry.account_name.as_str(), *month)),
+        );
+    }
+    gaps
+}
+
+/// The bitwise OR of the exit bits of every failed check; 0 when nothing failed.
+fn health_exit_code(results: &[CheckResult]) -> i32 {
+    results
+        .iter()
+        .filter(|result| result.status == CheckStatus::Fail)
+        .fold(0, |code, result| code | result.check.exit_bit())
+}
+
+fn vacuum_db_command() -> Result<(), HandlerError> {
+    let core = Core::from_environment().map_err(handler_error)?;
+    let (before, after) = core.vacuum_db().map_err(handler_error)?;
+    print!("{}", format_vacuum_report(&before, &after));
+    Ok(())
+}
+
+fn show_accounts_command(inputs: &CommandInputs) -> Result<(), HandlerError> {
+    let format = output_format(inputs)?;
+    let core = Core::from_environment().map_err(handler_error)?;
+    let accounts = core.list_accounts().map_err(handler_error)?;
+    match format {
+        Some(format) => print!("{}", render::render(&accounts, format)),
+        None => print!("{}", format_accounts(&accounts)),
+    }
+    Ok(())
+}
+
+fn show_statements_command(inputs: &CommandInputs) -> Result<(), HandlerError> {
+    let format = output_format(inputs)?;
+    let since = match inputs.labeled.get("imported-since") {
+        Some(value) => Some(ImportedSince::parse(value).ok_or_else(|| {
+            HandlerError(format!(
+                "invalid imported-since '{value}': use a duration like 7d, 24h or 3w, \
+                 or a date like 2026-01-01"
+            ))
+        })?),
+        None => None,
+    };
+    let core = Core::from_environment().map_err(handler_error)?;
+    let statements = match since {
+        Some(since) => core.list_statements_imported_since(&since),
+        None => core.list_statements(),
+    }
+    .map_err(handler_error)?;
+    match format {
+        Some(format) => print!("{}", render::render(&statements, format)),
+        None => print!("{}", format_statements(&statements)),
+    }
+    Ok(())
+}
+
+/// The optional `format` input; `None` keeps the human-readable table.
+fn output_format(inputs: &CommandInputs) -> Result<Option<OutputFormat>, HandlerError> {
+    let Some(value) = inputs.labeled.get("format") else {
+        return Ok(None);
+    };
+    OutputFormat::parse(value)
+        .map(Some)
+        .ok_or_else(|| HandlerError(format!("unknown format '{value}', expected csv or json")))
+}
+
+fn show_version_command() -> Result<(), HandlerError> {
+    let core = Core::from_environment().map_err(handler_error)?;
+    let info = core.version_info().map_err(handler_error)?;
+    print!("{}", format_version_info(&info));
+    Ok(())
+}
+
+fn show_migrations_command() -> Result<(), HandlerError> {
+    let core = Core::from_environment().map_err(handler_error)?;
+    let status = core.migration_status().map_err(handler_error)?;
+    print!("{}", format_migration_status(&status));
+    Ok(())
+}
+
+fn search_transactions_command(inputs: &CommandInputs) -> Result<(), HandlerError> {
+    let query = inputs
+        .labeled
+        .get("matching")
+        .ok_or_else(|| HandlerError("missing required labeled input: matching".to_string()))?;
+    let core = Core::from_environment().map_err(handler_error)?;
+    let transactions = core.search_transactions(query).map_err(handler_error)?;
+    print!("{}", format_transaction_search(&transactions));
+    Ok(())
+}
+
+fn create_account_command(inputs: &CommandInputs) -> Result<(), HandlerError> {
+    let name = inputs
+        .labeled
+        .get("name")
+        .ok_or_else(|| HandlerError("missing required labeled input: name".to_string()))?;
+    let currency = inputs
+        .labeled
+        .get("currency")
+        .ok_or_else(|| HandlerError("missing required labeled input: currency".to_string()))?;
+    let note = inputs
+        .labeled
+        .get("note")
+        .ok_or_else(|| HandlerError("missing required labeled input: note".to_string()))?;
+
+    let core = Core::from_environment().map_err(handler_error)?;
+    let account = core
+        .create_account(name, currency, note)
+        .map_err(handler_error)?;
+    print!("{}", format_created_account(&account));
+    Ok(())
+}
+
+fn import_accounts_command(
+    inputs: &CommandInputs,
+    update_notes: bool,
+) -> Result<(), HandlerError> {
+    let file = inputs
+        .positionals
+        .first()
+        .ok_or_else(|| HandlerError("missing required input: file".to_string()))?;
+    let core = Core::from_environment().map_err(handler_error)?;
+    let summary = core
+        .import_account_chart(std::path::Path::new(file), update_notes)
+        .map_err(handler_error)?;
+    print!("{}", format_chart_import(&summary));
+    Ok(())
+}
+
+fn import_statement_command(
+    inputs: &CommandInputs,
+    allow_currency_mismatch: bool,
+    infer_period: bool,
+    confirmed: bool,
+) -> Result<(), HandlerError> {
+    let file = inputs
+        .positionals
+        .first()
+        .ok_or_else(|| HandlerError("missing required input: file".to_string()))?;
+    let labeled = |label: &str| {
+        inputs
+            .labeled
+            .get(label)
+            .ok_or_else(|| HandlerError(format!("missing required labeled input: {label}")))
+    };
+    let account_name = labeled("account")?;
+    let hash_algorithm = match inputs.labeled.get("hash") {
+        Some(name) => HashAlgorithm::from_prefix(name).ok_or_else(|| {
+            HandlerError(format!("unknown hash algorithm '{name}', expected sha256 or blake3"))
+        })?,
+        None => HashAlgorithm::default(),
+    };
+    let core = Core::from_environment()
+        .map_err(handler_error)?
+        .with_hash_algorithm(hash_algorithm);
+    let accounts = core.list_accounts().map_err(handler_error)?;
+    let account = find_account(&accounts, account_name).map_err(HandlerError)?;
+    let (period_start, period_end, inferred) = statement_period(
+        std::path::Path::new(file),
+        inputs.labeled.get("from").map(String::as_str),
+        inputs.labeled.get("to").map(String::as_str),
+        infer_period,
+    )
+    .map_err(HandlerError)?;
+    if inferred {
+        println!("inferred period {period_start}..{period_end} from the file name");
+        if !confirmed {
+            println!("add `yes` to import with this period");
+            return Ok(());
+        }
+    }
+    // Best effort: a leftover from a crashed import must not block a new one.
+    if let Ok(removed) = core.cleanup_stale_temp_files() {
+        for path in removed {
+            eprintln!("removed stale temp statement file {}", path.display());
+        }
+    }
+    let (statement, stale) = core
+        .add_statement(
+            std::path::Path::new(file),
+            AddStatementInput {
+                institution: labeled("institution")?.clone(),
+                account_id: account.id,
+                period_start,
+                period_end,
+                currency: labeled("currency")?.clone(),
+                replaced_by: None,
+                allow_currency_mismatch,
+            },
+        )
+        .map_err(handler_error)?;
+    warn_stale_lock(stale.as_ref());
+    println!(
+        "imported statement {} for {} ({}..{})",
+        statement.id, account.name, statement.period_start, statement.period_end
+    );
+    Ok(())
+}
+
+/// The period to import `file` with: `from` and `to` as given, with any missing one
+/// taken from the period inferred from the file name when `infer` is set. The flag
+/// says whether anything was inferred.
+fn statement_period(
+    file: &std::path::Path,
+    from: Option<&str>,
+    to: Option<&str>,
+    infer: bool,
+) -> Result<(String, String, bool), String> {
+    if let (Some(from), Some(to)) = (from, to) {
+        return Ok((from.to_string(), to.to_string(), false));
+    }
+    if !infer {
+        let label = if from.is_none() { "from" } else { "to" };
+        return Err(format!("missing required labeled input: {label}"));
+    }
+    let file_name = file.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let inferred = infer_period_from_filename(&file_name).ok_or_else(|| {
+        format!("could not infer a period from '{file_name}'; give from and to")
+    })?;
+    Ok((
+        from.map_or(inferred.period_start, str::to_string),
+        to.map_or(inferred.period_end, str::to_string),
+        true,
+    ))
+}
+
+/// The account whose id or name is `name_or_id`. Names are only unique among
+/// siblings, so a name shared by several accounts is rejected with their ids.
+fn find_account<'a>(accounts: &'a [Account], name_or_id: &str) -> Result<&'a Account, String> {
+    if let Ok(id) = uuid::Uuid::parse_str(name_or_id) {
+        if let Some(account) = accounts.iter().find(|account| account.id == id) {
+            return Ok(account);
+        }
+    }
+    let matches: Vec<&Account> = accounts
+        .iter()
+        .filter(|account| account.name == name_or_id)
+        .collect();
+    match matches.as_slice() {
+        [] => Err(format!("unknown account: {name_or_id}")),
+        [account] => Ok(account),
+        _ => {
+            let candidates: Vec<String> = matches
+                .iter()
+                .map(|account| {
+                    let parent = account
+                        .parent_id
+                        .and_then(|id| accounts.iter().find(|parent| parent.id == id))
+                        .map_or("top level", |parent| parent.name.as_str());
+                    format!("{} (under {parent})", account.id)
+                })
+                .collect();
+            Err(format!(
+                "account name '{name_or_id}' is ambiguous; use one of these ids: {}",
+                candidates.join(", ")
+            ))
+        }
+    }
+}
+
+fn export_accounts_command(inputs: &CommandInputs) -> Result<(), HandlerError> {
+    let file = inputs
+        .positionals
+        .first()
+        .ok_or_else(|| HandlerError("missing required input: file".to_string()))?;
+    let core = Core::from_environment().map_err(handler_error)?;
+    let count = core
+        .export_account_chart(std::path::Path::new(file))
+        .map_err(handler_error)?;
+    println!("wrote {count} account(s) to {file}");
+    Ok(())
+}
+
+fn format_chart_import(summary: &ChartImportSummary) -> String {
+    let mut out = format!("created {} account(s)\n", summary.created.len());
+    for path in &summary.created {
+        out.push_str(&format!("  {path}\n"));
+    }
+    for path in &summary.skipped {
+        out.push_str(&format!("skipped existing account {path}\n"));
+    }
+    for path in &summary.notes_updated {
+        out.push_str(&format!("updated note of {path}\n"));
+    }
+    out
+}
+
+fn format_accounts(accounts: &[Account]) -> String {
+    if accounts.is_empty() {
+        return "accounts: (none)\n".to_string();
+    }
+
+    let width = accounts.iter().map(|account| account.name.len()).max().unwrap_or(0);
+    let mut out = String::from("accounts:\n");
+    for account in accounts {
+        let status = if account.is_closed { "closed" } else { "open" };
+        out.push_str(&format!(
+            "  {:<width$}  {}  {}\n",
+            account.name,
+            account.currency,
+            status,
+            width = width
+        ));
+    }
+    out
+}
+
+fn format_migration_status(status: &[MigrationStatus]) -> String {
+    if status.is_empty() {
+        return "migrations: (none)\n".to_string();
+    }
+
+    let width = status.iter().map(|entry| entry.name.len()).max().unwrap_or(0);
+    let mut out = String::from("migrations:\n");
+    for entry in status {
+        let applied_at = match (&entry.applied_at, entry.applied) {
+            (Some(applied_at), _) => applied_at.as_str(),
+            (None, true) => "-",
+            (None, false) => "pending",
+        };
+        let duration = entry
+            .duration_ms
+            .map(|ms| format!("{ms}ms"))
+            .unwrap_or_else(|| "-".to_string());
+        let checksum = match entry.checksum_matches {
+            Some(true) => "ok",
+            Some(false) => "changed",
+            None => "-",
+        };
+        out.push_str(&format!(
+            "  {:04}  {:<width$}  {}  {}  {}  {}\n",
+            entry.version,
+            entry.name,
+            applied_at,
+            duration,
+            entry.app_version.as_deref().unwrap_or("-"),
+            checksum,
+            width = width
+        ));
+    }
+    out
+}
+
+fn format_transaction_search(transactions: &[Transaction]) -> String {
+    if transactions.is_empty() {
+        return "transactions: (none)\n".to_string();
+    }
+
+    let mut out = String::from("transactions:\n");
+    for transaction in transactions {
+        out.push_str(&format_transaction_line(transaction));
+    }
+    out
+}
+
+fn format_transaction_line(transaction: &Transaction) -> String {
+    format!(
+        "  {}  {}\n",
+        transaction.posted_at,
+        transaction.description.as_deref().unwrap_or("")
+    )
+}
+
+fn format_created_account(account: &Account) -> String {
+    format!("created account {} ({})\n", account.name, account.currency)
+}
+
+fn format_version_info(info: &VersionInfo) -> String {
+    format!(
+        "tally42 version: {}\ndb schema version: {}\ndata dir: {}\n",
+        info.app_version,
+        info.schema_version,
+        info.data_dir.display()
+    )
+}
+
+fn format_migration_summary(summary: &MigrationSummary) -> String {
+    let mut out = format!(
+        "moved data from {} to {}\nstatement rows: {}\nstatement files: {} ({} bytes)\n",
+        summary.source_dir.display(),
+        summary.target_dir.display(),
+        summary.statement_rows,
+        summary.statement_files,
+        summary.statement_bytes
+    );
+    if summary.source_removed {
+        out.push_str("source removed\n");
+    } else {
+        out.push_str("source kept\n");
+    }
+    out
+}
+
+fn format_vacuum_report(before: &DbSizeInfo, after: &DbSizeInfo) -> String {
+    format!(
+        "vacuumed database: {} bytes -> {} bytes ({} bytes reclaimed)\n",
+        before.file_bytes(),
+        after.file_bytes(),
+        before.file_bytes().saturating_sub(after.file_bytes())
+    )
+}
+
+fn format_statements(statements: &[Statement]) -> String {
+    if statements.is_empty() {
+        return "statements: (none)\n".to_string();
+    }
+
+    let mut out = String::from("statements:\n");
+    for statement in statements {
+        out.push_str(&format!("  {}\n", format_statement_line(statement)));
+    }
+    out
+}
+
+fn format_statement_line(statement: &Statement) -> String {
+    format!(
+        "{}  {}  {}..{}  imported {}",
+        statement.id,
+        statement.institution,
+        statement.period_start,
+        statement.period_end,
+        statement.imported_at
+    )
+}
+
+fn format_period_fixes(periods: &[InvalidStatementPeriod], applied: bool) -> String {
+    if periods.is_empty() {
+        return "statement periods are valid\n".to_string();
+    }
+    let mut out = String::new();
+    let mut fixable = 0;
+    for period in periods {
+        let current = format!("{}..{}", period.period_start, period.period_end);
+        match &period.normalized {
+            Some((start, end)) => {
+                fixable += 1;
+                let verb = if applied { "rewrote" } else { "would rewrite" };
+                out.push_str(&format!(
+                    "{verb} {}: {current} -> {start}..{end}\n",
+                    period.statement_id
+                ));
+            }
+            None => out.push_str(&format!(
+                "cannot read {}: {current}; fix it by hand\n",
+                period.statement_id
+            )),
+        }
+    }
+    if !applied && fixable > 0 {
+        out.push_str(&format!(
+            "run `fix-periods yes` to rewrite {fixable} statement period(s)\n"
+        ));
+    }
+    out
+}
+
+fn format_undo_preview(latest: Option<&Statement>) -> String {
+    match latest {
+        Some(statement) => format!(
+            "latest import: {}\nrun `undo-statement yes` to remove it and its files\n",
+            format_statement_line(statement)
+        ),
+        None => "no statements to undo\n".to_string(),
+    }
+}
+
+fn format_undone_statement(undone: Option<&UndoneStatement>) -> String {
+    match undone {
+        Some(undone) => format!(
+            "removed statement: {}\nremoved {} statement file(s)\n",
+            format_statement_line(&undone.statement),
+            undone.removed_files.len()
+        ),
+        None => "no statements to undo\n".to_string(),
+    }
+}
+
+fn format_prune_summary(summary: &PruneSummary) -> String {
+    let mut out = String::new();
+    for chain in &summary.chains {
+        out.push_str(&format!(
+            "kept {}, removed {} superseded statement(s)\n",
+            format_statement_line(&chain.head),
+            chain.removed.len()
+        ));
+    }
+    if summary.chains.is_empty() {
+        out.push_str("no superseded statements to prune\n");
+    } else {
+        out.push_str(&format!(
+            "removed {} statement file(s)\n",
+            summary.removed_files.len()
+        ));
+    }
+    for cycle in &summary.cycles {
+        let ids: Vec<String> = cycle.iter().map(ToString::to_string).collect();
+        out.push_str(&format!(
+            "warning: skipped statements whose replaced_by links loop: {}\n",
+            ids.join(" -> ")
+        ));
+    }
+    out
+}
+
+fn format_coverage(coverage: &[AccountCoverage]) -> String {
+    if coverage.is_empty() {
+        return "coverage: (no statements)\n".to_string();
+    }
+
+    let width = coverage
+        .iter()
+        .map(|entry| entry.account_name.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::from("coverage (monthly):\n");
+    for entry in coverage {
+        let status = if entry.missing.is_empty() {
+            "complete".to_string()
+        } else {
+            let months: Vec<String> = entry.missing.iter().map(ToString::to_string).collect();
+            format!("missing {}", months.join(", "))
+        };
+        out.push_str(&format!(
+            "  {:<width$}  {}..{}  {}\n",
+            entry.account_name,
+            entry.first,
+            entry.last,
+            status,
+            width = width
+        ));
+    }
+    out
+}
+
+fn format_coverage_json(coverage: &[AccountCoverage]) -> String {
+    let accounts: Vec<serde_json::Value> = coverage
+        .iter()
+        .map(|entry| {
+            serde_json::json!({
+                "account_id": entry.account_id.to_string(),
+                "account": entry.account_name,
+                "first": entry.first.to_string(),
+                "last": entry.last.to_string(),
+                "missing": entry.missing.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    serde_json::Value::Array(accounts).to_string()
+}
+
+fn format_check_report(results: &[CheckResult]) -> String {
+    let width = results
+        .iter()
+        .map(|result| result.check.name().len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for result in results {
+        let status = match result.status {
+            CheckStatus::Pass => "ok",
+            CheckStatus::Fail => "FAIL",
+            CheckStatus::Skip => "skip",
+        };
+        out.push_str(&format!(
+            "{status:<4}  {:<width$}  {}\n",
+            result.check.name(),
+            result.detail,
+            width = width
+        ));
+    }
+    out
+}
+
+fn format_check_report_json(results: &[CheckResult]) -> String {
+    let checks: Vec<serde_json::Value> = results
+        .iter()
+        .map(|result| {
+            let status = match result.status {
+                CheckStatus::Pass => "pass",
+                CheckStatus::Fail => "fail",
+                CheckStatus::Skip => "skip",
+            };
+            serde_json::json!({
+                "name": result.check.name(),
+                "status": status,
+                "detail": result.detail,
+            })
+        })
+        .collect();
+    let exit_code = health_exit_code(results);
+    serde_json::json!({
+        "ok": exit_code == 0,
+        "exit_code": exit_code,
+        "checks": checks,
+    })
+    .to_string()
+}
+
+const HISTOGRAM_BAR_WIDTH: usize = 30;
+
+/// Render the `buckets` of amounts in `currency`, showing them with `places` decimal places.
+fn format_histogram(currency: &str, buckets: &[AmountBucket], places: u32) -> String {
+    let count: usize = buckets.iter().map(|bucket| bucket.count).sum();
+    if count == 0 {
+        return format!("histogram {currency}: (no transactions)\n");
+    }
+
+    let rows: Vec<(String, String, String)> = buckets
+        .iter()
+        .map(|bucket| {
+            let range = match bucket.upper {
+                Some(upper) => format!(
+                    "{}..{}",
+                    format_minor_units(bucket.lower, places),
+                    format_minor_units(upper, places)
+                ),
+                None => format!("> {}", format_minor_units(bucket.lower, places)),
+            };
+            (range, bucket.count.to_string(), format_minor_units(bucket.total, places))
+        })
+        .collect();
+    let width = |column: fn(&(String, String, String)) -> &String| {
+        rows.iter().map(|row| column(row).len()).max().unwrap_or(0)
+    };
+    let (range_width, count_width, total_width) =
+        (width(|row| &row.0), width(|row| &row.1), width(|row| &row.2));
+    let max_count = buckets.iter().map(|bucket| bucket.count).max().unwrap_or(0);
+
+    let mut out = format!("histogram {currency} ({count} transactions):\n");
+    for (bucket, (range, count, total)) in buckets.iter().zip(&rows) {
+        let bar = "#".repeat(bucket.count * HISTOGRAM_BAR_WIDTH / max_count);
+        out.push_str(
+            format!(
+                "  {range:>range_width$}  {count:>count_width$}  {total:>total_width$}  {bar}"
+            )
+            .trim_end(),
+        );
+        out.push('\n');
+    }
+    out
+}
+
+fn format_profiles(profiles: &[Profile]) -> String {
+    let width = profiles.iter().map(|profile| profile.name.len()).max().unwrap_or(0);
+    let mut out = String::from("profiles:\n");
+    for profile in profiles {
+        let marker = if profile.active { "*" } else { " " };
+        out.push_str(&format!(
+            "{marker} {:<width$}  {}\n",
+            profile.name,
+            profile.data_dir.display(),
+            width = width
+        ));
+    }
+    out
+}
+
+fn format_written_manifest(path: &std::path::Path, entries: &[ManifestEntry]) -> String {
+    let bytes: u64 = entries.iter().map(|entry| entry.size).sum();
+    format!(
+        "wrote {}: {} statement files, {} bytes\n",
+        path.display(),
+        entries.len(),
+        bytes
+    )
+}
+
+fn format_manifest_check(check: &ManifestCheck) -> String {
+    if check.is_clean() {
+        return format!("manifest: ok ({} files unchanged)\n", check.unchanged);
+    }
+
+    let mut out = format!("manifest: {} files unchanged\n", check.unchanged);
+    for (label, paths) in [
+        ("added", &check.added),
+        ("removed", &check.removed),
+        ("changed", &check.changed),
+    ] {
+        for path in paths {
+            out.push_str(&format!("  {label:<7}  {}\n", path.display()));
+        }
+    }
+    out
+}
+
+fn format_reshard_summary(summary: &ReshardSummary) -> String {
+    format!(
+        "resharded statement files to {} layout: {} moved, {} unchanged\n",
+        summary.layout.as_str(),
+        summary.moved,
+        summary.unchanged
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tli42::repl::{CompletionItem, RunOnceOutcome};
+
+    /// The `exit` or `quit` entry tli42 lists at the root of every mode.
+    fn exit_item(name: &str) -> CompletionItem {
+        CompletionItem {
+            token: name.to_string(),
+            doc: Some("leave the current mode, or quit from the root mode".to_string()),
+        }
+    }
+
+    #[test]
+    fn write_command_pushes_write_mode() {
+        let mut repl = build_repl().expect("repl should build");
+
+        let outcome = repl.run_once("write").expect("run_once should succeed");
+        assert_eq!(outcome, RunOnceOutcome::ActionApplied(Action::PushMode(1)));
+        assert_eq!(repl.current_mode_id().expect("current mode id"), 1);
+    }
+
+    #[test]
+    fn question_shows_annotated_write_mode_completions() {
+        let mut repl = build_repl().expect("repl should build");
+        repl.run_once("write").expect("enter write mode");
 
         let outcome = repl.run_once("?").expect("completion should succeed");
         assert_eq!(
             outcome,
             RunOnceOutcome::Completions(vec![
                 CompletionItem {
-                    token: "show".to_string(),
-                    doc: Some("display read-only information".to_string()),
+                    token: "create".to_string(),
+                    doc: Some("create data in the tally database".to_string()),
+                },
+                CompletionItem {
+                    token: "delete-db".to_string(),
+                    doc: Some("delete the tally database file".to_string()),
+                },
+                exit_item("exit"),
+                CompletionItem {
+                    token: "export-accounts".to_string(),
+                    doc: Some("write accounts to a chart-of-accounts file".to_string()),
+                },
+                CompletionItem {
+                    token: "fix-periods".to_string(),
+                    doc: Some("rewrite statement periods stored in other date formats".to_string()),
+                },
+                CompletionItem {
+                    token: "import-accounts".to_string(),
+                    doc: Some("create accounts from a chart-of-accounts file".to_string()),
+                },
+                CompletionItem {
+                    token: "import-statement".to_string(),
+                    doc: Some("copy in a statement file and record it".to_string()),
+                },
+                CompletionItem {
+                    token: "init".to_string(),
+                    doc: Some("initialize the tally database".to_string()),
+                },
+                CompletionItem {
+                    token: "move-data".to_string(),
+                    doc: Some("move the data directory to a new location".to_string()),
+                },
+                CompletionItem {
+                    token: "prune-statements".to_string(),
+                    doc: Some("delete statements superseded through replaced_by".to_string()),
+                },
+                exit_item("quit"),
+                CompletionItem {
+                    token: "remove-profile".to_string(),
+                    doc: Some("delete a profile and all of its data".to_string()),
+                },
+                CompletionItem {
+                    token: "reshard-statements".to_string(),
+                    doc: Some("move statement files into a new layout".to_string()),
+                },
+                CompletionItem {
+                    token: "undo-statement".to_string(),
+                    doc: Some("remove the most recent statement import".to_string()),
+                },
+                CompletionItem {
+                    token: "vacuum-db".to_string(),
+                    doc: Some("shrink the tally database file".to_string()),
+                },
+                CompletionItem {
+                    token: "write-manifest".to_string(),
+                    doc: Some("record hashes of managed statement files".to_string()),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn question_shows_annotated_root_completions() {
+        let mut repl = build_repl().expect("repl should build");
+
+        let outcome = repl.run_once("?").expect("completion should succeed");
+        assert_eq!(
+            outcome,
+            RunOnceOutcome::Completions(vec![
+                exit_item("exit"),
+                exit_item("quit"),
+                CompletionItem {
+                    token: "show".to_string(),
+                    doc: Some("display read-only information".to_string()),
+                },
+                CompletionItem {
+                    token: "write".to_string(),
+                    doc: Some("enter write mode".to_string()),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn show_question_lists_accounts_subcommand() {
+        let mut repl = build_repl().expect("repl should build");
+
+        let outcome = repl.run_once("show ?").expect("completion should succeed");
+        assert_eq!(
+            outcome,
+            RunOnceOutcome::Completions(vec![
+                CompletionItem {
+                    token: "accounts".to_string(),
+                    doc: Some("list accounts".to_string()),
+                },
+                CompletionItem {
+                    token: "coverage".to_string(),
+                    doc: Some("find months missing a monthly statement".to_string()),
+                },
+                CompletionItem {
+                    token: "health".to_string(),
+                    doc: Some("run the health checks used by `tally42 check`".to_string()),
+                },
+                CompletionItem {
+                    token: "histogram".to_string(),
+                    doc: Some("bucket transaction amounts by size".to_string()),
+                },
+                CompletionItem {
+                    token: "manifest".to_string(),
+                    doc: Some("check statement files against manifest.txt".to_string()),
+                },
+                CompletionItem {
+                    token: "migrations".to_string(),
+                    doc: Some("list schema migrations".to_string()),
+                },
+                CompletionItem {
+                    token: "profiles".to_string(),
+                    doc: Some("list profiles".to_string()),
+                },
+                CompletionItem {
+                    token: "statements".to_string(),
+                    doc: Some("list imported statements".to_string()),
+                },
+                CompletionItem {
+                    token: "transactions".to_string(),
+                    doc: Some("list transactions".to_string()),
+                },
+                CompletionItem {
+                    token: "version".to_string(),
+                    doc: Some("show tally42 and schema versions".to_string()),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn create_question_lists_account_and_profile_subcommands() {
+        let mut repl = build_repl().expect("repl should build");
+        repl.run_once("write").expect("enter write mode");
+
+        let outcome = repl.run_once("create ?").expect("completion should succeed");
+        assert_eq!(
+            outcome,
+            RunOnceOutcome::Completions(vec![
+                CompletionItem {
+                    token: "account".to_string(),
+                    doc: Some("create an account".to_string()),
+                },
+                CompletionItem {
+                    token: "profile".to_string(),
+                    doc: Some("create a separate ledger".to_string()),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn create_account_question_lists_name_label() {
+        let mut repl = build_repl().expect("repl should build");
+        repl.run_once("write").expect("enter write mode");
+
+        let outcome = repl
+            .run_once("create account ?")
+            .expect("completion should succeed");
+        assert_eq!(
+            outcome,
+            RunOnceOutcome::Completions(vec![CompletionItem {
+                token: "name".to_string(),
+                doc: Some("set the account name".to_string()),
+            }])
+        );
+    }
+
+    #[test]
+    fn create_account_name_question_lists_name_placeholder() {
+        let mut repl = build_repl().expect("repl should build");
+        repl.run_once("write").expect("enter write mode");
+
+        let outcome = repl
+            .run_once("create account name ?")
+            .expect("completion should succeed");
+        assert_eq!(
+            outcome,
+            RunOnceOutcome::Completions(vec![CompletionItem {
+                token: "<name>".to_string(),
+                doc: Some("set the account name".to_string()),
+            }])
+        );
+    }
+
+    #[test]
+    fn create_account_after_name_and_currency_lists_note() {
+        let mut repl = build_repl().expect("repl should build");
+        repl.run_once("write").expect("enter write mode");
+
+        let outcome = repl
+            .run_once("create account name cash currency USD ?")
+            .expect("completion should succeed");
+        assert_eq!(
+            outcome,
+            RunOnceOutcome::Completions(vec![CompletionItem {
+                token: "note".to_string(),
+                doc: Some("set the account note".to_string()),
+            }])
+        );
+    }
+
+    #[test]
+    fn create_account_currency_question_lists_currency_placeholder() {
+        let mut repl = build_repl().expect("repl should build");
+        repl.run_once("write").expect("enter write mode");
+
+        let outcome = repl
+            .run_once("create account name cash currency ?")
+            .expect("completion should succeed");
+        assert_eq!(
+            outcome,
+            RunOnceOutcome::Completions(vec![CompletionItem {
+                token: "<currency>".to_string(),
+                doc: Some("set the account currency".to_string()),
+            }])
+        );
+    }
+
+    #[test]
+    fn show_accounts_command_is_registered() {
+        let mut repl = build_repl().expect("repl should build");
+
+        let outcome = repl
+            .run_once("show accounts")
+            .expect("run_once should succeed");
+        assert!(matches!(
+            outcome,
+            RunOnceOutcome::ActionApplied(Action::None) | RunOnceOutcome::HandlerError(_)
+        ));
+    }
+
+    #[test]
+    fn show_version_command_is_registered() {
+        let mut repl = build_repl().expect("repl should build");
+
+        let outcome = repl
+            .run_once("show version")
+            .expect("run_once should succeed");
+        assert!(matches!(
+            outcome,
+            RunOnceOutcome::ActionApplied(Action::None) | RunOnceOutcome::HandlerError(_)
+        ));
+    }
+
+    #[test]
+    fn create_account_command_is_registered() {
+        let mut repl = build_repl().expect("repl should build");
+        repl.run_once("write").expect("enter write mode");
+
+        let outcome = repl
+            .run_once("create account name cash currency USD note wallet")
+            .expect("run_once should succeed");
+        assert!(matches!(
+            outcome,
+            RunOnceOutcome::ActionApplied(Action::None) | RunOnceOutcome::HandlerError(_)
+        ));
+    }
+
+    #[test]
+    fn format_chart_import_lists_created_skipped_and_updated_accounts() {
+        assert_eq!(
+            format_chart_import(&ChartImportSummary::default()),
+            "created 0 account(s)\n"
+        );
+        let summary = ChartImportSummary {
+            created: vec!["Assets:Savings".to_string(), "Expenses".to_string()],
+            skipped: vec!["Assets".to_string()],
+            notes_updated: vec!["Assets:Checking".to_string()],
+        };
+        assert_eq!(
+            format_chart_import(&summary),
+            "created 2 account(s)\n  Assets:Savings\n  Expenses\n\
+             skipped existing account Assets\nupdated note of Assets:Checking\n"
+        );
+    }
+
+    #[test]
+    fn format_accounts_renders_empty_state() {
+        assert_eq!(format_accounts(&[]), "accounts: (none)\n");
+    }
+
+    #[test]
+    fn format_accounts_renders_compact_table() {
+        let open_id = uuid::Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap();
+        let closed_id = uuid::Uuid::parse_str("22222222-2222-2222-2222-222222222222").unwrap();
+
+        let output = format_accounts(&[
+            Account {
+                id: open_id,
+                parent_id: None,
+                name: "checking".to_string(),
+                currency: "USD".to_string(),
+                is_closed: false,
+                created_at: "2026-02-28 00:00:00".to_string(),
+                note: None,
+            },
+            Account {
+                id: closed_id,
+                parent_id: None,
+                name: "longer-savings".to_string(),
+                currency: "EUR".to_string(),
+                is_closed: true,
+                created_at: "2026-02-28 00:00:00".to_string(),
+                note: Some("archived".to_string()),
+            },
+        ]);
+
+        assert_eq!(
+            output,
+            "accounts:\n  checking        USD  open\n  longer-savings  EUR  closed\n"
+        );
+    }
+
+    #[test]
+    fn statement_period_infers_only_missing_dates() {
+        let file = std::path::Path::new("downloads/statement_2026-02.pdf");
+        let period = |from, to, infer| statement_period(file, from, to, infer);
+
+        assert_eq!(
+            period(None, None, true),
+            Ok(("2026-02-01".to_string(), "2026-02-28".to_string(), true))
+        );
+        assert_eq!(
+            period(Some("2026-01-15"), None, true),
+            Ok(("2026-01-15".to_string(), "2026-02-28".to_string(), true))
+        );
+        assert_eq!(
+            period(Some("2026-01-01"), Some("2026-01-31"), true),
+            Ok(("2026-01-01".to_string(), "2026-01-31".to_string(), false))
+        );
+        assert_eq!(
+            period(Some("2026-01-01"), None, false),
+            Err("missing required labeled input: to".to_string())
+        );
+        assert_eq!(
+            statement_period(std::path::Path::new("2026/amex.pdf"), None, None, true),
+            Err("could not infer a period from 'amex.pdf'; give from and to".to_string())
+        );
+    }
+
+    #[test]
+    fn find_account_rejects_names_shared_by_several_accounts() {
+        let account = |id: &str, parent_id: Option<uuid::Uuid>, name: &str| Account {
+            id: uuid::Uuid::parse_str(id).unwrap(),
+            parent_id,
+            name: name.to_string(),
+            currency: "USD".to_string(),
+            is_closed: false,
+            created_at: "2026-02-28 00:00:00".to_string(),
+            note: None,
+        };
+        let amex = account("11111111-1111-1111-1111-111111111111", None, "amex");
+        let chase = account("22222222-2222-2222-2222-222222222222", None, "chase");
+        let amex_card = account("33333333-3333-3333-3333-333333333333", Some(amex.id), "card");
+        let chase_card = account("44444444-4444-4444-4444-444444444444", Some(chase.id), "card");
+        let accounts = [amex, chase, amex_card, chase_card];
+
+        assert_eq!(find_account(&accounts, "chase").map(|a| a.id), Ok(accounts[1].id));
+        assert_eq!(
+            find_account(&accounts, "44444444-4444-4444-4444-444444444444").map(|a| a.id),
+            Ok(accounts[3].id)
+        );
+        assert_eq!(
+            find_account(&accounts, "card").map(|a| a.id),
+            Err("account name 'card' is ambiguous; use one of these ids: \
+                 33333333-3333-3333-3333-333333333333 (under amex), \
+                 44444444-4444-4444-4444-444444444444 (under chase)"
+                .to_string())
+        );
+        assert_eq!(
+            find_account(&accounts, "visa").map(|a| a.id),
+            Err("unknown account: visa".to_string())
+        );
+    }
+
+    #[test]
+    fn format_created_account_renders_compact_summary() {
+        let account = Account {
+            id: uuid::Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
+            parent_id: None,
+            name: "cash".to_string(),
+            currency: "USD".to_string(),
+            is_closed: false,
+            created_at: "2026-02-28 00:00:00".to_string(),
+            note: Some("wallet".to_string()),
+        };
+
+        assert_eq!(format_created_account(&account), "created account cash (USD)\n");
+    }
+
+    #[test]
+    fn format_version_info_renders_expected_output() {
+        let info = VersionInfo {
+            app_version: "0.1.0".to_string(),
+            schema_version: 4,
+            data_dir: std::path::PathBuf::from("/tmp/tally42"),
+        };
+
+        assert_eq!(
+            format_version_info(&info),
+            "tally42 version: 0.1.0\ndb schema version: 4\ndata dir: /tmp/tally42\n"
+        );
+    }
+
+    #[test]
+    fn format_migration_summary_renders_expected_output() {
+        let summary = MigrationSummary {
+            source_dir: std::path::PathBuf::from("/old/tally42"),
+            target_dir: std::path::PathBuf::from("/new/tally42"),
+            statement_rows: 2,
+            statement_files: 2,
+            statement_bytes: 1024,
+            source_removed: true,
+        };
+
+        assert_eq!(
+            format_migration_summary(&summary),
+            "moved data from /old/tally42 to /new/tally42\nstatement rows: 2\nstatement files: 2 (1024 bytes)\nsource removed\n"
+        );
+    }
+
+    #[test]
+    fn reshard_statements_question_lists_layouts() {
+        let mut repl = build_repl().expect("repl should build");
+        repl.run_once("write").expect("enter write mode");
+
+        let outcome = repl
+            .run_once("reshard-statements ?")
+            .expect("completion should succeed");
+        assert_eq!(
+            outcome,
+            RunOnceOutcome::Completions(vec![
+                CompletionItem {
+                    token: "by-account".to_string(),
+                    doc: Some("store statement files in per-account directories".to_string()),
                 },
                 CompletionItem {
-                    token: "write".to_string(),
-                    doc: Some("enter write mode".to_string()),
+                    token: "flat".to_string(),
+                    doc: Some("store all statement files in one directory".to_string()),
                 },
             ])
         );
     }
 
     #[test]
-    fn show_question_lists_accounts_subcommand() {
-        let mut repl = build_repl().expect("repl should build");
+    fn format_reshard_summary_renders_counts() {
+        let summary = ReshardSummary {
+            layout: StatementLayout::ByAccount,
+            moved: 3,
+            unchanged: 1,
+        };
+
+        assert_eq!(
+            format_reshard_summary(&summary),
+            "resharded statement files to by-account layout: 3 moved, 1 unchanged\n"
+        );
+    }
+
+    #[test]
+    fn format_migration_status_renders_unknown_values_as_dash() {
+        let status = vec![
+            MigrationStatus {
+                version: 1,
+                name: "add_accounts_table".to_string(),
+                applied: true,
+                applied_at: None,
+                duration_ms: None,
+                app_version: None,
+                checksum: Some("sha256:aa".to_string()),
+                checksum_matches: Some(true),
+            },
+            MigrationStatus {
+                version: 2,
+                name: "accounts_redef".to_string(),
+                applied: true,
+                applied_at: Some("2026-03-01 12:00:00".to_string()),
+                duration_ms: Some(3),
+                app_version: Some("0.1.0".to_string()),
+                checksum: Some("sha256:bb".to_string()),
+                checksum_matches: Some(false),
+            },
+            MigrationStatus {
+                version: 3,
+                name: "pending".to_string(),
+                applied: false,
+                applied_at: None,
+                duration_ms: None,
+                app_version: None,
+                checksum: None,
+                checksum_matches: None,
+            },
+        ];
+
+        let output = format_migration_status(&status);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "migrations:",
+                "  0001  add_accounts_table  -  -  -  ok",
+                "  0002  accounts_redef      2026-03-01 12:00:00  3ms  0.1.0  changed",
+                "  0003  pending             pending  -  -  -",
+            ]
+        );
+    }
+
+    #[test]
+    fn format_transaction_search_renders_matches() {
+        let transactions = vec![Transaction {
+            id: uuid::Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
+            statement_id: None,
+            description: Some("Blue Bottle Coffee".to_string()),
+            posted_at: "2026-01-02".to_string(),
+            created_at: "2026-01-03 00:00:00".to_string(),
+        }];
+
+        assert_eq!(
+            format_transaction_search(&transactions),
+            "transactions:\n  2026-01-02  Blue Bottle Coffee\n"
+        );
+        assert_eq!(format_transaction_search(&[]), "transactions: (none)\n");
+    }
+
+    #[test]
+    fn format_vacuum_report_renders_sizes() {
+        let before = DbSizeInfo {
+            page_size: 4096,
+            page_count: 100,
+            freelist_count: 60,
+        };
+        let after = DbSizeInfo {
+            page_size: 4096,
+            page_count: 40,
+            freelist_count: 0,
+        };
 
-        let outcome = repl.run_once("show ?").expect("completion should succeed");
         assert_eq!(
-            outcome,
-            RunOnceOutcome::Completions(vec![
-                CompletionItem {
-                    token: "accounts".to_string(),
-                    doc: Some("list accounts".to_string()),
-                },
-                CompletionItem {
-                    token: "version".to_string(),
-                    doc: Some("show tally42 and schema versions".to_string()),
-                },
-            ])
+            format_vacuum_report(&before, &after),
+            "vacuumed database: 409600 bytes -> 163840 bytes (245760 bytes reclaimed)\n"
         );
     }
 
     #[test]
-    fn create_question_lists_account_subcommand() {
-        let mut repl = build_repl().expect("repl should build");
-        repl.run_once("write").expect("enter write mode");
+    fn format_manifest_check_renders_clean_and_differences() {
+        let clean = ManifestCheck {
+            unchanged: 3,
+            ..ManifestCheck::default()
+        };
+        assert_eq!(format_manifest_check(&clean), "manifest: ok (3 files unchanged)\n");
 
-        let outcome = repl.run_once("create ?").expect("completion should succeed");
+        let dirty = ManifestCheck {
+            added: vec!["statements/dddd.pdf".into()],
+            removed: vec!["statements/cccc.pdf".into()],
+            changed: vec!["statements/aaaa.pdf".into()],
+            unchanged: 1,
+        };
         assert_eq!(
-            outcome,
-            RunOnceOutcome::Completions(vec![CompletionItem {
-                token: "account".to_string(),
-                doc: Some("create an account".to_string()),
-            }])
+            format_manifest_check(&dirty),
+            "manifest: 1 files unchanged\n  added    statements/dddd.pdf\n  \
+             removed  statements/cccc.pdf\n  changed  statements/aaaa.pdf\n"
         );
     }
 
     #[test]
-    fn create_account_question_lists_name_label() {
-        let mut repl = build_repl().expect("repl should build");
-        repl.run_once("write").expect("enter write mode");
+    fn profile_from_args_accepts_both_forms() {
+        let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(profile_from_args(&args(&[])), Ok(None));
+        assert_eq!(profile_from_args(&args(&["--profile", "club"])), Ok(Some("club")));
+        assert_eq!(profile_from_args(&args(&["--profile=club"])), Ok(Some("club")));
+        assert!(profile_from_args(&args(&["--profile"])).is_err());
+        assert!(profile_from_args(&args(&["--verbose"])).is_err());
+    }
+
+    #[test]
+    fn format_profiles_marks_active_profile() {
+        let profiles = vec![
+            Profile {
+                name: "default".to_string(),
+                data_dir: "/data/tally42".into(),
+                active: false,
+            },
+            Profile {
+                name: "club".to_string(),
+                data_dir: "/data/tally42/profiles/club".into(),
+                active: true,
+            },
+        ];
 
-        let outcome = repl
-            .run_once("create account ?")
-            .expect("completion should succeed");
         assert_eq!(
-            outcome,
-            RunOnceOutcome::Completions(vec![CompletionItem {
-                token: "name".to_string(),
-                doc: Some("set the account name".to_string()),
-            }])
+            format_profiles(&profiles),
+            "profiles:\n  default  /data/tally42\n* club     /data/tally42/profiles/club\n"
         );
     }
 
     #[test]
-    fn create_account_name_question_lists_name_placeholder() {
-        let mut repl = build_repl().expect("repl should build");
-        repl.run_once("write").expect("enter write mode");
+    fn format_undo_preview_and_result() {
+        let statement = Statement {
+            id: uuid::Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
+            institution: "Chase".to_string(),
+            account_id: uuid::Uuid::parse_str("22222222-2222-2222-2222-222222222222").unwrap(),
+            period_start: "2026-01-01".to_string(),
+            period_end: "2026-01-31".to_string(),
+            currency: "USD".to_string(),
+            file_hash: "sha256:abc".to_string(),
+            file_size: 10,
+            imported_at: "2026-02-03 10:00:00".to_string(),
+            replaced_by: None,
+        };
 
-        let outcome = repl
-            .run_once("create account name ?")
-            .expect("completion should succeed");
+        assert_eq!(format_undo_preview(None), "no statements to undo\n");
         assert_eq!(
-            outcome,
-            RunOnceOutcome::Completions(vec![CompletionItem {
-                token: "<name>".to_string(),
-                doc: Some("set the account name".to_string()),
-            }])
+            format_undo_preview(Some(&statement)),
+            "latest import: 11111111-1111-1111-1111-111111111111  Chase  2026-01-01..2026-01-31  \
+             imported 2026-02-03 10:00:00\nrun `undo-statement yes` to remove it and its files\n"
         );
+
+        let undone = UndoneStatement {
+            statement,
+            removed_files: vec!["statements/abc.pdf".into()],
+        };
+        assert!(format_undone_statement(Some(&undone)).ends_with("removed 1 statement file(s)\n"));
     }
 
     #[test]
-    fn create_account_after_name_and_currency_lists_note() {
-        let mut repl = build_repl().expect("repl should build");
-        repl.run_once("write").expect("enter write mode");
+    fn format_prune_summary_lists_chains_and_cycles() {
+        let head = Statement {
+            id: uuid::Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
+            institution: "Chase".to_string(),
+            account_id: uuid::Uuid::parse_str("22222222-2222-2222-2222-222222222222").unwrap(),
+            period_start: "2026-01-01".to_string(),
+            period_end: "2026-01-31".to_string(),
+            currency: "USD".to_string(),
+            file_hash: "sha256:abc".to_string(),
+            file_size: 10,
+            imported_at: "2026-02-03 10:00:00".to_string(),
+            replaced_by: None,
+        };
+        let older = Statement {
+            id: uuid::Uuid::parse_str("33333333-3333-3333-3333-333333333333").unwrap(),
+            ..head.clone()
+        };
 
-        let outcome = repl
-            .run_once("create account name cash currency USD ?")
-            .expect("completion should succeed");
         assert_eq!(
-            outcome,
-            RunOnceOutcome::Completions(vec![CompletionItem {
-                token: "note".to_string(),
-                doc: Some("set the account note".to_string()),
-            }])
+            format_prune_summary(&PruneSummary::default()),
+            "no superseded statements to prune\n"
+        );
+        let summary = PruneSummary {
+            chains: vec![tally42_core::PrunedChain {
+                head,
+                removed: vec![older.clone(), older.clone()],
+            }],
+            removed_files: vec!["statements/old.pdf".into()],
+            cycles: vec![vec![older.id, older.account_id]],
+        };
+        assert_eq!(
+            format_prune_summary(&summary),
+            "kept 11111111-1111-1111-1111-111111111111  Chase  2026-01-01..2026-01-31  \
+             imported 2026-02-03 10:00:00, removed 2 superseded statement(s)\n\
+             removed 1 statement file(s)\n\
+             warning: skipped statements whose replaced_by links loop: \
+             33333333-3333-3333-3333-333333333333 -> 22222222-2222-2222-2222-222222222222\n"
         );
     }
 
     #[test]
-    fn create_account_currency_question_lists_currency_placeholder() {
+    fn prune_statements_rejects_bad_keep_latest() {
         let mut repl = build_repl().expect("repl should build");
         repl.run_once("write").expect("enter write mode");
 
-        let outcome = repl
-            .run_once("create account name cash currency ?")
-            .expect("completion should succeed");
-        assert_eq!(
-            outcome,
-            RunOnceOutcome::Completions(vec![CompletionItem {
-                token: "<currency>".to_string(),
-                doc: Some("set the account currency".to_string()),
-            }])
-        );
+        for value in ["0", "many"] {
+            let outcome = repl
+                .run_once(&format!("prune-statements keep-latest {value}"))
+                .expect("run_once should succeed");
+            assert_eq!(
+                outcome,
+                RunOnceOutcome::HandlerError(HandlerError(format!(
+                    "keep-latest must be a positive number, not '{value}'"
+                )))
+            );
+        }
     }
 
     #[test]
-    fn show_accounts_command_is_registered() {
+    fn format_statements_renders_empty_state() {
+        assert_eq!(format_statements(&[]), "statements: (none)\n");
+    }
+
+    #[test]
+    fn show_statements_rejects_unknown_format() {
         let mut repl = build_repl().expect("repl should build");
 
         let outcome = repl
-            .run_once("show accounts")
+            .run_once("show statements format xml")
             .expect("run_once should succeed");
-        assert!(matches!(
+        assert_eq!(
             outcome,
-            RunOnceOutcome::ActionApplied(Action::None) | RunOnceOutcome::HandlerError(_)
-        ));
+            RunOnceOutcome::HandlerError(HandlerError(
+                "unknown format 'xml', expected csv or json".to_string()
+            ))
+        );
     }
 
     #[test]
-    fn show_version_command_is_registered() {
+    fn show_statements_rejects_invalid_imported_since() {
         let mut repl = build_repl().expect("repl should build");
 
         let outcome = repl
-            .run_once("show version")
+            .run_once("show statements imported-since yesterday")
             .expect("run_once should succeed");
-        assert!(matches!(
+        assert_eq!(
             outcome,
-            RunOnceOutcome::ActionApplied(Action::None) | RunOnceOutcome::HandlerError(_)
-        ));
+            RunOnceOutcome::HandlerError(HandlerError(
+                "invalid imported-since 'yesterday': use a duration like 7d, 24h or 3w, \
+                 or a date like 2026-01-01"
+                    .to_string()
+            ))
+        );
+    }
+
+    fn sample_coverage() -> Vec<AccountCoverage> {
+        let month = |year, month| tally42_core::YearMonth { year, month };
+        vec![
+            AccountCoverage {
+                account_id: uuid::Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
+                account_name: "checking".to_string(),
+                first: month(2026, 1),
+                last: month(2026, 4),
+                missing: vec![month(2026, 2), month(2026, 4)],
+            },
+            AccountCoverage {
+                account_id: uuid::Uuid::parse_str("22222222-2222-2222-2222-222222222222").unwrap(),
+                account_name: "card".to_string(),
+                first: month(2025, 11),
+                last: month(2026, 1),
+                missing: vec![],
+            },
+        ]
     }
 
     #[test]
-    fn create_account_command_is_registered() {
-        let mut repl = build_repl().expect("repl should build");
-        repl.run_once("write").expect("enter write mode");
+    fn format_coverage_renders_gaps_and_complete_accounts() {
+        assert_eq!(format_coverage(&[]), "coverage: (no statements)\n");
+        assert_eq!(
+            format_coverage(&sample_coverage()),
+            "coverage (monthly):\n  checking  2026-01..2026-04  missing 2026-02, 2026-04\n  \
+             card      2025-11..2026-01  complete\n"
+        );
+    }
 
-        let outcome = repl
-            .run_once("create account name cash currency USD note wallet")
-            .expect("run_once should succeed");
-        assert!(matches!(
-            outcome,
-            RunOnceOutcome::ActionApplied(Action::None) | RunOnceOutcome::HandlerError(_)
-        ));
+    #[test]
+    fn format_coverage_json_lists_missing_months() {
+        let value: serde_json::Value =
+            serde_json::from_str(&format_coverage_json(&sample_coverage())).expect("valid json");
+
+        assert_eq!(value[0]["account"], "checking");
+        assert_eq!(value[0]["missing"], serde_json::json!(["2026-02", "2026-04"]));
+        assert_eq!(value[1]["missing"], serde_json::json!([]));
     }
 
     #[test]
-    fn format_accounts_renders_empty_state() {
-        assert_eq!(format_accounts(&[]), "accounts: (none)\n");
+    fn split_check_args_finds_subcommand_after_profile() {
+        let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        let plain = args(&["--profile", "club"]);
+        assert_eq!(split_check_args(&plain), (&plain[..], None));
+
+        let check = args(&["--profile", "check", "check", "--json"]);
+        assert_eq!(split_check_args(&check), (&check[..2], Some(&check[3..])));
     }
 
     #[test]
-    fn format_accounts_renders_compact_table() {
-        let open_id = uuid::Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap();
-        let closed_id = uuid::Uuid::parse_str("22222222-2222-2222-2222-222222222222").unwrap();
+    fn check_options_from_args_parses_skips_and_months() {
+        let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
 
-        let output = format_accounts(&[
-            Account {
-                id: open_id,
-                parent_id: None,
-                name: "checking".to_string(),
-                currency: "USD".to_string(),
-                is_closed: false,
-                created_at: "2026-02-28 00:00:00".to_string(),
-                note: None,
-            },
-            Account {
-                id: closed_id,
-                parent_id: None,
-                name: "longer-savings".to_string(),
-                currency: "EUR".to_string(),
-                is_closed: true,
-                created_at: "2026-02-28 00:00:00".to_string(),
-                note: Some("archived".to_string()),
+        assert_eq!(check_options_from_args(&[]), Ok(CheckOptions::default()));
+        assert_eq!(
+            check_options_from_args(&args(&[
+                "--skip", "coverage", "--json", "--skip", "db", "--months", "6"
+            ])),
+            Ok(CheckOptions {
+                json: true,
+                skip: vec![HealthCheck::Coverage, HealthCheck::Db],
+                months: 6,
+                remove_temp_files: false,
+            })
+        );
+        assert_eq!(
+            check_options_from_args(&args(&["--remove-temp-files", "--skip", "temp-files"])),
+            Ok(CheckOptions {
+                skip: vec![HealthCheck::TempFiles],
+                remove_temp_files: true,
+                ..CheckOptions::default()
+            })
+        );
+        assert!(check_options_from_args(&args(&["--skip", "workdir"])).is_err());
+        assert!(check_options_from_args(&args(&["--months", "0"])).is_err());
+        assert!(check_options_from_args(&args(&["--skip"])).is_err());
+        assert!(check_options_from_args(&args(&["--verbose"])).is_err());
+    }
+
+    #[test]
+    fn recent_coverage_gaps_only_reports_the_last_months() {
+        let month = |year, month| YearMonth { year, month };
+        let coverage = sample_coverage();
+
+        assert_eq!(recent_coverage_gaps(&coverage, 1), vec![("checking", month(2026, 4))]);
+        assert_eq!(
+            recent_coverage_gaps(&coverage, 3),
+            vec![("checking", month(2026, 2)), ("checking", month(2026, 4))]
+        );
+    }
+
+    fn sample_check_results() -> Vec<CheckResult> {
+        vec![
+            CheckResult::new(HealthCheck::Db, CheckStatus::Pass, "schema version 7"),
+            CheckResult::new(HealthCheck::Migrations, CheckStatus::Fail, "1 pending"),
+            CheckResult::new(HealthCheck::Statements, CheckStatus::Skip, "skipped"),
+            CheckResult::new(HealthCheck::Coverage, CheckStatus::Fail, "missing card 2026-09"),
+        ]
+    }
+
+    #[test]
+    fn health_exit_code_ors_failed_checks() {
+        assert_eq!(health_exit_code(&[]), 0);
+        assert_eq!(health_exit_code(&sample_check_results()), 2 | 8);
+    }
+
+    #[test]
+    fn format_check_report_lists_each_check() {
+        assert_eq!(
+            format_check_report(&sample_check_results()),
+            "ok    db          schema version 7\n\
+             FAIL  migrations  1 pending\n\
+             skip  statements  skipped\n\
+             FAIL  coverage    missing card 2026-09\n"
+        );
+    }
+
+    #[test]
+    fn format_check_report_json_includes_exit_code() {
+        let value: serde_json::Value =
+            serde_json::from_str(&format_check_report_json(&sample_check_results()))
+                .expect("valid json");
+
+        assert_eq!(value["ok"], false);
+        assert_eq!(value["exit_code"], 10);
+        assert_eq!(value["checks"][1]["name"], "migrations");
+        assert_eq!(value["checks"][1]["status"], "fail");
+        assert_eq!(value["checks"][2]["status"], "skip");
+    }
+
+    fn health_fixture() -> (tempfile::TempDir, tally42_core::UserDataManager) {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let data_dir = temp_dir.path().join("state");
+        Core::from_data_dir(&data_dir)
+            .expect("open core")
+            .init()
+            .expect("init db");
+        let manager = tally42_core::UserDataManager::from_data_dir(&data_dir);
+        std::fs::create_dir_all(manager.statements_dir()).expect("create statements dir");
+        std::fs::write(manager.statements_dir().join("aaaa.pdf"), b"statement").expect("write");
+        manager.write_manifest().expect("write manifest");
+        (temp_dir, manager)
+    }
+
+    fn run_fixture_checks(
+        manager: &tally42_core::UserDataManager,
+        options: &CheckOptions,
+    ) -> Vec<CheckResult> {
+        const DAY: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+        run_health_checks(
+            Core::open_read_only(manager.data_dir()),
+            || manager.check_manifest().map_err(CoreError::from),
+            |remove| {
+                let stale = if remove {
+                    manager.cleanup_stale_temp_files(DAY)
+                } else {
+                    manager.stale_temp_files(DAY)
+                };
+                stale.map_err(CoreError::from)
             },
-        ]);
+            options,
+        )
+    }
+
+    #[test]
+    fn health_checks_pass_for_healthy_data_dir() {
+        let (_temp_dir, manager) = health_fixture();
+
+        let results = run_fixture_checks(&manager, &CheckOptions::default());
+
+        assert!(
+            results.iter().all(|result| result.status == CheckStatus::Pass),
+            "{results:?}"
+        );
+        assert_eq!(health_exit_code(&results), 0);
+    }
+
+    #[test]
+    fn health_checks_fail_db_without_creating_it() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let data_dir = temp_dir.path().join("state");
+        let manager = tally42_core::UserDataManager::from_data_dir(&data_dir);
+
+        let results = run_fixture_checks(&manager, &CheckOptions::default());
+
+        assert_eq!(results[0].check, HealthCheck::Db);
+        assert_eq!(results[0].status, CheckStatus::Fail);
+        assert!(results[0].detail.contains("database does not exist"), "{results:?}");
+        assert!(!data_dir.exists());
+    }
+
+    #[test]
+    fn health_checks_fail_migrations_when_pending() {
+        let (_temp_dir, manager) = health_fixture();
+        rusqlite::Connection::open(manager.db_path())
+            .expect("open db")
+            .execute(
+                "DELETE FROM schema_migrations \
+                 WHERE version = (SELECT MAX(version) FROM schema_migrations)",
+                [],
+            )
+            .expect("forget latest migration");
+
+        let results = run_fixture_checks(&manager, &CheckOptions::default());
+
+        let migrations = results
+            .iter()
+            .find(|result| result.check == HealthCheck::Migrations)
+            .expect("migrations result");
+        assert_eq!(migrations.status, CheckStatus::Fail);
+        assert_eq!(migrations.detail, "1 pending, 0 changed since applied");
+    }
+
+    #[test]
+    fn health_checks_fail_statements_for_missing_file() {
+        let (_temp_dir, manager) = health_fixture();
+        std::fs::remove_file(manager.statements_dir().join("aaaa.pdf")).expect("remove file");
 
+        let results = run_fixture_checks(&manager, &CheckOptions::default());
+        assert_eq!(health_exit_code(&results), 4);
         assert_eq!(
-            output,
-            "accounts:\n  checking        USD  open\n  longer-savings  EUR  closed\n"
+            results[2],
+            CheckResult::new(
+                HealthCheck::Statements,
+                CheckStatus::Fail,
+                "0 added, 1 removed, 0 changed since the manifest"
+            )
         );
+
+        let skipped = CheckOptions {
+            skip: vec![HealthCheck::Statements],
+            ..CheckOptions::default()
+        };
+        let results = run_fixture_checks(&manager, &skipped);
+        assert_eq!(health_exit_code(&results), 0);
+        assert_eq!(results[2].status, CheckStatus::Skip);
     }
 
     #[test]
-    fn format_created_account_renders_compact_summary() {
-        let account = Account {
-            id: uuid::Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
-            parent_id: None,
-            name: "cash".to_string(),
-            currency: "USD".to_string(),
-            is_closed: false,
-            created_at: "2026-02-28 00:00:00".to_string(),
-            note: Some("wallet".to_string()),
+    fn health_checks_report_and_remove_stale_temp_files() {
+        let (_temp_dir, manager) = health_fixture();
+        let stale = manager.statements_dir().join(".tmp-statement-crashed");
+        std::fs::write(&stale, b"partial").expect("write temp file");
+        let two_days_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(2 * 86400);
+        std::fs::File::options()
+            .write(true)
+            .open(&stale)
+            .expect("open temp file")
+            .set_modified(two_days_ago)
+            .expect("backdate temp file");
+
+        let results = run_fixture_checks(&manager, &CheckOptions::default());
+        assert_eq!(health_exit_code(&results), 64);
+        assert_eq!(
+            results[6],
+            CheckResult::new(
+                HealthCheck::TempFiles,
+                CheckStatus::Fail,
+                format!("stale {}; run tally42 check --remove-temp-files", stale.display())
+            )
+        );
+        assert!(stale.is_file());
+
+        let removing = CheckOptions {
+            remove_temp_files: true,
+            ..CheckOptions::default()
         };
+        let results = run_fixture_checks(&manager, &removing);
+        assert_eq!(health_exit_code(&results), 0);
+        assert_eq!(results[6].detail, format!("removed {}", stale.display()));
+        assert!(!stale.exists());
+    }
 
-        assert_eq!(format_created_account(&account), "created account cash (USD)\n");
+    #[test]
+    fn format_error_chain_indents_each_cause_of_a_failed_migration() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let data_dir = temp_dir.path().join("state");
+        std::fs::create_dir_all(&data_dir).expect("create data dir");
+        let conn = rusqlite::Connection::open(data_dir.join("tally42.db")).expect("open db");
+        conn.execute_batch("CREATE TABLE accounts (id TEXT)")
+            .expect("create conflicting table");
+        drop(conn);
+
+        let err = match Core::from_data_dir(&data_dir) {
+            Ok(_) => panic!("migrations should fail"),
+            Err(err) => err,
+        };
+
+        let chain = format_error_chain(&err);
+        let lines: Vec<&str> = chain.lines().collect();
+        assert_eq!(
+            lines[..4],
+            [
+                "failed to initialize core",
+                "  caused by: failed to initialize sqlite database",
+                "    caused by: failed to run embedded migrations",
+                "      caused by: sqlite error while running migrations",
+            ]
+        );
+        assert!(
+            lines[4].starts_with("        caused by: table accounts already exists"),
+            "{chain}"
+        );
     }
 
     #[test]
-    fn format_version_info_renders_expected_output() {
-        let info = VersionInfo {
-            app_version: "0.1.0".to_string(),
-            schema_version: 4,
-            data_dir: std::path::PathBuf::from("/tmp/tally42"),
+    fn format_histogram_scales_bars_to_the_largest_bucket() {
+        let bucket = |lower, upper, count, total| AmountBucket {
+            lower,
+            upper,
+            count,
+            total,
         };
+        let buckets = [
+            bucket(0, Some(1000), 4, 1200),
+            bucket(1000, Some(2500), 2, 3600),
+            bucket(2500, None, 0, 0),
+        ];
 
         assert_eq!(
-            format_version_info(&info),
-            "tally42 version: 0.1.0\ndb schema version: 4\ndata dir: /tmp/tally42\n"
+            format_histogram("USD", &buckets, 2),
+            "histogram USD (6 transactions):\n   \
+             0.00..10.00  4  12.00  ##############################\n  \
+             10.00..25.00  2  36.00  ###############\n       \
+             > 25.00  0   0.00\n"
+        );
+        assert_eq!(
+            format_histogram("JPY", &buckets[..1], 0),
+            "histogram JPY (4 transactions):\n  0..1000  4  1200  ##############################\n"
+        );
+        assert_eq!(format_histogram("USD", &[], 2), "histogram USD: (no transactions)\n");
+    }
+
+    #[test]
+    fn health_checks_fail_currency_for_mismatched_statement() {
+        let (_temp_dir, manager) = health_fixture();
+        let db = manager.open_db().expect("open db");
+        let account_id = uuid::Uuid::new_v4();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        db.create_statement(
+            uuid::Uuid::new_v4(),
+            &AddStatementInput {
+                institution: "Chase".to_string(),
+                account_id,
+                period_start: "2026-01-01".to_string(),
+                period_end: "2026-01-31".to_string(),
+                currency: "EUR".to_string(),
+                replaced_by: None,
+                allow_currency_mismatch: true,
+            },
+            "sha256:eur",
+            1,
+        )
+        .expect("create statement");
+        drop(db);
+
+        let options = CheckOptions {
+            skip: vec![HealthCheck::Coverage],
+            ..CheckOptions::default()
+        };
+        let results = run_fixture_checks(&manager, &options);
+
+        assert_eq!(health_exit_code(&results), 16);
+        assert_eq!(results[4].check, HealthCheck::Currency);
+        assert!(results[4].detail.contains("EUR on USD account checking"));
+    }
+
+    #[test]
+    fn health_checks_fail_periods_for_legacy_rows() {
+        let (_temp_dir, manager) = health_fixture();
+        let db = manager.open_db().expect("open db");
+        let account_id = uuid::Uuid::new_v4();
+        db.create_account(account_id, None, "checking", "USD", None)
+            .expect("create account");
+        let statement_id = uuid::Uuid::new_v4();
+        db.create_statement(
+            statement_id,
+            &AddStatementInput {
+                institution: "Chase".to_string(),
+                account_id,
+                period_start: "2026-01-01".to_string(),
+                period_end: "2026-01-31".to_string(),
+                currency: "USD".to_string(),
+                replaced_by: None,
+                allow_currency_mismatch: false,
+            },
+            "sha256:period",
+            1,
+        )
+        .expect("create statement");
+        drop(db);
+        rusqlite::Connection::open(manager.db_path())
+            .expect("open sqlite")
+            .execute(
+                "UPDATE statements SET period_end = '01/31/2026' WHERE id = ?1",
+                [statement_id.to_string()],
+            )
+            .expect("store legacy period");
+
+        let options = CheckOptions {
+            skip: vec![HealthCheck::Coverage],
+            ..CheckOptions::default()
+        };
+        let results = run_fixture_checks(&manager, &options);
+
+        assert_eq!(health_exit_code(&results), 32);
+        assert_eq!(results[5].check, HealthCheck::Periods);
+        assert_eq!(
+            results[5].detail,
+            format!("invalid {statement_id} 2026-01-01..01/31/2026; run fix-periods in write mode")
         );
     }
+
+    #[test]
+    fn format_period_fixes_previews_and_reports_rewrites() {
+        let period = |id: &str, start: &str, end: &str, normalized: Option<(&str, &str)>| {
+            InvalidStatementPeriod {
+                statement_id: uuid::Uuid::parse_str(id).unwrap(),
+                period_start: start.to_string(),
+                period_end: end.to_string(),
+                normalized: normalized.map(|(start, end)| (start.to_string(), end.to_string())),
+            }
+        };
+        let periods = [
+            period(
+                "11111111-1111-1111-1111-111111111111",
+                "01/01/2026",
+                "01/31/2026",
+                Some(("2026-01-01", "2026-01-31")),
+            ),
+            period("22222222-2222-2222-2222-222222222222", "2026-01-31", "soon", None),
+        ];
+
+        assert_eq!(format_period_fixes(&[], false), "statement periods are valid\n");
+        assert_eq!(
+            format_period_fixes(&periods, false),
+            "would rewrite 11111111-1111-1111-1111-111111111111: \
+             01/01/2026..01/31/2026 -> 2026-01-01..2026-01-31\n\
+             cannot read 22222222-2222-2222-2222-222222222222: 2026-01-31..soon; \
+             fix it by hand\n\
+             run `fix-periods yes` to rewrite 1 statement period(s)\n"
+        );
+        assert!(format_period_fixes(&periods, true).starts_with(
+            "rewrote 11111111-1111-1111-1111-111111111111: "
+        ));
+        assert!(!format_period_fixes(&periods, true).contains("fix-periods yes"));
+    }
 }
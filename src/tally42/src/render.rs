@@ -0,0 +1,231 @@
+use serde_json::Value;
+use tally42_core::{Account, Statement};
+
+/// Machine-readable output formats shared by the listing commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Csv,
+    Json,
+}
+
+impl OutputFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// A row type that can be rendered with its full column set.
+pub trait Tabular {
+    fn headers() -> &'static [&'static str];
+    /// One value per header, in the same order.
+    fn values(&self) -> Vec<Value>;
+}
+
+impl Tabular for Account {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "id",
+            "parent_id",
+            "name",
+            "currency",
+            "is_closed",
+            "created_at",
+            "note",
+        ]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            Value::from(self.id.to_string()),
+            self.parent_id.map(|id| id.to_string()).into(),
+            Value::from(self.name.as_str()),
+            Value::from(self.currency.as_str()),
+            Value::from(self.is_closed),
+            Value::from(self.created_at.as_str()),
+            self.note.as_deref().into(),
+        ]
+    }
+}
+
+impl Tabular for Statement {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "id",
+            "institution",
+            "account_id",
+            "period_start",
+            "period_end",
+            "currency",
+            "file_hash",
+            "file_size",
+            "imported_at",
+            "replaced_by",
+        ]
+    }
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            Value::from(self.id.to_string()),
+            Value::from(self.institution.as_str()),
+            Value::from(self.account_id.to_string()),
+            Value::from(self.period_start.as_str()),
+            Value::from(self.period_end.as_str()),
+            Value::from(self.currency.as_str()),
+            Value::from(self.file_hash.as_str()),
+            Value::from(self.file_size),
+            Value::from(self.imported_at.as_str()),
+            self.replaced_by.map(|id| id.to_string()).into(),
+        ]
+    }
+}
+
+pub fn render<T: Tabular>(rows: &[T], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Csv => render_csv(rows),
+        OutputFormat::Json => render_json(rows),
+    }
+}
+
+fn render_csv<T: Tabular>(rows: &[T]) -> String {
+    let mut out = csv_line(T::headers().iter().map(|header| header.to_string()));
+    for row in rows {
+        out.push_str(&csv_line(row.values().iter().map(csv_field)));
+    }
+    out
+}
+
+fn render_json<T: Tabular>(rows: &[T]) -> String {
+    let objects: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let object = T::headers()
+                .iter()
+                .map(|header| header.to_string())
+                .zip(row.values())
+                .collect();
+            Value::Object(object)
+        })
+        .collect();
+    format!("{}\n", Value::Array(objects))
+}
+
+fn csv_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Join fields per RFC 4180, quoting any field containing a comma, quote, or newline.
+fn csv_line(fields: impl Iterator<Item = String>) -> String {
+    let fields: Vec<String> = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    format!("{}\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn sample_accounts() -> Vec<Account> {
+        vec![
+            Account {
+                id: Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
+                parent_id: None,
+                name: "checking".to_string(),
+                currency: "USD".to_string(),
+                is_closed: false,
+                created_at: "2026-01-01 00:00:00".to_string(),
+                note: None,
+            },
+            Account {
+                id: Uuid::parse_str("22222222-2222-2222-2222-222222222222").unwrap(),
+                parent_id: Some(Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap()),
+                name: "bills, \"misc\"".to_string(),
+                currency: "EUR".to_string(),
+                is_closed: true,
+                created_at: "2026-01-02 00:00:00".to_string(),
+                note: Some("line one\nline two".to_string()),
+            },
+        ]
+    }
+
+    fn sample_statements() -> Vec<Statement> {
+        vec![Statement {
+            id: Uuid::parse_str("33333333-3333-3333-3333-333333333333").unwrap(),
+            institution: "Chase".to_string(),
+            account_id: Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap(),
+            period_start: "2026-01-01".to_string(),
+            period_end: "2026-01-31".to_string(),
+            currency: "USD".to_string(),
+            file_hash: "sha256:abc".to_string(),
+            file_size: 4096,
+            imported_at: "2026-02-01 09:00:00".to_string(),
+            replaced_by: None,
+        }]
+    }
+
+    #[test]
+    fn output_format_parses_known_names() {
+        assert_eq!(OutputFormat::parse("csv"), Some(OutputFormat::Csv));
+        assert_eq!(OutputFormat::parse("json"), Some(OutputFormat::Json));
+        assert_eq!(OutputFormat::parse("table"), None);
+    }
+
+    #[test]
+    fn render_accounts_csv_escapes_fields() {
+        assert_eq!(
+            render(&sample_accounts(), OutputFormat::Csv),
+            "id,parent_id,name,currency,is_closed,created_at,note\n\
+             11111111-1111-1111-1111-111111111111,,checking,USD,false,2026-01-01 00:00:00,\n\
+             22222222-2222-2222-2222-222222222222,11111111-1111-1111-1111-111111111111,\
+             \"bills, \"\"misc\"\"\",EUR,true,2026-01-02 00:00:00,\"line one\nline two\"\n"
+        );
+    }
+
+    #[test]
+    fn render_accounts_json_keeps_types() {
+        assert_eq!(
+            render(&sample_accounts()[..1], OutputFormat::Json),
+            "[{\"created_at\":\"2026-01-01 00:00:00\",\"currency\":\"USD\",\
+             \"id\":\"11111111-1111-1111-1111-111111111111\",\"is_closed\":false,\
+             \"name\":\"checking\",\"note\":null,\"parent_id\":null}]\n"
+        );
+    }
+
+    #[test]
+    fn render_statements_csv() {
+        assert_eq!(
+            render(&sample_statements(), OutputFormat::Csv),
+            "id,institution,account_id,period_start,period_end,currency,file_hash,file_size,\
+             imported_at,replaced_by\n\
+             33333333-3333-3333-3333-333333333333,Chase,11111111-1111-1111-1111-111111111111,\
+             2026-01-01,2026-01-31,USD,sha256:abc,4096,2026-02-01 09:00:00,\n"
+        );
+    }
+
+    #[test]
+    fn render_statements_json() {
+        assert_eq!(
+            render(&sample_statements(), OutputFormat::Json),
+            "[{\"account_id\":\"11111111-1111-1111-1111-111111111111\",\"currency\":\"USD\",\
+             \"file_hash\":\"sha256:abc\",\"file_size\":4096,\
+             \"id\":\"33333333-3333-3333-3333-333333333333\",\
+             \"imported_at\":\"2026-02-01 09:00:00\",\"institution\":\"Chase\",\
+             \"period_end\":\"2026-01-31\",\"period_start\":\"2026-01-01\",\"replaced_by\":null}]\n"
+        );
+    }
+}
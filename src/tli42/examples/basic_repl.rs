@@ -1,32 +1,59 @@
-use tli42::cmd::CmdBuilder;
 use tli42::repl::{Action, Repl};
 
 fn main() -> std::io::Result<()> {
-    let mut repl = Repl::new();
-
-    let mut hello = CmdBuilder::new();
-    hello.literals(&["hello"]).positional_args(1);
-    let hello_cmd = hello.build();
-    repl.register_mode_command(
-        0,
-        &hello_cmd,
-        Box::new(|_, inputs| {
-            let name = inputs
-                .positionals
-                .first()
-                .map(String::as_str)
-                .unwrap_or("world");
-            println!("hello, {}", name);
+    let mut repl = Repl::builder()
+        .command("hello <name>", "greet someone by name", |ctx| {
+            let name = ctx.positional(0).unwrap_or_default();
+            ctx.println(format_args!("hello, {}", name));
             Ok(Action::None)
-        }),
-    )
-    .expect("register hello command");
+        })
+        .persist_history(true)
+        .build()
+        .expect("register commands");
+    if let Some(path) = Repl::default_alias_file()
+        && let Err(err) = repl.set_alias_file(&path)
+    {
+        eprintln!("warning: failed to load aliases {}: {}", path.display(), err);
+    }
+
+    let mut command = None;
+    let mut script = None;
+    let mut keep_going = false;
+    let mut rc_file = Repl::default_rc_file();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" => command = args.next(),
+            "--keep-going" => keep_going = true,
+            "--timings" => repl.set_timings(true),
+            "--width" => repl.set_terminal_width(args.next().and_then(|width| width.parse().ok())),
+            "--rcfile" => rc_file = args.next().map(Into::into),
+            "--norc" => rc_file = None,
+            "--prompt" => {
+                let template = args.next().unwrap_or_default();
+                if let Err(err) = repl.set_prompt_template(&template) {
+                    eprintln!("warning: ignoring prompt template: {}", err);
+                }
+            }
+            _ => script = Some(arg),
+        }
+    }
 
-    let mut quit = CmdBuilder::new();
-    quit.literals(&["quit"]);
-    let quit_cmd = quit.build();
-    repl.register_mode_command(0, &quit_cmd, Box::new(|_, _| Ok(Action::Exit)))
-        .expect("register quit command");
+    if let Some(command) = command {
+        let status = repl.run_command(&command)?;
+        std::process::exit(status);
+    }
+    if let Some(script) = script {
+        let status = repl.run_file(std::path::Path::new(&script), keep_going)?;
+        std::process::exit(status);
+    }
 
-    repl.run()
+    // Like a shell, only interactive sessions read the rc file.
+    if let Some(path) = rc_file
+        && let Err(err) = repl.load_rc_file(&path)
+    {
+        eprintln!("warning: failed to read {}: {}", path.display(), err);
+    }
+    let status = repl.run()?;
+    std::process::exit(status);
 }
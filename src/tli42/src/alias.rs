@@ -0,0 +1,367 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const ALIAS_FILE_NAME: &str = "aliases.toml";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum AliasError {
+    InvalidName(String),
+    Reserved(String),
+    EmptyExpansion(String),
+    Recursive(String),
+    NotFound(String),
+}
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidName(name) => write!(f, "invalid alias name '{}'", name),
+            Self::Reserved(name) => write!(f, "'{}' is a built-in command", name),
+            Self::EmptyExpansion(name) => write!(f, "alias '{}' needs an expansion", name),
+            Self::Recursive(name) => write!(f, "alias '{}' would expand to itself", name),
+            Self::NotFound(name) => write!(f, "no alias named '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for AliasError {}
+
+/// Names that expand to the leading tokens of another command line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Aliases {
+    expansions: BTreeMap<String, Vec<String>>,
+}
+
+impl Aliases {
+    /// Define or replace `name`, rejecting definitions that would loop.
+    pub(crate) fn define(
+        &mut self,
+        name: &str,
+        expansion: Vec<String>,
+        reserved: &[&str],
+    ) -> Result<(), AliasError> {
+        if !is_valid_name(name) {
+            return Err(AliasError::InvalidName(name.to_string()));
+        }
+        if reserved.contains(&name) {
+            return Err(AliasError::Reserved(name.to_string()));
+        }
+        if expansion.is_empty() {
+            return Err(AliasError::EmptyExpansion(name.to_string()));
+        }
+
+        let mut head = expansion[0].as_str();
+        let mut seen = BTreeSet::new();
+        loop {
+            if head == name {
+                return Err(AliasError::Recursive(name.to_string()));
+            }
+            if !seen.insert(head) {
+                break;
+            }
+            match self.expansions.get(head) {
+                Some(next) => head = next[0].as_str(),
+                None => break,
+            }
+        }
+
+        self.expansions.insert(name.to_string(), expansion);
+        Ok(())
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> Result<(), AliasError> {
+        self.expansions
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| AliasError::NotFound(name.to_string()))
+    }
+
+    /// Replace a leading alias in `tokens`, repeatedly, keeping any trailing
+    /// arguments.
+    pub(crate) fn expand(&self, mut tokens: Vec<String>) -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        while let Some(expansion) = tokens.first().and_then(|head| self.expansions.get(head)) {
+            if !seen.insert(tokens[0].clone()) {
+                break;
+            }
+            tokens.splice(..1, expansion.iter().cloned());
+        }
+        tokens
+    }
+
+    /// One `name = expansion` line per alias, sorted by name.
+    pub(crate) fn format(&self) -> String {
+        self.expansions
+            .iter()
+            .map(|(name, expansion)| format!("{} = {}\n", name, join_tokens(expansion)))
+            .collect()
+    }
+
+    /// Read aliases saved by `save_file`. A missing file is treated as empty.
+    ///
+    /// A file that is not valid TOML is reported as `InvalidData` without loading
+    /// anything. Otherwise every well-formed alias is loaded even if others are not,
+    /// and the first malformed one is reported as `InvalidData`.
+    pub(crate) fn load_file(&mut self, path: &Path) -> io::Result<()> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        let invalid = |location: String, message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}{}: {}", path.display(), location, message),
+            )
+        };
+        let saved: BTreeMap<String, String> = toml::from_str(&text).map_err(|err| {
+            let line = err
+                .span()
+                .map_or(1, |span| text[..span.start].matches('\n').count() + 1);
+            invalid(format!(":{line}"), err.message().to_string())
+        })?;
+
+        let mut first_error = None;
+        for (name, expansion) in saved {
+            match crate::repl::parse_tokens(&expansion) {
+                Some(expansion) if !expansion.is_empty() && is_valid_name(&name) => {
+                    self.expansions.insert(name, expansion);
+                }
+                _ => {
+                    first_error.get_or_insert_with(|| {
+                        invalid(String::new(), format!("malformed alias '{name}'"))
+                    });
+                }
+            }
+        }
+
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Write every alias as a TOML `name = "expansion"` pair, replacing the file
+    /// atomically.
+    pub(crate) fn save_file(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let saved: BTreeMap<&str, String> = self
+            .expansions
+            .iter()
+            .map(|(name, expansion)| (name.as_str(), join_tokens(expansion)))
+            .collect();
+        let text = toml::to_string(&saved).map_err(io::Error::other)?;
+
+        let tmp = path.with_extension("toml.tmp");
+        std::fs::write(&tmp, text)?;
+        std::fs::rename(&tmp, path)
+    }
+}
+
+#[cfg(test)]
+impl Aliases {
+    fn contains(&self, name: &str) -> bool {
+        self.expansions.contains_key(name)
+    }
+}
+
+/// Describe how `after` differs from `before`, one line per kind of change,
+/// or nothing if they are the same.
+pub(crate) fn format_changes(before: &Aliases, after: &Aliases) -> String {
+    let mut added = Vec::new();
+    let mut updated = Vec::new();
+    for (name, expansion) in &after.expansions {
+        match before.expansions.get(name) {
+            None => added.push(name.as_str()),
+            Some(previous) if previous != expansion => updated.push(name.as_str()),
+            Some(_) => {}
+        }
+    }
+    let removed = before
+        .expansions
+        .keys()
+        .filter(|name| !after.expansions.contains_key(*name))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+
+    [("added", added), ("removed", removed), ("updated", updated)]
+        .into_iter()
+        .filter(|(_, names)| !names.is_empty())
+        .map(|(kind, names)| format!("aliases {}: {}\n", kind, names.join(", ")))
+        .collect()
+}
+
+pub(crate) fn default_alias_path() -> Option<PathBuf> {
+    crate::history::data_dir().map(|dir| dir.join(ALIAS_FILE_NAME))
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Join tokens for display, quoting any that would otherwise split.
+fn join_tokens(tokens: &[String]) -> String {
+    tokens
+        .iter()
+        .map(|token| crate::repl::quote_token(token))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn expand_keeps_trailing_arguments() {
+        let mut aliases = Aliases::default();
+        aliases.define("gs", tokens("git status"), &[]).unwrap();
+
+        assert_eq!(
+            aliases.expand(tokens("gs --short")),
+            tokens("git status --short")
+        );
+        assert_eq!(aliases.expand(tokens("git gs")), tokens("git gs"));
+        assert_eq!(aliases.expand(Vec::new()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn expand_follows_chained_aliases() {
+        let mut aliases = Aliases::default();
+        aliases.define("g", tokens("git"), &[]).unwrap();
+        aliases.define("gs", tokens("g status"), &[]).unwrap();
+
+        assert_eq!(aliases.expand(tokens("gs")), tokens("git status"));
+    }
+
+    #[test]
+    fn define_rejects_recursion_reserved_and_invalid_names() {
+        let mut aliases = Aliases::default();
+        aliases.define("a", tokens("b x"), &[]).unwrap();
+        aliases.define("b", tokens("c"), &[]).unwrap();
+
+        assert_eq!(
+            aliases.define("c", tokens("a"), &[]),
+            Err(AliasError::Recursive("c".to_string()))
+        );
+        assert_eq!(
+            aliases.define("d", tokens("d"), &[]),
+            Err(AliasError::Recursive("d".to_string()))
+        );
+        assert_eq!(
+            aliases.define("exit", tokens("quit"), &["exit"]),
+            Err(AliasError::Reserved("exit".to_string()))
+        );
+        assert_eq!(
+            aliases.define("a=b", tokens("quit"), &[]),
+            Err(AliasError::InvalidName("a=b".to_string()))
+        );
+        assert_eq!(
+            aliases.define("e", Vec::new(), &[]),
+            Err(AliasError::EmptyExpansion("e".to_string()))
+        );
+        assert!(!aliases.contains("c"));
+    }
+
+    #[test]
+    fn remove_reports_missing_alias() {
+        let mut aliases = Aliases::default();
+        aliases.define("gs", tokens("git status"), &[]).unwrap();
+
+        assert_eq!(aliases.remove("gs"), Ok(()));
+        assert_eq!(
+            aliases.remove("gs"),
+            Err(AliasError::NotFound("gs".to_string()))
+        );
+    }
+
+    #[test]
+    fn format_changes_lists_added_removed_and_updated_names() {
+        let mut before = Aliases::default();
+        before.define("gs", tokens("git status"), &[]).unwrap();
+        before.define("gl", tokens("git log"), &[]).unwrap();
+        before.define("gd", tokens("git diff"), &[]).unwrap();
+        let mut after = before.clone();
+
+        assert_eq!(format_changes(&before, &after), "");
+
+        after.remove("gl").unwrap();
+        after.define("gd", tokens("git diff --stat"), &[]).unwrap();
+        after.define("gb", tokens("git branch"), &[]).unwrap();
+        after.define("gc", tokens("git commit"), &[]).unwrap();
+        assert_eq!(
+            format_changes(&before, &after),
+            "aliases added: gb, gc\naliases removed: gl\naliases updated: gd\n"
+        );
+    }
+
+    #[test]
+    fn save_and_load_round_trip_quoted_tokens() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(ALIAS_FILE_NAME);
+        let mut aliases = Aliases::default();
+        aliases.define("gs", tokens("git status"), &[]).unwrap();
+        aliases
+            .define(
+                "note",
+                vec![
+                    "create".to_string(),
+                    "note".to_string(),
+                    "a \\ b".to_string(),
+                ],
+                &[],
+            )
+            .unwrap();
+
+        aliases.save_file(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "gs = \"git status\"\nnote = 'create note \"a \\\\ b\"'\n"
+        );
+
+        let mut loaded = Aliases::default();
+        loaded.load_file(&path).unwrap();
+        assert_eq!(loaded, aliases);
+        assert_eq!(
+            loaded.format(),
+            "gs = git status\nnote = create note \"a \\\\ b\"\n"
+        );
+    }
+
+    #[test]
+    fn load_file_keeps_good_aliases_and_reports_malformed_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(ALIAS_FILE_NAME);
+        std::fs::write(
+            &path,
+            "# saved aliases\ngs = \"git status\"\nx = \"\"\n\"a b\" = \"c\"\n",
+        )
+        .unwrap();
+
+        let mut aliases = Aliases::default();
+        let err = aliases.load_file(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().ends_with(": malformed alias 'a b'"));
+        assert!(aliases.contains("gs"));
+        assert!(!aliases.contains("x"));
+
+        std::fs::write(&path, "gs = \"git status\"\nbroken\n").unwrap();
+        let mut aliases = Aliases::default();
+        let err = aliases.load_file(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().ends_with(":2: expected `.`, `=`"));
+        assert!(!aliases.contains("gs"));
+
+        let mut missing = Aliases::default();
+        missing.load_file(&dir.path().join("missing.toml")).unwrap();
+        assert_eq!(missing, Aliases::default());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::cmd::{Cmd, CmdBuilder};
+use crate::repl::{CommandInputs, HandlerResult, Repl, ReplError};
+use std::fmt;
+use std::io;
+
+/// A handler registered through `ReplBuilder::command`.
+pub type ContextHandler = Box<dyn FnMut(&mut CommandContext<'_>) -> HandlerResult>;
+
+/// What a `ReplBuilder` handler gets to work with: the command's arguments and
+/// the REPL's output.
+///
+/// Printing through the context rather than to stdout directly lets the
+/// output be redirected with `ReplBuilder::output`, and paged when it is
+/// longer than the terminal. It is shown once the handler returns.
+pub struct CommandContext<'a> {
+    inputs: &'a CommandInputs,
+    output: &'a mut dyn io::Write,
+}
+
+impl<'a> CommandContext<'a> {
+    pub(crate) fn new(inputs: &'a CommandInputs, output: &'a mut dyn io::Write) -> Self {
+        Self { inputs, output }
+    }
+
+    pub fn inputs(&self) -> &'a CommandInputs {
+        self.inputs
+    }
+
+    /// The `idx`th `<placeholder>` argument, counting from 0.
+    pub fn positional(&self, idx: usize) -> Option<&'a str> {
+        self.inputs.positionals.get(idx).map(String::as_str)
+    }
+
+    /// Write `text` to the REPL's output. Write errors are ignored, as they
+    /// are when stdout is closed.
+    pub fn print(&mut self, text: impl fmt::Display) {
+        let _ = write!(self.output, "{}", text);
+    }
+
+    /// Write `text` and a newline to the REPL's output.
+    pub fn println(&mut self, text: impl fmt::Display) {
+        let _ = writeln!(self.output, "{}", text);
+    }
+}
+
+/// Builds a `Repl` from command phrases, for applications that do not need
+/// modes or the lower-level `CmdBuilder`.
+///
+/// A phrase is a space-separated list of literal words and `<name>`
+/// placeholders, e.g. `"user add <name>"`.
+pub struct ReplBuilder {
+    commands: Vec<(String, String, ContextHandler)>,
+    prompt: Option<String>,
+    persist_history: bool,
+    output: Option<Box<dyn io::Write>>,
+}
+
+impl ReplBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+            prompt: None,
+            persist_history: false,
+            output: None,
+        }
+    }
+
+    /// Register `phrase` in the root mode, documented as `doc`.
+    pub fn command(
+        mut self,
+        phrase: &str,
+        doc: &str,
+        handler: impl FnMut(&mut CommandContext<'_>) -> HandlerResult + 'static,
+    ) -> Self {
+        self.commands
+            .push((phrase.to_string(), doc.to_string(), Box::new(handler)));
+        self
+    }
+
+    /// Use `template` for the prompt; see `Repl::set_prompt_template`.
+    pub fn prompt(mut self, template: &str) -> Self {
+        self.prompt = Some(template.to_string());
+        self
+    }
+
+    /// Load and append to `Repl::default_history_file`. Off by default.
+    pub fn persist_history(mut self, enabled: bool) -> Self {
+        self.persist_history = enabled;
+        self
+    }
+
+    /// Send what handlers print to `output` instead of stdout.
+    pub fn output(mut self, output: impl io::Write + 'static) -> Self {
+        self.output = Some(Box::new(output));
+        self
+    }
+
+    pub fn build(self) -> Result<Repl, ReplError> {
+        let mut repl = Repl::new();
+        if let Some(template) = &self.prompt {
+            repl.set_prompt_template(template)?;
+        }
+        if let Some(output) = self.output {
+            repl.set_output(output);
+        }
+        if self.persist_history
+            && let Some(path) = Repl::default_history_file()
+            && let Err(err) = repl.set_history_file(&path)
+        {
+            eprintln!(
+                "warning: failed to load history {}: {}",
+                path.display(),
+                err
+            );
+        }
+
+        for (phrase, doc, mut handler) in self.commands {
+            repl.register_mode_command(
+                0,
+                &phrase_cmd(&phrase, Some(&doc)),
+                Box::new(move |repl, inputs| {
+                    let mut buffer = Vec::new();
+                    let result = handler(&mut CommandContext::new(inputs, &mut buffer));
+                    repl.write_output(&buffer);
+                    result
+                }),
+            )?;
+        }
+        Ok(repl)
+    }
+
+    /// Build the REPL and run it until the user exits, returning the last
+    /// command's status.
+    pub fn run(self) -> io::Result<i32> {
+        self.build()
+            .map_err(|e| io::Error::other(format!("repl build error: {:?}", e)))?
+            .run()
+    }
+}
+
+/// The command for `phrase`, with `doc` on its last literal word and on the
+/// command itself.
+pub(crate) fn phrase_cmd(phrase: &str, doc: Option<&str>) -> Cmd {
+    let words = phrase.split_whitespace().collect::<Vec<_>>();
+    let last_literal = words.iter().rposition(|word| !is_placeholder(word));
+    let mut builder = CmdBuilder::new();
+    for (idx, word) in words.iter().enumerate() {
+        if is_placeholder(word) {
+            builder.positional_arg(&word[1..word.len() - 1]);
+        } else if let (Some(doc), true) = (doc, Some(idx) == last_literal) {
+            builder.literal_with_doc(word, doc);
+        } else {
+            builder.literals(&[word]);
+        }
+    }
+    if let Some(doc) = doc {
+        builder.command_doc(doc);
+    }
+    builder.build()
+}
+
+pub(crate) fn is_placeholder(word: &str) -> bool {
+    word.len() > 2 && word.starts_with('<') && word.ends_with('>')
+}
@@ -108,6 +108,14 @@ impl CmdBuilder {
         self
     }
 
+    pub fn positional_arg(&mut self, name: &str) -> &mut Self {
+        self.cmd.exprs.push(Expr::Sequence(vec![Atom::Var {
+            name: Some(name.to_string()),
+            doc: None,
+        }]));
+        self
+    }
+
     pub fn positional_arg_with_doc(&mut self, name: &str, doc: impl Into<String>) -> &mut Self {
         self.cmd.exprs.push(Expr::Sequence(vec![Atom::Var {
             name: Some(name.to_string()),
@@ -1,9 +1,15 @@
+#[cfg(feature = "rustyline")]
+use crate::panel;
+#[cfg(feature = "rustyline")]
+use crate::repl::TabCompletion;
 use crate::repl::{CompletionItem, CompletionSnapshot, format_completions};
+use crate::term::terminal_width;
 use std::io::{self, IsTerminal, Write};
 
 pub(crate) enum EditorRead {
     Line(String),
-    Interrupted,
+    /// Ctrl-C; `empty_line` is whether nothing had been typed yet.
+    Interrupted { empty_line: bool },
     Eof,
 }
 
@@ -50,7 +56,7 @@ impl LineEditor for BasicEditor {
     }
 
     fn print_completions(&mut self, items: &[CompletionItem]) -> io::Result<()> {
-        write!(self.stdout, "{}", format_completions(items))?;
+        write!(self.stdout, "{}", format_completions(items, terminal_width()))?;
         self.stdout.flush()
     }
 }
@@ -88,10 +94,15 @@ pub(crate) struct RustylineEditor {
 
 #[cfg(feature = "rustyline")]
 impl RustylineEditor {
-    pub(crate) fn new() -> io::Result<Self> {
+    pub(crate) fn new(history_size: usize) -> io::Result<Self> {
         use rustyline::EventHandler;
 
-        let mut editor = rustyline::DefaultEditor::new()
+        let config = rustyline::Config::builder()
+            .max_history_size(history_size)
+            .and_then(|builder| builder.history_ignore_dups(true))
+            .map_err(|err| io::Error::other(format!("rustyline config error: {}", err)))?
+            .build();
+        let mut editor = rustyline::DefaultEditor::with_config(config)
             .map_err(|err| io::Error::other(format!("rustyline init error: {}", err)))?;
         let printer = editor
             .create_external_printer()
@@ -99,6 +110,8 @@ impl RustylineEditor {
         let state = std::sync::Arc::new(std::sync::Mutex::new(RustylineState {
             snapshot: None,
             printer: Box::new(printer),
+            interrupted_empty_line: false,
+            tab_cycle: None,
         }));
 
         editor.bind_sequence(
@@ -114,6 +127,13 @@ impl RustylineEditor {
             })),
         );
 
+        editor.bind_sequence(
+            rustyline::KeyEvent::ctrl('C'),
+            EventHandler::Conditional(Box::new(InterruptHandler {
+                state: std::sync::Arc::clone(&state),
+            })),
+        );
+
         Ok(Self { editor, state })
     }
 }
@@ -123,7 +143,14 @@ impl LineEditor for RustylineEditor {
     fn read_line(&mut self, prompt: &str) -> io::Result<EditorRead> {
         match self.editor.readline(prompt) {
             Ok(line) => Ok(EditorRead::Line(line)),
-            Err(rustyline::error::ReadlineError::Interrupted) => Ok(EditorRead::Interrupted),
+            Err(rustyline::error::ReadlineError::Interrupted) => {
+                let empty_line = self
+                    .state
+                    .lock()
+                    .expect("rustyline state lock poisoned")
+                    .interrupted_empty_line;
+                Ok(EditorRead::Interrupted { empty_line })
+            }
             Err(rustyline::error::ReadlineError::Eof) => Ok(EditorRead::Eof),
             Err(err) => Err(io::Error::other(format!("rustyline read error: {}", err))),
         }
@@ -131,7 +158,7 @@ impl LineEditor for RustylineEditor {
 
     fn print_completions(&mut self, items: &[CompletionItem]) -> io::Result<()> {
         let mut stdout = io::stdout();
-        write!(stdout, "{}", format_completions(items))?;
+        write!(stdout, "{}", format_completions(items, terminal_width()))?;
         stdout.flush()
     }
 
@@ -155,6 +182,34 @@ impl LineEditor for RustylineEditor {
 struct RustylineState {
     snapshot: Option<CompletionSnapshot>,
     printer: Box<dyn rustyline::ExternalPrinter + Send>,
+    /// Whether the line was empty at the most recent Ctrl-C.
+    interrupted_empty_line: bool,
+    /// Candidates from the last ambiguous Tab, cycled by pressing it again.
+    tab_cycle: Option<panel::TabCycle>,
+}
+
+/// Notes whether anything was typed before Ctrl-C, then lets rustyline
+/// interrupt the read as usual.
+#[cfg(feature = "rustyline")]
+struct InterruptHandler {
+    state: std::sync::Arc<std::sync::Mutex<RustylineState>>,
+}
+
+#[cfg(feature = "rustyline")]
+impl rustyline::ConditionalEventHandler for InterruptHandler {
+    fn handle(
+        &self,
+        _evt: &rustyline::Event,
+        _n: rustyline::RepeatCount,
+        _positive: bool,
+        ctx: &rustyline::EventContext,
+    ) -> Option<rustyline::Cmd> {
+        self.state
+            .lock()
+            .expect("rustyline state lock poisoned")
+            .interrupted_empty_line = ctx.line().is_empty();
+        None
+    }
 }
 
 #[cfg(feature = "rustyline")]
@@ -179,8 +234,8 @@ impl rustyline::ConditionalEventHandler for HelpHandler {
             .map(|snapshot| snapshot.complete_prefix(prefix))
             .transpose()
         {
-            Ok(Some(items)) => format_completions(&items),
-            Ok(None) => format_completions(&[]),
+            Ok(Some(items)) => format_completions(&items, terminal_width()),
+            Ok(None) => format_completions(&[], terminal_width()),
             Err(err) => format!("\ncompletion error: {:?}\n\n", err),
         };
         let _ = state.printer.print(msg);
@@ -203,15 +258,50 @@ impl rustyline::ConditionalEventHandler for TabHandler {
         ctx: &rustyline::EventContext,
     ) -> Option<rustyline::Cmd> {
         let prefix = &ctx.line()[..ctx.pos()];
-        let state = self.state.lock().expect("rustyline state lock poisoned");
+        let mut state = self.state.lock().expect("rustyline state lock poisoned");
+        let state = &mut *state;
+
+        if let Some(cycle) = state.tab_cycle.as_mut()
+            && let Some((delete, insert)) = cycle.advance(prefix)
+        {
+            let _ = state.printer.print(panel::format_panel(
+                cycle.candidates(),
+                cycle.highlight(),
+                terminal_width(),
+                panel::MAX_PANEL_ROWS,
+            ));
+            let delete = rustyline::RepeatCount::try_from(delete).unwrap_or(u16::MAX);
+            return Some(rustyline::Cmd::Replace(
+                rustyline::Movement::BackwardChar(delete),
+                Some(insert),
+            ));
+        }
+        state.tab_cycle = None;
+
         let Some(snapshot) = state.snapshot.as_ref() else {
             return Some(rustyline::Cmd::Noop);
         };
-        let Some(completion) = snapshot.tab_completion(prefix).ok().flatten() else {
-            return Some(rustyline::Cmd::Noop);
-        };
-
-        Some(rustyline::Cmd::Insert(1, completion.insert_suffix))
+        match snapshot.tab_completion(prefix) {
+            Ok(TabCompletion::Insert(suffix)) => Some(rustyline::Cmd::Insert(1, suffix)),
+            Ok(TabCompletion::Candidates(items)) => {
+                let candidates = items.into_iter().map(|item| item.token).collect();
+                let cycle = panel::TabCycle::new(prefix, candidates);
+                let _ = state.printer.print(panel::format_panel(
+                    cycle.candidates(),
+                    None,
+                    terminal_width(),
+                    panel::MAX_PANEL_ROWS,
+                ));
+                state.tab_cycle = Some(cycle);
+                Some(rustyline::Cmd::Noop)
+            }
+            Ok(TabCompletion::NoMatch) | Err(_) => {
+                let mut stderr = io::stderr();
+                let _ = stderr.write_all(b"\x07");
+                let _ = stderr.flush();
+                Some(rustyline::Cmd::Noop)
+            }
+        }
     }
 }
 
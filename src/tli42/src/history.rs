@@ -0,0 +1,306 @@
+use std::collections::{HashSet, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub(crate) const DEFAULT_HISTORY_SIZE: usize = 1000;
+const APP_DIR_NAME: &str = "tli42";
+const HISTORY_FILE_NAME: &str = "history";
+
+/// Submitted lines, oldest first, capped at `max_len` entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct History {
+    entries: VecDeque<String>,
+    max_len: usize,
+}
+
+impl History {
+    pub(crate) fn new(max_len: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            max_len,
+        }
+    }
+
+    #[cfg(any(test, feature = "rustyline"))]
+    pub(crate) fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    pub(crate) fn set_max_len(&mut self, max_len: usize) {
+        self.max_len = max_len;
+        self.truncate_to_max_len();
+    }
+
+    /// Append `line`, ignoring blank lines and consecutive repeats.
+    ///
+    /// Returns whether the entry was recorded.
+    pub(crate) fn push(&mut self, line: &str) -> bool {
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.trim().is_empty() || self.max_len == 0 {
+            return false;
+        }
+        if self.entries.back().map(String::as_str) == Some(line) {
+            return false;
+        }
+
+        self.entries.push_back(line.to_string());
+        self.truncate_to_max_len();
+        true
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Add the entries stored in a history file.
+    ///
+    /// Concurrent sessions interleave their entries, so only the latest copy
+    /// of a repeated line is kept. A missing file is treated as empty and lines
+    /// that are not valid UTF-8 are skipped, so a damaged file only loses the
+    /// damaged entries.
+    pub(crate) fn load_file(&mut self, path: &Path) -> io::Result<()> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        let mut seen = HashSet::new();
+        let mut lines = bytes
+            .split(|&byte| byte == b'\n')
+            .rev()
+            .filter_map(|line| std::str::from_utf8(line).ok())
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| seen.insert(*line))
+            .collect::<Vec<_>>();
+        lines.reverse();
+        for line in lines {
+            self.push(line);
+        }
+        Ok(())
+    }
+
+    fn truncate_to_max_len(&mut self) {
+        while self.entries.len() > self.max_len {
+            self.entries.pop_front();
+        }
+    }
+}
+
+/// Append one entry, opening in append mode so concurrent sessions interleave
+/// whole lines instead of overwriting each other.
+pub(crate) fn append_to_file(path: &Path, line: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(format!("{}\n", line.trim_end_matches(['\n', '\r'])).as_bytes())
+}
+
+/// Replace the file with `entries`, one per line, atomically so a concurrent
+/// reader never sees a partial file.
+pub(crate) fn rewrite_file<'a>(
+    path: &Path,
+    entries: impl Iterator<Item = &'a str>,
+) -> io::Result<()> {
+    let text: String = entries.map(|entry| format!("{entry}\n")).collect();
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, text)?;
+    std::fs::rename(&tmp, path)
+}
+
+pub(crate) fn truncate_file(path: &Path) -> io::Result<()> {
+    match File::create(path) {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+/// `$XDG_DATA_HOME/tli42`, falling back to `~/.local/share/tli42`.
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    if let Ok(xdg_data_home) = std::env::var("XDG_DATA_HOME") {
+        return Some(PathBuf::from(xdg_data_home).join(APP_DIR_NAME));
+    }
+
+    std::env::var("HOME").ok().map(|home| {
+        PathBuf::from(home)
+            .join(".local")
+            .join("share")
+            .join(APP_DIR_NAME)
+    })
+}
+
+/// `$XDG_CONFIG_HOME/tli42`, falling back to `~/.config/tli42`.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    if let Ok(xdg_config_home) = std::env::var("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(xdg_config_home).join(APP_DIR_NAME));
+    }
+
+    std::env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".config").join(APP_DIR_NAME))
+}
+
+pub(crate) fn default_history_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(HISTORY_FILE_NAME))
+}
+
+/// Render entries numbered from 1, right-aligning the numbers.
+pub(crate) fn format_history<'a>(entries: impl Iterator<Item = &'a str>) -> String {
+    let entries = entries.collect::<Vec<_>>();
+    let width = entries.len().to_string().len();
+    let mut out = String::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        out.push_str(&format!(
+            "  {:>width$}  {}\n",
+            idx + 1,
+            entry,
+            width = width
+        ));
+    }
+    out
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(history: &History) -> Vec<&str> {
+        history.iter().collect()
+    }
+
+    #[test]
+    fn push_skips_blank_lines_and_strips_line_endings() {
+        let mut history = History::default();
+
+        assert!(!history.push(""));
+        assert!(!history.push("   \n"));
+        assert!(history.push("show\r\n"));
+        assert_eq!(entries(&history), vec!["show"]);
+    }
+
+    #[test]
+    fn push_deduplicates_only_consecutive_repeats() {
+        let mut history = History::default();
+
+        assert!(history.push("show"));
+        assert!(!history.push("show\n"));
+        assert!(history.push("write"));
+        assert!(history.push("show"));
+        assert_eq!(entries(&history), vec!["show", "write", "show"]);
+    }
+
+    #[test]
+    fn push_drops_oldest_entries_past_capacity() {
+        let mut history = History::new(2);
+
+        history.push("a");
+        history.push("b");
+        history.push("c");
+        assert_eq!(entries(&history), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn zero_capacity_records_nothing() {
+        let mut history = History::new(0);
+
+        assert!(!history.push("show"));
+        assert_eq!(entries(&history), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn load_file_round_trips_appended_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("history");
+
+        append_to_file(&path, "show\n").unwrap();
+        append_to_file(&path, "show").unwrap();
+        append_to_file(&path, "write").unwrap();
+
+        let mut history = History::default();
+        history.load_file(&path).unwrap();
+        assert_eq!(entries(&history), vec!["show", "write"]);
+    }
+
+    #[test]
+    fn load_file_keeps_the_latest_copy_of_interleaved_repeats() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history");
+        std::fs::write(&path, "show\nwrite\nshow\r\nstatus\nwrite\n").unwrap();
+
+        let mut history = History::default();
+        history.load_file(&path).unwrap();
+        assert_eq!(entries(&history), vec!["show", "status", "write"]);
+    }
+
+    #[test]
+    fn rewrite_file_replaces_the_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history");
+        append_to_file(&path, "old").unwrap();
+
+        rewrite_file(&path, ["show", "write"].into_iter()).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "show\nwrite\n");
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn load_file_tolerates_missing_file_and_invalid_utf8() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history");
+
+        let mut history = History::default();
+        history.load_file(&path).unwrap();
+        assert_eq!(entries(&history), Vec::<&str>::new());
+
+        std::fs::write(&path, b"show\n\xff\xfe\nwrite\n").unwrap();
+        history.load_file(&path).unwrap();
+        assert_eq!(entries(&history), vec!["show", "write"]);
+    }
+
+    #[test]
+    fn truncate_file_empties_existing_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history");
+        append_to_file(&path, "show").unwrap();
+
+        truncate_file(&path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"");
+    }
+
+    #[test]
+    fn format_history_numbers_entries() {
+        let lines = (1..=10).map(|n| format!("cmd {}", n)).collect::<Vec<_>>();
+
+        assert_eq!(format_history(std::iter::empty()), "");
+        assert_eq!(
+            format_history(lines.iter().map(String::as_str).skip(8)),
+            "  1  cmd 9\n  2  cmd 10\n"
+        );
+        assert!(format_history(lines.iter().map(String::as_str)).starts_with("   1  cmd 1\n"));
+    }
+
+    #[test]
+    fn set_max_len_trims_existing_entries() {
+        let mut history = History::new(3);
+        history.push("a");
+        history.push("b");
+        history.push("c");
+
+        history.set_max_len(1);
+        assert_eq!(history.max_len(), 1);
+        assert_eq!(entries(&history), vec!["c"]);
+    }
+}
@@ -1,7 +1,44 @@
+//! Building blocks for Cisco-style command shells: commands are phrases
+//! matched word by word, with `?` help and Tab completion at every step.
+//!
+//! `Repl::builder` covers the common case of a single mode:
+//!
+//! ```
+//! use tli42::repl::{Action, Repl};
+//!
+//! let mut repl = Repl::builder()
+//!     .command("greet <name>", "say hello to someone", |ctx| {
+//!         let name = ctx.positional(0).unwrap_or_default();
+//!         ctx.println(format_args!("hello, {}", name));
+//!         Ok(Action::None)
+//!     })
+//!     .prompt("admin> ")
+//!     .build()
+//!     .expect("commands register");
+//!
+//! let status = repl.run_command("greet ada").unwrap();
+//! assert_eq!(status, 0);
+//! ```
+//!
+//! Modes and argument docs are available through `Repl::add_mode`,
+//! `Repl::register_mode_command` and `cmd::CmdBuilder`.
+
 pub mod cmd;
 pub mod repl;
 
+mod alias;
+mod builder;
 mod editor;
+mod history;
 mod mode;
+mod pager;
+#[cfg(feature = "rustyline")]
+mod panel;
+mod prompt;
+mod shell;
 mod sm;
+mod spec;
+mod term;
+mod timing;
 mod trie;
+mod vars;
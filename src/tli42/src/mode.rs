@@ -91,6 +91,9 @@ impl Mode {
         self.sm.command_doc_at(state_id)
     }
 
+    pub(crate) fn command_paths(&self, state_id: sm::StateId) -> Vec<sm::CommandPath<'_>> {
+        self.sm.command_paths(state_id)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,228 @@
+use crate::term::terminal_height;
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+
+const MORE_PROMPT: &str = "--More-- (space: next page, enter: next line, q: quit)";
+const CLEAR_LINE: &str = "\r\x1b[K";
+
+/// How to show a command's output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PagerChoice {
+    /// Print the text as is.
+    Print,
+    /// Pipe the text to this shell command, taken from `$PAGER`.
+    External(String),
+    /// Show the text a screenful at a time with the built-in pager.
+    Builtin,
+}
+
+/// Decide how to show `text` on a terminal `height` rows tall.
+///
+/// Text that fits above the prompt, or that is not going to a terminal, is
+/// printed directly.
+pub(crate) fn choose_pager(
+    text: &str,
+    height: usize,
+    is_terminal: bool,
+    pager: Option<&str>,
+) -> PagerChoice {
+    if !is_terminal || text.lines().count() < height {
+        return PagerChoice::Print;
+    }
+    match pager.map(str::trim).filter(|pager| !pager.is_empty()) {
+        Some(pager) => PagerChoice::External(pager.to_string()),
+        None => PagerChoice::Builtin,
+    }
+}
+
+/// Lines shown per screenful, leaving a row for the `--More--` prompt.
+fn page_size(height: usize) -> usize {
+    height.saturating_sub(1).max(1)
+}
+
+/// How many more lines a key pressed at the `--More--` prompt shows, or
+/// `None` if it quits. Other keys are ignored.
+pub(crate) fn lines_for_key(key: u8, height: usize) -> Option<usize> {
+    match key {
+        b' ' | b'f' => Some(page_size(height)),
+        b'\r' | b'\n' | b'j' => Some(1),
+        // q, Ctrl-C, Ctrl-D and Escape.
+        b'q' | b'Q' | 0x03 | 0x04 | 0x1b => None,
+        _ => Some(0),
+    }
+}
+
+/// Print `text` to stdout, paging it when it is longer than the terminal.
+///
+/// If the pager cannot be started the text is printed instead.
+pub(crate) fn page(text: &str) {
+    let is_terminal = io::stdout().is_terminal() && io::stdin().is_terminal();
+    let pager = std::env::var("PAGER").ok();
+    let height = terminal_height();
+    let paged = match choose_pager(text, height, is_terminal, pager.as_deref()) {
+        PagerChoice::Print => Err(io::Error::other("not paged")),
+        PagerChoice::External(command) => run_external(&command, text),
+        PagerChoice::Builtin => run_builtin(text, height),
+    };
+    if paged.is_err() {
+        print!("{}", text);
+        let _ = io::stdout().flush();
+    }
+}
+
+fn run_external(command: &str, text: &str) -> io::Result<()> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager closes its input when the user quits early.
+        match stdin.write_all(text.as_bytes()) {
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => return Err(err),
+            _ => {}
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
+
+fn run_builtin(text: &str, height: usize) -> io::Result<()> {
+    let _raw = RawMode::enable()?;
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut stdout = io::stdout().lock();
+    let mut stdin = io::stdin().lock();
+    let mut shown = 0;
+    let mut step = page_size(height);
+
+    loop {
+        let end = (shown + step).min(lines.len());
+        for line in &lines[shown..end] {
+            // Raw mode turns off the terminal's newline translation.
+            write!(stdout, "{}\r\n", line)?;
+        }
+        shown = end;
+        if shown == lines.len() {
+            return Ok(());
+        }
+
+        write!(stdout, "{}", MORE_PROMPT)?;
+        stdout.flush()?;
+        let mut key = [0];
+        let read = stdin.read(&mut key)?;
+        write!(stdout, "{}", CLEAR_LINE)?;
+        match lines_for_key(key[0], height) {
+            Some(lines) if read > 0 => step = lines,
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// Puts the terminal on stdin in raw mode until dropped, so the pager sees
+/// each key as it is pressed.
+#[cfg(unix)]
+struct RawMode {
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let mut original = std::mem::MaybeUninit::<libc::termios>::uninit();
+        // SAFETY: tcgetattr fills in the termios struct when it returns 0.
+        let original = unsafe {
+            if libc::tcgetattr(libc::STDIN_FILENO, original.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            original.assume_init()
+        };
+        let mut raw = original;
+        // SAFETY: both calls only read and write the termios struct passed in.
+        unsafe {
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(Self { original })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: restores the settings read in `enable`.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct RawMode;
+
+#[cfg(not(unix))]
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the built-in pager needs a unix terminal",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered_lines(count: usize) -> String {
+        (1..=count).map(|line| format!("line {}\n", line)).collect()
+    }
+
+    #[test]
+    fn choose_pager_prints_output_that_fits() {
+        assert_eq!(
+            choose_pager(&numbered_lines(23), 24, true, None),
+            PagerChoice::Print
+        );
+        assert_eq!(choose_pager("", 24, true, None), PagerChoice::Print);
+    }
+
+    #[test]
+    fn choose_pager_pages_output_taller_than_the_terminal() {
+        assert_eq!(
+            choose_pager(&numbered_lines(24), 24, true, None),
+            PagerChoice::Builtin
+        );
+        assert_eq!(
+            choose_pager(&numbered_lines(30), 24, true, Some("less -R")),
+            PagerChoice::External("less -R".to_string())
+        );
+        assert_eq!(
+            choose_pager(&numbered_lines(30), 24, true, Some("  ")),
+            PagerChoice::Builtin
+        );
+        assert_eq!(
+            choose_pager(&numbered_lines(30), 50, true, Some("less")),
+            PagerChoice::Print
+        );
+    }
+
+    #[test]
+    fn choose_pager_prints_when_not_on_a_terminal() {
+        assert_eq!(
+            choose_pager(&numbered_lines(500), 24, false, Some("less")),
+            PagerChoice::Print
+        );
+    }
+
+    #[test]
+    fn lines_for_key_pages_steps_and_quits() {
+        assert_eq!(lines_for_key(b' ', 24), Some(23));
+        assert_eq!(lines_for_key(b'\r', 24), Some(1));
+        assert_eq!(lines_for_key(b' ', 1), Some(1));
+        assert_eq!(lines_for_key(b'x', 24), Some(0));
+        assert_eq!(lines_for_key(b'q', 24), None);
+        assert_eq!(lines_for_key(0x03, 24), None);
+    }
+}
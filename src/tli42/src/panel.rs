@@ -0,0 +1,284 @@
+use crate::repl::RET_COMPLETION_TOKEN;
+
+/// Candidate rows shown below the prompt before the rest are summarised.
+pub(crate) const MAX_PANEL_ROWS: usize = 8;
+
+const INDENT: &str = "  ";
+const COLUMN_GAP: usize = 2;
+const HIGHLIGHT: &str = "\x1b[7m";
+const RESET: &str = "\x1b[0m";
+
+/// Candidates packed into columns, filled top to bottom then left to right,
+/// the way `ls` lists files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PanelLayout {
+    /// Width of every column, including the gap after it.
+    pub(crate) column_width: usize,
+    /// Candidate indices shown on each row.
+    pub(crate) rows: Vec<Vec<usize>>,
+    /// Candidates that did not fit in the panel.
+    pub(crate) hidden: usize,
+}
+
+/// Pack candidates of the given display widths into at most `max_rows` rows
+/// of a `width`-column terminal, starting from candidate `first`.
+pub(crate) fn layout_panel(
+    lengths: &[usize],
+    first: usize,
+    width: usize,
+    max_rows: usize,
+) -> PanelLayout {
+    let column_width = lengths.iter().copied().max().unwrap_or(0) + COLUMN_GAP;
+    let usable = width.saturating_sub(INDENT.len()) + COLUMN_GAP;
+    let columns = (usable / column_width).max(1);
+    let remaining = lengths.len().saturating_sub(first);
+    let row_count = remaining.div_ceil(columns).min(max_rows.max(1));
+    let shown = remaining.min(row_count * columns);
+
+    let rows = (0..row_count)
+        .map(|row| {
+            (0..columns)
+                .map(|column| column * row_count + row)
+                .filter(|offset| *offset < shown)
+                .map(|offset| first + offset)
+                .collect()
+        })
+        .collect();
+
+    PanelLayout {
+        column_width,
+        rows,
+        hidden: lengths.len() - shown,
+    }
+}
+
+/// Render the candidate panel printed below the prompt, with the
+/// `highlight`ed candidate in reverse video.
+///
+/// When the highlight lies past the first screenful the panel pages forward
+/// so that it stays visible.
+pub(crate) fn format_panel(
+    candidates: &[String],
+    highlight: Option<usize>,
+    width: usize,
+    max_rows: usize,
+) -> String {
+    let lengths = candidates
+        .iter()
+        .map(|candidate| candidate.chars().count())
+        .collect::<Vec<_>>();
+    let page = layout_panel(&lengths, 0, width, max_rows);
+    let per_page = page.rows.len() * page.rows.first().map_or(1, Vec::len);
+    let first = match highlight {
+        Some(index) if per_page > 0 => index / per_page * per_page,
+        _ => 0,
+    };
+    let layout = layout_panel(&lengths, first, width, max_rows);
+
+    let mut out = String::new();
+    for row in &layout.rows {
+        let mut line = String::from(INDENT);
+        for (position, &index) in row.iter().enumerate() {
+            let candidate = &candidates[index];
+            if highlight == Some(index) {
+                line.push_str(HIGHLIGHT);
+                line.push_str(candidate);
+                line.push_str(RESET);
+            } else {
+                line.push_str(candidate);
+            }
+            if position + 1 < row.len() {
+                let padding = layout.column_width - lengths[index];
+                line.push_str(&" ".repeat(padding));
+            }
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    if layout.hidden > 0 {
+        out.push_str(&format!("{}… and {} more\n", INDENT, layout.hidden));
+    }
+    out
+}
+
+/// Cycles the partial word at the end of the line through the candidates of
+/// an ambiguous completion on repeated Tab presses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TabCycle {
+    candidates: Vec<String>,
+    /// The partial word as typed before cycling started.
+    original: String,
+    /// The line up to the cursor as the last step left it.
+    expected_prefix: String,
+    index: Option<usize>,
+}
+
+impl TabCycle {
+    /// Start cycling for a line whose text before the cursor is `prefix`.
+    ///
+    /// The `RET` entry for an already-complete command is moved to the front;
+    /// choosing it restores what was typed.
+    pub(crate) fn new(prefix: &str, mut candidates: Vec<String>) -> Self {
+        candidates.sort_by_key(|candidate| candidate != RET_COMPLETION_TOKEN);
+        let original = prefix
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        Self {
+            candidates,
+            original,
+            expected_prefix: prefix.to_string(),
+            index: None,
+        }
+    }
+
+    pub(crate) fn candidates(&self) -> &[String] {
+        &self.candidates
+    }
+
+    pub(crate) fn highlight(&self) -> Option<usize> {
+        self.index
+    }
+
+    /// Move to the next candidate if the line is still where the last step
+    /// left it.
+    ///
+    /// Returns how many characters to delete before the cursor and the text
+    /// to insert in their place, or `None` if the user has edited the line.
+    pub(crate) fn advance(&mut self, prefix: &str) -> Option<(usize, String)> {
+        if prefix != self.expected_prefix || self.candidates.is_empty() {
+            return None;
+        }
+
+        let current = self.current_text().to_string();
+        let next = self
+            .index
+            .map_or(0, |index| (index + 1) % self.candidates.len());
+        self.index = Some(next);
+        let replacement = self.current_text().to_string();
+
+        let kept = &prefix[..prefix.len() - current.len()];
+        self.expected_prefix = format!("{}{}", kept, replacement);
+        Some((current.chars().count(), replacement))
+    }
+
+    /// The text the partial word holds at the current step.
+    fn current_text(&self) -> &str {
+        match self.index.map(|index| self.candidates[index].as_str()) {
+            Some(candidate) if candidate != RET_COMPLETION_TOKEN && !candidate.starts_with('<') => {
+                candidate
+            }
+            _ => &self.original,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn layout_panel_fills_columns_top_to_bottom() {
+        let layout = layout_panel(&[3, 5, 4, 2, 1], 0, 21, 8);
+
+        assert_eq!(layout.column_width, 7);
+        assert_eq!(layout.rows, vec![vec![0, 2, 4], vec![1, 3]]);
+        assert_eq!(layout.hidden, 0);
+    }
+
+    #[test]
+    fn layout_panel_uses_one_column_when_candidates_are_wide() {
+        let layout = layout_panel(&[30, 2], 0, 20, 8);
+
+        assert_eq!(layout.rows, vec![vec![0], vec![1]]);
+    }
+
+    #[test]
+    fn layout_panel_caps_rows_and_counts_hidden_candidates() {
+        let layout = layout_panel(&[4; 10], 0, 14, 2);
+
+        assert_eq!(layout.rows, vec![vec![0, 2], vec![1, 3]]);
+        assert_eq!(layout.hidden, 6);
+
+        let layout = layout_panel(&[4; 10], 8, 14, 2);
+        assert_eq!(layout.rows, vec![vec![8, 9]]);
+        assert_eq!(layout.hidden, 8);
+    }
+
+    #[test]
+    fn layout_panel_fits_more_columns_on_wider_terminals() {
+        let lengths = [10; 12];
+
+        let columns = |width| layout_panel(&lengths, 0, width, 8).rows[0].len();
+        assert_eq!(columns(40), 3);
+        assert_eq!(columns(80), 6);
+        assert_eq!(columns(200), 12);
+        assert_eq!(layout_panel(&lengths, 0, 40, 8).rows.len(), 4);
+        assert_eq!(layout_panel(&lengths, 0, 200, 8).rows.len(), 1);
+    }
+
+    #[test]
+    fn layout_panel_handles_no_candidates() {
+        let layout = layout_panel(&[], 0, 80, 8);
+
+        assert_eq!(layout.rows, Vec::<Vec<usize>>::new());
+        assert_eq!(layout.hidden, 0);
+    }
+
+    #[test]
+    fn format_panel_pads_columns_and_adds_footer() {
+        let candidates = strings(&["alpha", "be", "gamma", "de", "eps"]);
+
+        assert_eq!(
+            format_panel(&candidates, None, 21, 8),
+            "  alpha  gamma  eps\n  be     de\n"
+        );
+        assert_eq!(
+            format_panel(&candidates, None, 10, 2),
+            "  alpha\n  be\n  … and 3 more\n"
+        );
+    }
+
+    #[test]
+    fn format_panel_highlights_and_pages_to_selection() {
+        let candidates = strings(&["a", "b", "c"]);
+
+        assert_eq!(
+            format_panel(&candidates, Some(1), 80, 8),
+            "  a  \x1b[7mb\x1b[0m  c\n"
+        );
+        assert_eq!(
+            format_panel(&candidates, Some(2), 3, 2),
+            "  \x1b[7mc\x1b[0m\n  … and 2 more\n"
+        );
+    }
+
+    #[test]
+    fn tab_cycle_replaces_partial_word_in_turn() {
+        let mut cycle = TabCycle::new("show ip", strings(&["ip", "ipv6", "RET"]));
+
+        assert_eq!(cycle.candidates(), strings(&["RET", "ip", "ipv6"]));
+        assert_eq!(cycle.advance("show ip"), Some((2, "ip".to_string())));
+        assert_eq!(cycle.highlight(), Some(0));
+        assert_eq!(cycle.advance("show ip"), Some((2, "ip".to_string())));
+        assert_eq!(cycle.advance("show ip"), Some((2, "ipv6".to_string())));
+        assert_eq!(cycle.highlight(), Some(2));
+        assert_eq!(cycle.advance("show ipv6"), Some((4, "ip".to_string())));
+        assert_eq!(cycle.highlight(), Some(0));
+    }
+
+    #[test]
+    fn tab_cycle_skips_placeholders_and_stops_after_edits() {
+        let mut cycle = TabCycle::new("set ", strings(&["<name>", "all"]));
+
+        assert_eq!(cycle.advance("set "), Some((0, String::new())));
+        assert_eq!(cycle.advance("set "), Some((0, "all".to_string())));
+        assert_eq!(cycle.advance("set al"), None);
+        assert_eq!(cycle.advance("set all"), Some((3, String::new())));
+    }
+}
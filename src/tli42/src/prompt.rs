@@ -0,0 +1,277 @@
+use std::fmt;
+
+pub(crate) const DEFAULT_PROMPT_TEMPLATE: &str = "{mode}> ";
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptTemplateError {
+    /// A `{` at this byte offset has no closing `}`.
+    UnterminatedPlaceholder(usize),
+}
+
+impl fmt::Display for PromptTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnterminatedPlaceholder(offset) => {
+                write!(f, "unterminated placeholder at byte {}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PromptTemplateError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Mode,
+    Cwd,
+    Time,
+    Status,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A parsed prompt template such as `"[{time}] {mode}> "`.
+///
+/// Known placeholders are `{mode}`, `{cwd}`, `{time}` and `{status}` (`✓`, or
+/// `✗` followed by the exit status); unknown ones are kept literally, and
+/// `{{` / `}}` produce single braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PromptTemplate {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+/// Values substituted into a prompt template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PromptContext {
+    pub(crate) mode: String,
+    pub(crate) cwd: String,
+    /// `HH:MM:SS`.
+    pub(crate) time: String,
+    /// Exit status of the last command.
+    pub(crate) status: i32,
+    pub(crate) color: bool,
+}
+
+impl PromptTemplate {
+    pub(crate) fn parse(source: &str) -> Result<Self, PromptTemplateError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = source;
+
+        while let Some(idx) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..idx]);
+            let tail = &rest[idx..];
+            if tail.starts_with("{{") || tail.starts_with("}}") {
+                literal.push_str(&tail[..1]);
+                rest = &tail[2..];
+                continue;
+            }
+            if let Some(after) = tail.strip_prefix('}') {
+                literal.push('}');
+                rest = after;
+                continue;
+            }
+
+            let offset = source.len() - tail.len();
+            let Some(end) = tail.find('}') else {
+                return Err(PromptTemplateError::UnterminatedPlaceholder(offset));
+            };
+            let placeholder = match &tail[1..end] {
+                "mode" => Some(Placeholder::Mode),
+                "cwd" => Some(Placeholder::Cwd),
+                "time" => Some(Placeholder::Time),
+                "status" => Some(Placeholder::Status),
+                _ => None,
+            };
+            match placeholder {
+                Some(placeholder) => {
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(placeholder));
+                }
+                None => literal.push_str(&tail[..=end]),
+            }
+            rest = &tail[end + 1..];
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            segments,
+        })
+    }
+
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub(crate) fn expand(&self, context: &PromptContext) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Placeholder(Placeholder::Mode) => out.push_str(&context.mode),
+                Segment::Placeholder(Placeholder::Cwd) => out.push_str(&context.cwd),
+                Segment::Placeholder(Placeholder::Time) => out.push_str(&context.time),
+                Segment::Placeholder(Placeholder::Status) => {
+                    let (text, color) = if context.status == 0 {
+                        ("✓".to_string(), GREEN)
+                    } else {
+                        (format!("✗{}", context.status), RED)
+                    };
+                    if context.color {
+                        out.push_str(color);
+                        out.push_str(&text);
+                        out.push_str(RESET);
+                    } else {
+                        out.push_str(&text);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+impl Default for PromptTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_PROMPT_TEMPLATE).expect("default prompt template parses")
+    }
+}
+
+/// Format seconds since the Unix epoch as a UTC `HH:MM:SS` time of day.
+pub(crate) fn format_time_of_day(epoch_secs: u64) -> String {
+    let secs = epoch_secs % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Shorten `cwd` by replacing a leading `home` directory with `~`.
+pub(crate) fn abbreviate_home(cwd: &str, home: Option<&str>) -> String {
+    match home {
+        Some(home) if !home.is_empty() => match cwd.strip_prefix(home) {
+            Some("") => "~".to_string(),
+            Some(rest) if rest.starts_with('/') => format!("~{}", rest),
+            _ => cwd.to_string(),
+        },
+        _ => cwd.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> PromptContext {
+        PromptContext {
+            mode: "global/write".to_string(),
+            cwd: "~/src".to_string(),
+            time: "09:05:03".to_string(),
+            status: 0,
+            color: false,
+        }
+    }
+
+    fn expand(template: &str, context: &PromptContext) -> String {
+        PromptTemplate::parse(template).unwrap().expand(context)
+    }
+
+    #[test]
+    fn default_template_shows_mode_path() {
+        assert_eq!(
+            PromptTemplate::default().expand(&context()),
+            "global/write> "
+        );
+    }
+
+    #[test]
+    fn expand_substitutes_each_placeholder() {
+        let ctx = context();
+
+        assert_eq!(expand("{mode}", &ctx), "global/write");
+        assert_eq!(expand("{cwd}", &ctx), "~/src");
+        assert_eq!(expand("[{time}] ", &ctx), "[09:05:03] ");
+        assert_eq!(expand("{status}", &ctx), "✓");
+        assert_eq!(
+            expand(
+                "{status}",
+                &PromptContext {
+                    status: 2,
+                    ..ctx
+                }
+            ),
+            "✗2"
+        );
+    }
+
+    #[test]
+    fn expand_colors_status_only_when_enabled() {
+        let ctx = PromptContext {
+            color: true,
+            ..context()
+        };
+
+        assert_eq!(expand("{status} ", &ctx), "\x1b[32m✓\x1b[0m ");
+        assert_eq!(
+            expand(
+                "{status}",
+                &PromptContext {
+                    status: 2,
+                    ..ctx
+                }
+            ),
+            "\x1b[31m✗2\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_and_escaped_braces_pass_through() {
+        let ctx = context();
+
+        assert_eq!(expand("{user}@{mode}", &ctx), "{user}@global/write");
+        assert_eq!(expand("{{mode}} }", &ctx), "{mode} }");
+    }
+
+    #[test]
+    fn parse_rejects_unterminated_placeholder() {
+        assert_eq!(
+            PromptTemplate::parse("ok {mode"),
+            Err(PromptTemplateError::UnterminatedPlaceholder(3))
+        );
+    }
+
+    #[test]
+    fn format_time_of_day_wraps_at_midnight() {
+        assert_eq!(format_time_of_day(0), "00:00:00");
+        assert_eq!(format_time_of_day(86_399), "23:59:59");
+        assert_eq!(format_time_of_day(86_400 + 3_723), "01:02:03");
+    }
+
+    #[test]
+    fn abbreviate_home_only_replaces_whole_components() {
+        assert_eq!(abbreviate_home("/home/me", Some("/home/me")), "~");
+        assert_eq!(abbreviate_home("/home/me/src", Some("/home/me")), "~/src");
+        assert_eq!(
+            abbreviate_home("/home/meow", Some("/home/me")),
+            "/home/meow"
+        );
+        assert_eq!(abbreviate_home("/tmp", None), "/tmp");
+    }
+}
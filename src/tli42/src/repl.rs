@@ -1,7 +1,14 @@
-use crate::{cmd, editor, mode, sm};
+use crate::{alias, cmd, editor, history, mode, prompt, pager, shell, sm, spec, term, timing, vars};
 use std::fmt;
 use std::collections::BTreeMap;
-use std::io;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use crate::builder::{CommandContext, ContextHandler, ReplBuilder};
+pub use crate::prompt::PromptTemplateError;
+pub use crate::shell::{ShellExecutor, SystemShell};
 
 pub type ModeId = u32;
 pub type CommandId = u32;
@@ -25,7 +32,32 @@ pub struct CommandInputs {
 
 pub type HandlerResult = Result<Action, HandlerError>;
 pub type Handler = Box<dyn FnMut(&mut Repl, &CommandInputs) -> HandlerResult>;
-const RET_COMPLETION_TOKEN: &str = "RET";
+pub(crate) const RET_COMPLETION_TOKEN: &str = "RET";
+const CONTINUATION_PROMPT: &str = "… ";
+const RC_FILE_NAME: &str = "rc";
+/// Commands registered in every mode to leave it.
+const EXIT_COMMANDS: &[&str] = &["exit", "quit"];
+const EXIT_DOC: &str = "leave the current mode, or quit from the root mode";
+const GOODBYE_MESSAGE: &str = "Goodbye.";
+/// A command phrase and its documentation, as listed by `help`.
+type HelpEntry = (String, Option<String>);
+/// Commands handled by the REPL itself, as listed by `help`.
+const BUILTIN_HELP: &[(&str, &str)] = &[
+    ("!<command>", "run a shell command; a bare ! repeats the last one"),
+    ("alias [<name> = <command>]", "list aliases, or define one"),
+    ("help [<prefix>]", "list commands, optionally only those under a prefix"),
+    ("history [clear]", "list previously entered lines, or forget them"),
+    ("prompt [<template>]", "show or set the prompt, e.g. \"{time} {mode}> \""),
+    ("reload", "re-read the spec, alias and rc files, keeping the old state on error"),
+    ("set -u|+u", "make undefined variables an error, or only a warning"),
+    ("set <name> <value>", "define a variable, used as $name or ${name}"),
+    ("set timings on|off", "print how long each command takes to run"),
+    ("status", "show the exit status of the last command"),
+    ("time <command>", "run a command and print how long it took"),
+    ("unalias <name>", "remove an alias"),
+    ("unset <name>", "remove a variable"),
+    ("vars", "list variables"),
+];
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CommandRegistrationError {
@@ -66,6 +98,7 @@ pub enum ReplError {
     EmptyModeStack,
     CannotPopRootMode,
     CmdInsert(CommandRegistrationError),
+    InvalidPromptTemplate(PromptTemplateError),
 }
 
 impl From<sm::CmdInsertError> for ReplError {
@@ -115,6 +148,12 @@ impl From<sm::CmdInsertError> for ReplError {
     }
 }
 
+impl From<PromptTemplateError> for ReplError {
+    fn from(value: PromptTemplateError) -> Self {
+        Self::InvalidPromptTemplate(value)
+    }
+}
+
 impl From<cmd::CmdSchemaError> for ReplError {
     fn from(value: cmd::CmdSchemaError) -> Self {
         let mapped = match value {
@@ -131,6 +170,37 @@ pub struct Repl {
     stack: Vec<ModeId>,
     handlers: Vec<Handler>,
     capture_specs: Vec<Vec<cmd::CaptureKind>>,
+    history: history::History,
+    history_path: Option<PathBuf>,
+    aliases: alias::Aliases,
+    alias_path: Option<PathBuf>,
+    arg_completers: Vec<ArgCompleter>,
+    /// The rc file last loaded, run again by `reload`.
+    rc_path: Option<PathBuf>,
+    /// The command spec loaded into the root mode, read again by `reload`.
+    spec: Option<LoadedSpec>,
+    /// Handler slots for spec commands, reused when the spec is reloaded.
+    spec_command_ids: Vec<CommandId>,
+    variables: vars::Variables,
+    /// Whether an undefined variable stops the command, as `set -u` does.
+    strict_variables: bool,
+    prompt_template: prompt::PromptTemplate,
+    last_status: i32,
+    shell: Box<dyn shell::ShellExecutor>,
+    last_shell_command: Option<String>,
+    /// Handlers for `exit` and `exit <status>`, shared by every mode.
+    exit_handlers: (CommandId, CommandId),
+    /// Status requested by `exit <status>`, applied once the command returns.
+    exit_status: Option<i32>,
+    /// Where `CommandContext` printing goes; `None` for stdout, through the
+    /// pager.
+    output: Option<Box<dyn io::Write>>,
+    clock: Box<dyn timing::Clock>,
+    /// Whether to print how long each command took.
+    timings: bool,
+    last_duration: Option<Duration>,
+    /// Set by `time` so the command is not reported twice.
+    duration_reported: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -139,6 +209,50 @@ pub struct CompletionItem {
     pub doc: Option<String>,
 }
 
+/// What an argument completer can see of the REPL when Tab is pressed.
+pub struct CompletionContext<'a> {
+    mode: &'a str,
+    variables: &'a [String],
+}
+
+impl<'a> CompletionContext<'a> {
+    /// Name of the mode the command is being typed in.
+    pub fn mode(&self) -> &'a str {
+        self.mode
+    }
+
+    /// Names of the variables currently set, sorted.
+    pub fn variable_names(&self) -> &'a [String] {
+        self.variables
+    }
+}
+
+/// Completes the words after a command stem: called with the words already
+/// typed after the stem and the partial word at the cursor.
+pub type ArgCompleterFn =
+    dyn Fn(&CompletionContext<'_>, &[&str], &str) -> Vec<CompletionItem> + Send + Sync;
+
+/// An argument completer registered with `Repl::set_arg_completer`, keyed by
+/// the state its stem leads to.
+#[derive(Clone)]
+struct ArgCompleter {
+    mode_id: ModeId,
+    state: sm::StateId,
+    complete: Arc<ArgCompleterFn>,
+}
+
+impl fmt::Debug for ArgCompleter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArgCompleter")
+            .field("mode_id", &self.mode_id)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Built-ins whose first argument is a variable name.
+const VARIABLE_BUILTINS: &[&str] = &["set", "unset"];
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RunOnceOutcome {
     Noop,
@@ -147,9 +261,47 @@ pub enum RunOnceOutcome {
     IncompleteCommand,
     ParseError(ParseLineError),
     HandlerError(HandlerError),
+    /// A built-in command was called with the wrong arguments.
+    UsageError(String),
+    /// A `!` command ran and exited with this status.
+    ShellExited(i32),
     ActionApplied(Action),
 }
 
+/// Exit status of a command that succeeded.
+pub const STATUS_SUCCESS: i32 = 0;
+/// Exit status of a command that ran and failed.
+pub const STATUS_FAILURE: i32 = 1;
+/// Exit status of a line that could not be run as written.
+pub const STATUS_USAGE: i32 = 2;
+
+impl RunOnceOutcome {
+    /// The exit status this outcome records, or `None` when no command ran.
+    ///
+    /// Lines that do not parse or do not name a command count as usage
+    /// errors; handler errors count as generic failures.
+    pub fn status(&self) -> Option<i32> {
+        match self {
+            Self::Noop | Self::Completions(_) => None,
+            Self::ActionApplied(_) => Some(STATUS_SUCCESS),
+            Self::ShellExited(status) => Some(*status),
+            Self::HandlerError(_) => Some(STATUS_FAILURE),
+            Self::UnknownCommand
+            | Self::IncompleteCommand
+            | Self::ParseError(_)
+            | Self::UsageError(_) => Some(STATUS_USAGE),
+        }
+    }
+}
+
+/// How a line run without an interactive editor ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ExecutedLine {
+    Succeeded,
+    Exited,
+    Failed { message: String, status: i32 },
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct CompletionRequest {
     exact_tokens: Vec<String>,
@@ -164,18 +316,30 @@ enum ParsedCompletionRequest {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParseLineError {
-    UnterminatedQuote,
-    UnexpectedQuote,
-    TrailingCharactersAfterQuote,
+    /// The quote opened at this byte offset is never closed.
+    UnterminatedQuote { offset: usize },
+    /// The line ends with a backslash, at this byte offset, that escapes
+    /// nothing.
+    TrailingBackslash { offset: usize },
+}
+
+impl ParseLineError {
+    /// Byte offset in the input line that the error points at.
+    pub fn offset(&self) -> usize {
+        match self {
+            Self::UnterminatedQuote { offset } | Self::TrailingBackslash { offset } => *offset,
+        }
+    }
 }
 
 impl fmt::Display for ParseLineError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::UnterminatedQuote => write!(f, "unterminated quote"),
-            Self::UnexpectedQuote => write!(f, "unexpected quote in unquoted token"),
-            Self::TrailingCharactersAfterQuote => {
-                write!(f, "unexpected trailing characters after closing quote")
+            Self::UnterminatedQuote { offset } => {
+                write!(f, "unterminated quote at byte {}", offset)
+            }
+            Self::TrailingBackslash { offset } => {
+                write!(f, "trailing backslash at byte {}", offset)
             }
         }
     }
@@ -191,65 +355,102 @@ struct ParsedLine {
 enum ParseState {
     Outside,
     Bare(String),
-    Quoted(String),
+    /// Inside `'...'`, opened at the given byte offset.
+    SingleQuoted(String, usize),
+    /// Inside `"..."`, opened at the given byte offset.
+    DoubleQuoted(String, usize),
     AfterQuoted(String),
 }
 
+/// Split a command line into tokens the same way the REPL does.
+pub(crate) fn parse_tokens(line: &str) -> Option<Vec<String>> {
+    parse_line(line).ok().map(|parsed| parsed.tokens)
+}
+
+/// Quote `token` so that `parse_line` reads it back unchanged.
+pub(crate) fn quote_token(token: &str) -> String {
+    let needs_quotes = token.is_empty()
+        || token
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\'));
+    if !needs_quotes {
+        return token.to_string();
+    }
+    format!("\"{}\"", token.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Split a line shell-style: whitespace separates tokens, `'...'` is taken
+/// literally, `"..."` honours `\"` and `\\`, a bare backslash escapes the next
+/// character, and adjacent segments (`"a"'b'c`) join into one token.
 fn parse_line(line: &str) -> Result<ParsedLine, ParseLineError> {
     let mut tokens = Vec::new();
     let mut state = ParseState::Outside;
-    let mut ends_with_whitespace = false;
+    let mut chars = line.char_indices().peekable();
 
-    for ch in line.chars() {
-        ends_with_whitespace = ch.is_whitespace();
+    while let Some((offset, ch)) = chars.next() {
         state = match state {
-            ParseState::Outside => {
-                if ch.is_whitespace() {
-                    ParseState::Outside
-                } else if ch == '"' {
-                    ParseState::Quoted(String::new())
-                } else {
-                    let mut buf = String::new();
-                    buf.push(ch);
-                    ParseState::Bare(buf)
+            ParseState::Outside | ParseState::Bare(_) | ParseState::AfterQuoted(_)
+                if ch.is_whitespace() =>
+            {
+                if let ParseState::Bare(buf) | ParseState::AfterQuoted(buf) = state {
+                    tokens.push(buf);
                 }
+                ParseState::Outside
             }
-            ParseState::Bare(mut buf) => {
-                if ch.is_whitespace() {
-                    tokens.push(buf);
-                    ParseState::Outside
-                } else if ch == '"' {
-                    return Err(ParseLineError::UnexpectedQuote);
-                } else {
-                    buf.push(ch);
-                    ParseState::Bare(buf)
+            ParseState::Outside | ParseState::Bare(_) | ParseState::AfterQuoted(_) => {
+                let mut buf = match state {
+                    ParseState::Bare(buf) | ParseState::AfterQuoted(buf) => buf,
+                    _ => String::new(),
+                };
+                match ch {
+                    '\'' => ParseState::SingleQuoted(buf, offset),
+                    '"' => ParseState::DoubleQuoted(buf, offset),
+                    '\\' => match chars.next() {
+                        Some((_, escaped)) => {
+                            buf.push(escaped);
+                            ParseState::Bare(buf)
+                        }
+                        None => return Err(ParseLineError::TrailingBackslash { offset }),
+                    },
+                    _ => {
+                        buf.push(ch);
+                        ParseState::Bare(buf)
+                    }
                 }
             }
-            ParseState::Quoted(mut buf) => {
-                if ch == '"' {
+            ParseState::SingleQuoted(mut buf, start) => {
+                if ch == '\'' {
                     ParseState::AfterQuoted(buf)
                 } else {
                     buf.push(ch);
-                    ParseState::Quoted(buf)
+                    ParseState::SingleQuoted(buf, start)
                 }
             }
-            ParseState::AfterQuoted(buf) => {
-                if ch.is_whitespace() {
-                    tokens.push(buf);
-                    ParseState::Outside
-                } else {
-                    return Err(ParseLineError::TrailingCharactersAfterQuote);
+            ParseState::DoubleQuoted(mut buf, start) => match ch {
+                '"' => ParseState::AfterQuoted(buf),
+                '\\' if matches!(chars.peek(), Some((_, '"' | '\\'))) => {
+                    let (_, escaped) = chars.next().expect("peeked character");
+                    buf.push(escaped);
+                    ParseState::DoubleQuoted(buf, start)
                 }
-            }
+                _ => {
+                    buf.push(ch);
+                    ParseState::DoubleQuoted(buf, start)
+                }
+            },
         };
     }
 
+    let ends_with_whitespace =
+        matches!(state, ParseState::Outside) && line.ends_with(char::is_whitespace);
     let ended_after_quoted_token = matches!(state, ParseState::AfterQuoted(_));
 
     match state {
         ParseState::Outside => {}
         ParseState::Bare(buf) | ParseState::AfterQuoted(buf) => tokens.push(buf),
-        ParseState::Quoted(_) => return Err(ParseLineError::UnterminatedQuote),
+        ParseState::SingleQuoted(_, offset) | ParseState::DoubleQuoted(_, offset) => {
+            return Err(ParseLineError::UnterminatedQuote { offset });
+        }
     }
 
     Ok(ParsedLine {
@@ -259,7 +460,46 @@ fn parse_line(line: &str) -> Result<ParsedLine, ParseLineError> {
     })
 }
 
-pub(crate) fn format_completions(items: &[CompletionItem]) -> String {
+/// Describe a parse error, echoing `line` with a caret under the offending
+/// character.
+fn format_parse_error(line: &str, err: &ParseLineError) -> String {
+    let column = line[..err.offset()].chars().count();
+    format!(
+        "parse error: {}\n  {}\n  {}^",
+        err,
+        line,
+        " ".repeat(column)
+    )
+}
+
+/// `<name> <status>`, which leaves like `<name>` and sets the exit status.
+fn exit_with_status_cmd(name: &str) -> cmd::Cmd {
+    let mut builder = cmd::CmdBuilder::new();
+    builder
+        .literal_with_doc(name, EXIT_DOC)
+        .positional_arg_with_doc("status", "exit status to report")
+        .command_doc("leave, setting the exit status");
+    builder.build()
+}
+
+fn exit_handler(repl: &mut Repl, inputs: &CommandInputs) -> HandlerResult {
+    if let Some(status) = inputs.positionals.first() {
+        let status = status.parse::<i32>().map_err(|_| {
+            HandlerError(format!("exit status must be a number, not '{}'", status))
+        })?;
+        repl.exit_status = Some(status);
+    }
+
+    let at_root = repl
+        .current_mode_id()
+        .map_err(|err| HandlerError(format!("exit error: {:?}", err)))?
+        == 0;
+    Ok(if at_root { Action::Exit } else { Action::PopMode })
+}
+
+/// Render completion candidates and their docs for a `width`-column terminal,
+/// laid out as `help` lays out commands.
+pub(crate) fn format_completions(items: &[CompletionItem], width: usize) -> String {
     let mut out = String::new();
     out.push('\n');
     out.push_str("Possible completions:\n");
@@ -269,40 +509,87 @@ pub(crate) fn format_completions(items: &[CompletionItem]) -> String {
         return out;
     }
 
-    let width = items
+    let entries = items
+        .iter()
+        .map(|item| (item.token.clone(), item.doc.clone()))
+        .collect::<Vec<_>>();
+    out.push_str(&format_help(&entries, width));
+    out.push('\n');
+    out
+}
+
+/// Render (phrase, doc) pairs as an aligned two-column listing.
+///
+/// Docs are wrapped to fit `width`, with continuation lines indented to the
+/// doc column.
+fn format_help(entries: &[HelpEntry], width: usize) -> String {
+    let phrase_width = entries
         .iter()
-        .filter(|item| !item.token.is_empty())
-        .map(|item| item.token.len())
+        .map(|(phrase, _)| phrase.chars().count())
         .max()
         .unwrap_or(0);
+    let doc_column = phrase_width + 4;
+    let doc_width = width.saturating_sub(doc_column).max(20);
 
-    for item in items {
-        match item.doc.as_deref() {
-            Some(doc) => {
+    let mut out = String::new();
+    for (phrase, doc) in entries {
+        let Some(doc) = doc else {
+            out.push_str(&format!("  {}\n", phrase));
+            continue;
+        };
+        for (idx, line) in wrap_words(doc, doc_width).iter().enumerate() {
+            if idx == 0 {
                 out.push_str(&format!(
                     "  {:<width$}  {}\n",
-                    item.token,
-                    doc,
-                    width = width
+                    phrase,
+                    line,
+                    width = phrase_width
                 ));
+            } else {
+                out.push_str(&format!("{:doc_column$}{}\n", "", line));
             }
-            None => out.push_str(&format!("  {}\n", item.token)),
         }
     }
-
-    out.push('\n');
     out
 }
 
+/// Greedily wrap `text` at whitespace. Words longer than `width` get a line of
+/// their own rather than being split.
+fn wrap_words(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let needed = current.chars().count() + 1 + word.chars().count();
+        if !current.is_empty() && needed > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct CompletionSnapshot {
     modes: Vec<mode::Mode>,
     stack: Vec<ModeId>,
+    arg_completers: Vec<ArgCompleter>,
+    variables: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct TabCompletion {
-    pub insert_suffix: String,
+pub(crate) enum TabCompletion {
+    /// Text to insert at the cursor to extend the partial token.
+    Insert(String),
+    /// Several candidates share no longer prefix; list them for the user.
+    Candidates(Vec<CompletionItem>),
+    /// Nothing matches the current input.
+    NoMatch,
 }
 
 impl CompletionSnapshot {
@@ -324,7 +611,72 @@ impl CompletionSnapshot {
         self.complete_request(&req)
     }
 
+    /// Candidates for the words after a command stem that has an argument
+    /// completer, or after a built-in that takes a variable name. `None`
+    /// leaves completion to the command tree.
+    ///
+    /// The deepest stem the typed words reach wins, so a completer also takes
+    /// over any longer phrases registered under its stem.
+    fn complete_arguments(
+        &self,
+        req: &CompletionRequest,
+    ) -> Result<Option<Vec<CompletionItem>>, ReplError> {
+        let mode_id = self.current_mode_id()?;
+        let mode = self.current_mode()?;
+        let mut state = mode.root_state();
+        let mut found = None;
+        for (idx, token) in req.exact_tokens.iter().enumerate() {
+            let Some(step) = mode.step(state, token) else {
+                break;
+            };
+            state = step.next_state;
+            if let Some(completer) = self
+                .arg_completers
+                .iter()
+                .find(|completer| completer.mode_id == mode_id && completer.state == state)
+            {
+                found = Some((completer, idx + 1));
+            }
+        }
+
+        if let Some((completer, stem_len)) = found {
+            let context = CompletionContext {
+                mode: mode.name(),
+                variables: &self.variables,
+            };
+            let prior_args = req.exact_tokens[stem_len..]
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            return Ok(Some((completer.complete)(&context, &prior_args, &req.partial)));
+        }
+
+        match req.exact_tokens.as_slice() {
+            [builtin]
+                if VARIABLE_BUILTINS.contains(&builtin.as_str())
+                    && mode.step(mode.root_state(), builtin).is_none() =>
+            {
+                Ok(Some(
+                    self.variables
+                        .iter()
+                        .filter(|name| name.starts_with(&req.partial))
+                        .map(|name| CompletionItem {
+                            token: name.clone(),
+                            doc: None,
+                        })
+                        .collect(),
+                ))
+            }
+            _ => Ok(None),
+        }
+    }
+
     fn complete_request(&self, req: &CompletionRequest) -> Result<Vec<CompletionItem>, ReplError> {
+        if let Some(mut completions) = self.complete_arguments(req)? {
+            completions.sort_by(|a, b| a.token.cmp(&b.token));
+            return Ok(completions);
+        }
+
         let mode = self.current_mode()?;
         let mut state = mode.root_state();
 
@@ -355,29 +707,34 @@ impl CompletionSnapshot {
         Ok(completions)
     }
 
-    pub(crate) fn tab_completion(&self, prefix: &str) -> Result<Option<TabCompletion>, ReplError> {
+    pub(crate) fn tab_completion(&self, prefix: &str) -> Result<TabCompletion, ReplError> {
         let Some(req) = Repl::completion_request_from_prefix(prefix) else {
-            return Ok(None);
+            return Ok(TabCompletion::NoMatch);
         };
-        let mode = self.current_mode()?;
-        let mut state = mode.root_state();
-
-        for token in &req.exact_tokens {
-            let step = match mode.step(state, token) {
-                Some(step) => step,
-                None => return Ok(None),
-            };
-            state = step.next_state;
-        }
+        let mut candidates = match self.complete_arguments(&req)? {
+            Some(items) => items.into_iter().map(|item| item.token).collect::<Vec<_>>(),
+            None => {
+                let mode = self.current_mode()?;
+                let mut state = mode.root_state();
+
+                for token in &req.exact_tokens {
+                    let step = match mode.step(state, token) {
+                        Some(step) => step,
+                        None => return Ok(TabCompletion::NoMatch),
+                    };
+                    state = step.next_state;
+                }
 
-        let mut candidates = mode
-            .get_completions(state, &req.partial)
-            .into_iter()
-            .map(str::to_string)
-            .collect::<Vec<_>>();
+                mode.get_completions(state, &req.partial)
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            }
+        };
         candidates.sort();
+        candidates.dedup();
         if candidates.is_empty() {
-            return Ok(None);
+            return Ok(TabCompletion::NoMatch);
         }
 
         let replacement = if candidates.len() == 1 {
@@ -386,16 +743,47 @@ impl CompletionSnapshot {
             longest_common_prefix(&candidates)
         };
 
-        if replacement == req.partial {
-            return Ok(None);
+        match replacement.strip_prefix(&req.partial) {
+            Some(insert_suffix) if !insert_suffix.is_empty() => {
+                Ok(TabCompletion::Insert(insert_suffix.to_string()))
+            }
+            _ if candidates.len() > 1 => {
+                Ok(TabCompletion::Candidates(self.complete_request(&req)?))
+            }
+            _ => Ok(TabCompletion::NoMatch),
+        }
+    }
+}
+
+/// Joins physical lines ending in a backslash into one logical line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct LineContinuation {
+    pending: String,
+    continuing: bool,
+}
+
+impl LineContinuation {
+    /// Add a physical line. Returns the logical line once one is complete.
+    fn push(&mut self, line: &str) -> Option<String> {
+        let line = line.trim_end_matches(['\n', '\r']);
+        if let Some(head) = line.strip_suffix('\\') {
+            self.pending.push_str(head);
+            self.continuing = true;
+            return None;
         }
 
-        let Some(insert_suffix) = replacement.strip_prefix(&req.partial) else {
-            return Ok(None);
-        };
-        Ok(Some(TabCompletion {
-            insert_suffix: insert_suffix.to_string(),
-        }))
+        self.pending.push_str(line);
+        self.continuing = false;
+        Some(std::mem::take(&mut self.pending))
+    }
+
+    fn is_continuing(&self) -> bool {
+        self.continuing
+    }
+
+    fn cancel(&mut self) {
+        self.pending.clear();
+        self.continuing = false;
     }
 }
 
@@ -421,14 +809,374 @@ fn longest_common_prefix(candidates: &[String]) -> String {
     prefix
 }
 
+/// Levenshtein distance over chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b_chars.len()).collect::<Vec<_>>();
+    let mut curr = vec![0; b_chars.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b_chars.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b_chars.len()]
+}
+
+/// The names of the built-in commands listed by `help`.
+fn builtin_names() -> Vec<&'static str> {
+    BUILTIN_HELP
+        .iter()
+        .filter_map(|(usage, _)| usage.split_whitespace().next())
+        .filter(|name| !name.starts_with('!'))
+        .collect()
+}
+
+/// A command spec loaded into the root mode.
+struct LoadedSpec {
+    path: PathBuf,
+    /// The root mode without the spec's commands. Commands registered in the
+    /// root mode later are added here too, so the spec's commands always come
+    /// last and rebuilding the root mode leaves every other state in place.
+    base: mode::Mode,
+    commands: Vec<(CommandId, spec::SpecCommand)>,
+}
+
+impl LoadedSpec {
+    /// `base` with the spec's commands added, reporting the first one that
+    /// conflicts with another command as `InvalidData`.
+    fn root_mode(&self) -> io::Result<mode::Mode> {
+        let mut root = self.base.clone();
+        for (command_id, command) in &self.commands {
+            root.insert_cmd(&command.cmd(), *command_id).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{}:{}: '{}' conflicts with an existing command",
+                        self.path.display(),
+                        command.line,
+                        command.phrase
+                    ),
+                )
+            })?;
+        }
+        Ok(root)
+    }
+
+    fn action(&self, command_id: CommandId) -> Option<&spec::SpecAction> {
+        self.commands
+            .iter()
+            .find(|(id, _)| *id == command_id)
+            .map(|(_, command)| &command.action)
+    }
+}
+
+/// Read an rc file for `reload`, rejecting it if any line does not parse.
+///
+/// A missing file is `None`, as it is when loading at startup.
+fn read_rc_file(path: &Path) -> io::Result<Option<String>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    for (idx, line) in text.lines().enumerate() {
+        if let Err(err) = parse_line(line) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}:{}: {}", path.display(), idx + 1, err),
+            ));
+        }
+    }
+    Ok(Some(text))
+}
+
+fn reload_error(path: &Path, err: io::Error) -> HandlerError {
+    // Malformed files already name the offending line.
+    let location = match err.kind() {
+        io::ErrorKind::InvalidData => String::new(),
+        _ => format!("{}: ", path.display()),
+    };
+    HandlerError(format!("reload failed, keeping the current state: {}{}", location, err))
+}
+
 impl Repl {
     pub fn new() -> Self {
-        Self {
+        let mut repl = Self {
             modes: vec![mode::Mode::new(0, "global")],
             stack: vec![0],
             handlers: Vec::new(),
             capture_specs: Vec::new(),
+            history: history::History::default(),
+            history_path: None,
+            aliases: alias::Aliases::default(),
+            alias_path: None,
+            arg_completers: Vec::new(),
+            rc_path: None,
+            spec: None,
+            spec_command_ids: Vec::new(),
+            variables: vars::Variables::default(),
+            strict_variables: false,
+            prompt_template: prompt::PromptTemplate::default(),
+            last_status: STATUS_SUCCESS,
+            shell: Box::new(shell::SystemShell),
+            last_shell_command: None,
+            exit_handlers: (0, 0),
+            exit_status: None,
+            output: None,
+            clock: Box::new(timing::SystemClock),
+            timings: false,
+            last_duration: None,
+            duration_reported: false,
+        };
+        repl.exit_handlers = (
+            repl.register_handler(Box::new(exit_handler), Vec::new()),
+            repl.register_handler(
+                Box::new(exit_handler),
+                exit_with_status_cmd(EXIT_COMMANDS[0])
+                    .capture_spec()
+                    .expect("exit command schema is valid"),
+            ),
+        );
+        repl.register_exit_commands(0);
+        repl
+    }
+
+    /// Replace the prompt template. On error the current prompt is kept.
+    ///
+    /// Placeholders are `{mode}` (the mode stack, e.g. `global/write`),
+    /// `{cwd}`, `{time}` (UTC `HH:MM:SS`) and `{status}` (whether the last
+    /// command succeeded, colored when stdout is a terminal and `NO_COLOR` is
+    /// unset). Unknown placeholders are printed as written.
+    pub fn set_prompt_template(&mut self, template: &str) -> Result<(), PromptTemplateError> {
+        self.prompt_template = prompt::PromptTemplate::parse(template)?;
+        Ok(())
+    }
+
+    /// Cap the number of remembered lines, dropping the oldest beyond it.
+    pub fn set_history_size(&mut self, size: usize) {
+        self.history.set_max_len(size);
+    }
+
+    /// Lines submitted in this session, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &str> {
+        self.history.iter()
+    }
+
+    /// `$XDG_DATA_HOME/tli42/history`, falling back to `~/.local/share`.
+    pub fn default_history_file() -> Option<PathBuf> {
+        history::default_history_path()
+    }
+
+    /// Load history from `path`, compact the file to the loaded entries and
+    /// append each new entry to it.
+    ///
+    /// Compacting drops what loading dropped, the repeats left by concurrent
+    /// sessions and entries past the history size, so the file does not grow
+    /// without bound. The path is kept even when loading fails, so a damaged or
+    /// unreadable file never prevents the REPL from starting.
+    pub fn set_history_file(&mut self, path: impl Into<PathBuf>) -> io::Result<()> {
+        let path = path.into();
+        let loaded = self.history.load_file(&path).and_then(|()| {
+            if self.history.iter().next().is_none() {
+                return Ok(());
+            }
+            history::rewrite_file(&path, self.history.iter())
+        });
+        self.history_path = Some(path);
+        loaded
+    }
+
+    /// `$XDG_DATA_HOME/tli42/aliases.toml`, falling back to `~/.local/share`.
+    pub fn default_alias_file() -> Option<PathBuf> {
+        alias::default_alias_path()
+    }
+
+    /// Load aliases from `path` and save every later change back to it.
+    ///
+    /// As with the history file, the path is kept even when loading fails.
+    pub fn set_alias_file(&mut self, path: impl Into<PathBuf>) -> io::Result<()> {
+        let path = path.into();
+        let loaded = self.aliases.load_file(&path);
+        self.alias_path = Some(path);
+        loaded
+    }
+
+    /// `rc` in the user's config directory, if one can be determined.
+    pub fn default_rc_file() -> Option<PathBuf> {
+        history::config_dir().map(|dir| dir.join(RC_FILE_NAME))
+    }
+
+    /// Run the startup commands in the rc file at `path`, e.g. to define
+    /// aliases or set the prompt before the first prompt is shown.
+    ///
+    /// Lines run exactly as in `run_file`, but a failing line only prints a
+    /// warning with its line number and the rest of the file still runs. A
+    /// missing file is not an error.
+    pub fn load_rc_file(&mut self, path: &Path) -> io::Result<i32> {
+        self.rc_path = Some(path.to_path_buf());
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(STATUS_SUCCESS),
+            Err(err) => return Err(err),
+        };
+        self.execute_lines(io::BufReader::new(file), Some(path), true)
+    }
+
+    /// Register the commands declared in the TOML spec at `path` in the root
+    /// mode, replacing those of a spec loaded before.
+    ///
+    /// The spec is a list of `[[command]]` tables, each with a `phrase` as for
+    /// `ReplBuilder::command`, an optional `doc` and an `action`:
+    ///
+    /// ```toml
+    /// [[command]]
+    /// phrase = "git commit <message>"
+    /// doc = "record changes"
+    /// action = "shell:git commit -m"
+    ///
+    /// [[command]]
+    /// phrase = "past"
+    /// action = "builtin:history"
+    /// ```
+    ///
+    /// A `shell:` action runs through the shell executor, as `!` does, with
+    /// the command's arguments quoted and appended. A `builtin:` action runs
+    /// the named built-in with the arguments appended. Nothing is registered
+    /// if any command is invalid or conflicts with another; the error names
+    /// the offending line. `reload` reads the spec again.
+    pub fn load_spec(&mut self, path: &Path) -> io::Result<()> {
+        let commands = spec::load_file(path, &builtin_names())?;
+        self.install_spec(path.to_path_buf(), commands)?;
+        Ok(())
+    }
+
+    /// Rebuild the root mode with `commands` in place of the current spec's,
+    /// returning the commands replaced. On error nothing changes.
+    fn install_spec(
+        &mut self,
+        path: PathBuf,
+        commands: Vec<spec::SpecCommand>,
+    ) -> io::Result<Vec<spec::SpecCommand>> {
+        while self.spec_command_ids.len() < commands.len() {
+            // Spec commands are run by `dispatch_tokens`, not their handler.
+            let id = self.register_handler(Box::new(|_, _| Ok(Action::None)), Vec::new());
+            self.spec_command_ids.push(id);
+        }
+        let base = match &self.spec {
+            Some(loaded) => loaded.base.clone(),
+            None => self.modes[0].clone(),
+        };
+        let ids = self.spec_command_ids.iter().copied();
+        let loaded = LoadedSpec {
+            path,
+            base,
+            commands: ids.zip(commands).collect(),
+        };
+        let root = loaded.root_mode()?;
+
+        for (command_id, command) in &loaded.commands {
+            self.capture_specs[*command_id as usize] = command
+                .cmd()
+                .capture_spec()
+                .expect("phrases have no labeled arguments");
+        }
+        self.modes[0] = root;
+        let previous = self.spec.replace(loaded).map(|loaded| loaded.commands);
+        Ok(previous
+            .into_iter()
+            .flatten()
+            .map(|(_, command)| command)
+            .collect())
+    }
+
+    /// Re-read the spec and alias files and run the rc file again, reporting
+    /// which commands and aliases changed.
+    ///
+    /// All three files are read and checked before anything is replaced, so
+    /// a malformed file leaves the current commands and aliases in place.
+    /// Variables and history are kept.
+    fn reload(&mut self) -> Result<String, HandlerError> {
+        let spec = match &self.spec {
+            Some(loaded) => {
+                let commands = spec::load_file(&loaded.path, &builtin_names())
+                    .map_err(|err| reload_error(&loaded.path, err))?;
+                Some((loaded.path.clone(), commands))
+            }
+            None => None,
+        };
+        let mut aliases = alias::Aliases::default();
+        if let Some(path) = &self.alias_path {
+            aliases.load_file(path).map_err(|err| reload_error(path, err))?;
+        }
+        let rc = match &self.rc_path {
+            Some(path) => read_rc_file(path)
+                .map_err(|err| reload_error(path, err))?
+                .map(|text| (path.clone(), text)),
+            None => None,
+        };
+
+        let mut changes = String::new();
+        if let Some((path, commands)) = spec {
+            let previous = self
+                .install_spec(path.clone(), commands)
+                .map_err(|err| reload_error(&path, err))?;
+            let current = self.spec.as_ref().expect("spec just installed");
+            let current = current.commands.iter().map(|(_, command)| command.clone());
+            changes += &spec::format_changes(&previous, &current.collect::<Vec<_>>());
+        }
+        let previous = std::mem::replace(&mut self.aliases, aliases);
+        if let Some((path, text)) = rc {
+            self.execute_lines(io::Cursor::new(text), Some(&path), true)
+                .map_err(|err| reload_error(&path, err))?;
+        }
+        changes += &alias::format_changes(&previous, &self.aliases);
+        if changes.is_empty() {
+            changes = "no changes\n".to_string();
+        }
+        Ok(changes)
+    }
+
+    fn save_aliases(&self) -> Result<(), HandlerError> {
+        let Some(path) = &self.alias_path else {
+            return Ok(());
+        };
+        self.aliases.save_file(path).map_err(|err| {
+            HandlerError(format!("failed to save aliases to {}: {}", path.display(), err))
+        })
+    }
+
+    fn record_history(&mut self, line: &str) -> bool {
+        if !self.history.push(line) {
+            return false;
+        }
+        if let Some(path) = &self.history_path
+            && let Err(err) = history::append_to_file(path, line)
+        {
+            eprintln!(
+                "warning: failed to write history file {}: {}",
+                path.display(),
+                err
+            );
         }
+        true
+    }
+
+    fn clear_history(&mut self) -> Result<(), HandlerError> {
+        self.history.clear();
+        let Some(path) = &self.history_path else {
+            return Ok(());
+        };
+        history::truncate_file(path).map_err(|err| {
+            HandlerError(format!(
+                "failed to clear history file {}: {}",
+                path.display(),
+                err
+            ))
+        })
     }
 
     pub fn current_mode_id(&self) -> Result<ModeId, ReplError> {
@@ -440,12 +1188,60 @@ impl Repl {
         self.get_mode(id)
     }
 
+    /// Start building a REPL from command phrases.
+    pub fn builder() -> ReplBuilder {
+        ReplBuilder::new()
+    }
+
+    /// Lay out help and completions for a terminal `width` columns wide
+    /// instead of the detected width, or detect it again with `None`.
+    ///
+    /// The width is otherwise taken from `COLUMNS` or the terminal itself,
+    /// and re-read before each listing so resizes are noticed.
+    pub fn set_terminal_width(&mut self, width: Option<usize>) {
+        term::set_width_override(width);
+    }
+
+    /// Send what handlers print through a `CommandContext` to `output`
+    /// instead of stdout. Output sent elsewhere is never paged.
+    pub fn set_output(&mut self, output: Box<dyn io::Write>) {
+        self.output = Some(output);
+    }
+
+    /// Show what a command printed: through the pager when it goes to stdout,
+    /// as is otherwise.
+    pub(crate) fn write_output(&mut self, text: &[u8]) {
+        match &mut self.output {
+            Some(output) => {
+                let _ = output.write_all(text).and_then(|()| output.flush());
+            }
+            None => pager::page(&String::from_utf8_lossy(text)),
+        }
+    }
+
+    /// Add a mode, which starts out with the `exit` and `quit` commands.
     pub fn add_mode(&mut self, name: impl Into<String>) -> ModeId {
         let id = self.modes.len() as ModeId;
         self.modes.push(mode::Mode::new(id, name));
+        self.register_exit_commands(id);
         id
     }
 
+    fn register_exit_commands(&mut self, mode_id: ModeId) {
+        let (bare, with_status) = self.exit_handlers;
+        for name in EXIT_COMMANDS {
+            let mut builder = cmd::CmdBuilder::new();
+            builder
+                .literal_with_doc(name, EXIT_DOC)
+                .command_doc(EXIT_DOC);
+            self.register_command_in_mode(mode_id, &builder.build(), bare)
+                .and_then(|()| {
+                    self.register_command_in_mode(mode_id, &exit_with_status_cmd(name), with_status)
+                })
+                .expect("a new mode has no other commands");
+        }
+    }
+
     fn get_mode(&self, id: ModeId) -> Result<&mode::Mode, ReplError> {
         self.modes
             .get(id as usize)
@@ -496,6 +1292,14 @@ impl Repl {
         cmd.capture_spec()?;
         let mode = self.get_mode_mut(mode_id)?;
         mode.insert_cmd(cmd, command_id)?;
+        if mode_id == 0
+            && let Some(loaded) = &mut self.spec
+        {
+            loaded.base.insert_cmd(cmd, command_id)?;
+            self.modes[0] = loaded
+                .root_mode()
+                .expect("the spec's commands fit alongside this one");
+        }
         Ok(())
     }
 
@@ -523,8 +1327,18 @@ impl Repl {
     ) -> Result<(), ReplError> {
         let tokens = Self::normalize_stem(stem)?;
         let (parent_state, literal) = self.resolve_edge_doc_target(mode_id, &tokens)?;
+        let doc = doc.into();
+        if mode_id == 0
+            && let Some(loaded) = &mut self.spec
+        {
+            // States of the spec's commands are not in the base mode; their
+            // docs come from the spec.
+            let _ = loaded
+                .base
+                .set_literal_edge_doc(parent_state, literal, doc.clone());
+        }
         let mode = self.get_mode_mut(mode_id)?;
-        let found = mode.set_literal_edge_doc(parent_state, literal, doc.into())?;
+        let found = mode.set_literal_edge_doc(parent_state, literal, doc)?;
         if found {
             Ok(())
         } else {
@@ -540,8 +1354,14 @@ impl Repl {
     ) -> Result<(), ReplError> {
         let tokens = Self::normalize_stem(stem)?;
         let state = self.resolve_state_path(mode_id, &tokens)?;
+        let doc = doc.into();
+        if mode_id == 0
+            && let Some(loaded) = &mut self.spec
+        {
+            let _ = loaded.base.set_command_doc(state, doc.clone());
+        }
         let mode = self.get_mode_mut(mode_id)?;
-        let found = mode.set_command_doc(state, doc.into())?;
+        let found = mode.set_command_doc(state, doc)?;
         if found {
             Ok(())
         } else {
@@ -549,6 +1369,31 @@ impl Repl {
         }
     }
 
+    /// Complete the words typed after `stem` in `mode_id` with `completer`
+    /// instead of the command tree, e.g. to offer file names or values the
+    /// application knows about. Its candidates' docs are shown when they are
+    /// listed. Setting a completer for the same stem again replaces it.
+    pub fn set_arg_completer<F>(
+        &mut self,
+        mode_id: ModeId,
+        stem: &str,
+        completer: F,
+    ) -> Result<(), ReplError>
+    where
+        F: Fn(&CompletionContext<'_>, &[&str], &str) -> Vec<CompletionItem> + Send + Sync + 'static,
+    {
+        let tokens = Self::normalize_stem(stem)?;
+        let state = self.resolve_state_path(mode_id, &tokens)?;
+        self.arg_completers
+            .retain(|existing| existing.mode_id != mode_id || existing.state != state);
+        self.arg_completers.push(ArgCompleter {
+            mode_id,
+            state,
+            complete: Arc::new(completer),
+        });
+        Ok(())
+    }
+
     fn prompt(&self) -> Result<String, ReplError> {
         if self.stack.is_empty() {
             return Err(ReplError::EmptyModeStack);
@@ -558,7 +1403,24 @@ impl Repl {
         for mode_id in &self.stack {
             names.push(self.get_mode(*mode_id)?.name().to_string());
         }
-        Ok(format!("{}> ", names.join("/")))
+
+        let cwd = std::env::current_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        let home = std::env::var("HOME").ok();
+        let epoch_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        let context = prompt::PromptContext {
+            mode: names.join("/"),
+            cwd: prompt::abbreviate_home(&cwd, home.as_deref()),
+            time: prompt::format_time_of_day(epoch_secs),
+            status: self.last_status,
+            color: io::stdout().is_terminal()
+                && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+        };
+        Ok(self.prompt_template.expand(&context))
     }
 
     fn normalize_stem(stem: &str) -> Result<Vec<String>, ReplError> {
@@ -660,6 +1522,8 @@ impl Repl {
         CompletionSnapshot {
             modes: self.modes.clone(),
             stack: self.stack.clone(),
+            arg_completers: self.arg_completers.clone(),
+            variables: self.variables.names().map(str::to_string).collect(),
         }
     }
 
@@ -750,72 +1614,312 @@ impl Repl {
     }
 
     fn run_with_editor<E: editor::LineEditor>(&mut self, editor: &mut E) -> io::Result<()> {
+        for entry in self.history.iter() {
+            editor.add_history_entry(entry)?;
+        }
+
+        let mut continuation = LineContinuation::default();
+        let mut interrupted_empty_line = false;
         loop {
             editor.set_completion_snapshot(self.completion_snapshot())?;
-            let prompt = self
-                .prompt()
-                .map_err(|e| io::Error::other(format!("repl prompt error: {:?}", e)))?;
+            let prompt = if continuation.is_continuing() {
+                CONTINUATION_PROMPT.to_string()
+            } else {
+                self.prompt()
+                    .map_err(|e| io::Error::other(format!("repl prompt error: {:?}", e)))?
+            };
 
-            let line = match editor.read_line(&prompt)? {
+            let read = editor.read_line(&prompt)?;
+            let was_interrupted_empty_line = std::mem::take(&mut interrupted_empty_line);
+            let line = match read {
                 editor::EditorRead::Line(line) => line,
-                editor::EditorRead::Interrupted => continue,
-                editor::EditorRead::Eof => break,
+                editor::EditorRead::Interrupted { empty_line } => {
+                    // Ctrl-C discards the pending input. A second one on an
+                    // empty prompt leaves the REPL.
+                    if empty_line && !continuation.is_continuing() {
+                        if was_interrupted_empty_line {
+                            break;
+                        }
+                        interrupted_empty_line = true;
+                        println!("(press Ctrl-C again to exit)");
+                    }
+                    continuation.cancel();
+                    continue;
+                }
+                editor::EditorRead::Eof => {
+                    // Like bash, end the dangling prompt line and say why.
+                    println!("\nexit");
+                    break;
+                }
+            };
+            let Some(line) = continuation.push(&line) else {
+                continue;
             };
 
-            if self.should_add_history_entry(&line) {
+            if self.should_add_history_entry(&line) && self.record_history(&line) {
                 editor.add_history_entry(&line)?;
             }
 
-            match self
+            let outcome = self
                 .run_once(&line)
-                .map_err(|e| io::Error::other(format!("repl runtime error: {:?}", e)))?
-            {
-                RunOnceOutcome::Noop => {}
+                .map_err(|e| io::Error::other(format!("repl runtime error: {:?}", e)))?;
+            if let Some(message) = self.failure_message(&line, &outcome) {
+                println!("{}", message);
+            }
+            match outcome {
                 RunOnceOutcome::Completions(items) => {
                     editor.print_completions(&items)?;
                 }
-                RunOnceOutcome::UnknownCommand => {
-                    println!("unknown command");
+                RunOnceOutcome::ActionApplied(Action::Exit) => {
+                    println!("{}", GOODBYE_MESSAGE);
+                    break;
                 }
-                RunOnceOutcome::IncompleteCommand => {
-                    println!("incomplete command");
-                }
-                RunOnceOutcome::ParseError(err) => {
-                    println!("parse error: {}", err);
-                }
-                RunOnceOutcome::HandlerError(err) => {
-                    println!("handler error: {}", err.0);
-                }
-                RunOnceOutcome::ActionApplied(Action::Exit) => break,
-                RunOnceOutcome::ActionApplied(_) => {}
+                _ => {}
             }
         }
 
         Ok(())
     }
 
-    pub fn run_once(&mut self, line: &str) -> Result<RunOnceOutcome, ReplError> {
-        if let Some(completions) = self.complete_line(line)? {
-            return Ok(RunOnceOutcome::Completions(completions));
+    /// Describe an outcome that means the line failed, or `None` otherwise.
+    fn failure_message(&self, line: &str, outcome: &RunOnceOutcome) -> Option<String> {
+        match outcome {
+            RunOnceOutcome::UnknownCommand => match self.suggest_command(line) {
+                Ok(Some(suggestion)) => Some(format!(
+                    "unknown command; did you mean '{}'?",
+                    suggestion
+                )),
+                _ => Some("unknown command".to_string()),
+            },
+            RunOnceOutcome::IncompleteCommand => Some("incomplete command".to_string()),
+            RunOnceOutcome::ParseError(err) => Some(format_parse_error(line, err)),
+            RunOnceOutcome::HandlerError(err) => Some(format!("handler error: {}", err.0)),
+            RunOnceOutcome::UsageError(usage) => Some(usage.clone()),
+            RunOnceOutcome::ShellExited(0) => None,
+            RunOnceOutcome::ShellExited(status) => {
+                Some(format!("shell command exited with status {}", status))
+            }
+            RunOnceOutcome::Noop
+            | RunOnceOutcome::Completions(_)
+            | RunOnceOutcome::ActionApplied(_) => None,
+        }
+    }
+
+    /// Run `!` commands with `executor` instead of the user's shell.
+    pub fn set_shell_executor(&mut self, executor: Box<dyn ShellExecutor>) {
+        self.shell = executor;
+    }
+
+    /// Exit status of the last command run: `STATUS_SUCCESS`,
+    /// `STATUS_FAILURE`, `STATUS_USAGE`, or whatever a `!` command exited with.
+    pub fn last_status(&self) -> i32 {
+        self.last_status
+    }
+
+    /// Print how long each command takes to run after it finishes, as the
+    /// `set timings` built-in does.
+    pub fn set_timings(&mut self, enabled: bool) {
+        self.timings = enabled;
+    }
+
+    pub fn timings(&self) -> bool {
+        self.timings
+    }
+
+    /// How long the last command took, including any `!` command.
+    pub fn last_duration(&self) -> Option<Duration> {
+        self.last_duration
+    }
+
+    /// Run each line of `input` as a command, without prompting.
+    ///
+    /// Blank lines and lines starting with `#` are skipped. Failures are
+    /// reported on stderr with their line number, and stop the script unless
+    /// `keep_going` is set. `exit` in the root mode ends the script early.
+    ///
+    /// Returns the exit status of the last command that ran, suitable for use
+    /// as the process exit code.
+    pub fn run_script<R: io::BufRead>(&mut self, input: R, keep_going: bool) -> io::Result<i32> {
+        self.execute_lines(input, None, keep_going)
+    }
+
+    /// Run the commands in the file at `path`; see `run_script`.
+    pub fn run_file(&mut self, path: &Path, keep_going: bool) -> io::Result<i32> {
+        let file = std::fs::File::open(path)?;
+        self.execute_lines(io::BufReader::new(file), Some(path), keep_going)
+    }
+
+    fn execute_lines<R: io::BufRead>(
+        &mut self,
+        input: R,
+        source: Option<&Path>,
+        keep_going: bool,
+    ) -> io::Result<i32> {
+        let mut status = STATUS_SUCCESS;
+        for (idx, line) in input.lines().enumerate() {
+            let line = line?;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            match self.execute_line(&line)? {
+                ExecutedLine::Succeeded => status = STATUS_SUCCESS,
+                ExecutedLine::Exited => {
+                    status = self.last_status;
+                    break;
+                }
+                ExecutedLine::Failed {
+                    message,
+                    status: failed,
+                } => {
+                    match source {
+                        Some(path) => eprintln!("{}:{}: {}", path.display(), idx + 1, message),
+                        None => eprintln!("line {}: {}", idx + 1, message),
+                    }
+                    status = failed;
+                    if !keep_going {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(status)
+    }
+
+    /// Run a single command line without prompting, as a shell's `-c` does.
+    ///
+    /// The line goes through the same tokenizer and dispatch as interactive
+    /// input. A failure is reported on stderr. Returns the command's exit
+    /// status.
+    pub fn run_command(&mut self, line: &str) -> io::Result<i32> {
+        match self.execute_line(line)? {
+            ExecutedLine::Succeeded => Ok(STATUS_SUCCESS),
+            ExecutedLine::Exited => Ok(self.last_status),
+            ExecutedLine::Failed { message, status } => {
+                eprintln!("{}", message);
+                Ok(status)
+            }
+        }
+    }
+
+    fn execute_line(&mut self, line: &str) -> io::Result<ExecutedLine> {
+        let outcome = self
+            .run_once(line)
+            .map_err(|e| io::Error::other(format!("repl runtime error: {:?}", e)))?;
+        if let Some(message) = self.failure_message(line, &outcome) {
+            return Ok(ExecutedLine::Failed {
+                message,
+                status: outcome.status().unwrap_or(STATUS_FAILURE),
+            });
+        }
+        match outcome {
+            RunOnceOutcome::Completions(items) => {
+                print!("{}", format_completions(&items, term::terminal_width()))
+            }
+            RunOnceOutcome::ActionApplied(Action::Exit) => return Ok(ExecutedLine::Exited),
+            _ => {}
+        }
+        Ok(ExecutedLine::Succeeded)
+    }
+
+    /// Suggest the closest known command for a line that failed to match.
+    ///
+    /// Tokens are followed through the current mode until one does not match;
+    /// that token is replaced by the nearest literal at that point, if any is
+    /// within a small edit distance.
+    pub fn suggest_command(&self, line: &str) -> Result<Option<String>, ReplError> {
+        let Ok(parsed) = parse_line(line) else {
+            return Ok(None);
+        };
+        let mode = self.current_mode()?;
+        let mut state = mode.root_state();
+
+        for (idx, token) in parsed.tokens.iter().enumerate() {
+            if let Some(step) = mode.step(state, token) {
+                state = step.next_state;
+                continue;
+            }
+
+            let max_distance = (token.chars().count() / 2).clamp(1, 3);
+            let nearest = mode
+                .get_completions(state, "")
+                .into_iter()
+                .map(|candidate| (edit_distance(token, candidate), candidate))
+                .filter(|(distance, _)| *distance <= max_distance)
+                .min();
+            let Some((_, candidate)) = nearest else {
+                return Ok(None);
+            };
+
+            let mut suggestion = parsed.tokens[..idx].to_vec();
+            suggestion.push(candidate.to_string());
+            return Ok(Some(suggestion.join(" ")));
+        }
+
+        Ok(None)
+    }
+
+    pub fn run_once(&mut self, line: &str) -> Result<RunOnceOutcome, ReplError> {
+        let start = self.clock.now();
+        let outcome = self.dispatch_line(line)?;
+        let elapsed = self.clock.now().saturating_duration_since(start);
+        let reported = std::mem::take(&mut self.duration_reported);
+        if let Some(status) = outcome.status() {
+            self.last_status = status;
+            self.last_duration = Some(elapsed);
+            if self.timings && !reported {
+                print!("{}", timing::format_timing(elapsed));
+            }
+        }
+        if let Some(status) = self.exit_status.take() {
+            self.last_status = status;
+        }
+        Ok(outcome)
+    }
+
+    fn dispatch_line(&mut self, line: &str) -> Result<RunOnceOutcome, ReplError> {
+        if let Some(command) = line.trim_start().strip_prefix('!') {
+            return Ok(self.run_shell_command(command));
+        }
+
+        let expansion = self.variables.expand(line);
+        if let Some(name) = expansion.undefined.first()
+            && self.strict_variables
+        {
+            let err = vars::VarError::Undefined(name.clone());
+            return Ok(RunOnceOutcome::HandlerError(HandlerError(err.to_string())));
+        }
+        for name in &expansion.undefined {
+            eprintln!("warning: {}", vars::VarError::Undefined(name.clone()));
+        }
+        let line = expansion.line.as_str();
+
+        if let Some(completions) = self.complete_line(line)? {
+            return Ok(RunOnceOutcome::Completions(completions));
         }
 
         let parsed = match parse_line(line) {
             Ok(parsed) => parsed,
             Err(err) => return Ok(RunOnceOutcome::ParseError(err)),
         };
-        if parsed.tokens.is_empty() {
+        self.dispatch_tokens(parsed.tokens)
+    }
+
+    /// Run an already expanded and tokenized command line: aliases, then
+    /// built-ins, then the current mode's commands.
+    fn dispatch_tokens(&mut self, tokens: Vec<String>) -> Result<RunOnceOutcome, ReplError> {
+        if tokens.is_empty() {
             return Ok(RunOnceOutcome::Noop);
         }
-        let tokens = parsed.tokens;
+        let tokens = if self.mode_claims(&tokens[0])? {
+            tokens
+        } else {
+            self.aliases.expand(tokens)
+        };
 
-        if tokens.first().map(String::as_str) == Some("exit") {
-            let action = if self.current_mode_id()? == 0 {
-                Action::Exit
-            } else {
-                Action::PopMode
-            };
-            let applied = self.apply(action)?;
-            return Ok(RunOnceOutcome::ActionApplied(applied));
+        if let Some(outcome) = self.run_builtin(&tokens)? {
+            return Ok(outcome);
         }
 
         let (command_id, captures) = {
@@ -846,6 +1950,13 @@ impl Repl {
             Ok(inputs) => inputs,
             Err(err) => return Ok(RunOnceOutcome::HandlerError(err)),
         };
+        let spec_action = self
+            .spec
+            .as_ref()
+            .and_then(|loaded| loaded.action(command_id));
+        if let Some(action) = spec_action.cloned() {
+            return self.run_spec_action(action, inputs.positionals);
+        }
 
         let action = match self.invoke_handler(command_id, &inputs) {
             Ok(action) => action,
@@ -856,17 +1967,279 @@ impl Repl {
         Ok(RunOnceOutcome::ActionApplied(applied))
     }
 
-    pub fn run(&mut self) -> io::Result<()> {
+    /// Run the text after `!` with the shell executor. A bare `!` repeats
+    /// the previous shell command.
+    fn run_shell_command(&mut self, command: &str) -> RunOnceOutcome {
+        let command = if command.trim().is_empty() {
+            match &self.last_shell_command {
+                Some(previous) => previous.clone(),
+                None => {
+                    return RunOnceOutcome::HandlerError(HandlerError(
+                        "no previous shell command".to_string(),
+                    ));
+                }
+            }
+        } else {
+            command.to_string()
+        };
+
+        self.last_shell_command = Some(command.clone());
+        match self.shell.execute(&command) {
+            Ok(status) => RunOnceOutcome::ShellExited(status),
+            Err(err) => RunOnceOutcome::HandlerError(HandlerError(format!(
+                "failed to run shell command: {}",
+                err
+            ))),
+        }
+    }
+
+    /// Run a spec command's action with the command's arguments appended.
+    fn run_spec_action(
+        &mut self,
+        action: spec::SpecAction,
+        args: Vec<String>,
+    ) -> Result<RunOnceOutcome, ReplError> {
+        match action {
+            spec::SpecAction::Shell(mut command) => {
+                for arg in &args {
+                    command.push(' ');
+                    command.push_str(&shell::quote(arg));
+                }
+                Ok(self.run_shell_command(&command))
+            }
+            spec::SpecAction::Builtin(mut tokens) => {
+                tokens.extend(args);
+                let outcome = self.run_builtin_command(&tokens)?;
+                Ok(outcome.unwrap_or(RunOnceOutcome::UnknownCommand))
+            }
+        }
+    }
+
+    /// Run a command provided by the REPL itself, if `tokens` names one.
+    ///
+    /// Commands registered in the current mode take precedence, so a mode
+    /// may define its own `history`.
+    fn run_builtin(&mut self, tokens: &[String]) -> Result<Option<RunOnceOutcome>, ReplError> {
+        if self.mode_claims(&tokens[0])? {
+            return Ok(None);
+        }
+        self.run_builtin_command(tokens)
+    }
+
+    /// Run the built-in `tokens` names, if any, even when the current mode
+    /// has a command of the same name.
+    fn run_builtin_command(
+        &mut self,
+        tokens: &[String],
+    ) -> Result<Option<RunOnceOutcome>, ReplError> {
+        if tokens[0] == "time" {
+            return self.time_command(&tokens[1..]).map(Some);
+        }
+        let Some(result) = self.builtin_output(tokens) else {
+            return Ok(None);
+        };
+        match result {
+            Ok(output) => {
+                pager::page(&output);
+                Ok(Some(RunOnceOutcome::ActionApplied(Action::None)))
+            }
+            Err(outcome) => Ok(Some(outcome)),
+        }
+    }
+
+    /// Run `command` as if typed on its own and print how long it took,
+    /// whether or not timings are on.
+    fn time_command(&mut self, command: &[String]) -> Result<RunOnceOutcome, ReplError> {
+        if command.is_empty() {
+            return Ok(RunOnceOutcome::UsageError(
+                "usage: time <command>".to_string(),
+            ));
+        }
+
+        // The tokens were expanded along with `time` itself; dispatch them as
+        // they are so variables and quoting are not processed a second time.
+        let start = self.clock.now();
+        let outcome = self.dispatch_tokens(command.to_vec())?;
+        let elapsed = self.clock.now().saturating_duration_since(start);
+        print!("{}", timing::format_timing(elapsed));
+        self.duration_reported = true;
+        Ok(outcome)
+    }
+
+    /// Whether the current mode has a command starting with `token`.
+    fn mode_claims(&self, token: &str) -> Result<bool, ReplError> {
+        let mode = self.current_mode()?;
+        Ok(mode.step(mode.root_state(), token).is_some())
+    }
+
+    /// Run a built-in and return what it prints, or the failed outcome.
+    fn builtin_output(&mut self, tokens: &[String]) -> Option<Result<String, RunOnceOutcome>> {
+        let usage = |text: &str| Err(RunOnceOutcome::UsageError(format!("usage: {}", text)));
+        let args = tokens[1..].iter().map(String::as_str).collect::<Vec<_>>();
+        let result = match (tokens[0].as_str(), args.as_slice()) {
+            ("history", []) => Ok(history::format_history(self.history.iter())),
+            ("history", ["clear"]) => self
+                .clear_history()
+                .map(|()| String::new())
+                .map_err(RunOnceOutcome::HandlerError),
+            ("history", _) => usage("history [clear]"),
+            ("alias", []) => Ok(self.aliases.format()),
+            ("alias", [name, "=", expansion @ ..]) => {
+                let reserved = builtin_names()
+                    .into_iter()
+                    .chain(EXIT_COMMANDS.iter().copied())
+                    .collect::<Vec<_>>();
+                let expansion = expansion.iter().map(|token| token.to_string()).collect();
+                self.aliases
+                    .define(name, expansion, &reserved)
+                    .map_err(|err| HandlerError(err.to_string()))
+                    .and_then(|()| self.save_aliases())
+                    .map(|()| String::new())
+                    .map_err(RunOnceOutcome::HandlerError)
+            }
+            ("alias", _) => usage("alias [<name> = <command>...]"),
+            ("unalias", [name]) => self
+                .aliases
+                .remove(name)
+                .map_err(|err| HandlerError(err.to_string()))
+                .and_then(|()| self.save_aliases())
+                .map(|()| String::new())
+                .map_err(RunOnceOutcome::HandlerError),
+            ("unalias", _) => usage("unalias <name>"),
+            ("prompt", []) => Ok(format!("{}\n", self.prompt_template.source())),
+            ("prompt", [template]) => self
+                .set_prompt_template(template)
+                .map(|()| String::new())
+                .map_err(|err| {
+                    RunOnceOutcome::HandlerError(HandlerError(format!(
+                        "invalid prompt template: {}",
+                        err
+                    )))
+                }),
+            ("prompt", _) => usage("prompt [<template>]"),
+            ("reload", []) => self.reload().map_err(RunOnceOutcome::HandlerError),
+            ("reload", _) => usage("reload"),
+            ("set", ["timings", setting @ ("on" | "off")]) => {
+                self.timings = *setting == "on";
+                Ok(String::new())
+            }
+            ("set", ["-u"]) => {
+                self.strict_variables = true;
+                Ok(String::new())
+            }
+            ("set", ["+u"]) => {
+                self.strict_variables = false;
+                Ok(String::new())
+            }
+            ("set", [name, value @ ..]) if !value.is_empty() => self
+                .variables
+                .set(name, value.join(" "))
+                .map(|()| String::new())
+                .map_err(|err| RunOnceOutcome::HandlerError(HandlerError(err.to_string()))),
+            ("set", _) => usage("set <name> <value>... | set -u|+u | set timings on|off"),
+            ("unset", [name]) => self
+                .variables
+                .remove(name)
+                .map(|()| String::new())
+                .map_err(|err| RunOnceOutcome::HandlerError(HandlerError(err.to_string()))),
+            ("unset", _) => usage("unset <name>"),
+            ("vars", []) => Ok(self.variables.format()),
+            ("vars", _) => usage("vars"),
+            ("status", []) => Ok(format!("{}\n", self.last_status)),
+            ("status", _) => usage("status"),
+            ("help", prefix) => match self.help_entries(&tokens[1..]) {
+                Ok(Some(entries)) => Ok(format_help(&entries, term::terminal_width())),
+                Ok(None) => Err(RunOnceOutcome::HandlerError(HandlerError(format!(
+                    "no commands match '{}'",
+                    prefix.join(" ")
+                )))),
+                Err(err) => Err(RunOnceOutcome::HandlerError(HandlerError(format!(
+                    "help error: {:?}",
+                    err
+                )))),
+            },
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// Commands in the current mode under `prefix`, as sorted (phrase, doc)
+    /// pairs. Abbreviated literals in the prefix are shown in full.
+    ///
+    /// Returns `None` when nothing is registered under the prefix.
+    fn help_entries(
+        &self,
+        prefix: &[String],
+    ) -> Result<Option<Vec<HelpEntry>>, ReplError> {
+        let mode = self.current_mode()?;
+        let mut state = mode.root_state();
+        let mut phrase = Vec::with_capacity(prefix.len());
+
+        for token in prefix {
+            let Some(step) = mode.step(state, token) else {
+                return Ok(None);
+            };
+            let shown = match step.matched {
+                sm::MatchedEdgeKind::Literal => {
+                    let candidates = mode.get_completions(state, token);
+                    candidates
+                        .iter()
+                        .find(|candidate| **candidate == token)
+                        .or(candidates.first())
+                        .map_or_else(|| token.clone(), |literal| literal.to_string())
+                }
+                sm::MatchedEdgeKind::Var => token.clone(),
+            };
+            phrase.push(shown);
+            state = step.next_state;
+        }
+
+        let mut entries = mode
+            .command_paths(state)
+            .into_iter()
+            .map(|path| {
+                let mut tokens = phrase.iter().map(String::as_str).collect::<Vec<_>>();
+                tokens.extend(path.tokens);
+                (tokens.join(" "), path.doc.map(str::to_string))
+            })
+            .collect::<Vec<_>>();
+
+        if prefix.is_empty() {
+            for (name, doc) in BUILTIN_HELP {
+                let command = name.split_whitespace().next().unwrap_or(name);
+                if mode.step(mode.root_state(), command).is_none() {
+                    entries.push((name.to_string(), Some(doc.to_string())));
+                }
+            }
+        }
+
+        if entries.is_empty() {
+            return Ok(None);
+        }
+        entries.sort();
+        Ok(Some(entries))
+    }
+
+    /// Read commands interactively, or run them as a script when stdin is not
+    /// a terminal. Returns the status of the last command, for use as the
+    /// process exit code.
+    pub fn run(&mut self) -> io::Result<i32> {
+        if !io::stdin().is_terminal() {
+            return self.run_script(io::stdin().lock(), false);
+        }
+
         if editor::prefer_rustyline_backend() {
             #[cfg(feature = "rustyline")]
             {
-                let mut editor = editor::RustylineEditor::new()?;
-                return self.run_with_editor(&mut editor);
+                let mut editor = editor::RustylineEditor::new(self.history.max_len())?;
+                self.run_with_editor(&mut editor)?;
+                return Ok(self.last_status);
             }
         }
 
         let mut editor = editor::BasicEditor::new();
-        self.run_with_editor(&mut editor)
+        self.run_with_editor(&mut editor)?;
+        Ok(self.last_status)
     }
 
 }
@@ -925,6 +2298,19 @@ mod tests {
         builder.build()
     }
 
+    /// Handlers every `Repl` registers for `exit` and `quit`.
+    const BUILTIN_HANDLERS: usize = 2;
+
+    /// `items` plus the `exit` and `quit` entries every mode's root lists.
+    fn with_exit_items(mut items: Vec<CompletionItem>) -> Vec<CompletionItem> {
+        items.extend(EXIT_COMMANDS.iter().map(|name| CompletionItem {
+            token: name.to_string(),
+            doc: Some(EXIT_DOC.to_string()),
+        }));
+        items.sort_by(|a, b| a.token.cmp(&b.token));
+        items
+    }
+
     fn completion_items(tokens: &[&str]) -> Vec<CompletionItem> {
         tokens
             .iter()
@@ -983,19 +2369,93 @@ mod tests {
 
     #[test]
     fn parse_line_rejects_unterminated_quote() {
-        assert_eq!(parse_line("note \"foo").unwrap_err(), ParseLineError::UnterminatedQuote);
+        assert_eq!(
+            parse_line("note \"foo").unwrap_err(),
+            ParseLineError::UnterminatedQuote { offset: 5 }
+        );
+    }
+
+    #[test]
+    fn parse_line_tokenizes_quotes_and_escapes() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            ("   ", &[]),
+            ("a  b\tc", &["a", "b", "c"]),
+            ("'a b'", &["a b"]),
+            ("'a \\ b'", &["a \\ b"]),
+            ("'say \"hi\"'", &["say \"hi\""]),
+            ("\"a b\"", &["a b"]),
+            ("\"a \\\" b\"", &["a \" b"]),
+            ("\"a \\\\ b\"", &["a \\ b"]),
+            ("\"a \\n b\"", &["a \\n b"]),
+            ("\"it's\"", &["it's"]),
+            ("a\\ b", &["a b"]),
+            ("a\\\\b", &["a\\b"]),
+            ("\\'a\\'", &["'a'"]),
+            ("\"a\"'b'", &["ab"]),
+            ("x\"a\"y'b'z", &["xaybz"]),
+            ("''", &[""]),
+            ("'' \"\"", &["", ""]),
+            ("é 'ü'", &["é", "ü"]),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(
+                parse_line(line).unwrap().tokens,
+                expected.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+                "line: {:?}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn parse_line_reports_error_offsets() {
+        let cases: &[(&str, ParseLineError)] = &[
+            ("'a", ParseLineError::UnterminatedQuote { offset: 0 }),
+            ("a \"b", ParseLineError::UnterminatedQuote { offset: 2 }),
+            ("\"a\" 'b", ParseLineError::UnterminatedQuote { offset: 4 }),
+            ("\"a\\\"", ParseLineError::UnterminatedQuote { offset: 0 }),
+            ("é 'b", ParseLineError::UnterminatedQuote { offset: 3 }),
+            ("a\\", ParseLineError::TrailingBackslash { offset: 1 }),
+            ("a b \\", ParseLineError::TrailingBackslash { offset: 4 }),
+        ];
+
+        for (line, expected) in cases {
+            let err = parse_line(line).unwrap_err();
+            assert_eq!(&err, expected, "line: {:?}", line);
+            assert_eq!(err.offset(), expected.offset());
+        }
+    }
+
+    #[test]
+    fn parse_line_tracks_trailing_separator() {
+        assert!(parse_line("show ").unwrap().ends_with_whitespace);
+        assert!(!parse_line("show\\ ").unwrap().ends_with_whitespace);
+        assert!(!parse_line("'show '").unwrap().ends_with_whitespace);
+        assert!(parse_line("'show'").unwrap().ended_after_quoted_token);
+        assert!(!parse_line("'show'x").unwrap().ended_after_quoted_token);
     }
 
     #[test]
-    fn parse_line_rejects_quote_inside_bare_token() {
-        assert_eq!(parse_line("foo\"bar").unwrap_err(), ParseLineError::UnexpectedQuote);
+    fn quote_token_round_trips_through_parse_line() {
+        let tokens = ["plain", "", "a b", "say \"hi\"", "a\\b", "it's"];
+
+        for token in tokens {
+            assert_eq!(
+                parse_line(&quote_token(token)).unwrap().tokens,
+                vec![token.to_string()]
+            );
+        }
+        assert_eq!(quote_token("plain"), "plain");
+        assert_eq!(quote_token("a \"b\""), "\"a \\\"b\\\"\"");
     }
 
     #[test]
-    fn parse_line_rejects_trailing_characters_after_quote() {
+    fn format_parse_error_points_at_offset() {
         assert_eq!(
-            parse_line("\"foo\"bar").unwrap_err(),
-            ParseLineError::TrailingCharactersAfterQuote
+            format_parse_error("é 'b", &ParseLineError::UnterminatedQuote { offset: 3 }),
+            "parse error: unterminated quote at byte 3\n  é 'b\n    ^"
         );
     }
 
@@ -1007,8 +2467,8 @@ mod tests {
         assert_eq!(repl.current_mode().unwrap().id(), 0);
         assert_eq!(repl.current_mode().unwrap().name(), "global");
         assert_eq!(repl.modes_len(), 1);
-        assert_eq!(repl.handlers_len(), 0);
-        assert_eq!(repl.capture_specs_len(), 0);
+        assert_eq!(repl.handlers_len(), BUILTIN_HANDLERS);
+        assert_eq!(repl.capture_specs_len(), BUILTIN_HANDLERS);
     }
 
     #[test]
@@ -1111,6 +2571,36 @@ mod tests {
         assert_eq!(repl.prompt().unwrap(), "global/config/interface> ");
     }
 
+    #[test]
+    fn prompt_builtin_sets_template_and_keeps_old_one_on_error() {
+        let (mut repl, _) = recording_repl();
+
+        assert_eq!(
+            repl.run_once("prompt \"{status} {mode}> \"").unwrap(),
+            RunOnceOutcome::ActionApplied(Action::None)
+        );
+        assert_eq!(repl.prompt().unwrap(), "✓ global> ");
+        assert_eq!(
+            repl.builtin_output(&tokens("prompt")),
+            Some(Ok("{status} {mode}> \n".to_string()))
+        );
+
+        repl.run_once("fail").unwrap();
+        assert_eq!(repl.prompt().unwrap(), "✗1 global> ");
+        repl.run_once("?").unwrap();
+        assert_eq!(repl.prompt().unwrap(), "✗1 global> ");
+        repl.run_once("say ok").unwrap();
+        assert_eq!(repl.prompt().unwrap(), "✓ global> ");
+
+        assert_eq!(
+            repl.run_once("prompt \"{mode\"").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError(
+                "invalid prompt template: unterminated placeholder at byte 0".to_string()
+            ))
+        );
+        assert_eq!(repl.prompt().unwrap(), "✗1 global> ");
+    }
+
     #[test]
     fn register_handler_returns_sequential_command_ids() {
         let mut repl = Repl::new();
@@ -1125,11 +2615,12 @@ mod tests {
         );
         let c = repl.register_handler(noop_handler(), Vec::new());
 
-        assert_eq!(a, 0);
-        assert_eq!(b, 1);
-        assert_eq!(c, 2);
-        assert_eq!(repl.handlers_len(), 3);
-        assert_eq!(repl.capture_specs_len(), 3);
+        let first = BUILTIN_HANDLERS as CommandId;
+        assert_eq!(a, first);
+        assert_eq!(b, first + 1);
+        assert_eq!(c, first + 2);
+        assert_eq!(repl.handlers_len(), BUILTIN_HANDLERS + 3);
+        assert_eq!(repl.capture_specs_len(), BUILTIN_HANDLERS + 3);
     }
 
     #[test]
@@ -1146,10 +2637,10 @@ mod tests {
 
         assert_eq!(
             repl.run_once("?").unwrap(),
-            RunOnceOutcome::Completions(vec![CompletionItem {
+            RunOnceOutcome::Completions(with_exit_items(vec![CompletionItem {
                 token: "show".to_string(),
                 doc: Some("show data".to_string())
-            }])
+            }]))
         );
         assert_eq!(
             repl.run_once("show ?").unwrap(),
@@ -1255,9 +2746,9 @@ mod tests {
 
         let command_id = repl.register_mode_command(0, &cmd, noop_handler()).unwrap();
 
-        assert_eq!(command_id, 0);
-        assert_eq!(repl.handlers_len(), 1);
-        assert_eq!(repl.capture_specs_len(), 1);
+        assert_eq!(command_id, BUILTIN_HANDLERS as CommandId);
+        assert_eq!(repl.handlers_len(), BUILTIN_HANDLERS + 1);
+        assert_eq!(repl.capture_specs_len(), BUILTIN_HANDLERS + 1);
 
         let mode = repl.get_mode(0).unwrap();
         let show = mode.next_state(mode.root_state(), "show").unwrap();
@@ -1271,9 +2762,9 @@ mod tests {
         let cmd = build_cmd(&["show", "version"], 0);
 
         let first_id = repl.register_mode_command(0, &cmd, noop_handler()).unwrap();
-        assert_eq!(first_id, 0);
-        assert_eq!(repl.handlers_len(), 1);
-        assert_eq!(repl.capture_specs_len(), 1);
+        assert_eq!(first_id, BUILTIN_HANDLERS as CommandId);
+        assert_eq!(repl.handlers_len(), BUILTIN_HANDLERS + 1);
+        assert_eq!(repl.capture_specs_len(), BUILTIN_HANDLERS + 1);
 
         let err = repl
             .register_mode_command(0, &cmd, noop_handler())
@@ -1281,12 +2772,12 @@ mod tests {
         assert_eq!(
             err,
             ReplError::CmdInsert(CommandRegistrationError::DuplicateCommandPath {
-                existing: 0,
-                attempted: 1,
+                existing: first_id,
+                attempted: first_id + 1,
             })
         );
-        assert_eq!(repl.handlers_len(), 1);
-        assert_eq!(repl.capture_specs_len(), 1);
+        assert_eq!(repl.handlers_len(), BUILTIN_HANDLERS + 1);
+        assert_eq!(repl.capture_specs_len(), BUILTIN_HANDLERS + 1);
     }
 
     #[test]
@@ -1330,8 +2821,8 @@ mod tests {
                 label: "name".to_string()
             })
         );
-        assert_eq!(repl.handlers_len(), 0);
-        assert_eq!(repl.capture_specs_len(), 0);
+        assert_eq!(repl.handlers_len(), BUILTIN_HANDLERS);
+        assert_eq!(repl.capture_specs_len(), BUILTIN_HANDLERS);
     }
 
     #[test]
@@ -1354,7 +2845,7 @@ mod tests {
 
         assert_eq!(
             repl.run_once("?").unwrap(),
-            RunOnceOutcome::Completions(completion_items(&["show", "write"]))
+            RunOnceOutcome::Completions(with_exit_items(completion_items(&["show", "write"])))
         );
     }
 
@@ -1449,8 +2940,8 @@ mod tests {
     }
 
     #[test]
-    fn completion_request_from_prefix_disables_completion_for_malformed_quote() {
-        assert_eq!(Repl::completion_request_from_prefix("\"foo\"bar"), None);
+    fn completion_request_from_prefix_disables_completion_after_trailing_backslash() {
+        assert_eq!(Repl::completion_request_from_prefix("note foo\\"), None);
     }
 
     #[test]
@@ -1477,7 +2968,7 @@ mod tests {
 
         assert_eq!(
             repl.complete_prefix("").unwrap(),
-            completion_items(&["foo"])
+            with_exit_items(completion_items(&["foo"]))
         );
         assert_eq!(
             repl.complete_prefix("foo ").unwrap(),
@@ -1502,9 +2993,7 @@ mod tests {
 
         assert_eq!(
             repl.completion_snapshot().tab_completion("sh").unwrap(),
-            Some(TabCompletion {
-                insert_suffix: "ow".to_string()
-            })
+            TabCompletion::Insert("ow".to_string())
         );
     }
 
@@ -1518,16 +3007,134 @@ mod tests {
 
         assert_eq!(
             repl.completion_snapshot().tab_completion("d").unwrap(),
-            Some(TabCompletion {
-                insert_suffix: "e".to_string()
-            })
+            TabCompletion::Insert("e".to_string())
         );
         assert_eq!(
             repl.completion_snapshot().tab_completion("de").unwrap(),
-            None
+            TabCompletion::Candidates(completion_items(&["delete-db", "describe"]))
+        );
+    }
+
+    #[test]
+    fn completion_snapshot_tab_completion_reports_no_match() {
+        let mut repl = Repl::new();
+        repl.register_mode_command(0, &build_cmd(&["show"], 0), noop_handler())
+            .unwrap();
+
+        assert_eq!(
+            repl.completion_snapshot().tab_completion("x").unwrap(),
+            TabCompletion::NoMatch
+        );
+        assert_eq!(
+            repl.completion_snapshot().tab_completion("show ").unwrap(),
+            TabCompletion::NoMatch
+        );
+        assert_eq!(
+            repl.completion_snapshot().tab_completion("bogus sh").unwrap(),
+            TabCompletion::NoMatch
         );
     }
 
+    fn file_completer_repl() -> Repl {
+        let mut repl = Repl::new();
+        repl.register_mode_command(0, &build_cmd(&["open"], 2), noop_handler())
+            .unwrap();
+        repl.register_mode_command(0, &build_cmd(&["show"], 0), noop_handler())
+            .unwrap();
+        repl.set_arg_completer(0, "open", |context, prior_args, partial| {
+            let files: &[&str] = match prior_args {
+                [] => &["notes.txt", "notes.md", "todo.txt"],
+                _ => &["read-only"],
+            };
+            files
+                .iter()
+                .filter(|file| file.starts_with(partial))
+                .map(|file| CompletionItem {
+                    token: file.to_string(),
+                    doc: Some(format!("in {}", context.mode())),
+                })
+                .collect()
+        })
+        .unwrap();
+        repl
+    }
+
+    #[test]
+    fn arg_completer_replaces_trie_completion_past_its_stem() {
+        let repl = file_completer_repl();
+        let snapshot = repl.completion_snapshot();
+
+        assert_eq!(snapshot.tab_completion("op").unwrap(), TabCompletion::Insert("en".into()));
+        assert_eq!(
+            snapshot.tab_completion("open t").unwrap(),
+            TabCompletion::Insert("odo.txt".into())
+        );
+        assert_eq!(snapshot.tab_completion("o n").unwrap(), TabCompletion::Insert("otes.".into()));
+        assert_eq!(
+            snapshot.tab_completion("open notes.").unwrap(),
+            TabCompletion::Candidates(vec![
+                CompletionItem {
+                    token: "notes.md".to_string(),
+                    doc: Some("in global".to_string()),
+                },
+                CompletionItem {
+                    token: "notes.txt".to_string(),
+                    doc: Some("in global".to_string()),
+                },
+            ])
+        );
+        assert_eq!(
+            snapshot.tab_completion("open todo.txt r").unwrap(),
+            TabCompletion::Insert("ead-only".into())
+        );
+        assert_eq!(snapshot.tab_completion("open x").unwrap(), TabCompletion::NoMatch);
+        assert_eq!(
+            snapshot.complete_prefix("open todo.txt ").unwrap(),
+            vec![CompletionItem {
+                token: "read-only".to_string(),
+                doc: Some("in global".to_string()),
+            }]
+        );
+        assert_eq!(snapshot.tab_completion("sh").unwrap(), TabCompletion::Insert("ow".into()));
+    }
+
+    #[test]
+    fn set_arg_completer_rejects_unknown_stems_and_replaces_existing() {
+        let mut repl = file_completer_repl();
+
+        assert_eq!(
+            repl.set_arg_completer(0, "close", |_, _, _| Vec::new()),
+            Err(ReplError::DocPathNotFound("close".to_string()))
+        );
+        assert_eq!(
+            repl.set_arg_completer(0, " ", |_, _, _| Vec::new()),
+            Err(ReplError::InvalidDocStem)
+        );
+        repl.set_arg_completer(0, "o", |_, _, _| Vec::new()).unwrap();
+        assert_eq!(
+            repl.completion_snapshot().tab_completion("open n").unwrap(),
+            TabCompletion::NoMatch
+        );
+    }
+
+    #[test]
+    fn variable_builtins_complete_variable_names() {
+        let mut repl = Repl::new();
+        repl.run_once("set host example.com").unwrap();
+        repl.run_once("set port 80").unwrap();
+        let snapshot = repl.completion_snapshot();
+
+        assert_eq!(
+            snapshot.tab_completion("unset h").unwrap(),
+            TabCompletion::Insert("ost".into())
+        );
+        assert_eq!(
+            snapshot.complete_prefix("set ").unwrap(),
+            completion_items(&["host", "port"])
+        );
+        assert_eq!(snapshot.tab_completion("set host e").unwrap(), TabCompletion::NoMatch);
+    }
+
     #[test]
     fn longest_common_prefix_handles_disjoint_and_shared_candidates() {
         assert_eq!(longest_common_prefix(&[]), "");
@@ -1541,10 +3148,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn edit_distance_counts_single_char_edits() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("show", "show"), 0);
+        assert_eq!(edit_distance("shwo", "show"), 2);
+        assert_eq!(edit_distance("sho", "show"), 1);
+        assert_eq!(edit_distance("shows", "show"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn suggest_command_replaces_first_unmatched_token() {
+        let mut repl = Repl::new();
+        repl.register_mode_command(0, &build_cmd(&["show", "accounts"], 0), noop_handler())
+            .unwrap();
+        repl.register_mode_command(0, &build_cmd(&["show", "version"], 0), noop_handler())
+            .unwrap();
+
+        assert_eq!(
+            repl.suggest_command("shw").unwrap(),
+            Some("show".to_string())
+        );
+        assert_eq!(
+            repl.suggest_command("show acounts").unwrap(),
+            Some("show accounts".to_string())
+        );
+        assert_eq!(repl.suggest_command("show zzzzzzz").unwrap(), None);
+        assert_eq!(repl.suggest_command("show version").unwrap(), None);
+    }
+
     #[test]
     fn format_completions_renders_doc_table_and_empty_state() {
         assert_eq!(
-            format_completions(&[]),
+            format_completions(&[], 80),
             "\nPossible completions:\n  (none)\n\n"
         );
         assert_eq!(
@@ -1557,7 +3194,7 @@ mod tests {
                     token: "bar".to_string(),
                     doc: None
                 }
-            ]),
+            ], 80),
             "\nPossible completions:\n  RET  run foo\n  bar\n\n"
         );
     }
@@ -1612,12 +3249,886 @@ mod tests {
         repl.run_with_editor(&mut editor).unwrap();
 
         assert_eq!(editor.prompts, vec!["global> ", "global> ", "global> "]);
-        assert_eq!(editor.printed, vec![completion_items(&["show"])]);
-        assert_eq!(editor.history, vec!["show\n".to_string()]);
+        assert_eq!(
+            editor.printed,
+            vec![with_exit_items(completion_items(&["show"]))]
+        );
+        assert_eq!(editor.history, vec!["show".to_string()]);
     }
 
     #[test]
-    fn run_once_completion_on_terminal_state_returns_empty_completions() {
+    fn run_with_editor_skips_consecutive_duplicate_history_entries() {
+        let mut repl = Repl::new();
+        repl.register_mode_command(0, &build_cmd(&["show"], 0), noop_handler())
+            .unwrap();
+        repl.set_history_size(1);
+
+        let mut editor = MockEditor::new(vec![
+            editor::EditorRead::Line("show\n".to_string()),
+            editor::EditorRead::Line("show\n".to_string()),
+            editor::EditorRead::Line("bogus\n".to_string()),
+            editor::EditorRead::Eof,
+        ]);
+
+        repl.run_with_editor(&mut editor).unwrap();
+
+        assert_eq!(
+            editor.history,
+            vec!["show".to_string(), "bogus".to_string()]
+        );
+        assert_eq!(repl.history().collect::<Vec<_>>(), vec!["bogus"]);
+    }
+
+    fn tokens(line: &str) -> Vec<String> {
+        parse_line(line).unwrap().tokens
+    }
+
+    #[test]
+    fn history_file_round_trips_entries_across_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history");
+
+        let mut first = Repl::new();
+        first
+            .register_mode_command(0, &build_cmd(&["show"], 0), noop_handler())
+            .unwrap();
+        first.set_history_file(&path).unwrap();
+        let mut editor = MockEditor::new(vec![
+            editor::EditorRead::Line("show\n".to_string()),
+            editor::EditorRead::Line("bogus\n".to_string()),
+            editor::EditorRead::Eof,
+        ]);
+        first.run_with_editor(&mut editor).unwrap();
+
+        let mut second = Repl::new();
+        second.set_history_file(&path).unwrap();
+        assert_eq!(second.history().collect::<Vec<_>>(), vec!["show", "bogus"]);
+
+        let mut editor = MockEditor::new(vec![editor::EditorRead::Eof]);
+        second.run_with_editor(&mut editor).unwrap();
+        assert_eq!(editor.history, vec!["show".to_string(), "bogus".to_string()]);
+
+        // Loading compacts the file to the history size.
+        let mut third = Repl::new();
+        third.set_history_size(1);
+        third.set_history_file(&path).unwrap();
+        assert_eq!(third.history().collect::<Vec<_>>(), vec!["bogus"]);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "bogus\n");
+    }
+
+    #[test]
+    fn history_builtin_lists_and_clears_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history");
+        let mut repl = Repl::new();
+        repl.set_history_file(&path).unwrap();
+        repl.record_history("show");
+        repl.record_history("write");
+
+        assert_eq!(
+            repl.builtin_output(&tokens("history")),
+            Some(Ok("  1  show\n  2  write\n".to_string()))
+        );
+        assert_eq!(
+            repl.builtin_output(&tokens("history bogus")),
+            Some(Err(RunOnceOutcome::UsageError(
+                "usage: history [clear]".to_string()
+            )))
+        );
+        assert_eq!(
+            repl.run_once("history clear").unwrap(),
+            RunOnceOutcome::ActionApplied(Action::None)
+        );
+        assert_eq!(repl.history().count(), 0);
+        assert_eq!(std::fs::read(&path).unwrap(), b"");
+    }
+
+    fn help_entry(phrase: &str, doc: Option<&str>) -> HelpEntry {
+        (phrase.to_string(), doc.map(str::to_string))
+    }
+
+    fn help_repl() -> Repl {
+        let mut repl = Repl::new();
+        repl.register_mode_command(0, &build_cmd(&["show", "version"], 0), noop_handler())
+            .unwrap();
+        repl.register_mode_command(0, &build_cmd(&["show", "ip"], 1), noop_handler())
+            .unwrap();
+        repl.register_mode_command(0, &build_cmd(&["show"], 0), noop_handler())
+            .unwrap();
+        repl.set_edge_doc(0, "show", "display information").unwrap();
+        repl.set_command_doc(0, "show version", "show software version")
+            .unwrap();
+        repl
+    }
+
+    #[test]
+    fn help_entries_lists_every_command_with_builtins() {
+        let repl = help_repl();
+
+        assert_eq!(
+            repl.help_entries(&[]).unwrap(),
+            Some(vec![
+                help_entry("!<command>", Some(BUILTIN_HELP[0].1)),
+                help_entry("alias [<name> = <command>]", Some(BUILTIN_HELP[1].1)),
+                help_entry("exit", Some(EXIT_DOC)),
+                help_entry("exit <status>", Some("leave, setting the exit status")),
+                help_entry("help [<prefix>]", Some(BUILTIN_HELP[2].1)),
+                help_entry("history [clear]", Some(BUILTIN_HELP[3].1)),
+                help_entry("prompt [<template>]", Some(BUILTIN_HELP[4].1)),
+                help_entry("quit", Some(EXIT_DOC)),
+                help_entry("quit <status>", Some("leave, setting the exit status")),
+                help_entry("reload", Some(BUILTIN_HELP[5].1)),
+                help_entry("set -u|+u", Some(BUILTIN_HELP[6].1)),
+                help_entry("set <name> <value>", Some(BUILTIN_HELP[7].1)),
+                help_entry("set timings on|off", Some(BUILTIN_HELP[8].1)),
+                help_entry("show", Some("display information")),
+                help_entry("show ip <arg>", None),
+                help_entry("show version", Some("show software version")),
+                help_entry("status", Some(BUILTIN_HELP[9].1)),
+                help_entry("time <command>", Some(BUILTIN_HELP[10].1)),
+                help_entry("unalias <name>", Some(BUILTIN_HELP[11].1)),
+                help_entry("unset <name>", Some(BUILTIN_HELP[12].1)),
+                help_entry("vars", Some(BUILTIN_HELP[13].1)),
+            ])
+        );
+    }
+
+    #[test]
+    fn help_entries_narrows_to_prefix_and_expands_abbreviations() {
+        let repl = help_repl();
+
+        assert_eq!(
+            repl.help_entries(&tokens("sh v")).unwrap(),
+            Some(vec![help_entry("show version", Some("show software version"))])
+        );
+        assert_eq!(
+            repl.help_entries(&tokens("show")).unwrap().unwrap().len(),
+            3
+        );
+        assert_eq!(repl.help_entries(&tokens("bogus")).unwrap(), None);
+    }
+
+    #[test]
+    fn help_builtin_reports_unknown_prefix() {
+        let mut repl = help_repl();
+
+        assert_eq!(
+            repl.run_once("help bogus").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError("no commands match 'bogus'".to_string()))
+        );
+    }
+
+    #[test]
+    fn format_help_aligns_docs_and_wraps_with_hanging_indent() {
+        let entries = vec![
+            help_entry("show", Some("display information")),
+            help_entry("show version", None),
+            help_entry(
+                "write",
+                Some("enter write mode where commands can change data on disk"),
+            ),
+        ];
+
+        assert_eq!(
+            format_help(&entries, 80),
+            "  show          display information\n\
+             \x20 show version\n\
+             \x20 write         enter write mode where commands can change data on disk\n"
+        );
+        assert_eq!(
+            format_help(&entries, 40),
+            "  show          display information\n\
+             \x20 show version\n\
+             \x20 write         enter write mode where\n\
+             \x20               commands can change data\n\
+             \x20               on disk\n"
+        );
+        assert_eq!(format_help(&[], 80), "");
+    }
+
+    #[test]
+    fn format_help_fills_wide_terminals_and_keeps_a_doc_column_when_narrow() {
+        let doc = "enter write mode where commands can change data on disk, \
+                   after asking for confirmation";
+        let entries = vec![help_entry("write", Some(doc))];
+
+        assert_eq!(format_help(&entries, 200), format!("  write  {}\n", doc));
+        assert_eq!(
+            format_help(&entries, 80),
+            "  write  enter write mode where commands can change data on disk, after asking\n\
+             \x20        for confirmation\n"
+        );
+        assert_eq!(
+            format_help(&entries, 40),
+            "  write  enter write mode where commands\n\
+             \x20        can change data on disk, after\n\
+             \x20        asking for confirmation\n"
+        );
+        assert_eq!(
+            format_help(&entries, 10),
+            "  write  enter write mode\n\
+             \x20        where commands can\n\
+             \x20        change data on disk,\n\
+             \x20        after asking for\n\
+             \x20        confirmation\n"
+        );
+    }
+
+    #[test]
+    fn format_completions_wraps_docs_to_the_width() {
+        let items = [
+            CompletionItem {
+                token: "version".to_string(),
+                doc: Some("show the software version and the build it came from".to_string()),
+            },
+            CompletionItem {
+                token: "ip".to_string(),
+                doc: None,
+            },
+        ];
+
+        assert_eq!(
+            format_completions(&items, 200),
+            "\nPossible completions:\n  \
+             version  show the software version and the build it came from\n  ip\n\n"
+        );
+        assert_eq!(
+            format_completions(&items, 80),
+            format_completions(&items, 200)
+        );
+        assert_eq!(
+            format_completions(&items, 40),
+            "\nPossible completions:\n  \
+             version  show the software version and\n\
+             \x20          the build it came from\n  ip\n\n"
+        );
+    }
+
+    #[test]
+    fn wrap_words_keeps_long_words_whole() {
+        assert_eq!(wrap_words("", 10), vec![String::new()]);
+        assert_eq!(
+            wrap_words("a reallylongword b", 5),
+            vec!["a".to_string(), "reallylongword".to_string(), "b".to_string()]
+        );
+    }
+
+    #[test]
+    fn alias_builtin_expands_before_dispatch_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aliases.toml");
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let seen_clone = std::rc::Rc::clone(&seen);
+        let mut repl = Repl::new();
+        repl.set_alias_file(&path).unwrap();
+        repl.register_mode_command(
+            0,
+            &build_cmd(&["show", "ip"], 1),
+            Box::new(move |_, inputs| {
+                seen_clone.borrow_mut().extend(inputs.positionals.clone());
+                Ok(Action::None)
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(
+            repl.run_once("alias si = show ip").unwrap(),
+            RunOnceOutcome::ActionApplied(Action::None)
+        );
+        assert_eq!(
+            repl.run_once("si eth0").unwrap(),
+            RunOnceOutcome::ActionApplied(Action::None)
+        );
+        assert_eq!(*seen.borrow(), vec!["eth0".to_string()]);
+        assert_eq!(
+            repl.builtin_output(&tokens("alias")),
+            Some(Ok("si = show ip\n".to_string()))
+        );
+
+        let mut reloaded = Repl::new();
+        reloaded.set_alias_file(&path).unwrap();
+        assert_eq!(
+            reloaded.builtin_output(&tokens("alias")),
+            Some(Ok("si = show ip\n".to_string()))
+        );
+
+        assert_eq!(
+            repl.run_once("unalias si").unwrap(),
+            RunOnceOutcome::ActionApplied(Action::None)
+        );
+        assert_eq!(repl.run_once("si eth0").unwrap(), RunOnceOutcome::UnknownCommand);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+    }
+
+    #[test]
+    fn reload_swaps_in_edited_alias_and_rc_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let alias_path = dir.path().join("aliases.toml");
+        let rc_path = dir.path().join("rc");
+        std::fs::write(&alias_path, "si = \"show ip\"\nold = \"say old\"\n").unwrap();
+        std::fs::write(&rc_path, "alias greet = say hello\n").unwrap();
+        let (mut repl, seen) = recording_repl();
+        repl.set_alias_file(&alias_path).unwrap();
+        repl.load_rc_file(&rc_path).unwrap();
+        repl.run_once("set who me").unwrap();
+        repl.record_history("greet");
+
+        std::fs::write(&alias_path, "si = \"show interfaces\"\nsh = \"say hi\"\n").unwrap();
+        std::fs::write(&rc_path, "alias greet = say hello\nprompt \"new> \"\n").unwrap();
+        assert_eq!(
+            repl.builtin_output(&tokens("reload")),
+            Some(Ok("aliases added: sh\naliases removed: old\naliases updated: si\n"
+                .to_string()))
+        );
+        assert_eq!(repl.prompt_template.source(), "new> ");
+        assert_eq!(repl.variables.format(), "who = me\n");
+        assert_eq!(repl.history.iter().collect::<Vec<_>>(), vec!["greet"]);
+
+        repl.run_once("sh").unwrap();
+        assert_eq!(*seen.borrow(), vec!["hi".to_string()]);
+        assert_eq!(
+            repl.builtin_output(&tokens("reload")),
+            Some(Ok("no changes\n".to_string()))
+        );
+    }
+
+    #[test]
+    fn reload_keeps_current_state_when_a_file_is_malformed() {
+        let dir = tempfile::tempdir().unwrap();
+        let alias_path = dir.path().join("aliases.toml");
+        let rc_path = dir.path().join("rc");
+        std::fs::write(&alias_path, "si = \"show ip\"\n").unwrap();
+        std::fs::write(&rc_path, "prompt \"rc> \"\n").unwrap();
+        let mut repl = Repl::new();
+        repl.set_alias_file(&alias_path).unwrap();
+        repl.load_rc_file(&rc_path).unwrap();
+
+        std::fs::write(&rc_path, "prompt \"new> \"\nalias x = \"unterminated\n").unwrap();
+        assert_eq!(
+            repl.builtin_output(&tokens("reload")),
+            Some(Err(RunOnceOutcome::HandlerError(HandlerError(format!(
+                "reload failed, keeping the current state: {}:2: unterminated quote at byte 10",
+                rc_path.display()
+            )))))
+        );
+        assert_eq!(repl.prompt_template.source(), "rc> ");
+
+        std::fs::write(&rc_path, "prompt \"new> \"\n").unwrap();
+        std::fs::write(&alias_path, "si = \"show ip\"\nbroken\n").unwrap();
+        assert_eq!(
+            repl.builtin_output(&tokens("reload")),
+            Some(Err(RunOnceOutcome::HandlerError(HandlerError(format!(
+                "reload failed, keeping the current state: {}:2: expected `.`, `=`",
+                alias_path.display()
+            )))))
+        );
+        assert_eq!(repl.aliases.format(), "si = show ip\n");
+        assert_eq!(repl.prompt_template.source(), "rc> ");
+        assert_eq!(
+            repl.run_once("reload now").unwrap(),
+            RunOnceOutcome::UsageError("usage: reload".to_string())
+        );
+    }
+
+    const SPEC: &str = "[[command]]\n\
+        phrase = \"greet <name>\"\n\
+        doc = \"say hello\"\n\
+        action = \"shell:printf 'hello %s'\"\n\
+        \n\
+        [[command]]\n\
+        phrase = \"last\"\n\
+        action = \"builtin:status\"\n";
+
+    #[test]
+    fn load_spec_runs_shell_and_builtin_actions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spec.toml");
+        std::fs::write(&path, SPEC).unwrap();
+        let (mut repl, outputs) = shell_repl();
+        repl.load_spec(&path).unwrap();
+
+        assert_eq!(
+            repl.run_once("greet \"ada l\"").unwrap(),
+            RunOnceOutcome::ShellExited(0)
+        );
+        assert_eq!(*outputs.borrow(), vec!["hello ada l".to_string()]);
+        assert_eq!(
+            repl.run_once("last").unwrap(),
+            RunOnceOutcome::ActionApplied(Action::None)
+        );
+        assert_eq!(
+            repl.complete_prefix("gr").unwrap(),
+            vec![CompletionItem {
+                token: "greet".to_string(),
+                doc: Some("say hello".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn load_spec_rejects_commands_that_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spec.toml");
+        std::fs::write(&path, SPEC).unwrap();
+        let (mut repl, _) = recording_repl();
+        repl.register_mode_command(0, &build_cmd(&["last"], 0), noop_handler())
+            .unwrap();
+
+        let err = repl.load_spec(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            format!("{}:7: 'last' conflicts with an existing command", path.display())
+        );
+        assert_eq!(repl.run_once("greet ada").unwrap(), RunOnceOutcome::UnknownCommand);
+    }
+
+    #[test]
+    fn reload_swaps_in_an_edited_spec() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spec.toml");
+        std::fs::write(&path, SPEC).unwrap();
+        let (mut repl, seen) = recording_repl();
+        repl.load_spec(&path).unwrap();
+        // Registered after the spec, so it has to survive the rebuild.
+        repl.register_mode_command(0, &build_cmd(&["late"], 0), noop_handler())
+            .unwrap();
+
+        std::fs::write(
+            &path,
+            "[[command]]\nphrase = \"last\"\naction = \"builtin:history\"\n\
+             [[command]]\nphrase = \"hi\"\naction = \"builtin:vars\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            repl.builtin_output(&tokens("reload")),
+            Some(Ok(
+                "commands added: hi\ncommands removed: greet <name>\ncommands updated: last\n"
+                    .to_string()
+            ))
+        );
+        assert_eq!(repl.run_once("greet ada").unwrap(), RunOnceOutcome::UnknownCommand);
+        for line in ["hi", "last", "late", "say hello"] {
+            assert_eq!(
+                repl.run_once(line).unwrap(),
+                RunOnceOutcome::ActionApplied(Action::None),
+                "{line}"
+            );
+        }
+        assert_eq!(*seen.borrow(), vec!["hello".to_string()]);
+        assert_eq!(
+            repl.builtin_output(&tokens("reload")),
+            Some(Ok("no changes\n".to_string()))
+        );
+    }
+
+    #[test]
+    fn reload_keeps_the_current_spec_when_it_is_broken() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spec.toml");
+        std::fs::write(&path, SPEC).unwrap();
+        let (mut repl, _) = shell_repl();
+        repl.load_spec(&path).unwrap();
+
+        std::fs::write(
+            &path,
+            "[[command]]\nphrase = \"greet\"\naction = \"exec:hi\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            repl.builtin_output(&tokens("reload")),
+            Some(Err(RunOnceOutcome::HandlerError(HandlerError(format!(
+                "reload failed, keeping the current state: {}:2: \
+                 unknown action kind 'exec', expected shell: or builtin:",
+                path.display()
+            )))))
+        );
+        assert_eq!(
+            repl.run_once("greet ada").unwrap(),
+            RunOnceOutcome::ShellExited(0)
+        );
+        assert_eq!(
+            repl.run_once("last").unwrap(),
+            RunOnceOutcome::ActionApplied(Action::None)
+        );
+    }
+
+    #[test]
+    fn alias_builtin_rejects_recursion_and_reserved_names() {
+        let mut repl = Repl::new();
+        repl.run_once("alias a = b").unwrap();
+
+        assert_eq!(
+            repl.run_once("alias b = a x").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError(
+                "alias 'b' would expand to itself".to_string()
+            ))
+        );
+        assert_eq!(
+            repl.run_once("alias help = history").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError("'help' is a built-in command".to_string()))
+        );
+        assert_eq!(
+            repl.run_once("alias x y").unwrap(),
+            RunOnceOutcome::UsageError("usage: alias [<name> = <command>...]".to_string())
+        );
+        assert_eq!(
+            repl.run_once("unalias missing").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError("no alias named 'missing'".to_string()))
+        );
+    }
+
+    #[test]
+    fn mode_commands_shadow_builtins() {
+        let mut repl = Repl::new();
+        repl.register_mode_command(
+            0,
+            &build_cmd(&["history"], 0),
+            Box::new(|_, _| Err(HandlerError("custom".to_string()))),
+        )
+        .unwrap();
+
+        assert_eq!(
+            repl.run_once("history").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError("custom".to_string()))
+        );
+    }
+
+    fn recording_repl() -> (Repl, std::rc::Rc<std::cell::RefCell<Vec<String>>>) {
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut repl = Repl::new();
+        let seen_clone = std::rc::Rc::clone(&seen);
+        repl.register_mode_command(
+            0,
+            &build_cmd(&["say"], 1),
+            Box::new(move |_, inputs| {
+                seen_clone.borrow_mut().push(inputs.positionals[0].clone());
+                Ok(Action::None)
+            }),
+        )
+        .unwrap();
+        repl.register_mode_command(
+            0,
+            &build_cmd(&["fail"], 0),
+            Box::new(|_, _| Err(HandlerError("failed".to_string()))),
+        )
+        .unwrap();
+        (repl, seen)
+    }
+
+    #[test]
+    fn run_script_skips_comments_and_stops_at_first_failure() {
+        let (mut repl, seen) = recording_repl();
+        let script = "# setup\n\nsay one\nfail\nsay two\n";
+
+        assert_eq!(repl.run_script(io::Cursor::new(script), false).unwrap(), 1);
+        assert_eq!(*seen.borrow(), vec!["one".to_string()]);
+    }
+
+    #[test]
+    fn run_script_keep_going_reports_last_command_status() {
+        let (mut repl, seen) = recording_repl();
+
+        assert_eq!(
+            repl.run_script(io::Cursor::new("fail\nbogus\nsay two\n"), true)
+                .unwrap(),
+            STATUS_SUCCESS
+        );
+        assert_eq!(
+            repl.run_script(io::Cursor::new("say three\nfail\n"), true)
+                .unwrap(),
+            STATUS_FAILURE
+        );
+        assert_eq!(
+            repl.run_script(io::Cursor::new("fail\nbogus\n"), true)
+                .unwrap(),
+            STATUS_USAGE
+        );
+        assert_eq!(*seen.borrow(), vec!["two".to_string(), "three".to_string()]);
+    }
+
+    #[test]
+    fn run_script_stops_at_exit_in_root_mode() {
+        let (mut repl, seen) = recording_repl();
+
+        assert_eq!(
+            repl.run_script(io::Cursor::new("say one\nexit\nsay two\n"), false)
+                .unwrap(),
+            STATUS_SUCCESS
+        );
+        assert_eq!(*seen.borrow(), vec!["one".to_string()]);
+    }
+
+    #[test]
+    fn run_command_returns_exit_status() {
+        let (mut repl, seen) = recording_repl();
+
+        assert_eq!(repl.run_command("say \"two words\"").unwrap(), STATUS_SUCCESS);
+        assert_eq!(repl.run_command("history").unwrap(), STATUS_SUCCESS);
+        assert_eq!(repl.run_command("fail").unwrap(), STATUS_FAILURE);
+        assert_eq!(repl.run_command("history bogus").unwrap(), STATUS_USAGE);
+        assert_eq!(repl.run_command("say \"open").unwrap(), STATUS_USAGE);
+        assert_eq!(*seen.borrow(), vec!["two words".to_string()]);
+    }
+
+    #[test]
+    fn status_builtin_reports_last_exit_status() {
+        let (mut repl, _) = recording_repl();
+
+        assert_eq!(repl.last_status(), STATUS_SUCCESS);
+        assert_eq!(
+            repl.run_once("fail").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError("failed".to_string()))
+        );
+        assert_eq!(repl.last_status(), STATUS_FAILURE);
+        assert_eq!(
+            repl.builtin_output(&tokens("status")),
+            Some(Ok("1\n".to_string()))
+        );
+
+        repl.run_once("bogus").unwrap();
+        assert_eq!(
+            repl.builtin_output(&tokens("status")),
+            Some(Ok("2\n".to_string()))
+        );
+
+        repl.run_once("").unwrap();
+        assert_eq!(repl.last_status(), STATUS_USAGE);
+        assert_eq!(
+            repl.run_once("status").unwrap(),
+            RunOnceOutcome::ActionApplied(Action::None)
+        );
+        assert_eq!(repl.last_status(), STATUS_SUCCESS);
+        assert_eq!(
+            repl.run_once("status now").unwrap(),
+            RunOnceOutcome::UsageError("usage: status".to_string())
+        );
+        assert_eq!(repl.last_status(), STATUS_USAGE);
+    }
+
+    /// Runs `!` commands with `sh -c`, capturing their stdout.
+    struct CapturingShell {
+        outputs: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+    }
+
+    impl ShellExecutor for CapturingShell {
+        fn execute(&mut self, command: &str) -> io::Result<i32> {
+            let output = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .output()?;
+            self.outputs
+                .borrow_mut()
+                .push(String::from_utf8_lossy(&output.stdout).into_owned());
+            Ok(shell::exit_code(output.status))
+        }
+    }
+
+    struct BrokenShell;
+
+    impl ShellExecutor for BrokenShell {
+        fn execute(&mut self, _command: &str) -> io::Result<i32> {
+            Err(io::Error::new(io::ErrorKind::NotFound, "no shell"))
+        }
+    }
+
+    fn shell_repl() -> (Repl, std::rc::Rc<std::cell::RefCell<Vec<String>>>) {
+        let outputs = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut repl = Repl::new();
+        repl.set_shell_executor(Box::new(CapturingShell {
+            outputs: outputs.clone(),
+        }));
+        (repl, outputs)
+    }
+
+    #[test]
+    fn bang_runs_shell_command_and_records_status() {
+        let (mut repl, outputs) = shell_repl();
+
+        assert_eq!(
+            repl.run_once("!echo hi").unwrap(),
+            RunOnceOutcome::ShellExited(0)
+        );
+        assert_eq!(repl.last_status(), 0);
+        assert_eq!(
+            repl.run_once("  ! exit 3").unwrap(),
+            RunOnceOutcome::ShellExited(3)
+        );
+        assert_eq!(repl.last_status(), 3);
+        assert_eq!(
+            repl.builtin_output(&tokens("status")),
+            Some(Ok("3\n".to_string()))
+        );
+        assert_eq!(
+            *outputs.borrow(),
+            vec!["hi\n".to_string(), String::new()]
+        );
+    }
+
+    #[test]
+    fn bare_bang_repeats_previous_shell_command() {
+        let (mut repl, outputs) = shell_repl();
+
+        assert_eq!(
+            repl.run_once("!").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError("no previous shell command".to_string()))
+        );
+        repl.run_once("!echo 'a  b'").unwrap();
+        repl.run_once("say nothing").unwrap();
+        assert_eq!(
+            repl.run_once("!").unwrap(),
+            RunOnceOutcome::ShellExited(0)
+        );
+        assert_eq!(
+            *outputs.borrow(),
+            vec!["a  b\n".to_string(), "a  b\n".to_string()]
+        );
+    }
+
+    #[test]
+    fn shell_failures_do_not_end_the_session() {
+        let (mut repl, _) = shell_repl();
+
+        assert_eq!(
+            repl.run_script(io::Cursor::new("!exit 4\n!echo after\n"), false)
+                .unwrap(),
+            4
+        );
+        assert_eq!(
+            repl.run_script(io::Cursor::new("!exit 4\n!echo after\n"), true)
+                .unwrap(),
+            STATUS_SUCCESS
+        );
+
+        repl.set_shell_executor(Box::new(BrokenShell));
+        assert_eq!(
+            repl.run_once("!true").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError(
+                "failed to run shell command: no shell".to_string()
+            ))
+        );
+        assert_eq!(repl.last_status(), STATUS_FAILURE);
+    }
+
+    #[test]
+    fn rc_file_defines_aliases_for_the_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rc");
+        std::fs::write(
+            &path,
+            "# startup\nalias greet = say hello\nbogus\nprompt \"rc> \"\n",
+        )
+        .unwrap();
+        let (mut repl, seen) = recording_repl();
+
+        assert_eq!(repl.load_rc_file(&path).unwrap(), STATUS_SUCCESS);
+        assert_eq!(repl.load_rc_file(&dir.path().join("missing")).unwrap(), STATUS_SUCCESS);
+
+        let mut editor = MockEditor::new(vec![
+            editor::EditorRead::Line("greet\n".to_string()),
+            editor::EditorRead::Eof,
+        ]);
+        repl.run_with_editor(&mut editor).unwrap();
+        assert_eq!(editor.prompts[0], "rc> ");
+        assert_eq!(*seen.borrow(), vec!["hello".to_string()]);
+        assert_eq!(editor.history, vec!["greet".to_string()]);
+    }
+
+    #[test]
+    fn run_file_executes_commands_from_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("script.tli");
+        std::fs::write(&path, "say \"from file\"\n").unwrap();
+        let (mut repl, seen) = recording_repl();
+
+        assert_eq!(repl.run_file(&path, false).unwrap(), STATUS_SUCCESS);
+        assert_eq!(*seen.borrow(), vec!["from file".to_string()]);
+        assert!(repl.run_file(&dir.path().join("missing.tli"), false).is_err());
+    }
+
+    #[test]
+    fn line_continuation_joins_backslash_terminated_lines() {
+        let mut continuation = LineContinuation::default();
+
+        assert_eq!(continuation.push("show\n"), Some("show".to_string()));
+        assert_eq!(continuation.push("say \\\n"), None);
+        assert!(continuation.is_continuing());
+        assert_eq!(continuation.push("two \\\r\n"), None);
+        assert_eq!(continuation.push("words"), Some("say two words".to_string()));
+        assert!(!continuation.is_continuing());
+    }
+
+    #[test]
+    fn line_continuation_cancel_discards_pending_input() {
+        let mut continuation = LineContinuation::default();
+
+        assert_eq!(continuation.push("say \\"), None);
+        continuation.cancel();
+        assert!(!continuation.is_continuing());
+        assert_eq!(continuation.push("show"), Some("show".to_string()));
+        assert_eq!(continuation.push("\\"), None);
+        assert_eq!(continuation.push(""), Some(String::new()));
+    }
+
+    #[test]
+    fn run_with_editor_submits_continued_lines_as_one_command() {
+        let (mut repl, seen) = recording_repl();
+        let mut editor = MockEditor::new(vec![
+            editor::EditorRead::Line("say \\\n".to_string()),
+            editor::EditorRead::Line("one\n".to_string()),
+            editor::EditorRead::Line("say \\\n".to_string()),
+            editor::EditorRead::Interrupted { empty_line: false },
+            editor::EditorRead::Line("say two\n".to_string()),
+            editor::EditorRead::Eof,
+        ]);
+
+        repl.run_with_editor(&mut editor).unwrap();
+
+        assert_eq!(*seen.borrow(), vec!["one".to_string(), "two".to_string()]);
+        assert_eq!(
+            editor.prompts,
+            vec!["global> ", "… ", "global> ", "… ", "global> ", "global> "]
+        );
+        assert_eq!(editor.history, vec!["say one".to_string(), "say two".to_string()]);
+    }
+
+    #[test]
+    fn run_with_editor_exits_on_double_interrupt_at_empty_prompt() {
+        let (mut repl, seen) = recording_repl();
+        let mut editor = MockEditor::new(vec![
+            editor::EditorRead::Interrupted { empty_line: true },
+            editor::EditorRead::Line("say one\n".to_string()),
+            editor::EditorRead::Interrupted { empty_line: true },
+            editor::EditorRead::Interrupted { empty_line: false },
+            editor::EditorRead::Interrupted { empty_line: true },
+            editor::EditorRead::Interrupted { empty_line: true },
+            editor::EditorRead::Line("say never\n".to_string()),
+        ]);
+
+        repl.run_with_editor(&mut editor).unwrap();
+
+        assert_eq!(*seen.borrow(), vec!["one".to_string()]);
+        assert_eq!(editor.prompts.len(), 6);
+    }
+
+    #[test]
+    fn run_with_editor_interrupt_during_continuation_does_not_count_toward_exit() {
+        let (mut repl, seen) = recording_repl();
+        let mut editor = MockEditor::new(vec![
+            editor::EditorRead::Interrupted { empty_line: true },
+            editor::EditorRead::Line("say \\\n".to_string()),
+            editor::EditorRead::Interrupted { empty_line: true },
+            editor::EditorRead::Line("say two\n".to_string()),
+            editor::EditorRead::Eof,
+        ]);
+
+        repl.run_with_editor(&mut editor).unwrap();
+
+        assert_eq!(*seen.borrow(), vec!["two".to_string()]);
+    }
+
+    #[test]
+    fn run_once_completion_on_terminal_state_returns_empty_completions() {
         let mut repl = Repl::new();
         let cmd = build_cmd(&["show", "version"], 0);
         repl.register_mode_command(0, &cmd, noop_handler()).unwrap();
@@ -1720,7 +4231,7 @@ mod tests {
 
         assert_eq!(
             repl.run_once("?").unwrap(),
-            RunOnceOutcome::Completions(vec![
+            RunOnceOutcome::Completions(with_exit_items(vec![
                 CompletionItem {
                     token: "show".to_string(),
                     doc: None
@@ -1729,7 +4240,7 @@ mod tests {
                     token: "write".to_string(),
                     doc: Some("enter write mode".to_string())
                 }
-            ])
+            ]))
         );
     }
 
@@ -1743,10 +4254,10 @@ mod tests {
 
         assert_eq!(
             repl.run_once("?").unwrap(),
-            RunOnceOutcome::Completions(vec![CompletionItem {
+            RunOnceOutcome::Completions(with_exit_items(vec![CompletionItem {
                 token: "write".to_string(),
                 doc: Some("new".to_string())
-            }])
+            }]))
         );
     }
 
@@ -1829,6 +4340,55 @@ mod tests {
         assert_eq!(repl.current_mode_id().unwrap(), 0);
     }
 
+    #[test]
+    fn run_once_exit_with_status_sets_last_status() {
+        let mut repl = Repl::new();
+        let cfg = repl.add_mode("config");
+        repl.push_mode(cfg).unwrap();
+
+        assert_eq!(
+            repl.run_once("quit 3").unwrap(),
+            RunOnceOutcome::ActionApplied(Action::PopMode)
+        );
+        assert_eq!(repl.last_status(), 3);
+        assert_eq!(
+            repl.run_once("exit soon").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError(
+                "exit status must be a number, not 'soon'".to_string()
+            ))
+        );
+        assert_eq!(repl.last_status(), STATUS_FAILURE);
+        assert_eq!(
+            repl.run_once("exit 2").unwrap(),
+            RunOnceOutcome::ActionApplied(Action::Exit)
+        );
+        assert_eq!(repl.last_status(), 2);
+    }
+
+    #[test]
+    fn scripts_and_commands_return_requested_exit_status() {
+        let (mut repl, seen) = recording_repl();
+
+        assert_eq!(
+            repl.run_script(io::Cursor::new("say one\nexit 4\nsay two\n"), false)
+                .unwrap(),
+            4
+        );
+        assert_eq!(repl.run_command("quit 5").unwrap(), 5);
+        assert_eq!(repl.run_command("exit").unwrap(), STATUS_SUCCESS);
+        assert_eq!(*seen.borrow(), vec!["one".to_string()]);
+    }
+
+    #[test]
+    fn exit_commands_cannot_be_aliased() {
+        let mut repl = Repl::new();
+
+        assert_eq!(
+            repl.run_once("alias quit = help").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError("'quit' is a built-in command".to_string()))
+        );
+    }
+
     #[test]
     fn run_once_returns_unknown_for_unmatched_command() {
         let mut repl = Repl::new();
@@ -2090,8 +4650,195 @@ mod tests {
 
         assert_eq!(
             repl.run_once("note \"foo").unwrap(),
-            RunOnceOutcome::ParseError(ParseLineError::UnterminatedQuote)
+            RunOnceOutcome::ParseError(ParseLineError::UnterminatedQuote { offset: 5 })
         );
         assert_eq!(repl.current_mode_id().unwrap(), 0);
     }
+
+    /// Moves forward by `step` every time it is read.
+    struct SteppingClock {
+        now: std::time::Instant,
+        step: Duration,
+    }
+
+    impl timing::Clock for SteppingClock {
+        fn now(&mut self) -> std::time::Instant {
+            let now = self.now;
+            self.now += self.step;
+            now
+        }
+    }
+
+    fn use_stepping_clock(repl: &mut Repl, step: Duration) {
+        repl.clock = Box::new(SteppingClock {
+            now: std::time::Instant::now(),
+            step,
+        });
+    }
+
+    #[test]
+    fn run_once_measures_each_command_with_the_clock() {
+        let mut repl = help_repl();
+        use_stepping_clock(&mut repl, Duration::from_micros(250));
+
+        assert_eq!(repl.last_duration(), None);
+        repl.run_once("show version").unwrap();
+        assert_eq!(repl.last_duration(), Some(Duration::from_micros(250)));
+
+        use_stepping_clock(&mut repl, Duration::from_secs(2));
+        repl.run_once("").unwrap();
+        assert_eq!(repl.last_duration(), Some(Duration::from_micros(250)));
+        repl.run_once("nosuch").unwrap();
+        assert_eq!(repl.last_duration(), Some(Duration::from_secs(2)));
+        assert_eq!(
+            timing::format_timing(repl.last_duration().unwrap()),
+            "took 2.00s\n"
+        );
+    }
+
+    #[test]
+    fn run_once_times_shell_commands() {
+        let (mut repl, _outputs) = shell_repl();
+        use_stepping_clock(&mut repl, Duration::from_millis(3));
+        repl.set_timings(true);
+
+        assert_eq!(
+            repl.run_once("!true").unwrap(),
+            RunOnceOutcome::ShellExited(0)
+        );
+        assert_eq!(repl.last_duration(), Some(Duration::from_millis(3)));
+    }
+
+    #[test]
+    fn time_runs_the_rest_of_the_line_as_a_command() {
+        let mut repl = help_repl();
+        use_stepping_clock(&mut repl, Duration::from_millis(1));
+
+        assert_eq!(
+            repl.run_once("time show version").unwrap(),
+            RunOnceOutcome::ActionApplied(Action::None)
+        );
+        assert!(!repl.duration_reported);
+        assert_eq!(
+            repl.run_once("time nosuch").unwrap(),
+            RunOnceOutcome::UnknownCommand
+        );
+        assert_eq!(repl.last_status(), STATUS_USAGE);
+        assert_eq!(
+            repl.run_once("time").unwrap(),
+            RunOnceOutcome::UsageError("usage: time <command>".to_string())
+        );
+    }
+
+    #[test]
+    fn set_timings_turns_reporting_on_and_off() {
+        let mut repl = Repl::new();
+
+        assert!(!repl.timings());
+        assert_eq!(
+            repl.builtin_output(&tokens("set timings on")),
+            Some(Ok(String::new()))
+        );
+        assert!(repl.timings());
+        assert_eq!(
+            repl.builtin_output(&tokens("set timings off")),
+            Some(Ok(String::new()))
+        );
+        assert!(!repl.timings());
+        for line in ["set", "set timings"] {
+            assert_eq!(
+                repl.builtin_output(&tokens(line)),
+                Some(Err(RunOnceOutcome::UsageError(
+                    "usage: set <name> <value>... | set -u|+u | set timings on|off".to_string()
+                ))),
+                "{}",
+                line
+            );
+        }
+    }
+
+    /// A REPL whose `echo <arg>` command records what it was given.
+    fn echo_repl() -> (Repl, std::rc::Rc<std::cell::RefCell<Vec<String>>>) {
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let recorder = seen.clone();
+        let mut repl = Repl::new();
+        repl.register_mode_command(
+            0,
+            &build_cmd(&["echo"], 1),
+            Box::new(move |_repl, inputs| {
+                recorder.borrow_mut().push(inputs.positionals[0].clone());
+                Ok(Action::None)
+            }),
+        )
+        .unwrap();
+        (repl, seen)
+    }
+
+    #[test]
+    fn variables_are_substituted_before_tokenizing() {
+        let (mut repl, seen) = echo_repl();
+
+        repl.run_once("set who ada lovelace").unwrap();
+        assert_eq!(
+            repl.builtin_output(&tokens("vars")),
+            Some(Ok("who = \"ada lovelace\"\n".to_string()))
+        );
+        repl.run_once("echo \"$who\"").unwrap();
+        repl.run_once("echo '$who'").unwrap();
+        repl.run_once("echo \"x${who}\"").unwrap();
+        assert_eq!(
+            repl.run_once("echo $who").unwrap(),
+            RunOnceOutcome::UnknownCommand
+        );
+        assert_eq!(
+            *seen.borrow(),
+            vec!["ada lovelace", "$who", "xada lovelace"]
+        );
+
+        repl.run_once("unset who").unwrap();
+        assert_eq!(repl.builtin_output(&tokens("vars")), Some(Ok(String::new())));
+        assert_eq!(
+            repl.run_once("unset who").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError("no variable named 'who'".to_string()))
+        );
+    }
+
+    #[test]
+    fn time_does_not_expand_variables_twice() {
+        let (mut repl, seen) = echo_repl();
+        use_stepping_clock(&mut repl, Duration::from_millis(1));
+
+        repl.run_once("set x secret").unwrap();
+        repl.run_once("time set b '$x'").unwrap();
+        repl.run_once("time echo '$x'").unwrap();
+        assert_eq!(
+            repl.builtin_output(&tokens("vars")),
+            Some(Ok("b = $x\nx = secret\n".to_string()))
+        );
+        assert_eq!(*seen.borrow(), vec!["$x"]);
+    }
+
+    #[test]
+    fn undefined_variables_expand_to_nothing_unless_strict() {
+        let (mut repl, seen) = echo_repl();
+
+        repl.run_once("echo \"[$missing]\"").unwrap();
+        assert_eq!(*seen.borrow(), vec!["[]"]);
+
+        repl.run_once("set -u").unwrap();
+        assert_eq!(
+            repl.run_once("echo \"[$missing]\"").unwrap(),
+            RunOnceOutcome::HandlerError(HandlerError(
+                "undefined variable 'missing'".to_string()
+            ))
+        );
+        assert_eq!(repl.last_status(), STATUS_FAILURE);
+        assert_eq!(seen.borrow().len(), 1);
+        repl.run_once("echo '$missing'").unwrap();
+        assert_eq!(seen.borrow().last().unwrap(), "$missing");
+
+        repl.run_once("set +u").unwrap();
+        repl.run_once("echo $missing.").unwrap();
+        assert_eq!(seen.borrow().last().unwrap(), ".");
+    }
 }
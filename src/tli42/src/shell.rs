@@ -0,0 +1,89 @@
+use std::io;
+use std::process::{Command, ExitStatus};
+
+const FALLBACK_SHELL: &str = "/bin/sh";
+
+/// Runs the command lines entered after `!`.
+pub trait ShellExecutor {
+    /// Run `command` to completion and return its exit status.
+    ///
+    /// An error means the command could not be started at all.
+    fn execute(&mut self, command: &str) -> io::Result<i32>;
+}
+
+/// Runs commands through `$SHELL -c`, or `/bin/sh` when `SHELL` is unset,
+/// with the terminal's stdin, stdout and stderr.
+///
+/// The line editor restores the terminal before returning each line, so the
+/// child never sees raw mode.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemShell;
+
+impl ShellExecutor for SystemShell {
+    fn execute(&mut self, command: &str) -> io::Result<i32> {
+        let shell = std::env::var("SHELL")
+            .ok()
+            .filter(|shell| !shell.is_empty())
+            .unwrap_or_else(|| FALLBACK_SHELL.to_string());
+        let status = Command::new(shell).arg("-c").arg(command).status()?;
+        Ok(exit_code(status))
+    }
+}
+
+/// Quote `arg` so a POSIX shell passes it to the command unchanged.
+pub(crate) fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | ',' | ':' | '=')
+        });
+    if plain {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// The exit code of `status`, using the shell convention of `128 + signal`
+/// for a child killed by a signal.
+pub(crate) fn exit_code(status: ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sh(command: &str) -> ExitStatus {
+        Command::new("sh").arg("-c").arg(command).status().unwrap()
+    }
+
+    #[test]
+    fn exit_code_reports_exit_status_and_signals() {
+        assert_eq!(exit_code(sh("exit 0")), 0);
+        assert_eq!(exit_code(sh("exit 3")), 3);
+        #[cfg(unix)]
+        assert_eq!(exit_code(sh("kill -9 $$")), 137);
+    }
+
+    #[test]
+    fn quote_survives_the_shell() {
+        for arg in ["plain", "two words", "it's", "$HOME", "", "a\\b"] {
+            let output = Command::new("sh")
+                .arg("-c")
+                .arg(format!("printf %s {}", quote(arg)))
+                .output()
+                .unwrap();
+            assert_eq!(String::from_utf8(output.stdout).unwrap(), arg);
+        }
+        assert_eq!(quote("a/b.txt"), "a/b.txt");
+    }
+}
@@ -92,6 +92,16 @@ pub(crate) struct Completion<'a> {
     pub(crate) doc: Option<&'a str>,
 }
 
+/// A command reachable from some state, described by the edges leading to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CommandPath<'a> {
+    /// Literals and variable placeholders along the path, in order.
+    pub(crate) tokens: Vec<&'a str>,
+    /// The command documentation, falling back to the documentation of the last
+    /// edge on the path.
+    pub(crate) doc: Option<&'a str>,
+}
+
 /// Result of querying possible transitions, e.g. from a given state and given a
 /// partial input.
 #[derive(Debug, Default)]
@@ -511,6 +521,41 @@ impl Sm {
         Ok(state.accept.as_ref().and_then(|a| a.doc.as_deref()))
     }
 
+    /// Every command reachable from `state_id` (including one at `state_id`
+    /// itself), in depth-first edge insertion order.
+    pub(crate) fn command_paths(&self, state_id: StateId) -> Vec<CommandPath<'_>> {
+        let mut paths = Vec::new();
+        let mut tokens = Vec::new();
+        self.collect_command_paths(state_id, None, &mut tokens, &mut paths);
+        paths
+    }
+
+    fn collect_command_paths<'a>(
+        &'a self,
+        state_id: StateId,
+        edge_doc: Option<&'a str>,
+        tokens: &mut Vec<&'a str>,
+        paths: &mut Vec<CommandPath<'a>>,
+    ) {
+        let Some(state) = self.states.get(state_id) else {
+            return;
+        };
+        if let Some(accept) = &state.accept {
+            paths.push(CommandPath {
+                tokens: tokens.clone(),
+                doc: accept.doc.as_deref().or(edge_doc),
+            });
+        }
+        for link in &state.edges {
+            let token = match &link.edge {
+                Edge::Literal(literal) => literal.as_str(),
+                Edge::Var { placeholder } => placeholder.as_str(),
+            };
+            tokens.push(token);
+            self.collect_command_paths(link.next_state, link.doc.as_deref(), tokens, paths);
+            tokens.pop();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -553,6 +598,69 @@ mod tests {
         v
     }
 
+    #[test]
+    fn command_paths_walks_accepting_states_with_docs() {
+        let sm = sm_with_states(vec![
+            State {
+                edges: vec![lit_edge("show", 1)],
+                accept: None,
+            },
+            State {
+                edges: vec![
+                    EdgeLink {
+                        edge: Edge::Literal("version".to_string()),
+                        next_state: 2,
+                        doc: Some("edge doc".to_string()),
+                    },
+                    var_edge(3),
+                ],
+                accept: Some(AcceptMeta {
+                    command_id: 0,
+                    doc: None,
+                }),
+            },
+            State {
+                edges: Vec::new(),
+                accept: Some(AcceptMeta {
+                    command_id: 1,
+                    doc: Some("command doc".to_string()),
+                }),
+            },
+            State {
+                edges: Vec::new(),
+                accept: Some(AcceptMeta {
+                    command_id: 2,
+                    doc: None,
+                }),
+            },
+        ]);
+
+        assert_eq!(
+            sm.command_paths(0),
+            vec![
+                CommandPath {
+                    tokens: vec!["show"],
+                    doc: None,
+                },
+                CommandPath {
+                    tokens: vec!["show", "version"],
+                    doc: Some("command doc"),
+                },
+                CommandPath {
+                    tokens: vec!["show", "<arg>"],
+                    doc: None,
+                },
+            ]
+        );
+        assert_eq!(
+            sm.command_paths(1)[1],
+            CommandPath {
+                tokens: vec!["version"],
+                doc: Some("command doc"),
+            }
+        );
+    }
+
     #[test]
     fn get_completions_returns_matching_literals_only() {
         let sm = sm_with_states(vec![State {
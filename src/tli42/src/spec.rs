@@ -0,0 +1,292 @@
+use crate::builder::{is_placeholder, phrase_cmd};
+use crate::cmd;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::Path;
+
+const COMMAND_TABLE: &str = "command";
+const COMMAND_KEYS: &[&str] = &["phrase", "doc", "action"];
+const SHELL_PREFIX: &str = "shell:";
+const BUILTIN_PREFIX: &str = "builtin:";
+
+/// What a spec command runs, with the command's arguments appended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SpecAction {
+    /// A command line for the shell executor, as if typed after `!`.
+    Shell(String),
+    /// A built-in command and its leading arguments.
+    Builtin(Vec<String>),
+}
+
+/// One `[[command]]` table of a spec file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SpecCommand {
+    pub(crate) phrase: String,
+    pub(crate) doc: Option<String>,
+    pub(crate) action: SpecAction,
+    /// Where the command is defined, for conflicts found when registering it.
+    pub(crate) line: usize,
+}
+
+impl SpecCommand {
+    pub(crate) fn cmd(&self) -> cmd::Cmd {
+        phrase_cmd(&self.phrase, self.doc.as_deref())
+    }
+
+    fn same_definition(&self, other: &Self) -> bool {
+        self.doc == other.doc && self.action == other.action
+    }
+}
+
+/// Read the `[[command]]` tables of the spec at `path`.
+///
+/// Any problem is reported as `InvalidData` naming the offending line: a file
+/// that is not valid TOML, an unknown key, a missing phrase or action, a phrase
+/// defined twice, or an action that is neither `shell:` nor `builtin:` one of
+/// `builtins`.
+pub(crate) fn load_file(path: &Path, builtins: &[&str]) -> io::Result<Vec<SpecCommand>> {
+    let text = std::fs::read_to_string(path)?;
+    let line_at = |offset: usize| text[..offset].matches('\n').count() + 1;
+    let invalid = |line: usize, message: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}:{}: {}", path.display(), line, message),
+        )
+    };
+
+    type Table = BTreeMap<String, toml::Spanned<String>>;
+    let file: BTreeMap<toml::Spanned<String>, Vec<Table>> =
+        toml::from_str(&text).map_err(|err| {
+            let line = err.span().map_or(1, |span| line_at(span.start));
+            invalid(line, err.message().to_string())
+        })?;
+
+    let mut commands = Vec::new();
+    let mut defined = HashMap::new();
+    for (key, tables) in file {
+        if key.get_ref() != COMMAND_TABLE {
+            let line = line_at(key.span().start);
+            return Err(invalid(line, format!("unknown table '{}'", key.get_ref())));
+        }
+        for table in tables {
+            let line = table
+                .values()
+                .map(|value| line_at(value.span().start))
+                .min()
+                .unwrap_or_else(|| line_at(key.span().start));
+            if let Some((key, value)) = table
+                .iter()
+                .find(|(key, _)| !COMMAND_KEYS.contains(&key.as_str()))
+            {
+                let line = line_at(value.span().start);
+                return Err(invalid(line, format!("unknown key '{key}'")));
+            }
+            let field = |key: &str| table.get(key).map(|value| value.get_ref().trim());
+
+            let Some(phrase) = field("phrase").filter(|phrase| !phrase.is_empty()) else {
+                return Err(invalid(line, "command has no phrase".to_string()));
+            };
+            let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+            // Placeholder names do not matter when matching input.
+            let path_key = phrase
+                .split_whitespace()
+                .map(|word| if is_placeholder(word) { "<>" } else { word })
+                .collect::<Vec<_>>()
+                .join(" ");
+            if let Some(first) = defined.insert(path_key, line) {
+                return Err(invalid(
+                    line,
+                    format!("duplicate phrase '{phrase}', first defined on line {first}"),
+                ));
+            }
+
+            let Some(action) = field("action") else {
+                return Err(invalid(line, format!("'{phrase}' has no action")));
+            };
+            let action =
+                parse_action(action, builtins).map_err(|message| invalid(line, message))?;
+            commands.push(SpecCommand {
+                phrase,
+                doc: field("doc").map(str::to_string),
+                action,
+                line,
+            });
+        }
+    }
+    Ok(commands)
+}
+
+fn parse_action(action: &str, builtins: &[&str]) -> Result<SpecAction, String> {
+    if let Some(command) = action.strip_prefix(SHELL_PREFIX) {
+        let command = command.trim();
+        if command.is_empty() {
+            return Err("shell action has no command".to_string());
+        }
+        return Ok(SpecAction::Shell(command.to_string()));
+    }
+    if let Some(command) = action.strip_prefix(BUILTIN_PREFIX) {
+        let tokens = crate::repl::parse_tokens(command)
+            .ok_or_else(|| format!("malformed built-in action '{command}'"))?;
+        return match tokens.first() {
+            Some(name) if builtins.contains(&name.as_str()) => Ok(SpecAction::Builtin(tokens)),
+            Some(name) => Err(format!("unknown built-in '{name}'")),
+            None => Err("built-in action has no command".to_string()),
+        };
+    }
+    let kind = action.split_once(':').map_or(action, |(kind, _)| kind);
+    Err(format!(
+        "unknown action kind '{kind}', expected {SHELL_PREFIX} or {BUILTIN_PREFIX}"
+    ))
+}
+
+/// Describe how the spec commands in `after` differ from `before`, one line
+/// per kind of change.
+pub(crate) fn format_changes(before: &[SpecCommand], after: &[SpecCommand]) -> String {
+    let find = |commands: &'_ [SpecCommand], phrase: &str| {
+        commands.iter().position(|command| command.phrase == phrase)
+    };
+    let mut added = Vec::new();
+    let mut updated = Vec::new();
+    for command in after {
+        match find(before, &command.phrase) {
+            None => added.push(command.phrase.as_str()),
+            Some(idx) if !before[idx].same_definition(command) => {
+                updated.push(command.phrase.as_str())
+            }
+            Some(_) => {}
+        }
+    }
+    let removed = before
+        .iter()
+        .filter(|command| find(after, &command.phrase).is_none())
+        .map(|command| command.phrase.as_str())
+        .collect::<Vec<_>>();
+
+    [("added", added), ("removed", removed), ("updated", updated)]
+        .into_iter()
+        .filter(|(_, phrases)| !phrases.is_empty())
+        .map(|(kind, phrases)| format!("commands {}: {}\n", kind, phrases.join(", ")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUILTINS: &[&str] = &["history", "status"];
+
+    fn load(text: &str) -> (tempfile::TempDir, io::Result<Vec<SpecCommand>>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spec.toml");
+        std::fs::write(&path, text).unwrap();
+        let loaded = load_file(&path, BUILTINS);
+        (dir, loaded)
+    }
+
+    fn error_message(text: &str) -> String {
+        let (dir, loaded) = load(text);
+        let err = loaded.expect_err("spec is invalid");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let prefix = format!("{}:", dir.path().join("spec.toml").display());
+        err.to_string()
+            .strip_prefix(&prefix)
+            .expect("error names the file")
+            .to_string()
+    }
+
+    #[test]
+    fn load_file_reads_shell_and_builtin_commands() {
+        let (_dir, loaded) = load(
+            "[[command]]\n\
+             phrase = \"git  commit <message>\"\n\
+             doc = \"record changes\"\n\
+             action = \"shell:git commit -m\"\n\
+             \n\
+             [[command]]\n\
+             phrase = \"past\"\n\
+             action = \"builtin:history\"\n",
+        );
+
+        assert_eq!(
+            loaded.unwrap(),
+            vec![
+                SpecCommand {
+                    phrase: "git commit <message>".to_string(),
+                    doc: Some("record changes".to_string()),
+                    action: SpecAction::Shell("git commit -m".to_string()),
+                    line: 2,
+                },
+                SpecCommand {
+                    phrase: "past".to_string(),
+                    doc: None,
+                    action: SpecAction::Builtin(vec!["history".to_string()]),
+                    line: 7,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn load_file_rejects_duplicate_phrases() {
+        assert_eq!(
+            error_message(
+                "[[command]]\nphrase = \"greet <name>\"\naction = \"shell:echo hi\"\n\
+                 [[command]]\nphrase = \"greet <who>\"\naction = \"builtin:status\"\n"
+            ),
+            "5: duplicate phrase 'greet <who>', first defined on line 2"
+        );
+    }
+
+    #[test]
+    fn load_file_rejects_unknown_actions() {
+        assert_eq!(
+            error_message("[[command]]\nphrase = \"go\"\naction = \"python:run()\"\n"),
+            "2: unknown action kind 'python', expected shell: or builtin:"
+        );
+        assert_eq!(
+            error_message("[[command]]\nphrase = \"go\"\naction = \"builtin:commit\"\n"),
+            "2: unknown built-in 'commit'"
+        );
+        assert_eq!(
+            error_message("[[command]]\nphrase = \"go\"\naction = \"shell: \"\n"),
+            "2: shell action has no command"
+        );
+    }
+
+    #[test]
+    fn load_file_reports_malformed_tables_with_their_line() {
+        assert_eq!(
+            error_message("[[command]]\nphrase = \"go\"\nation = \"shell:ls\"\n"),
+            "3: unknown key 'ation'"
+        );
+        assert_eq!(
+            error_message("[[command]]\n\nphrase = \"go\"\n"),
+            "3: 'go' has no action"
+        );
+        assert_eq!(
+            error_message("[[commands]]\nphrase = \"go\"\n"),
+            "1: unknown table 'commands'"
+        );
+        assert!(error_message("[[command]]\nphrase = go\n").starts_with("2: "));
+    }
+
+    #[test]
+    fn format_changes_lists_added_removed_and_updated_phrases() {
+        let command = |phrase: &str, action: &str| SpecCommand {
+            phrase: phrase.to_string(),
+            doc: None,
+            action: SpecAction::Shell(action.to_string()),
+            line: 1,
+        };
+        let before = vec![command("a", "x"), command("b", "x"), command("c", "x")];
+        let mut after = before.clone();
+        after.iter_mut().for_each(|command| command.line += 1);
+        assert_eq!(format_changes(&before, &after), "");
+
+        let after = vec![command("a", "y"), command("c", "x"), command("d", "x")];
+        assert_eq!(
+            format_changes(&before, &after),
+            "commands added: d\ncommands removed: b\ncommands updated: a\n"
+        );
+    }
+}
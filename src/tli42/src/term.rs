@@ -0,0 +1,102 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+const DEFAULT_TERMINAL_HEIGHT: usize = 24;
+
+/// Width set with `Repl::set_terminal_width`, or 0 to detect it.
+static WIDTH_OVERRIDE: AtomicUsize = AtomicUsize::new(0);
+
+/// Columns and rows of the controlling terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TerminalSize {
+    pub(crate) columns: usize,
+    pub(crate) rows: usize,
+}
+
+pub(crate) fn set_width_override(width: Option<usize>) {
+    WIDTH_OVERRIDE.store(width.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// Columns to lay output out in. Read afresh on every call, so a resize
+/// between commands is picked up by the next render.
+pub(crate) fn terminal_width() -> usize {
+    let width_override = Some(WIDTH_OVERRIDE.load(Ordering::Relaxed)).filter(|width| *width > 0);
+    resolve_dimension(
+        width_override,
+        std::env::var("COLUMNS").ok().as_deref(),
+        detect_size().map(|size| size.columns),
+        DEFAULT_TERMINAL_WIDTH,
+    )
+}
+
+/// Rows available for output; see `terminal_width`.
+pub(crate) fn terminal_height() -> usize {
+    resolve_dimension(
+        None,
+        std::env::var("LINES").ok().as_deref(),
+        detect_size().map(|size| size.rows),
+        DEFAULT_TERMINAL_HEIGHT,
+    )
+}
+
+/// Pick the first usable value: an explicit override, then the environment
+/// variable, then what the terminal reports, then `default`.
+pub(crate) fn resolve_dimension(
+    explicit: Option<usize>,
+    env: Option<&str>,
+    detected: Option<usize>,
+    default: usize,
+) -> usize {
+    let env = env.and_then(|value| value.trim().parse::<usize>().ok());
+    [explicit, env, detected]
+        .into_iter()
+        .flatten()
+        .find(|value| *value > 0)
+        .unwrap_or(default)
+}
+
+/// Ask the terminal on stdout, stderr or stdin for its size, in that order.
+#[cfg(unix)]
+fn detect_size() -> Option<TerminalSize> {
+    [libc::STDOUT_FILENO, libc::STDERR_FILENO, libc::STDIN_FILENO]
+        .into_iter()
+        .find_map(|fd| {
+            let mut size = std::mem::MaybeUninit::<libc::winsize>::uninit();
+            // SAFETY: TIOCGWINSZ fills in the winsize struct when it returns 0.
+            let size = unsafe {
+                if libc::ioctl(fd, libc::TIOCGWINSZ, size.as_mut_ptr()) != 0 {
+                    return None;
+                }
+                size.assume_init()
+            };
+            (size.ws_col > 0 && size.ws_row > 0).then_some(TerminalSize {
+                columns: usize::from(size.ws_col),
+                rows: usize::from(size.ws_row),
+            })
+        })
+}
+
+#[cfg(not(unix))]
+fn detect_size() -> Option<TerminalSize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_dimension_prefers_override_then_env_then_terminal() {
+        assert_eq!(resolve_dimension(Some(40), Some("120"), Some(200), 80), 40);
+        assert_eq!(resolve_dimension(None, Some("120"), Some(200), 80), 120);
+        assert_eq!(resolve_dimension(None, None, Some(200), 80), 200);
+        assert_eq!(resolve_dimension(None, None, None, 80), 80);
+    }
+
+    #[test]
+    fn resolve_dimension_skips_unusable_values() {
+        assert_eq!(resolve_dimension(Some(0), Some("wide"), Some(0), 80), 80);
+        assert_eq!(resolve_dimension(None, Some("0"), Some(100), 80), 100);
+        assert_eq!(resolve_dimension(None, Some(" 60 "), None, 80), 60);
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+/// Where the REPL reads the time when measuring how long a command took.
+pub(crate) trait Clock {
+    fn now(&mut self) -> Instant;
+}
+
+/// Reads `Instant::now`.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&mut self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Format `elapsed` in the largest unit that keeps it above 1: whole
+/// microseconds, then milliseconds to one decimal, then seconds to two.
+pub(crate) fn format_duration(elapsed: Duration) -> String {
+    if elapsed < Duration::from_millis(1) {
+        format!("{}µs", elapsed.as_micros())
+    } else if elapsed < Duration::from_secs(1) {
+        format!("{:.1}ms", elapsed.as_secs_f64() * 1000.0)
+    } else {
+        format!("{:.2}s", elapsed.as_secs_f64())
+    }
+}
+
+/// The line printed after a timed command.
+pub(crate) fn format_timing(elapsed: Duration) -> String {
+    format!("took {}\n", format_duration(elapsed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_duration_picks_unit_by_magnitude() {
+        let cases = [
+            (Duration::ZERO, "0µs"),
+            (Duration::from_nanos(999), "0µs"),
+            (Duration::from_micros(999), "999µs"),
+            (Duration::from_millis(1), "1.0ms"),
+            (Duration::from_micros(1_560), "1.6ms"),
+            (Duration::from_micros(999_900), "999.9ms"),
+            (Duration::from_secs(1), "1.00s"),
+            (Duration::from_millis(62_507), "62.51s"),
+        ];
+        for (elapsed, expected) in cases {
+            assert_eq!(format_duration(elapsed), expected, "{:?}", elapsed);
+        }
+    }
+
+    #[test]
+    fn format_timing_is_one_line() {
+        assert_eq!(format_timing(Duration::from_micros(20)), "took 20µs\n");
+    }
+}
@@ -1,12 +1,38 @@
 #![cfg_attr(not(test), allow(dead_code))]
 
-use std::collections::{HashMap, hash_map};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+use unicode_normalization::{IsNormalized, UnicodeNormalization, is_nfc_quick};
 
 type InternedStringType = u32;
 
+/// `s` in Unicode NFC, borrowing when it already is.
+fn nfc(s: &str) -> Cow<'_, str> {
+    match is_nfc_quick(s.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(s),
+        _ => Cow::Owned(s.nfc().collect()),
+    }
+}
+
+/// Token separators: Unicode whitespace (which includes no-break spaces) plus the
+/// invisible zero-width characters that tend to come along with pasted text.
+fn is_token_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '\u{200B}' | '\u{2060}' | '\u{FEFF}')
+}
+
+/// Split `s` into trie tokens.
+fn tokenize(s: &str) -> impl Iterator<Item = &str> {
+    s.split(is_token_separator)
+        .filter(|token| !token.is_empty())
+}
+
 struct StringInterner {
     string_to_interned_value: HashMap<String, InternedStringType>,
     interned_value_to_string: Vec<String>,
+    /// Lowercased string to every interned id with that spelling, in interning order.
+    lowercase_to_interned_values: HashMap<String, Vec<InternedStringType>>,
 }
 
 impl StringInterner {
@@ -14,10 +40,14 @@ impl StringInterner {
         Self {
             string_to_interned_value: HashMap::new(),
             interned_value_to_string: Vec::new(),
+            lowercase_to_interned_values: HashMap::new(),
         }
     }
 
+    /// Strings are stored in NFC, so NFC-equal spellings share an id.
     pub fn intern(&mut self, s: &str) -> InternedStringType {
+        let s = nfc(s);
+        let s = s.as_ref();
         if let Some(interned_value) = self.string_to_interned_value.get(s) {
             *interned_value
         } else {
@@ -25,12 +55,24 @@ impl StringInterner {
             self.interned_value_to_string.push(s.to_string());
             self.string_to_interned_value
                 .insert(s.to_string(), interned_value);
+            self.lowercase_to_interned_values
+                .entry(s.to_lowercase())
+                .or_default()
+                .push(interned_value);
             interned_value
         }
     }
 
     pub fn get_interned(&self, s: &str) -> Option<InternedStringType> {
-        self.string_to_interned_value.get(s).copied()
+        self.string_to_interned_value.get(nfc(s).as_ref()).copied()
+    }
+
+    /// Every interned id whose string equals `s` ignoring case, in interning order.
+    pub fn get_interned_ignore_case(&self, s: &str) -> &[InternedStringType] {
+        self.lowercase_to_interned_values
+            .get(&nfc(s).to_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
     }
 
     pub fn resolve(&self, id: InternedStringType) -> Option<&str> {
@@ -40,70 +82,245 @@ impl StringInterner {
     }
 }
 
+/// Key token that stands for any single input token, e.g. `open <*>`.
+const WILDCARD_TOKEN: &str = "<*>";
+
 type TrieNodeEdge = InternedStringType;
 type TrieNodeValue = u32;
 type TrieNodeIdx = usize;
 
-struct TrieNode {
-    value: Option<TrieNodeValue>,
-    children: HashMap<TrieNodeEdge, TrieNodeIdx>,
+/// How query tokens are compared against registered tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum MatchMode {
+    #[default]
+    CaseSensitive,
+    /// Tokens match ignoring case; results keep the registered casing. When several
+    /// registered tokens differ only by case, the first one interned wins for `get`.
+    CaseInsensitive,
+}
+
+/// A node's outgoing edges, kept sorted by edge for binary search. Nodes usually
+/// have only a handful of children, where this is smaller and faster than a map.
+#[derive(Debug, Default)]
+struct Children(Vec<(TrieNodeEdge, TrieNodeIdx)>);
+
+impl Children {
+    fn get(&self, edge: &TrieNodeEdge) -> Option<&TrieNodeIdx> {
+        let pos = self.0.binary_search_by_key(edge, |(e, _)| *e).ok()?;
+        Some(&self.0[pos].1)
+    }
+
+    /// Add or replace the child at `edge`, returning the replaced index.
+    fn insert(&mut self, edge: TrieNodeEdge, idx: TrieNodeIdx) -> Option<TrieNodeIdx> {
+        match self.0.binary_search_by_key(&edge, |(e, _)| *e) {
+            Ok(pos) => Some(std::mem::replace(&mut self.0[pos].1, idx)),
+            Err(pos) => {
+                self.0.insert(pos, (edge, idx));
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, edge: &TrieNodeEdge) -> Option<TrieNodeIdx> {
+        let pos = self.0.binary_search_by_key(edge, |(e, _)| *e).ok()?;
+        Some(self.0.remove(pos).1)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&TrieNodeEdge, &TrieNodeIdx)> {
+        self.0.iter().map(|(edge, idx)| (edge, idx))
+    }
+
+    fn values(&self) -> impl Iterator<Item = &TrieNodeIdx> {
+        self.0.iter().map(|(_, idx)| idx)
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+struct TrieNode<V> {
+    value: Option<V>,
+    /// Help text for the entry; only ever set alongside `value`.
+    doc: Option<String>,
+    /// Times this exact key was used. Kept when the key is re-added or removed.
+    uses: u64,
+    children: Children,
 }
 
-struct Trie {
+/// A token trie mapping whitespace-separated keys to values of type `V`; the default
+/// `u32` is what command ids use.
+struct Trie<V = TrieNodeValue> {
     string_interner: StringInterner,
-    nodes: Vec<TrieNode>,
-    root: TrieNode,
+    nodes: Vec<TrieNode<V>>,
+    root: TrieNode<V>,
+    match_mode: MatchMode,
 }
 
-struct Completions<'a> {
-    partial: &'a str,
-    iter: Option<hash_map::Iter<'a, TrieNodeEdge, TrieNodeIdx>>,
-    nodes: &'a [TrieNode],
-    interner: &'a StringInterner,
+/// How far an input matched the trie; see `Trie::longest_match`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MatchResult<'a, V> {
+    /// Number of leading input tokens that follow a path in the trie.
+    matched_tokens: usize,
+    /// Value of the deepest node on that path that has one.
+    value: Option<&'a V>,
+    /// The input from the first unmatched token onwards, or `""`.
+    remaining: &'a str,
 }
 
-impl<'a> Completions<'a> {
-    fn empty(partial: &'a str, nodes: &'a [TrieNode], interner: &'a StringInterner) -> Self {
-        Self {
-            partial,
-            iter: None,
-            nodes,
-            interner,
-        }
+/// Where an input leads in the trie, token by token; see `Trie::describe_path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PathDescription<'a> {
+    /// Number of tokens in the input.
+    tokens: usize,
+    /// Number of leading input tokens that follow a path in the trie. Matching stops
+    /// at the first miss, so later tokens never count as matched.
+    matched_tokens: usize,
+    /// The node reached when every token matched.
+    node: Option<PathNode<'a>>,
+    /// When only the final token missed and the input does not end in a separator,
+    /// the number of children that token is a prefix of; zero otherwise.
+    partial_candidates: usize,
+}
+
+/// The node at the end of a fully matched path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PathNode<'a> {
+    /// Whether the node has a value, i.e. the input can be run as is.
+    actionable: bool,
+    children: usize,
+    doc: Option<&'a str>,
+}
+
+impl PathDescription<'_> {
+    /// Whether the `idx`th input token matched a trie node.
+    fn token_matched(&self, idx: usize) -> bool {
+        idx < self.matched_tokens
+    }
+
+    /// Whether no completion of the input can reach the trie.
+    fn is_dead_end(&self) -> bool {
+        self.node.is_none() && self.partial_candidates == 0
+    }
+}
+
+/// One completion candidate. Stems without a value have neither `value` nor `doc`.
+#[derive(Debug, PartialEq, Eq)]
+struct Completion<'a, V> {
+    token: &'a str,
+    /// Whether this is a `<*>` position, where an argument rather than `token` is
+    /// expected.
+    is_argument: bool,
+    value: Option<&'a V>,
+    doc: Option<&'a str>,
+    /// Uses recorded for entries at or below this token; see `Trie::record_use`.
+    weight: u64,
+}
+
+impl<V> Completion<'_, V> {
+    /// Ordering for ranking by use: heaviest first, then by token.
+    fn cmp_by_weight(&self, other: &Self) -> std::cmp::Ordering {
+        other
+            .weight
+            .cmp(&self.weight)
+            .then_with(|| self.token.cmp(other.token))
     }
 }
 
-impl<'a> Iterator for Completions<'a> {
-    type Item = (&'a str, Option<TrieNodeValue>);
+/// Completion candidates for one input, sorted by token. (Children are stored in
+/// edge id order, which follows interning order rather than the token text.)
+struct Completions<'a, V> {
+    candidates: std::vec::IntoIter<Completion<'a, V>>,
+}
+
+impl<'a, V> Iterator for Completions<'a, V> {
+    type Item = Completion<'a, V>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let iter = self.iter.as_mut()?;
-        for (edge, child_idx) in iter.by_ref() {
-            let token = self.interner.resolve(*edge)?;
-            if token.starts_with(self.partial) {
-                return Some((token, self.nodes[*child_idx].value));
-            }
-        }
-        None
+        self.candidates.next()
     }
 }
 
-impl Trie {
+impl<V> Trie<V> {
     pub fn new() -> Self {
         Trie {
             string_interner: StringInterner::new(),
             nodes: Vec::new(),
             root: TrieNode {
                 value: None,
-                children: HashMap::new(),
+                doc: None,
+                uses: 0,
+                children: Children::default(),
             },
+            match_mode: MatchMode::CaseSensitive,
+        }
+    }
+
+    pub fn with_match_mode(match_mode: MatchMode) -> Self {
+        Trie {
+            match_mode,
+            ..Self::new()
+        }
+    }
+
+    fn children_of(&self, node_idx: Option<TrieNodeIdx>) -> &Children {
+        match node_idx {
+            None => &self.root.children,
+            Some(node_idx) => &self.nodes[node_idx].children,
         }
     }
 
-    pub fn add_string(&mut self, s: &str, value: TrieNodeValue) {
+    /// The child of `node_idx` reached by `token`: a literal match under the trie's
+    /// match mode if there is one, otherwise the wildcard child. Matching is greedy,
+    /// so a literal is taken even if only the wildcard would lead to an entry.
+    fn find_child(
+        &self,
+        node_idx: Option<TrieNodeIdx>,
+        token: &str,
+    ) -> Option<(TrieNodeEdge, TrieNodeIdx)> {
+        self.find_literal_child(node_idx, token).or_else(|| {
+            let edge = self.string_interner.get_interned(WILDCARD_TOKEN)?;
+            Some((edge, *self.children_of(node_idx).get(&edge)?))
+        })
+    }
+
+    /// Like `find_child`, but `<*>` only matches itself.
+    fn find_literal_child(
+        &self,
+        node_idx: Option<TrieNodeIdx>,
+        token: &str,
+    ) -> Option<(TrieNodeEdge, TrieNodeIdx)> {
+        let children = self.children_of(node_idx);
+        let exact = self.string_interner.get_interned(token);
+        if let Some(child) = exact.and_then(|edge| Some((edge, *children.get(&edge)?))) {
+            return Some(child);
+        }
+        if self.match_mode == MatchMode::CaseSensitive {
+            return None;
+        }
+        self.string_interner
+            .get_interned_ignore_case(token)
+            .iter()
+            .find_map(|edge| Some((*edge, *children.get(edge)?)))
+    }
+
+    pub fn add_string(&mut self, s: &str, value: V) {
+        self.insert(s, value, None);
+    }
+
+    /// Like `add_string`, also recording help text that completions surface.
+    pub fn add_string_with_doc(&mut self, s: &str, value: V, doc: impl Into<String>) {
+        self.insert(s, value, Some(doc.into()));
+    }
+
+    fn insert(&mut self, s: &str, value: V, doc: Option<String>) {
         let mut current_idx: Option<TrieNodeIdx> = None;
 
-        for token in s.split_whitespace() {
+        for token in tokenize(s) {
             let interned_token = self.string_interner.intern(token);
             let existing_child = match current_idx {
                 None => self.root.children.get(&interned_token).copied(),
@@ -118,7 +335,9 @@ impl Trie {
             let new_idx = self.nodes.len();
             self.nodes.push(TrieNode {
                 value: None,
-                children: HashMap::new(),
+                doc: None,
+                uses: 0,
+                children: Children::default(),
             });
 
             match current_idx {
@@ -135,38 +354,208 @@ impl Trie {
             current_idx = Some(new_idx);
         }
 
-        match current_idx {
-            None => {
-                self.root.value = Some(value);
+        let node = match current_idx {
+            None => &mut self.root,
+            Some(node_idx) => &mut self.nodes[node_idx],
+        };
+        node.value = Some(value);
+        node.doc = doc;
+    }
+
+    pub fn get(&self, s: &str) -> Option<&V> {
+        self.get_entry(s).map(|(value, _)| value)
+    }
+
+    /// The value stored at `s` together with its doc, if one was given.
+    pub fn get_entry(&self, s: &str) -> Option<(&V, Option<&str>)> {
+        let mut current_idx: Option<TrieNodeIdx> = None;
+
+        for token in tokenize(s) {
+            let (_, child_idx) = self.find_child(current_idx, token)?;
+            current_idx = Some(child_idx);
+        }
+
+        let node = match current_idx {
+            None => &self.root,
+            Some(node_idx) => &self.nodes[node_idx],
+        };
+        Some((node.value.as_ref()?, node.doc.as_deref()))
+    }
+
+    /// Clear the value stored at exactly `s` (`<*>` only matches itself), returning
+    /// it. Nodes left with neither a value nor children are unlinked from their
+    /// parent, walking back towards the root. Unlinked nodes stay in `nodes` as
+    /// unreachable tombstones so that existing indices remain valid; `add_string`
+    /// never reuses them.
+    pub fn remove(&mut self, s: &str) -> Option<V> {
+        let mut path: Vec<(Option<TrieNodeIdx>, TrieNodeEdge, TrieNodeIdx)> = Vec::new();
+        let mut current_idx: Option<TrieNodeIdx> = None;
+
+        for token in tokenize(s) {
+            let (edge, child_idx) = self.find_literal_child(current_idx, token)?;
+            path.push((current_idx, edge, child_idx));
+            current_idx = Some(child_idx);
+        }
+
+        let node = match current_idx {
+            None => &mut self.root,
+            Some(node_idx) => &mut self.nodes[node_idx],
+        };
+        let removed = node.value.take()?;
+        node.doc = None;
+
+        for (parent_idx, edge, node_idx) in path.into_iter().rev() {
+            let node = &self.nodes[node_idx];
+            if node.value.is_some() || !node.children.is_empty() {
+                break;
+            }
+            match parent_idx {
+                None => self.root.children.remove(&edge),
+                Some(parent_idx) => self.nodes[parent_idx].children.remove(&edge),
+            };
+        }
+
+        Some(removed)
+    }
+
+    /// Every stored value with its space-joined key, depth first from the root (whose
+    /// key is the empty string), visiting siblings in lexicographic token order.
+    pub fn iter(&self) -> impl Iterator<Item = (String, &V)> + '_ {
+        let mut entries = Vec::new();
+        let mut stack = vec![(String::new(), &self.root)];
+
+        while let Some((key, node)) = stack.pop() {
+            if let Some(value) = &node.value {
+                entries.push((key.clone(), value));
             }
-            Some(node_idx) => {
-                self.nodes[node_idx].value = Some(value);
+            let mut children = node
+                .children
+                .iter()
+                .filter_map(|(edge, child_idx)| {
+                    Some((
+                        self.string_interner.resolve(*edge)?,
+                        &self.nodes[*child_idx],
+                    ))
+                })
+                .collect::<Vec<_>>();
+            children.sort_by(|a, b| b.0.cmp(a.0));
+            for (token, child) in children {
+                let child_key = if key.is_empty() {
+                    token.to_string()
+                } else {
+                    format!("{key} {token}")
+                };
+                stack.push((child_key, child));
             }
         }
+
+        entries.into_iter()
     }
 
-    pub fn get(&self, s: &str) -> Option<TrieNodeValue> {
+    /// Follow `s` token by token for as long as the trie has a matching child. Returns
+    /// `None` when not even the root matches, i.e. the first token is unknown and the
+    /// root has no value.
+    pub fn longest_match<'a>(&'a self, s: &'a str) -> Option<MatchResult<'a, V>> {
         let mut current_idx: Option<TrieNodeIdx> = None;
+        let mut matched_tokens = 0;
+        let mut value = self.root.value.as_ref();
+        let mut rest = s.trim_start_matches(is_token_separator);
 
-        for token in s.split_whitespace() {
-            let edge = self.string_interner.get_interned(token)?;
-            current_idx = match current_idx {
-                None => self.root.children.get(&edge).copied(),
-                Some(node_idx) => self.nodes[node_idx].children.get(&edge).copied(),
+        while let Some(token) = tokenize(rest).next() {
+            let Some((_, child_idx)) = self.find_child(current_idx, token) else {
+                break;
             };
+            current_idx = Some(child_idx);
+            matched_tokens += 1;
+            value = self.nodes[child_idx].value.as_ref().or(value);
+            rest = rest[token.len()..].trim_start_matches(is_token_separator);
+        }
+
+        if matched_tokens == 0 && value.is_none() {
+            return None;
+        }
+        Some(MatchResult {
+            matched_tokens,
+            value,
+            remaining: rest,
+        })
+    }
 
-            current_idx?;
+    /// Describe how far `s` follows the trie, for rendering breadcrumbs while the
+    /// user types. Only allocates when a partial final token needs NFC normalization
+    /// or case folding.
+    pub fn describe_path<'a>(&'a self, s: &str) -> PathDescription<'a> {
+        let mut current_idx: Option<TrieNodeIdx> = None;
+        let mut tokens = 0;
+        let mut matched_tokens = 0;
+        let mut missed = None;
+
+        for token in tokenize(s) {
+            tokens += 1;
+            if missed.is_some() {
+                continue;
+            }
+            match self.find_child(current_idx, token) {
+                Some((_, child_idx)) => {
+                    current_idx = Some(child_idx);
+                    matched_tokens += 1;
+                }
+                None => missed = Some(token),
+            }
         }
 
-        match current_idx {
-            None => self.root.value,
-            Some(node_idx) => self.nodes[node_idx].value,
+        let node = (matched_tokens == tokens).then(|| {
+            let node = match current_idx {
+                None => &self.root,
+                Some(node_idx) => &self.nodes[node_idx],
+            };
+            PathNode {
+                actionable: node.value.is_some(),
+                children: node.children.len(),
+                doc: node.doc.as_deref(),
+            }
+        });
+        let ends_with_whitespace = s.chars().next_back().is_some_and(is_token_separator);
+        let partial_candidates = match missed {
+            Some(partial) if matched_tokens + 1 == tokens && !ends_with_whitespace => {
+                self.count_partial_matches(current_idx, partial)
+            }
+            _ => 0,
+        };
+
+        PathDescription {
+            tokens,
+            matched_tokens,
+            node,
+            partial_candidates,
         }
     }
 
-    pub fn get_completions<'a>(&'a self, s: &'a str) -> Completions<'a> {
-        let ends_with_whitespace = s.chars().last().is_some_and(char::is_whitespace);
-        let mut tokens = s.split_whitespace().collect::<Vec<_>>();
+    /// Number of children of `node_idx` whose token starts with `partial`.
+    fn count_partial_matches(&self, node_idx: Option<TrieNodeIdx>, partial: &str) -> usize {
+        let partial = nfc(partial);
+        let folded_partial =
+            (self.match_mode == MatchMode::CaseInsensitive).then(|| partial.to_lowercase());
+
+        self.children_of(node_idx)
+            .iter()
+            .filter(|(edge, _)| {
+                let Some(token) = self.string_interner.resolve(**edge) else {
+                    return false;
+                };
+                match &folded_partial {
+                    None => token.starts_with(partial.as_ref()),
+                    Some(folded_partial) => token.to_lowercase().starts_with(folded_partial),
+                }
+            })
+            .count()
+    }
+
+    /// Walk every complete token of `s` and return the reached node along with the
+    /// trailing partial token. The outer `None` means the walk left the trie.
+    fn completion_parent<'s>(&self, s: &'s str) -> (Option<Option<TrieNodeIdx>>, &'s str) {
+        let ends_with_whitespace = s.chars().last().is_some_and(is_token_separator);
+        let mut tokens = tokenize(s).collect::<Vec<_>>();
 
         let partial = if ends_with_whitespace {
             ""
@@ -177,32 +566,483 @@ impl Trie {
 
         let mut current_idx: Option<TrieNodeIdx> = None;
         for token in exact_tokens {
-            let edge = match self.string_interner.get_interned(token) {
-                Some(edge) => edge,
-                None => return Completions::empty(partial, &self.nodes, &self.string_interner),
+            match self.find_child(current_idx, token) {
+                Some((_, child_idx)) => current_idx = Some(child_idx),
+                None => return (None, partial),
+            }
+        }
+        (Some(current_idx), partial)
+    }
+
+    pub fn get_completions<'a>(&'a self, s: &str) -> Completions<'a, V> {
+        let candidates = self
+            .matching_children(s)
+            .into_iter()
+            .map(|(token, child_idx)| {
+                let node = &self.nodes[child_idx];
+                Completion {
+                    token,
+                    is_argument: token == WILDCARD_TOKEN,
+                    value: node.value.as_ref(),
+                    doc: node.doc.as_deref(),
+                    weight: self.subtree_uses(child_idx),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Completions {
+            candidates: candidates.into_iter(),
+        }
+    }
+
+    /// Like `get_completions`, but each candidate is extended through nodes that have
+    /// no value and exactly one child, so a linear chain such as "commit amend" is
+    /// offered whole. Expansion stops at branching points and at valued nodes; the
+    /// returned value is that of the node where expansion stopped.
+    pub fn get_deep_completions(&self, s: &str) -> Vec<(String, Option<&V>)> {
+        let mut completions = Vec::new();
+        for (token, mut node_idx) in self.matching_children(s) {
+            let mut phrase = token.to_string();
+            loop {
+                let node = &self.nodes[node_idx];
+                if node.value.is_some() || node.children.len() != 1 {
+                    break;
+                }
+                let Some((edge, child_idx)) = node.children.iter().next() else {
+                    break;
+                };
+                let Some(next_token) = self.string_interner.resolve(*edge) else {
+                    break;
+                };
+                phrase.push(' ');
+                phrase.push_str(next_token);
+                node_idx = *child_idx;
+            }
+            completions.push((phrase, self.nodes[node_idx].value.as_ref()));
+        }
+        completions
+    }
+
+    /// Children of the node reached by the complete tokens of `s` whose token matches
+    /// the trailing partial, sorted by token.
+    fn matching_children(&self, s: &str) -> Vec<(&str, TrieNodeIdx)> {
+        let mut candidates = self.iter_matching_children(s).collect::<Vec<_>>();
+        candidates.sort_by(|a, b| a.0.cmp(b.0));
+        candidates
+    }
+
+    /// Unsorted `matching_children`, without collecting.
+    fn iter_matching_children(&self, s: &str) -> impl Iterator<Item = (&str, TrieNodeIdx)> {
+        let (current_idx, partial) = self.completion_parent(s);
+        let partial = nfc(partial).into_owned();
+        let folded_partial = partial.to_lowercase();
+
+        current_idx
+            .into_iter()
+            .flat_map(|node_idx| self.children_of(node_idx).iter())
+            .filter_map(move |(edge, child_idx)| {
+                let token = self.string_interner.resolve(*edge)?;
+                if token == WILDCARD_TOKEN {
+                    // Any partial could be the start of the argument.
+                    return Some((token, *child_idx));
+                }
+                let matches = match self.match_mode {
+                    MatchMode::CaseSensitive => token.starts_with(&partial),
+                    MatchMode::CaseInsensitive => token.to_lowercase().starts_with(&folded_partial),
+                };
+                matches.then_some((token, *child_idx))
+            })
+    }
+
+    /// Number of valued entries at or below the children `get_completions(s)` would
+    /// offer.
+    pub fn count_with_prefix(&self, s: &str) -> usize {
+        self.iter_matching_children(s)
+            .map(|(_, child_idx)| self.count_subtree(child_idx))
+            .sum()
+    }
+
+    /// Whether `count_with_prefix(s)` would be non-zero, stopping at the first entry.
+    pub fn contains_prefix(&self, s: &str) -> bool {
+        self.iter_matching_children(s)
+            .any(|(_, child_idx)| self.subtree_has_value(child_idx))
+    }
+
+    /// Count one use of the entry stored at exactly `s`. Returns false, recording
+    /// nothing, when there is no such entry.
+    pub fn record_use(&mut self, s: &str) -> bool {
+        let mut current_idx: Option<TrieNodeIdx> = None;
+        for token in tokenize(s) {
+            let Some((_, child_idx)) = self.find_child(current_idx, token) else {
+                return false;
             };
+            current_idx = Some(child_idx);
+        }
+
+        let node = match current_idx {
+            None => &mut self.root,
+            Some(node_idx) => &mut self.nodes[node_idx],
+        };
+        if node.value.is_none() {
+            return false;
+        }
+        node.uses += 1;
+        true
+    }
+
+    /// Scale every use count by `factor` (e.g. `0.5` to halve), rounding down, so
+    /// that old habits fade.
+    pub fn decay(&mut self, factor: f64) {
+        for node in std::iter::once(&mut self.root).chain(self.nodes.iter_mut()) {
+            node.uses = (node.uses as f64 * factor) as u64;
+        }
+    }
 
-            current_idx = match current_idx {
-                None => self.root.children.get(&edge).copied(),
-                Some(node_idx) => self.nodes[node_idx].children.get(&edge).copied(),
+    fn subtree_uses(&self, node_idx: TrieNodeIdx) -> u64 {
+        let mut uses = 0;
+        let mut stack = vec![node_idx];
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            uses += node.uses;
+            stack.extend(node.children.values());
+        }
+        uses
+    }
+
+    fn count_subtree(&self, node_idx: TrieNodeIdx) -> usize {
+        let mut count = 0;
+        let mut stack = vec![node_idx];
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            count += usize::from(node.value.is_some());
+            stack.extend(node.children.values());
+        }
+        count
+    }
+
+    fn subtree_has_value(&self, node_idx: TrieNodeIdx) -> bool {
+        let mut stack = vec![node_idx];
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            if node.value.is_some() {
+                return true;
+            }
+            stack.extend(node.children.values());
+        }
+        false
+    }
+
+    /// Like `get_completions`, but the final partial token matches any child token
+    /// containing its characters in order. Results are ranked best score first, then
+    /// by token.
+    pub fn get_completions_fuzzy<'a>(
+        &'a self,
+        s: &str,
+    ) -> Vec<(&'a str, Option<&'a V>, FuzzyScore)> {
+        let (Some(current_idx), partial) = self.completion_parent(s) else {
+            return Vec::new();
+        };
+        let partial = match self.match_mode {
+            MatchMode::CaseSensitive => nfc(partial).into_owned(),
+            MatchMode::CaseInsensitive => nfc(partial).to_lowercase(),
+        };
+
+        let mut results = Vec::new();
+        for (edge, child_idx) in self.children_of(current_idx).iter() {
+            let Some(token) = self.string_interner.resolve(*edge) else {
+                continue;
             };
+            let score = match self.match_mode {
+                MatchMode::CaseSensitive => fuzzy_score(token, &partial),
+                MatchMode::CaseInsensitive => fuzzy_score(&token.to_lowercase(), &partial),
+            };
+            if let Some(score) = score {
+                results.push((token, self.nodes[*child_idx].value.as_ref(), score));
+            }
+        }
+        results.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(b.0)));
+        results
+    }
+}
+
+/// How well a token matched a fuzzy partial; better matches compare greater.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum FuzzyScore {
+    /// The partial's characters appear in order, with gaps.
+    Subsequence,
+    /// The partial appears contiguously, but not at the start.
+    Substring,
+    Prefix,
+}
+
+/// Score `token` against `partial`, or `None` if `partial` is not a subsequence of it.
+fn fuzzy_score(token: &str, partial: &str) -> Option<FuzzyScore> {
+    if token.starts_with(partial) {
+        return Some(FuzzyScore::Prefix);
+    }
+    if token.contains(partial) {
+        return Some(FuzzyScore::Substring);
+    }
+    let mut token_chars = token.chars();
+    partial
+        .chars()
+        .all(|wanted| token_chars.any(|c| c == wanted))
+        .then_some(FuzzyScore::Subsequence)
+}
+
+const SAVE_MAGIC: &[u8; 4] = b"tli4";
+const SAVE_FORMAT_VERSION: u8 = 1;
+
+/// A trie value that can be written by `Trie::save` and read back by `Trie::load`.
+trait TrieValueCodec: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+    fn decode(input: &mut SaveReader<'_>) -> Result<Self, TrieLoadError>;
+}
+
+impl TrieValueCodec for u32 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn decode(input: &mut SaveReader<'_>) -> Result<Self, TrieLoadError> {
+        input.u32()
+    }
+}
+
+impl TrieValueCodec for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        encode_str(self, out);
+    }
+
+    fn decode(input: &mut SaveReader<'_>) -> Result<Self, TrieLoadError> {
+        input.string()
+    }
+}
+
+#[derive(Debug)]
+enum TrieLoadError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u8),
+    /// The input is truncated or structurally invalid.
+    Corrupt(&'static str),
+}
 
-            if current_idx.is_none() {
-                return Completions::empty(partial, &self.nodes, &self.string_interner);
+impl fmt::Display for TrieLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read saved trie: {err}"),
+            Self::BadMagic => write!(f, "not a saved trie"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported saved trie format version {version}")
             }
+            Self::Corrupt(reason) => write!(f, "corrupt saved trie: {reason}"),
         }
+    }
+}
 
-        let children = match current_idx {
-            None => &self.root.children,
-            Some(node_idx) => &self.nodes[node_idx].children,
+impl std::error::Error for TrieLoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::BadMagic | Self::UnsupportedVersion(_) | Self::Corrupt(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for TrieLoadError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+fn encode_str(s: &str, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Bounds-checked cursor over a saved trie.
+struct SaveReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> SaveReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], TrieLoadError> {
+        if self.bytes.len() < len {
+            return Err(TrieLoadError::Corrupt("unexpected end of input"));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, TrieLoadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, TrieLoadError> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn u64(&mut self) -> Result<u64, TrieLoadError> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// A count of items that each take at least `min_len` bytes, rejected when the rest
+    /// of the input is too short to hold them so that it can safely size an allocation.
+    fn count(&mut self, min_len: usize) -> Result<usize, TrieLoadError> {
+        let count = self.u32()? as usize;
+        if count.saturating_mul(min_len) > self.bytes.len() {
+            return Err(TrieLoadError::Corrupt("count exceeds input"));
+        }
+        Ok(count)
+    }
+
+    fn flag(&mut self) -> Result<bool, TrieLoadError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(TrieLoadError::Corrupt("invalid flag byte")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, TrieLoadError> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| TrieLoadError::Corrupt("invalid utf-8"))
+    }
+}
+
+/// The save format is, little-endian throughout: the magic bytes, a format version
+/// byte, the match mode byte, the interned strings in id order, then the root node
+/// followed by every other node in index order (tombstones included). Each node is
+/// its optional value, optional doc, use count, and `(edge, child index)` pairs.
+impl<V: TrieValueCodec> Trie<V> {
+    pub fn save(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut out = Vec::new();
+        out.extend_from_slice(SAVE_MAGIC);
+        out.push(SAVE_FORMAT_VERSION);
+        out.push(match self.match_mode {
+            MatchMode::CaseSensitive => 0,
+            MatchMode::CaseInsensitive => 1,
+        });
+
+        let strings = &self.string_interner.interned_value_to_string;
+        out.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+        for string in strings {
+            encode_str(string, &mut out);
+        }
+
+        out.extend_from_slice(&(self.nodes.len() as u32).to_le_bytes());
+        for node in std::iter::once(&self.root).chain(self.nodes.iter()) {
+            match &node.value {
+                Some(value) => {
+                    out.push(1);
+                    value.encode(&mut out);
+                }
+                None => out.push(0),
+            }
+            match &node.doc {
+                Some(doc) => {
+                    out.push(1);
+                    encode_str(doc, &mut out);
+                }
+                None => out.push(0),
+            }
+            out.extend_from_slice(&node.uses.to_le_bytes());
+            let children = &node.children;
+            out.extend_from_slice(&(children.len() as u32).to_le_bytes());
+            for (edge, child_idx) in children.iter() {
+                out.extend_from_slice(&edge.to_le_bytes());
+                out.extend_from_slice(&(*child_idx as u32).to_le_bytes());
+            }
+        }
+
+        writer.write_all(&out)
+    }
+
+    pub fn load(mut reader: impl Read) -> Result<Self, TrieLoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let mut input = SaveReader { bytes: &bytes };
+
+        if input.take(SAVE_MAGIC.len()).ok() != Some(SAVE_MAGIC.as_slice()) {
+            return Err(TrieLoadError::BadMagic);
+        }
+        let version = input.u8()?;
+        if version != SAVE_FORMAT_VERSION {
+            return Err(TrieLoadError::UnsupportedVersion(version));
+        }
+        let match_mode = match input.u8()? {
+            0 => MatchMode::CaseSensitive,
+            1 => MatchMode::CaseInsensitive,
+            _ => return Err(TrieLoadError::Corrupt("invalid match mode")),
         };
 
-        Completions {
-            partial,
-            iter: Some(children.iter()),
-            nodes: &self.nodes,
-            interner: &self.string_interner,
+        let mut trie = Trie::with_match_mode(match_mode);
+        // A string is at least its length; a node its two flags, use count and child
+        // count; a child its edge and index.
+        let string_count = input.count(4)?;
+        for expected_id in 0..string_count as u32 {
+            let string = input.string()?;
+            if trie.string_interner.intern(&string) != expected_id {
+                return Err(TrieLoadError::Corrupt("duplicate interned string"));
+            }
+        }
+
+        let node_count = input.count(14)?;
+        let mut has_parent = vec![false; node_count];
+        let mut nodes = Vec::new();
+        for _ in 0..=node_count {
+            let value = if input.flag()? {
+                Some(V::decode(&mut input)?)
+            } else {
+                None
+            };
+            let doc = if input.flag()? {
+                Some(input.string()?)
+            } else {
+                None
+            };
+            let uses = input.u64()?;
+            let child_count = input.count(8)?;
+            let mut children = Children::default();
+            for _ in 0..child_count {
+                let edge = input.u32()?;
+                let child_idx = input.u32()? as usize;
+                if edge as usize >= string_count {
+                    return Err(TrieLoadError::Corrupt("edge refers to unknown string"));
+                }
+                // Every node has at most one parent, so the nodes reachable from the root
+                // form a tree. Unreachable ones, like tombstones, may still link to each
+                // other, but lookups only ever walk down from the root.
+                match has_parent.get_mut(child_idx) {
+                    Some(seen) if !*seen => *seen = true,
+                    _ => return Err(TrieLoadError::Corrupt("invalid child index")),
+                }
+                if children.insert(edge, child_idx).is_some() {
+                    return Err(TrieLoadError::Corrupt("duplicate edge"));
+                }
+            }
+            nodes.push(TrieNode {
+                value,
+                doc,
+                uses,
+                children,
+            });
+        }
+        if !input.bytes.is_empty() {
+            return Err(TrieLoadError::Corrupt("trailing bytes"));
         }
+
+        let mut nodes = nodes.into_iter();
+        trie.root = nodes.next().ok_or(TrieLoadError::Corrupt("missing root"))?;
+        trie.nodes = nodes.collect();
+        Ok(trie)
     }
 }
 
@@ -270,22 +1110,49 @@ mod string_interner_tests {
     }
 
     #[test]
-    fn resolve_returns_original_string_for_valid_id() {
+    fn get_interned_ignore_case_returns_every_casing() {
         let mut interner = StringInterner::new();
-        let alpha = interner.intern("alpha");
-        let beta = interner.intern("beta");
+        let upper = interner.intern("Exit");
+        interner.intern("other");
+        let lower = interner.intern("exit");
 
-        assert_eq!(interner.resolve(alpha), Some("alpha"));
-        assert_eq!(interner.resolve(beta), Some("beta"));
+        assert_eq!(interner.get_interned("EXIT"), None);
+        assert_eq!(interner.get_interned_ignore_case("EXIT"), &[upper, lower]);
+        assert!(interner.get_interned_ignore_case("missing").is_empty());
     }
 
     #[test]
-    fn resolve_returns_none_for_unknown_id() {
+    fn interns_nfc_equal_strings_to_same_id() {
         let mut interner = StringInterner::new();
-        interner.intern("alpha");
+        let composed = interner.intern("caf\u{e9}");
+        let decomposed = interner.intern("cafe\u{301}");
 
-        assert_eq!(interner.resolve(1), None);
-        assert_eq!(interner.resolve(42), None);
+        assert_eq!(composed, decomposed);
+        assert_eq!(interner.resolve(decomposed), Some("caf\u{e9}"));
+        assert_eq!(interner.get_interned("cafe\u{301}"), Some(composed));
+        assert_eq!(
+            interner.get_interned_ignore_case("CAFE\u{301}"),
+            &[composed]
+        );
+    }
+
+    #[test]
+    fn resolve_returns_original_string_for_valid_id() {
+        let mut interner = StringInterner::new();
+        let alpha = interner.intern("alpha");
+        let beta = interner.intern("beta");
+
+        assert_eq!(interner.resolve(alpha), Some("alpha"));
+        assert_eq!(interner.resolve(beta), Some("beta"));
+    }
+
+    #[test]
+    fn resolve_returns_none_for_unknown_id() {
+        let mut interner = StringInterner::new();
+        interner.intern("alpha");
+
+        assert_eq!(interner.resolve(1), None);
+        assert_eq!(interner.resolve(42), None);
     }
 
     #[test]
@@ -304,24 +1171,51 @@ mod string_interner_tests {
     }
 }
 
+#[cfg(test)]
+mod fuzzy_score_tests {
+    use super::*;
+
+    #[test]
+    fn prefix_beats_substring_beats_subsequence() {
+        assert!(FuzzyScore::Prefix > FuzzyScore::Substring);
+        assert!(FuzzyScore::Substring > FuzzyScore::Subsequence);
+    }
+
+    #[test]
+    fn scores_each_kind_of_match() {
+        assert_eq!(fuzzy_score("checkout", "che"), Some(FuzzyScore::Prefix));
+        assert_eq!(fuzzy_score("checkout", "out"), Some(FuzzyScore::Substring));
+        assert_eq!(fuzzy_score("checkout", "cko"), Some(FuzzyScore::Substring));
+        assert_eq!(
+            fuzzy_score("checkout", "cht"),
+            Some(FuzzyScore::Subsequence)
+        );
+        assert_eq!(fuzzy_score("checkout", ""), Some(FuzzyScore::Prefix));
+    }
+
+    #[test]
+    fn rejects_out_of_order_or_missing_characters() {
+        assert_eq!(fuzzy_score("checkout", "tc"), None);
+        assert_eq!(fuzzy_score("checkout", "chz"), None);
+        assert_eq!(fuzzy_score("co", "coo"), None);
+    }
+}
+
 #[cfg(test)]
 mod trie_tests {
     use super::*;
 
-    fn sorted_completions(trie: &Trie, input: &str) -> Vec<(String, Option<TrieNodeValue>)> {
-        let mut results = trie
-            .get_completions(input)
-            .map(|(token, value)| (token.to_string(), value))
-            .collect::<Vec<_>>();
-        results.sort_by(|a, b| a.0.cmp(&b.0));
-        results
+    fn collect_completions(trie: &Trie, input: &str) -> Vec<(String, Option<TrieNodeValue>)> {
+        trie.get_completions(input)
+            .map(|completion| (completion.token.to_string(), completion.value.copied()))
+            .collect()
     }
 
     #[test]
     fn get_returns_inserted_single_token_value() {
         let mut trie = Trie::new();
         trie.add_string("foo", 1);
-        assert_eq!(trie.get("foo"), Some(1));
+        assert_eq!(trie.get("foo"), Some(&1));
     }
 
     #[test]
@@ -339,8 +1233,8 @@ mod trie_tests {
         trie.add_string("foo bar", 10);
         trie.add_string("foo baz", 20);
 
-        assert_eq!(trie.get("foo bar"), Some(10));
-        assert_eq!(trie.get("foo baz"), Some(20));
+        assert_eq!(trie.get("foo bar"), Some(&10));
+        assert_eq!(trie.get("foo baz"), Some(&20));
         assert_eq!(trie.get("foo"), None);
     }
 
@@ -350,8 +1244,8 @@ mod trie_tests {
         trie.add_string("foo", 7);
         trie.add_string("foo bar", 8);
 
-        assert_eq!(trie.get("foo"), Some(7));
-        assert_eq!(trie.get("foo bar"), Some(8));
+        assert_eq!(trie.get("foo"), Some(&7));
+        assert_eq!(trie.get("foo bar"), Some(&8));
     }
 
     #[test]
@@ -360,18 +1254,18 @@ mod trie_tests {
         trie.add_string("foo bar", 3);
         trie.add_string("foo bar", 9);
 
-        assert_eq!(trie.get("foo bar"), Some(9));
+        assert_eq!(trie.get("foo bar"), Some(&9));
     }
 
     #[test]
     fn get_uses_root_value_for_empty_or_whitespace_input() {
         let mut trie = Trie::new();
         trie.add_string("", 42);
-        assert_eq!(trie.get(""), Some(42));
-        assert_eq!(trie.get("   \n\t"), Some(42));
+        assert_eq!(trie.get(""), Some(&42));
+        assert_eq!(trie.get("   \n\t"), Some(&42));
 
         trie.add_string(" ", 99);
-        assert_eq!(trie.get(""), Some(99));
+        assert_eq!(trie.get(""), Some(&99));
     }
 
     #[test]
@@ -381,7 +1275,7 @@ mod trie_tests {
         trie.add_string("foo baz", 2);
         trie.add_string("foo qux", 3);
 
-        let got = sorted_completions(&trie, "foo ba");
+        let got = collect_completions(&trie, "foo ba");
         assert_eq!(
             got,
             vec![("bar".to_string(), Some(1)), ("baz".to_string(), Some(2))]
@@ -395,7 +1289,7 @@ mod trie_tests {
         trie.add_string("foo baz", 2);
         trie.add_string("foo qux", 3);
 
-        let got = sorted_completions(&trie, "foo ");
+        let got = collect_completions(&trie, "foo ");
         assert_eq!(
             got,
             vec![
@@ -411,7 +1305,7 @@ mod trie_tests {
         let mut trie = Trie::new();
         trie.add_string("foo bar", 1);
 
-        let got = sorted_completions(&trie, "unknown ba");
+        let got = collect_completions(&trie, "unknown ba");
         assert!(got.is_empty());
     }
 
@@ -422,10 +1316,953 @@ mod trie_tests {
         trie.add_string("beta two", 2);
         trie.add_string("alphabet three", 3);
 
-        let got = sorted_completions(&trie, "alp");
+        let got = collect_completions(&trie, "alp");
         assert_eq!(
             got,
             vec![("alpha".to_string(), None), ("alphabet".to_string(), None)]
         );
     }
+
+    #[test]
+    fn remove_leaf_prunes_valueless_chain() {
+        let mut trie = Trie::new();
+        trie.add_string("foo bar baz", 1);
+        trie.add_string("qux", 2);
+
+        assert_eq!(trie.remove("foo bar baz"), Some(1));
+        assert_eq!(trie.get("foo bar baz"), None);
+        assert_eq!(
+            collect_completions(&trie, ""),
+            vec![("qux".to_string(), Some(2))]
+        );
+        assert!(collect_completions(&trie, "foo ").is_empty());
+    }
+
+    #[test]
+    fn remove_internal_node_keeps_longer_key() {
+        let mut trie = Trie::new();
+        trie.add_string("foo", 7);
+        trie.add_string("foo bar", 8);
+
+        assert_eq!(trie.remove("foo"), Some(7));
+        assert_eq!(trie.get("foo"), None);
+        assert_eq!(trie.get("foo bar"), Some(&8));
+        assert_eq!(
+            collect_completions(&trie, "fo"),
+            vec![("foo".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn remove_prunes_only_up_to_shared_prefix() {
+        let mut trie = Trie::new();
+        trie.add_string("show accounts", 1);
+        trie.add_string("show version", 2);
+        trie.add_string("show", 3);
+
+        assert_eq!(trie.remove("show accounts"), Some(1));
+        assert_eq!(
+            collect_completions(&trie, "show "),
+            vec![("version".to_string(), Some(2))]
+        );
+
+        assert_eq!(trie.remove("show version"), Some(2));
+        assert_eq!(
+            collect_completions(&trie, ""),
+            vec![("show".to_string(), Some(3))]
+        );
+
+        assert_eq!(trie.remove("show"), Some(3));
+        assert!(collect_completions(&trie, "").is_empty());
+    }
+
+    #[test]
+    fn remove_missing_or_valueless_key_returns_none() {
+        let mut trie = Trie::new();
+        trie.add_string("foo bar", 1);
+
+        assert_eq!(trie.remove("foo"), None);
+        assert_eq!(trie.remove("foo baz"), None);
+        assert_eq!(trie.remove("unknown"), None);
+        assert_eq!(trie.get("foo bar"), Some(&1));
+    }
+
+    #[test]
+    fn remove_root_value_and_reinsert() {
+        let mut trie = Trie::new();
+        trie.add_string("", 42);
+        trie.add_string("foo", 1);
+
+        assert_eq!(trie.remove(""), Some(42));
+        assert_eq!(trie.get(""), None);
+        assert_eq!(trie.remove("foo"), Some(1));
+
+        trie.add_string("foo", 5);
+        assert_eq!(trie.get("foo"), Some(&5));
+    }
+
+    #[test]
+    fn iter_lists_entries_depth_first_in_token_order() {
+        let mut trie = Trie::new();
+        trie.add_string("show version", 4);
+        trie.add_string("write", 5);
+        trie.add_string("show", 1);
+        trie.add_string("show accounts json", 3);
+        trie.add_string("show accounts", 2);
+        trie.add_string("", 0);
+
+        let got = trie.iter().collect::<Vec<_>>();
+        assert_eq!(
+            got,
+            vec![
+                ("".to_string(), &0),
+                ("show".to_string(), &1),
+                ("show accounts".to_string(), &2),
+                ("show accounts json".to_string(), &3),
+                ("show version".to_string(), &4),
+                ("write".to_string(), &5),
+            ]
+        );
+    }
+
+    #[test]
+    fn iter_skips_removed_entries() {
+        let mut trie = Trie::new();
+        trie.add_string("foo bar", 1);
+        trie.add_string("foo baz", 2);
+        trie.remove("foo bar");
+
+        assert_eq!(
+            trie.iter().collect::<Vec<_>>(),
+            vec![("foo baz".to_string(), &2)]
+        );
+        assert_eq!(Trie::<u32>::new().iter().count(), 0);
+    }
+
+    #[test]
+    fn case_sensitive_mode_is_the_default() {
+        let mut trie = Trie::new();
+        trie.add_string("exit", 1);
+        trie.add_string("Show Accounts", 2);
+
+        assert!(collect_completions(&trie, "Ex").is_empty());
+        assert_eq!(trie.get("EXIT"), None);
+        assert_eq!(trie.get("Show Accounts"), Some(&2));
+        assert_eq!(trie.get("show accounts"), None);
+    }
+
+    #[test]
+    fn case_insensitive_mode_keeps_registered_casing() {
+        let mut trie = Trie::with_match_mode(MatchMode::CaseInsensitive);
+        trie.add_string("exit", 1);
+        trie.add_string("Show Accounts", 2);
+        trie.add_string("Show Version", 3);
+
+        assert_eq!(
+            collect_completions(&trie, "Ex"),
+            vec![("exit".to_string(), Some(1))]
+        );
+        assert_eq!(
+            collect_completions(&trie, "SHOW a"),
+            vec![("Accounts".to_string(), Some(2))]
+        );
+        assert_eq!(trie.get("EXIT"), Some(&1));
+        assert_eq!(trie.get("show version"), Some(&3));
+        assert_eq!(trie.remove("show ACCOUNTS"), Some(2));
+        assert_eq!(trie.get("Show Accounts"), None);
+    }
+
+    #[test]
+    fn case_insensitive_get_prefers_exact_casing() {
+        let mut trie = Trie::with_match_mode(MatchMode::CaseInsensitive);
+        trie.add_string("Exit", 1);
+        trie.add_string("exit", 2);
+
+        assert_eq!(trie.get("exit"), Some(&2));
+        assert_eq!(trie.get("Exit"), Some(&1));
+        assert_eq!(trie.get("EXIT"), Some(&1));
+        assert_eq!(
+            collect_completions(&trie, "E"),
+            vec![("Exit".to_string(), Some(1)), ("exit".to_string(), Some(2))]
+        );
+    }
+
+    #[test]
+    fn get_completions_fuzzy_ranks_by_score_then_token() {
+        let mut trie = Trie::new();
+        trie.add_string("git checkout", 1);
+        trie.add_string("git commit", 2);
+        trie.add_string("git cherry-pick", 3);
+        trie.add_string("git config", 4);
+        trie.add_string("git reco", 5);
+
+        let got = trie.get_completions_fuzzy("git co");
+        assert_eq!(
+            got,
+            vec![
+                ("commit", Some(&2), FuzzyScore::Prefix),
+                ("config", Some(&4), FuzzyScore::Prefix),
+                ("reco", Some(&5), FuzzyScore::Substring),
+                ("checkout", Some(&1), FuzzyScore::Subsequence),
+            ]
+        );
+    }
+
+    #[test]
+    fn get_completions_fuzzy_keeps_earlier_tokens_strict() {
+        let mut trie = Trie::new();
+        trie.add_string("git checkout", 1);
+
+        assert!(trie.get_completions_fuzzy("gt co").is_empty());
+        assert!(trie.get_completions_fuzzy("git xyz").is_empty());
+        assert_eq!(
+            trie.get_completions_fuzzy("git "),
+            vec![("checkout", Some(&1), FuzzyScore::Prefix)]
+        );
+    }
+
+    #[test]
+    fn get_completions_fuzzy_follows_match_mode() {
+        let mut trie = Trie::with_match_mode(MatchMode::CaseInsensitive);
+        trie.add_string("CheckOut", 1);
+
+        assert_eq!(
+            trie.get_completions_fuzzy("cko"),
+            vec![("CheckOut", Some(&1), FuzzyScore::Substring)]
+        );
+        assert!(Trie::<u32>::new().get_completions_fuzzy("cko").is_empty());
+    }
+
+    #[test]
+    fn longest_match_full_match() {
+        let mut trie = Trie::new();
+        trie.add_string("show accounts", 1);
+
+        assert_eq!(
+            trie.longest_match("  show   accounts "),
+            Some(MatchResult {
+                matched_tokens: 2,
+                value: Some(&1),
+                remaining: "",
+            })
+        );
+    }
+
+    #[test]
+    fn longest_match_partial_match_keeps_deepest_value() {
+        let mut trie = Trie::new();
+        trie.add_string("show", 1);
+        trie.add_string("show accounts format csv", 2);
+
+        assert_eq!(
+            trie.longest_match("show accounts extra  words"),
+            Some(MatchResult {
+                matched_tokens: 2,
+                value: Some(&1),
+                remaining: "extra  words",
+            })
+        );
+        assert_eq!(
+            trie.longest_match("show accounts format"),
+            Some(MatchResult {
+                matched_tokens: 3,
+                value: Some(&1),
+                remaining: "",
+            })
+        );
+    }
+
+    #[test]
+    fn longest_match_without_match() {
+        let mut trie = Trie::new();
+        trie.add_string("show accounts", 1);
+
+        assert_eq!(trie.longest_match("write accounts"), None);
+        assert_eq!(trie.longest_match("   "), None);
+        assert_eq!(
+            trie.longest_match("show"),
+            Some(MatchResult {
+                matched_tokens: 1,
+                value: None,
+                remaining: "",
+            })
+        );
+    }
+
+    #[test]
+    fn longest_match_falls_back_to_root_value() {
+        let mut trie = Trie::new();
+        trie.add_string("", 9);
+        trie.add_string("show accounts", 1);
+
+        assert_eq!(
+            trie.longest_match(" \t "),
+            Some(MatchResult {
+                matched_tokens: 0,
+                value: Some(&9),
+                remaining: "",
+            })
+        );
+        assert_eq!(
+            trie.longest_match("unknown command"),
+            Some(MatchResult {
+                matched_tokens: 0,
+                value: Some(&9),
+                remaining: "unknown command",
+            })
+        );
+    }
+
+    #[test]
+    fn describe_path_fully_matched_actionable() {
+        let mut trie = Trie::new();
+        trie.add_string_with_doc("git commit", 1, "record changes");
+        trie.add_string("git commit amend", 2);
+
+        let path = trie.describe_path("git  commit");
+        assert_eq!(path.tokens, 2);
+        assert_eq!(path.matched_tokens, 2);
+        assert!(path.token_matched(0) && path.token_matched(1));
+        assert_eq!(
+            path.node,
+            Some(PathNode {
+                actionable: true,
+                children: 1,
+                doc: Some("record changes"),
+            })
+        );
+        assert!(!path.is_dead_end());
+
+        let path = trie.describe_path("git ");
+        assert_eq!(
+            path.node,
+            Some(PathNode {
+                actionable: false,
+                children: 1,
+                doc: None,
+            })
+        );
+
+        let path = trie.describe_path("");
+        assert_eq!((path.tokens, path.matched_tokens), (0, 0));
+        assert_eq!(path.node.map(|node| node.children), Some(1));
+    }
+
+    #[test]
+    fn describe_path_dead_end() {
+        let mut trie = Trie::new();
+        trie.add_string("git commit", 1);
+
+        let path = trie.describe_path("git push origin");
+        assert_eq!(path.tokens, 3);
+        assert_eq!(path.matched_tokens, 1);
+        assert!(path.token_matched(0));
+        assert!(!path.token_matched(1) && !path.token_matched(2));
+        assert_eq!(path.node, None);
+        assert_eq!(path.partial_candidates, 0);
+        assert!(path.is_dead_end());
+
+        // A complete but unknown final token is a dead end too.
+        assert!(trie.describe_path("git com ").is_dead_end());
+        assert!(trie.describe_path("svn").is_dead_end());
+    }
+
+    #[test]
+    fn describe_path_partial_final_token() {
+        let mut trie = Trie::with_match_mode(MatchMode::CaseInsensitive);
+        trie.add_string("git commit", 1);
+        trie.add_string("git config", 2);
+        trie.add_string("git checkout", 3);
+
+        let path = trie.describe_path("git co");
+        assert_eq!((path.tokens, path.matched_tokens), (2, 1));
+        assert_eq!(path.node, None);
+        assert_eq!(path.partial_candidates, 2);
+        assert!(!path.is_dead_end());
+
+        assert_eq!(trie.describe_path("GIT C").partial_candidates, 3);
+        assert_eq!(trie.describe_path("git x").partial_candidates, 0);
+        assert_eq!(trie.describe_path("gi commit").partial_candidates, 0);
+    }
+
+    #[test]
+    fn get_completions_order_is_sorted_and_repeatable() {
+        let mut trie = Trie::new();
+        for token in [
+            "zeta", "alpha", "mu", "beta", "omega", "delta", "kappa", "gamma",
+        ] {
+            trie.add_string(&format!("cmd {token}"), 0);
+        }
+
+        let first = trie.get_completions("cmd ").collect::<Vec<_>>();
+        let second = trie.get_completions("cmd ").collect::<Vec<_>>();
+
+        assert_eq!(first, second);
+        assert_eq!(
+            first
+                .iter()
+                .map(|completion| completion.token)
+                .collect::<Vec<_>>(),
+            vec![
+                "alpha", "beta", "delta", "gamma", "kappa", "mu", "omega", "zeta"
+            ]
+        );
+    }
+
+    #[test]
+    fn get_deep_completions_expands_linear_chain() {
+        let mut trie = Trie::new();
+        trie.add_string("git commit amend", 1);
+        trie.add_string("git status", 2);
+
+        assert_eq!(
+            trie.get_deep_completions("git c"),
+            vec![("commit amend".to_string(), Some(&1))]
+        );
+        assert_eq!(
+            trie.get_deep_completions("gi"),
+            vec![("git".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn get_deep_completions_stops_at_branch() {
+        let mut trie = Trie::new();
+        trie.add_string("remote add origin", 1);
+        trie.add_string("remote remove origin", 2);
+
+        assert_eq!(
+            trie.get_deep_completions(""),
+            vec![("remote".to_string(), None)]
+        );
+        assert_eq!(
+            trie.get_deep_completions("remote "),
+            vec![
+                ("add origin".to_string(), Some(&1)),
+                ("remove origin".to_string(), Some(&2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn get_deep_completions_stops_at_valued_node() {
+        let mut trie = Trie::new();
+        trie.add_string("stash", 1);
+        trie.add_string("stash pop now", 2);
+
+        assert_eq!(
+            trie.get_deep_completions("st"),
+            vec![("stash".to_string(), Some(&1))]
+        );
+        assert_eq!(
+            trie.get_deep_completions("stash "),
+            vec![("pop now".to_string(), Some(&2))]
+        );
+        assert!(trie.get_deep_completions("unknown ").is_empty());
+    }
+
+    #[test]
+    fn stores_non_clone_values() {
+        struct Entry {
+            description: String,
+            handler: Box<dyn Fn(u32) -> u32>,
+        }
+
+        let mut trie = Trie::new();
+        trie.add_string(
+            "double",
+            Entry {
+                description: "double the input".to_string(),
+                handler: Box::new(|n| n * 2),
+            },
+        );
+        trie.add_string(
+            "double twice",
+            Entry {
+                description: "quadruple the input".to_string(),
+                handler: Box::new(|n| n * 4),
+            },
+        );
+
+        let entry = trie.get("double").expect("entry should exist");
+        assert_eq!(entry.description, "double the input");
+        assert_eq!((entry.handler)(21), 42);
+
+        let completion = trie.get_completions("double t").next().expect("completion");
+        assert_eq!(completion.token, "twice");
+        assert_eq!((completion.value.expect("valued").handler)(2), 8);
+
+        let removed = trie.remove("double twice").expect("removed entry");
+        assert_eq!(removed.description, "quadruple the input");
+    }
+
+    fn completion_docs<'a>(trie: &'a Trie, input: &str) -> Vec<(&'a str, Option<&'a str>)> {
+        trie.get_completions(input)
+            .map(|completion| (completion.token, completion.doc))
+            .collect()
+    }
+
+    #[test]
+    fn completions_surface_docs_for_terminals_only() {
+        let mut trie = Trie::new();
+        trie.add_string_with_doc("show accounts", 1, "list accounts");
+        trie.add_string("show version", 2);
+        trie.add_string_with_doc("set value", 3, "set a value");
+
+        assert_eq!(
+            completion_docs(&trie, "show "),
+            vec![("accounts", Some("list accounts")), ("version", None)]
+        );
+        assert_eq!(
+            completion_docs(&trie, "s"),
+            vec![("set", None), ("show", None)]
+        );
+    }
+
+    #[test]
+    fn get_entry_returns_value_and_doc() {
+        let mut trie = Trie::new();
+        trie.add_string_with_doc("exit", 1, "leave the repl");
+        trie.add_string("quit", 2);
+        trie.add_string("show accounts", 3);
+
+        assert_eq!(trie.get_entry("exit"), Some((&1, Some("leave the repl"))));
+        assert_eq!(trie.get_entry("quit"), Some((&2, None)));
+        assert_eq!(trie.get_entry("show"), None);
+
+        trie.add_string("exit", 4);
+        assert_eq!(trie.get_entry("exit"), Some((&4, None)));
+        trie.add_string_with_doc("quit", 5, "leave the repl");
+        assert_eq!(trie.remove("quit"), Some(5));
+        trie.add_string("quit", 6);
+        assert_eq!(trie.get_entry("quit"), Some((&6, None)));
+    }
+
+    #[test]
+    fn count_and_contains_prefix_at_root() {
+        let mut trie = Trie::new();
+        trie.add_string("", 0);
+        trie.add_string("show accounts", 1);
+        trie.add_string("show version", 2);
+        trie.add_string("set", 3);
+
+        assert_eq!(trie.count_with_prefix(""), 3);
+        assert_eq!(trie.count_with_prefix("s"), 3);
+        assert_eq!(trie.count_with_prefix("sh"), 2);
+        assert!(trie.contains_prefix("se"));
+        assert_eq!(Trie::<u32>::new().count_with_prefix(""), 0);
+        assert!(!Trie::<u32>::new().contains_prefix(""));
+    }
+
+    #[test]
+    fn count_and_contains_prefix_mid_tree() {
+        let mut trie = Trie::new();
+        trie.add_string("show", 1);
+        trie.add_string("show accounts", 2);
+        trie.add_string("show accounts format csv", 3);
+        trie.add_string("show version", 4);
+
+        assert_eq!(trie.count_with_prefix("show"), 4);
+        assert_eq!(trie.count_with_prefix("show "), 3);
+        assert_eq!(trie.count_with_prefix("show acc"), 2);
+        assert_eq!(trie.count_with_prefix("show accounts format "), 1);
+        assert!(trie.contains_prefix("show v"));
+    }
+
+    #[test]
+    fn count_and_contains_prefix_when_absent() {
+        let mut trie = Trie::new();
+        trie.add_string("show accounts", 1);
+
+        assert_eq!(trie.count_with_prefix("write "), 0);
+        assert_eq!(trie.count_with_prefix("show x"), 0);
+        assert_eq!(trie.count_with_prefix("show accounts "), 0);
+        assert!(!trie.contains_prefix("show x"));
+        assert!(!trie.contains_prefix("unknown path "));
+
+        trie.remove("show accounts");
+        assert!(!trie.contains_prefix("sh"));
+    }
+
+    fn ranked_tokens<'a>(trie: &'a Trie, input: &str) -> Vec<(&'a str, u64)> {
+        let mut completions = trie.get_completions(input).collect::<Vec<_>>();
+        completions.sort_by(Completion::cmp_by_weight);
+        completions
+            .into_iter()
+            .map(|completion| (completion.token, completion.weight))
+            .collect()
+    }
+
+    #[test]
+    fn record_use_reorders_weighted_completions() {
+        let mut trie = Trie::new();
+        trie.add_string("show accounts", 1);
+        trie.add_string("show version", 2);
+        trie.add_string("set", 3);
+
+        assert_eq!(
+            ranked_tokens(&trie, "show "),
+            vec![("accounts", 0), ("version", 0)]
+        );
+
+        assert!(trie.record_use("show version"));
+        assert!(trie.record_use("show version"));
+        assert!(trie.record_use("show accounts"));
+        assert!(trie.record_use("set"));
+        assert!(!trie.record_use("show"));
+        assert!(!trie.record_use("unknown"));
+
+        assert_eq!(
+            ranked_tokens(&trie, "show "),
+            vec![("version", 2), ("accounts", 1)]
+        );
+        assert_eq!(ranked_tokens(&trie, "s"), vec![("show", 3), ("set", 1)]);
+    }
+
+    #[test]
+    fn weights_survive_re_adding_and_decay() {
+        let mut trie = Trie::new();
+        trie.add_string("show accounts", 1);
+        trie.add_string("show version", 2);
+        for _ in 0..4 {
+            trie.record_use("show accounts");
+        }
+        trie.record_use("show version");
+
+        trie.add_string_with_doc("show accounts", 10, "list accounts");
+        assert_eq!(
+            ranked_tokens(&trie, "show "),
+            vec![("accounts", 4), ("version", 1)]
+        );
+
+        trie.decay(0.5);
+        assert_eq!(
+            ranked_tokens(&trie, "show "),
+            vec![("accounts", 2), ("version", 0)]
+        );
+    }
+
+    fn populated_trie() -> Trie {
+        let mut trie = Trie::with_match_mode(MatchMode::CaseInsensitive);
+        trie.add_string("", 0);
+        trie.add_string_with_doc("show accounts", 1, "list accounts");
+        trie.add_string("show version", 2);
+        trie.add_string("Set value", 3);
+        trie.add_string("removed entry", 4);
+        trie.remove("removed entry");
+        trie.record_use("show accounts");
+        trie.record_use("show accounts");
+        trie
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let trie = populated_trie();
+        let mut saved = Vec::new();
+        trie.save(&mut saved).expect("save trie");
+
+        let loaded = Trie::<u32>::load(saved.as_slice()).expect("load trie");
+
+        assert_eq!(
+            loaded.iter().collect::<Vec<_>>(),
+            trie.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            loaded.get_entry("show accounts"),
+            Some((&1, Some("list accounts")))
+        );
+        assert_eq!(loaded.get("SET VALUE"), Some(&3));
+        assert_eq!(
+            ranked_tokens(&loaded, "show "),
+            vec![("accounts", 2), ("version", 0)]
+        );
+        assert_eq!(loaded.get("removed entry"), None);
+
+        let mut resaved = Vec::new();
+        loaded.save(&mut resaved).expect("save loaded trie");
+        assert_eq!(resaved, saved);
+    }
+
+    #[test]
+    fn save_and_load_string_values() {
+        let mut trie = Trie::new();
+        trie.add_string("open file", "opener".to_string());
+
+        let mut saved = Vec::new();
+        trie.save(&mut saved).expect("save trie");
+        let loaded = Trie::<String>::load(saved.as_slice()).expect("load trie");
+
+        assert_eq!(loaded.get("open file").map(String::as_str), Some("opener"));
+    }
+
+    #[test]
+    fn load_rejects_truncated_input() {
+        let mut saved = Vec::new();
+        populated_trie().save(&mut saved).expect("save trie");
+
+        for len in 0..saved.len() {
+            assert!(
+                Trie::<u32>::load(&saved[..len]).is_err(),
+                "truncated to {len} bytes should fail"
+            );
+        }
+    }
+
+    #[test]
+    fn load_rejects_bad_header_and_structure() {
+        let mut saved = Vec::new();
+        populated_trie().save(&mut saved).expect("save trie");
+
+        let mut bad_version = saved.clone();
+        bad_version[4] = 99;
+        assert!(matches!(
+            Trie::<u32>::load(bad_version.as_slice()),
+            Err(TrieLoadError::UnsupportedVersion(99))
+        ));
+        assert!(matches!(
+            Trie::<u32>::load(&b"nope"[..]),
+            Err(TrieLoadError::BadMagic)
+        ));
+
+        let mut trailing = saved.clone();
+        trailing.push(0);
+        assert!(matches!(
+            Trie::<u32>::load(trailing.as_slice()),
+            Err(TrieLoadError::Corrupt("trailing bytes"))
+        ));
+
+        // The root and node 0 both list node 0 as a child, which would form a cycle.
+        let mut cyclic = Vec::new();
+        cyclic.extend_from_slice(SAVE_MAGIC);
+        cyclic.extend_from_slice(&[SAVE_FORMAT_VERSION, 0]);
+        cyclic.extend_from_slice(&1u32.to_le_bytes());
+        encode_str("loop", &mut cyclic);
+        cyclic.extend_from_slice(&1u32.to_le_bytes());
+        for _ in 0..2 {
+            cyclic.extend_from_slice(&[0, 0]);
+            cyclic.extend_from_slice(&0u64.to_le_bytes());
+            cyclic.extend_from_slice(&1u32.to_le_bytes());
+            cyclic.extend_from_slice(&0u32.to_le_bytes());
+            cyclic.extend_from_slice(&0u32.to_le_bytes());
+        }
+        assert!(matches!(
+            Trie::<u32>::load(cyclic.as_slice()),
+            Err(TrieLoadError::Corrupt("invalid child index"))
+        ));
+
+        // A node count far beyond what the input holds fails before allocating for it.
+        let mut oversized = Vec::new();
+        oversized.extend_from_slice(SAVE_MAGIC);
+        oversized.extend_from_slice(&[SAVE_FORMAT_VERSION, 0]);
+        oversized.extend_from_slice(&0u32.to_le_bytes());
+        oversized.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            Trie::<u32>::load(oversized.as_slice()),
+            Err(TrieLoadError::Corrupt("count exceeds input"))
+        ));
+        let string_count = SAVE_MAGIC.len() + 2;
+        oversized[string_count..string_count + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            Trie::<u32>::load(oversized.as_slice()),
+            Err(TrieLoadError::Corrupt("count exceeds input"))
+        ));
+    }
+
+    #[test]
+    fn wildcard_matches_any_single_token() {
+        let mut trie = Trie::new();
+        trie.add_string("open <*>", 1);
+        trie.add_string("copy <*> <*>", 2);
+
+        assert_eq!(trie.get("open notes.txt"), Some(&1));
+        assert_eq!(trie.get("open"), None);
+        assert_eq!(trie.get("open a b"), None);
+        assert_eq!(trie.get("copy a.txt b.txt"), Some(&2));
+        assert_eq!(trie.get("copy a.txt"), None);
+        assert_eq!(
+            trie.longest_match("copy a b extra"),
+            Some(MatchResult {
+                matched_tokens: 3,
+                value: Some(&2),
+                remaining: "extra",
+            })
+        );
+    }
+
+    #[test]
+    fn literal_children_take_precedence_over_wildcard() {
+        let mut trie = Trie::new();
+        trie.add_string("open <*>", 1);
+        trie.add_string("open recent", 2);
+        trie.add_string("open <*> readonly", 3);
+
+        assert_eq!(trie.get("open recent"), Some(&2));
+        assert_eq!(trie.get("open other"), Some(&1));
+        assert_eq!(trie.get("open other readonly"), Some(&3));
+        // Greedy: "recent" takes the literal branch, which has no "readonly".
+        assert_eq!(trie.get("open recent readonly"), None);
+
+        assert!(trie.record_use("open other"));
+        assert_eq!(trie.remove("open other"), None);
+        assert_eq!(trie.remove("open <*>"), Some(1));
+        assert_eq!(trie.get("open other"), None);
+        assert_eq!(trie.get("open other readonly"), Some(&3));
+    }
+
+    #[test]
+    fn completions_at_wildcard_yield_argument_placeholder() {
+        let mut trie = Trie::new();
+        trie.add_string_with_doc("open <*>", 1, "file to open");
+        trie.add_string("open recent", 2);
+        trie.add_string("copy <*> <*>", 3);
+
+        let placeholders = |input: &str| {
+            trie.get_completions(input)
+                .map(|completion| (completion.token, completion.is_argument, completion.doc))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            placeholders("open "),
+            vec![("<*>", true, Some("file to open")), ("recent", false, None)]
+        );
+        assert_eq!(
+            placeholders("open no"),
+            vec![("<*>", true, Some("file to open"))]
+        );
+        assert_eq!(placeholders("copy a.txt "), vec![("<*>", true, None)]);
+    }
+
+    #[test]
+    fn composed_and_decomposed_keys_are_equal() {
+        let mut trie = Trie::new();
+        trie.add_string("order caf\u{e9}", 1);
+        trie.add_string("order cafe\u{301} now", 2);
+
+        assert_eq!(trie.get("order cafe\u{301}"), Some(&1));
+        assert_eq!(trie.get("order caf\u{e9} now"), Some(&2));
+        assert_eq!(
+            collect_completions(&trie, "order cafe\u{301}"),
+            vec![("caf\u{e9}".to_string(), Some(1))]
+        );
+        assert_eq!(trie.iter().count(), 2);
+    }
+
+    #[test]
+    fn no_break_and_zero_width_spaces_separate_tokens() {
+        let mut trie = Trie::new();
+        trie.add_string("show accounts", 1);
+
+        assert_eq!(trie.get("show\u{a0}accounts"), Some(&1));
+        assert_eq!(trie.get("show\u{202f}accounts"), Some(&1));
+        assert_eq!(trie.get("show\u{200b}accounts\u{feff}"), Some(&1));
+        assert_eq!(
+            collect_completions(&trie, "show\u{a0}"),
+            vec![("accounts".to_string(), Some(1))]
+        );
+        assert_eq!(
+            trie.longest_match("show\u{a0}accounts\u{200b}extra"),
+            Some(MatchResult {
+                matched_tokens: 2,
+                value: Some(&1),
+                remaining: "extra",
+            })
+        );
+    }
+
+    /// Deterministic phrases of one to four tokens drawn from a small vocabulary, so
+    /// most nodes end up with only a few children.
+    fn generated_phrases(count: usize) -> Vec<String> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        (0..count)
+            .map(|i| {
+                let len = 1 + (next() % 4) as usize;
+                let mut tokens = vec![format!("cmd{}", i % 5_000)];
+                tokens.extend((1..len).map(|_| format!("w{}", next() % 64)));
+                tokens.join(" ")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn generated_phrases_match_reference_map() {
+        let phrases = generated_phrases(2_000);
+        let mut trie = Trie::new();
+        let mut reference = std::collections::BTreeMap::new();
+        for (value, phrase) in phrases.iter().enumerate() {
+            trie.add_string(phrase, value as u32);
+            reference.insert(phrase.clone(), value as u32);
+        }
+
+        for (phrase, value) in &reference {
+            assert_eq!(trie.get(phrase), Some(value), "{phrase}");
+        }
+        let entries = trie
+            .iter()
+            .map(|(key, value)| (key, *value))
+            .collect::<Vec<_>>();
+        let mut expected = reference.into_iter().collect::<Vec<_>>();
+        expected.sort_by(|a, b| {
+            let a_tokens = a.0.split(' ').collect::<Vec<_>>();
+            let b_tokens = b.0.split(' ').collect::<Vec<_>>();
+            a_tokens.cmp(&b_tokens)
+        });
+        assert_eq!(entries, expected);
+
+        let completions = collect_completions(&trie, "cmd12");
+        let tokens = completions
+            .iter()
+            .map(|(token, _)| token.as_str())
+            .collect::<Vec<_>>();
+        let mut sorted = tokens.clone();
+        sorted.sort();
+        assert_eq!(tokens, sorted);
+        assert!(tokens.contains(&"cmd12") && tokens.contains(&"cmd1234"));
+    }
+
+    /// Timing harness rather than a correctness test; run with
+    /// `cargo test --release -- --ignored --nocapture trie_throughput`.
+    #[test]
+    #[ignore]
+    fn trie_throughput() {
+        use std::time::Instant;
+
+        let phrases = generated_phrases(50_000);
+        let start = Instant::now();
+        let mut trie = Trie::new();
+        for (value, phrase) in phrases.iter().enumerate() {
+            trie.add_string(phrase, value as u32);
+        }
+        let build = start.elapsed();
+
+        let start = Instant::now();
+        let mut found = 0;
+        for _ in 0..10 {
+            found += phrases
+                .iter()
+                .filter(|phrase| trie.get(phrase).is_some())
+                .count();
+        }
+        let lookups = start.elapsed();
+
+        let start = Instant::now();
+        let mut offered = 0;
+        for i in 0..50_000 {
+            offered += trie.get_completions(&format!("cmd{} w", i % 5_000)).count();
+        }
+        let completions = start.elapsed();
+
+        assert_eq!(found, 10 * phrases.len());
+        assert!(offered > 0);
+        println!(
+            "build {build:?}; 500k lookups {lookups:?}; 50k completions {completions:?} \
+             ({} nodes)",
+            trie.nodes.len()
+        );
+    }
 }
@@ -0,0 +1,233 @@
+use crate::repl::quote_token;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum VarError {
+    InvalidName(String),
+    NotFound(String),
+    Undefined(String),
+}
+
+impl fmt::Display for VarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidName(name) => write!(f, "invalid variable name '{}'", name),
+            Self::NotFound(name) => write!(f, "no variable named '{}'", name),
+            Self::Undefined(name) => write!(f, "undefined variable '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for VarError {}
+
+/// A command line after variable substitution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Expansion {
+    pub(crate) line: String,
+    /// Variables that were referenced but not set, in order of use.
+    pub(crate) undefined: Vec<String>,
+}
+
+/// Named strings substituted for `$name` and `${name}` in command lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Variables {
+    values: BTreeMap<String, String>,
+}
+
+impl Variables {
+    pub(crate) fn set(&mut self, name: &str, value: String) -> Result<(), VarError> {
+        if !is_valid_name(name) {
+            return Err(VarError::InvalidName(name.to_string()));
+        }
+        self.values.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> Result<(), VarError> {
+        self.values
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| VarError::NotFound(name.to_string()))
+    }
+
+    /// Variable names, sorted.
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+
+    /// One `name = value` line per variable, sorted by name.
+    pub(crate) fn format(&self) -> String {
+        self.values
+            .iter()
+            .map(|(name, value)| format!("{} = {}\n", name, quote_token(value)))
+            .collect()
+    }
+
+    /// Substitute variables in `line`, leaving single-quoted text and
+    /// backslash-escaped `$` alone. Undefined variables expand to nothing.
+    ///
+    /// Values are inserted as typed, so a value containing spaces becomes
+    /// several tokens unless the reference is inside double quotes.
+    pub(crate) fn expand(&self, line: &str) -> Expansion {
+        let mut out = String::with_capacity(line.len());
+        let mut undefined = Vec::new();
+        let mut quote = None;
+        let mut idx = 0;
+
+        while let Some(c) = line[idx..].chars().next() {
+            idx += c.len_utf8();
+            match (quote, c) {
+                (Some(open), _) if open == c => quote = None,
+                (None, '\'' | '"') => quote = Some(c),
+                (Some('\''), _) => {}
+                (_, '\\') => {
+                    out.push(c);
+                    if let Some(next) = line[idx..].chars().next() {
+                        idx += next.len_utf8();
+                        out.push(next);
+                    }
+                    continue;
+                }
+                (_, '$') => {
+                    if let Some((name, len)) = reference_at(&line[idx..]) {
+                        idx += len;
+                        match self.values.get(name) {
+                            Some(value) => out.push_str(value),
+                            None => undefined.push(name.to_string()),
+                        }
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            out.push(c);
+        }
+
+        Expansion {
+            line: out,
+            undefined,
+        }
+    }
+}
+
+/// The variable named at the start of `text`, which follows a `$`, and how
+/// many bytes the reference takes up.
+fn reference_at(text: &str) -> Option<(&str, usize)> {
+    if let Some(braced) = text.strip_prefix('{') {
+        let end = braced.find('}')?;
+        let name = &braced[..end];
+        return is_valid_name(name).then_some((name, end + 2));
+    }
+
+    let end = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    let name = &text[..end];
+    is_valid_name(name).then_some((name, end))
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(pairs: &[(&str, &str)]) -> Variables {
+        let mut variables = Variables::default();
+        for (name, value) in pairs {
+            variables.set(name, value.to_string()).unwrap();
+        }
+        variables
+    }
+
+    fn expanded(variables: &Variables, line: &str) -> String {
+        variables.expand(line).line
+    }
+
+    #[test]
+    fn expand_substitutes_plain_and_braced_references() {
+        let variables = variables(&[("host", "db1"), ("port", "5432")]);
+
+        assert_eq!(expanded(&variables, "ping $host"), "ping db1");
+        assert_eq!(
+            expanded(&variables, "connect ${host}:$port"),
+            "connect db1:5432"
+        );
+        assert_eq!(expanded(&variables, "x${host}y"), "xdb1y");
+        assert_eq!(expanded(&variables, "$host-$port."), "db1-5432.");
+    }
+
+    #[test]
+    fn expand_respects_quotes_and_escapes() {
+        let variables = variables(&[("name", "ada lovelace")]);
+
+        assert_eq!(
+            expanded(&variables, "greet \"$name\""),
+            "greet \"ada lovelace\""
+        );
+        assert_eq!(expanded(&variables, "greet '$name'"), "greet '$name'");
+        assert_eq!(
+            expanded(&variables, "greet '\"$name\"' \"'$name'\""),
+            "greet '\"$name\"' \"'ada lovelace'\""
+        );
+        assert_eq!(expanded(&variables, "greet \\$name"), "greet \\$name");
+        assert_eq!(expanded(&variables, "greet $name"), "greet ada lovelace");
+    }
+
+    #[test]
+    fn expand_leaves_non_references_alone() {
+        let variables = variables(&[("a", "1")]);
+
+        for line in [
+            "cost $",
+            "cost $5",
+            "cost ${",
+            "cost ${a",
+            "cost ${1}",
+            "$ $-",
+        ] {
+            assert_eq!(expanded(&variables, line), line, "{}", line);
+        }
+    }
+
+    #[test]
+    fn expand_reports_undefined_variables() {
+        let variables = variables(&[("a", "1")]);
+
+        assert_eq!(
+            variables.expand("show $a $b ${c} '$d'"),
+            Expansion {
+                line: "show 1   '$d'".to_string(),
+                undefined: vec!["b".to_string(), "c".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn set_and_remove_check_names() {
+        let mut variables = variables(&[("a", "one two")]);
+
+        assert_eq!(
+            variables.set("1a", String::new()),
+            Err(VarError::InvalidName("1a".to_string()))
+        );
+        assert_eq!(
+            variables.set("a-b", String::new()),
+            Err(VarError::InvalidName("a-b".to_string()))
+        );
+        assert_eq!(variables.format(), "a = \"one two\"\n");
+        assert_eq!(variables.remove("a"), Ok(()));
+        assert_eq!(
+            variables.remove("a"),
+            Err(VarError::NotFound("a".to_string()))
+        );
+        assert_eq!(variables.format(), "");
+    }
+}
@@ -0,0 +1,85 @@
+use std::cell::RefCell;
+use std::io::{self, Cursor, Write};
+use std::rc::Rc;
+
+use tli42::repl::{Action, HandlerError, Repl, ReplError};
+
+/// Collects what handlers print so the test can inspect it.
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl SharedOutput {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).expect("utf-8 output")
+    }
+}
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn counter_repl(output: &SharedOutput) -> Repl {
+    let count = Rc::new(RefCell::new(0));
+    let add_count = Rc::clone(&count);
+
+    Repl::builder()
+        .command("counter add <amount>", "add to the counter", move |ctx| {
+            let amount = ctx
+                .positional(0)
+                .unwrap_or_default()
+                .parse::<i64>()
+                .map_err(|_| HandlerError("amount must be a number".to_string()))?;
+            *add_count.borrow_mut() += amount;
+            Ok(Action::None)
+        })
+        .command("counter show", "print the counter", move |ctx| {
+            ctx.println(format_args!("count = {}", count.borrow()));
+            Ok(Action::None)
+        })
+        .output(output.clone())
+        .build()
+        .expect("commands register")
+}
+
+#[test]
+fn builder_repl_runs_scripted_commands_and_captures_output() {
+    let output = SharedOutput::default();
+    let mut repl = counter_repl(&output);
+
+    let script = "counter add 2\ncounter add 40\ncounter show\n";
+    assert_eq!(repl.run_script(Cursor::new(script), false).unwrap(), 0);
+    assert_eq!(output.text(), "count = 42\n");
+}
+
+#[test]
+fn builder_repl_reports_handler_failures_as_status() {
+    let output = SharedOutput::default();
+    let mut repl = counter_repl(&output);
+
+    assert_eq!(repl.run_command("counter add lots").unwrap(), 1);
+    assert_eq!(repl.run_command("counter").unwrap(), 2);
+    assert_eq!(repl.run_command("counter show").unwrap(), 0);
+    assert_eq!(output.text(), "count = 0\n");
+}
+
+#[test]
+fn builder_rejects_bad_prompt_and_duplicate_phrases() {
+    assert!(matches!(
+        Repl::builder().prompt("{mode").build(),
+        Err(ReplError::InvalidPromptTemplate(_))
+    ));
+    assert!(matches!(
+        Repl::builder()
+            .command("ping", "first", |_| Ok(Action::None))
+            .command("ping", "second", |_| Ok(Action::None))
+            .build(),
+        Err(ReplError::CmdInsert(_))
+    ));
+}
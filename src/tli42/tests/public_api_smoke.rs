@@ -5,6 +5,14 @@ use std::rc::Rc;
 use tli42::cmd::CmdBuilder;
 use tli42::repl::{Action, CommandInputs, CompletionItem, Repl, RunOnceOutcome};
 
+/// The `exit` or `quit` entry listed at the root of every mode.
+fn exit_item(name: &str) -> CompletionItem {
+    CompletionItem {
+        token: name.to_string(),
+        doc: Some("leave the current mode, or quit from the root mode".to_string()),
+    }
+}
+
 #[test]
 fn public_repl_register_and_run_once_captures_vars() {
     let mut repl = Repl::new();
@@ -80,6 +88,15 @@ fn public_repl_meta_exit_returns_exit_action_at_root() {
     assert_eq!(outcome, RunOnceOutcome::ActionApplied(Action::Exit));
 }
 
+#[test]
+fn public_repl_exit_with_status_sets_last_status() {
+    let mut repl = Repl::new();
+
+    let outcome = repl.run_once("quit 3").expect("run_once");
+    assert_eq!(outcome, RunOnceOutcome::ActionApplied(Action::Exit));
+    assert_eq!(repl.last_status(), 3);
+}
+
 #[test]
 fn public_repl_question_returns_completions() {
     let mut repl = Repl::new();
@@ -100,6 +117,8 @@ fn public_repl_question_returns_completions() {
     assert_eq!(
         outcome,
         RunOnceOutcome::Completions(vec![
+            exit_item("exit"),
+            exit_item("quit"),
             CompletionItem {
                 token: "show".to_string(),
                 doc: None,
@@ -127,10 +146,14 @@ fn public_repl_question_returns_doc_annotated_completion() {
     let outcome = repl.run_once("?").expect("run_once");
     assert_eq!(
         outcome,
-        RunOnceOutcome::Completions(vec![CompletionItem {
-            token: "write".to_string(),
-            doc: Some("enter write mode".to_string()),
-        }])
+        RunOnceOutcome::Completions(vec![
+            exit_item("exit"),
+            exit_item("quit"),
+            CompletionItem {
+                token: "write".to_string(),
+                doc: Some("enter write mode".to_string()),
+            },
+        ])
     );
 }
 